@@ -26,6 +26,7 @@ use std::process::exit;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use bytes::Bytes;
 use octseq::Octets;
 use rand::distributions::Alphanumeric;
 use rand::Rng;
@@ -35,7 +36,7 @@ use tracing_subscriber::EnvFilter;
 use domain::base::iana::{Class, Rcode};
 use domain::base::name::OwnedLabel;
 use domain::base::net::IpAddr;
-use domain::base::{Name, Rtype, Serial, ToName, Ttl};
+use domain::base::{Name, Rtype, Serial, Ttl};
 use domain::net::server::buf::VecBufSource;
 use domain::net::server::dgram::DgramServer;
 use domain::net::server::message::Request;
@@ -245,12 +246,13 @@ fn my_service(
     zones: Arc<ZoneTree>,
 ) -> ServiceResult<Vec<u8>> {
     let question = request.message().sole_question().unwrap();
+    let question = question.to_canonical::<Bytes>();
     let zone = zones
         .find_zone(question.qname(), question.qclass())
         .map(|zone| zone.read());
     let answer = match zone {
         Some(zone) => {
-            let qname = question.qname().to_bytes();
+            let qname = question.qname().clone();
             let qtype = question.qtype();
             zone.query(qname, qtype).unwrap()
         }
@@ -306,14 +308,11 @@ impl ZoneTreeWithDiffs {
 
     fn get_diffs(&self, diff_from: Option<Serial>) -> Vec<InMemoryZoneDiff> {
         let diffs = self.diffs.lock().unwrap();
-        if let Some(idx) = diffs
-            .iter()
-            .position(|diff| Some(diff.start_serial) == diff_from)
-        {
-            diffs[idx..].to_vec()
-        } else {
-            vec![]
-        }
+        let (Some(start), Some(last)) = (diff_from, diffs.last()) else {
+            return vec![];
+        };
+        InMemoryZoneDiff::diffs_for_range(&diffs, start, last.end_serial)
+            .unwrap_or_default()
     }
 }
 
@@ -81,6 +81,9 @@ impl<Octs: AsRef<[u8]>> fmt::Display for DigPrinter<'_, Octs> {
                         ExtendedError(extendederror) => {
                             writeln!(f, "; EDE: {}", extendederror)?
                         }
+                        ReportChannel(opt) => {
+                            writeln!(f, "; REPORTCHANNEL: {}", opt)?
+                        }
                         Other(other) => {
                             writeln!(f, "; {}", other.code())?;
                         }
@@ -455,6 +455,151 @@ impl FromStr for Flags {
     }
 }
 
+//------------ HeaderFlags --------------------------------------------------
+
+/// A set of changes to the flags of a message header.
+///
+/// While [`Flags`] together with [`Header::set_flags`] replaces all flags of
+/// a header, this type only touches those flags that have explicitly been
+/// set or cleared, leaving all other flags of the header unchanged. This is
+/// useful when preparing a response header where some flags are mandated by
+/// the protocol and others are copied from the request.
+///
+/// The flag changes are collected via builder-style methods and are then
+/// applied to a header in one go via [`apply`][Self::apply]:
+///
+/// ```
+/// use domain::base::header::{Header, HeaderFlags};
+///
+/// let mut header = Header::new();
+/// header.set_rd(true);
+/// header.set_ad(true);
+///
+/// HeaderFlags::new().qr(true).aa(true).ad(false).apply(&mut header);
+/// assert!(header.qr() && header.aa() && header.rd());
+/// assert!(!header.ad());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct HeaderFlags {
+    qr: Option<bool>,
+    aa: Option<bool>,
+    tc: Option<bool>,
+    rd: Option<bool>,
+    ra: Option<bool>,
+    z: Option<bool>,
+    ad: Option<bool>,
+    cd: Option<bool>,
+}
+
+impl HeaderFlags {
+    /// Creates a new, empty set of flag changes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets or clears the [QR](Flags::qr) bit.
+    #[must_use]
+    pub fn qr(mut self, set: bool) -> Self {
+        self.qr = Some(set);
+        self
+    }
+
+    /// Sets or clears the [AA](Flags::aa) bit.
+    #[must_use]
+    pub fn aa(mut self, set: bool) -> Self {
+        self.aa = Some(set);
+        self
+    }
+
+    /// Sets or clears the [TC](Flags::tc) bit.
+    #[must_use]
+    pub fn tc(mut self, set: bool) -> Self {
+        self.tc = Some(set);
+        self
+    }
+
+    /// Sets or clears the [RD](Flags::rd) bit.
+    #[must_use]
+    pub fn rd(mut self, set: bool) -> Self {
+        self.rd = Some(set);
+        self
+    }
+
+    /// Sets or clears the [RA](Flags::ra) bit.
+    #[must_use]
+    pub fn ra(mut self, set: bool) -> Self {
+        self.ra = Some(set);
+        self
+    }
+
+    /// Sets or clears the [Z](Header::z) bit.
+    #[must_use]
+    pub fn z(mut self, set: bool) -> Self {
+        self.z = Some(set);
+        self
+    }
+
+    /// Sets or clears the [AD](Flags::ad) bit.
+    #[must_use]
+    pub fn ad(mut self, set: bool) -> Self {
+        self.ad = Some(set);
+        self
+    }
+
+    /// Sets or clears the [CD](Flags::cd) bit.
+    #[must_use]
+    pub fn cd(mut self, set: bool) -> Self {
+        self.cd = Some(set);
+        self
+    }
+
+    /// Applies the flag changes to a header.
+    ///
+    /// Flags that have neither been set nor cleared are left untouched.
+    pub fn apply(self, header: &mut Header) {
+        if let Some(set) = self.qr {
+            header.set_qr(set);
+        }
+        if let Some(set) = self.aa {
+            header.set_aa(set);
+        }
+        if let Some(set) = self.tc {
+            header.set_tc(set);
+        }
+        if let Some(set) = self.rd {
+            header.set_rd(set);
+        }
+        if let Some(set) = self.ra {
+            header.set_ra(set);
+        }
+        if let Some(set) = self.z {
+            header.set_z(set);
+        }
+        if let Some(set) = self.ad {
+            header.set_ad(set);
+        }
+        if let Some(set) = self.cd {
+            header.set_cd(set);
+        }
+    }
+}
+
+//--- From
+
+impl From<Flags> for HeaderFlags {
+    fn from(flags: Flags) -> Self {
+        HeaderFlags::new()
+            .qr(flags.qr)
+            .aa(flags.aa)
+            .tc(flags.tc)
+            .rd(flags.rd)
+            .ra(flags.ra)
+            .ad(flags.ad)
+            .cd(flags.cd)
+    }
+}
+
 //------------ HeaderCounts -------------------------------------------------
 
 /// The section count part of the header section of a DNS message.
@@ -962,6 +1107,31 @@ impl std::error::Error for CountOverflow {}
 mod test {
     use super::*;
 
+    #[test]
+    fn header_flags() {
+        let mut header = Header::new();
+        header.set_id(0x1234);
+        header.set_opcode(Opcode::NOTIFY);
+        header.set_rd(true);
+        header.set_tc(true);
+        header.set_cd(true);
+
+        HeaderFlags::new()
+            .qr(true)
+            .aa(true)
+            .tc(false)
+            .cd(false)
+            .apply(&mut header);
+
+        assert_eq!(header.id(), 0x1234);
+        assert_eq!(header.opcode(), Opcode::NOTIFY);
+        assert_eq!(header.flags(), Flags::from_str("QR AA RD").unwrap());
+
+        // Converting from Flags applies all flags.
+        HeaderFlags::from(Flags::from_str("RA").unwrap()).apply(&mut header);
+        assert_eq!(header.flags(), Flags::from_str("RA").unwrap());
+    }
+
     #[test]
     #[cfg(feature = "std")]
     fn for_slice() {
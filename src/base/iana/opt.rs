@@ -162,6 +162,15 @@ int_enum! {
     /// [draft-bellis-dnsop-edns-tags]: https://datatracker.ietf.org/doc/draft-bellis-dnsop-edns-tags/
     (SERVER_TAG => 17, "EDNS-Server-Tag")
 
+    /// Report-Channel (18).
+    ///
+    /// The Report-Channel option allows an authoritative server to signal
+    /// the agent domain to which resolvers can report errors. The option
+    /// is defined in [RFC 9567].
+    ///
+    /// [RFC 9567]: https://tools.ietf.org/html/rfc9567
+    (REPORT_CHANNEL => 18, "Report-Channel")
+
     /// DeviceID (26946).
     ///
     /// Ths option is used by the [Cisco Umbrella network device API].
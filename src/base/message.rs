@@ -18,11 +18,12 @@ use super::name::ParsedName;
 use super::opt::{Opt, OptRecord};
 use super::question::Question;
 use super::rdata::{ParseAnyRecordData, ParseRecordData};
-use super::record::{ComposeRecord, ParsedRecord, Record};
+use super::record::{ComposeRecord, ParsedRecord, Record, Ttl};
 use super::wire::{Composer, ParseError};
-use crate::rdata::rfc1035::Cname;
+use crate::rdata::rfc1035::{Cname, Soa};
 use core::marker::PhantomData;
 use core::{fmt, mem};
+use octseq::builder::Truncate;
 use octseq::{Octets, OctetsFrom, Parser};
 
 //------------ Message -------------------------------------------------------
@@ -545,6 +546,68 @@ impl<Octs: Octets + ?Sized> Message<Octs> {
         None
     }
 
+    /// Returns the TTL for which the message can be cached.
+    ///
+    /// This is the smallest TTL of all records in the answer, authority,
+    /// and additional sections. The OPT record is ignored since its TTL
+    /// field contains EDNS information rather than a TTL.
+    ///
+    /// For negative responses, i.e., NXDOMAIN responses and NODATA
+    /// responses with an empty answer section, the TTL of the SOA record in
+    /// the authority section is further limited by its MINIMUM field as
+    /// described in [RFC 2308, section 5]. As negative responses without a
+    /// SOA record should not be cached, the method returns `None` for them.
+    /// A response with an empty answer section and NS records in the
+    /// authority section is considered a referral rather than a NODATA
+    /// response.
+    ///
+    /// The method also returns `None` if the message has no records or if
+    /// there is a parse error.
+    ///
+    /// [RFC 2308, section 5]: https://tools.ietf.org/html/rfc2308#section-5
+    pub fn cache_ttl(&self) -> Option<Ttl> {
+        let (_, answer, authority, additional) = self.sections().ok()?;
+        let nxdomain = self.header().rcode() == Rcode::NXDOMAIN;
+        let negative = nxdomain || self.header_counts().ancount() == 0;
+
+        let mut ttl: Option<Ttl> = None;
+        for record in answer.chain(additional) {
+            let record = record.ok()?;
+            if record.rtype() != Rtype::OPT {
+                ttl = Some(
+                    ttl.map_or(record.ttl(), |ttl| ttl.min(record.ttl())),
+                );
+            }
+        }
+
+        let mut has_soa = false;
+        let mut has_ns = false;
+        for record in authority {
+            let record = record.ok()?;
+            let mut record_ttl = record.ttl();
+            match record.rtype() {
+                Rtype::SOA => {
+                    has_soa = true;
+                    if negative {
+                        let soa = record
+                            .to_record::<Soa<ParsedName<_>>>()
+                            .ok()??;
+                        record_ttl = record_ttl.min(soa.data().minimum());
+                    }
+                }
+                Rtype::NS => has_ns = true,
+                Rtype::OPT => continue,
+                _ => {}
+            }
+            ttl = Some(ttl.map_or(record_ttl, |ttl| ttl.min(record_ttl)));
+        }
+
+        if negative && !has_soa && (nxdomain || !has_ns) {
+            return None;
+        }
+        ttl
+    }
+
     /// Returns the OPT record from the message, if there is one.
     pub fn opt(&self) -> Option<OptRecord<Octs::Range<'_>>> {
         match self.additional() {
@@ -607,6 +670,106 @@ impl<Octs: Octets + ?Sized> Message<Octs> {
             .dec_arcount();
     }
 
+    /// Truncates the message so that it fits into `size` octets.
+    ///
+    /// Records are removed from the end of the message, i.e., first from
+    /// the additional section, then from the authority section, and
+    /// finally from the answer section, until the message fits. The record
+    /// counts in the header are updated accordingly. If records had to be
+    /// removed from the answer section, the TC bit is set.
+    ///
+    /// An OPT record is kept as the last record of the message if there is
+    /// room for it, so the EDNS parameters of a truncated response are
+    /// retained.
+    ///
+    /// If even the header and question section exceed `size`, all records
+    /// are removed and the message remains larger than `size`.
+    pub fn truncate_to_size(&mut self, size: usize) -> Result<(), ParseError>
+    where
+        Octs: AsMut<[u8]> + Truncate,
+    {
+        if self.as_slice().len() <= size {
+            return Ok(());
+        }
+
+        // Find the start and end of the OPT record, if any.
+        let mut opt = None;
+        let mut section = self.additional()?;
+        loop {
+            let start = section.pos();
+            match section.next() {
+                Some(record) => {
+                    if record?.rtype() == Rtype::OPT {
+                        opt = Some((start, section.pos()));
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        let opt_len = opt.map(|(start, end)| end - start).unwrap_or(0);
+
+        // Find the longest sequence of records that fits, making room for
+        // the OPT record unless it is part of the sequence already.
+        let mut counts = [0u16; 3];
+        let mut section = self.answer()?;
+        let mut cut = section.pos();
+        let mut idx = 0;
+        'sections: loop {
+            loop {
+                let start = section.pos();
+                match section.next() {
+                    Some(record) => record?,
+                    None => break,
+                };
+                let end = section.pos();
+                if opt == Some((start, end)) {
+                    continue;
+                }
+                let needed = match opt {
+                    Some((opt_start, _)) if opt_start < start => end,
+                    _ => end + opt_len,
+                };
+                if needed > size {
+                    break 'sections;
+                }
+                counts[idx] += 1;
+                cut = end;
+            }
+            match section.next_section()? {
+                Some(next) => {
+                    section = next;
+                    idx += 1;
+                }
+                None => break,
+            }
+        }
+
+        // Move the OPT record right behind the records we keep.
+        let mut len = cut;
+        if let Some((start, end)) = opt {
+            if start < cut {
+                counts[2] += 1;
+            } else if cut + opt_len <= size {
+                self.octets.as_mut().copy_within(start..end, cut);
+                len += opt_len;
+                counts[2] += 1;
+            }
+        }
+
+        let truncated = counts[0] < self.header_counts().ancount();
+        self.octets.truncate(len);
+        let header_counts =
+            HeaderCounts::for_message_slice_mut(self.octets.as_mut());
+        header_counts.set_ancount(counts[0]);
+        header_counts.set_nscount(counts[1]);
+        header_counts.set_arcount(counts[2]);
+        if truncated {
+            self.header_mut().set_tc(true);
+        }
+        Ok(())
+    }
+
     /// Copy records from a message into the target message builder.
     ///
     /// The method uses `op` to process records from all record sections
@@ -663,6 +826,59 @@ impl<Octs: Octets + ?Sized> Message<Octs> {
     }
 }
 
+/// # Canonical form
+#[cfg(feature = "std")]
+impl<Octs: Octets + ?Sized> Message<Octs> {
+    /// Returns the canonical form of the records in the message.
+    ///
+    /// Each of the answer, authority and additional sections is converted
+    /// into the number of its records as a 16 bit value followed by the
+    /// records sorted into canonical order and in the canonical wire format
+    /// defined in [RFC 4034, Section 6], i.e., with lowercase, uncompressed
+    /// owner names and record data. OPT and TSIG records are left out as
+    /// they describe the transaction rather than the data.
+    ///
+    /// Two messages that only differ in the case of names, name compression,
+    /// and the order of records within a section therefore have the same
+    /// canonical form. This makes it suitable for hashing or comparing
+    /// messages. The header and question section are not included.
+    ///
+    /// [RFC 4034, Section 6]:
+    ///     https://datatracker.ietf.org/doc/html/rfc4034#section-6
+    pub fn canonical_records(&self) -> Result<std::vec::Vec<u8>, ParseError> {
+        use super::cmp::CanonicalOrd;
+        use super::wire::Compose;
+        use crate::rdata::AllRecordData;
+        use octseq::builder::infallible;
+
+        let mut target = std::vec::Vec::new();
+        let mut section = Some(self.answer()?);
+        while let Some(current) = section {
+            let mut records = std::vec::Vec::new();
+            for rr in
+                current.into_records::<AllRecordData<
+                    Octs::Range<'_>,
+                    ParsedName<Octs::Range<'_>>,
+                >>()
+            {
+                let rr = rr?;
+                if !matches!(rr.rtype(), Rtype::OPT | Rtype::TSIG) {
+                    records.push(rr);
+                }
+            }
+            records.sort_by(|a, b| a.canonical_cmp(b));
+
+            // The number of records fits as it was in the header.
+            infallible((records.len() as u16).compose(&mut target));
+            for rr in &records {
+                infallible(rr.compose_canonical(&mut target));
+            }
+            section = current.next_section()?;
+        }
+        Ok(target)
+    }
+}
+
 /// # Printing
 impl<Octs: AsRef<[u8]>> Message<Octs> {
     /// Create a wrapper that displays the message in a dig style
@@ -1453,6 +1669,86 @@ mod test {
         assert!(msg.as_message().canonical_name().is_none());
     }
 
+    #[test]
+    #[cfg(feature = "std")]
+    fn cache_ttl() {
+        use crate::base::Serial;
+        use crate::rdata::A;
+
+        fn name(s: &str) -> Name<Vec<u8>> {
+            Name::vec_from_str(s).unwrap()
+        }
+
+        fn soa() -> Soa<Name<Vec<u8>>> {
+            Soa::new(
+                name("ns.example.com."),
+                name("hostmaster.example.com."),
+                Serial(1),
+                Ttl::from_secs(3600),
+                Ttl::from_secs(600),
+                Ttl::from_secs(86400),
+                Ttl::from_secs(300),
+            )
+        }
+
+        // Positive response with several RRsets and an OPT record.
+        let mut msg = MessageBuilder::new_vec();
+        msg.header_mut().set_qr(true);
+        let mut msg = msg.question();
+        msg.push((name("www.example.com."), Rtype::A)).unwrap();
+        let mut msg = msg.answer();
+        msg.push((
+            name("www.example.com."),
+            3600,
+            Cname::new(name("web.example.com.")),
+        ))
+        .unwrap();
+        msg.push((
+            name("web.example.com."),
+            1200,
+            A::from_octets(192, 0, 2, 1),
+        ))
+        .unwrap();
+        let mut msg = msg.authority();
+        msg.push((
+            name("example.com."),
+            7200,
+            Ns::new(name("ns.example.com.")),
+        ))
+        .unwrap();
+        let mut msg = msg.additional();
+        msg.push((
+            name("ns.example.com."),
+            900,
+            A::from_octets(192, 0, 2, 53),
+        ))
+        .unwrap();
+        msg.opt(|_| Ok(())).unwrap();
+        let msg = msg.into_message();
+        assert_eq!(msg.cache_ttl(), Some(Ttl::from_secs(900)));
+
+        // NODATA response: the SOA MINIMUM limits the TTL.
+        let mut msg = MessageBuilder::new_vec();
+        msg.header_mut().set_qr(true);
+        let mut msg = msg.question();
+        msg.push((name("www.example.com."), Rtype::AAAA)).unwrap();
+        let mut msg = msg.authority();
+        msg.push((name("example.com."), 3600, soa())).unwrap();
+        let mut msg = msg.additional();
+        msg.opt(|_| Ok(())).unwrap();
+        let msg = msg.into_message();
+        assert_eq!(msg.cache_ttl(), Some(Ttl::from_secs(300)));
+
+        // NXDOMAIN response without a SOA record can't be cached.
+        let mut msg = MessageBuilder::new_vec();
+        msg.header_mut().set_qr(true);
+        msg.header_mut().set_rcode(Rcode::NXDOMAIN);
+        let mut msg = msg.question();
+        msg.push((name("www.example.com."), Rtype::A)).unwrap();
+        let msg = msg.into_message();
+        assert_eq!(msg.cache_ttl(), None);
+    }
+
     #[test]
     #[cfg(feature = "std")]
     fn message_iterator() {
@@ -1491,4 +1787,102 @@ mod test {
             assert_eq!(0, msg.header_counts().arcount());
         }
     }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn canonical_records() {
+        let rr = |owner: &str, target: &str| {
+            (
+                Name::vec_from_str(owner).unwrap(),
+                3600,
+                Ns::new(Name::vec_from_str(target).unwrap()),
+            )
+        };
+
+        let mut msg = MessageBuilder::new_vec().answer();
+        msg.push(rr("example.com.", "ns1.example.com.")).unwrap();
+        msg.push(rr("example.com.", "ns2.example.com.")).unwrap();
+        let mut msg = msg.authority();
+        msg.push(rr("sub.example.com.", "ns.example.net.")).unwrap();
+        let one = msg.into_message();
+
+        let mut msg = MessageBuilder::new_vec().answer();
+        msg.push(rr("Example.COM.", "NS2.example.com.")).unwrap();
+        msg.push(rr("EXAMPLE.com.", "ns1.Example.Com.")).unwrap();
+        let mut msg = msg.authority();
+        msg.push(rr("SUB.example.com.", "ns.EXAMPLE.net.")).unwrap();
+        let two = msg.into_message();
+
+        assert_eq!(
+            one.canonical_records().unwrap(),
+            two.canonical_records().unwrap()
+        );
+
+        // Moving a record to a different section changes the form.
+        let mut msg = MessageBuilder::new_vec().answer();
+        msg.push(rr("example.com.", "ns1.example.com.")).unwrap();
+        msg.push(rr("example.com.", "ns2.example.com.")).unwrap();
+        msg.push(rr("sub.example.com.", "ns.example.net.")).unwrap();
+        let three = msg.into_message();
+        assert_ne!(
+            one.canonical_records().unwrap(),
+            three.canonical_records().unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn truncate_to_size() {
+        use crate::rdata::A;
+
+        let mut msg = MessageBuilder::new_vec().answer();
+        let www = Name::vec_from_str("www.example.com.").unwrap();
+        msg.push((&www, 3600, A::from_octets(192, 0, 2, 1)))
+            .unwrap();
+        msg.push((&www, 3600, A::from_octets(192, 0, 2, 2)))
+            .unwrap();
+        let mut msg = msg.authority();
+        msg.push((
+            Name::vec_from_str("example.com.").unwrap(),
+            3600,
+            Ns::new(Name::vec_from_str("ns.example.com.").unwrap()),
+        ))
+        .unwrap();
+        let mut msg = msg.additional();
+        msg.push((
+            Name::vec_from_str("ns.example.com.").unwrap(),
+            3600,
+            A::from_octets(192, 0, 2, 53),
+        ))
+        .unwrap();
+        msg.opt(|_| Ok(())).unwrap();
+        let msg = msg.into_message();
+        let len = msg.as_slice().len();
+
+        // Returns the counts, the TC bit, and whether there is an OPT
+        // record after truncating to `size`.
+        let truncate = |size: usize| {
+            let mut msg = msg.clone();
+            msg.truncate_to_size(size).unwrap();
+            assert!(msg.as_slice().len() <= size);
+            let counts = msg.header_counts();
+            (
+                counts.ancount(),
+                counts.nscount(),
+                counts.arcount(),
+                msg.header().tc(),
+                msg.opt().is_some(),
+            )
+        };
+
+        // 12 octets header, 31 per answer, 39 for the NS, 30 for the glue
+        // and 11 for the OPT record.
+        assert_eq!(len, 154);
+        assert_eq!(truncate(len), (2, 1, 2, false, true));
+        assert_eq!(truncate(len - 1), (2, 1, 1, false, true));
+        assert_eq!(truncate(123), (2, 0, 1, false, true));
+        assert_eq!(truncate(84), (1, 0, 1, true, true));
+        assert_eq!(truncate(23), (0, 0, 1, true, true));
+        assert_eq!(truncate(22), (0, 0, 0, true, false));
+    }
 }
@@ -133,7 +133,7 @@ use super::iana::Rtype;
 use super::iana::{OptRcode, OptionCode, Rcode};
 use super::message::Message;
 use super::name::{Label, ToName};
-use super::opt::{ComposeOptData, OptHeader, OptRecord};
+use super::opt::{ComposeOptData, OptFlags, OptHeader, OptRecord};
 use super::question::ComposeQuestion;
 use super::record::ComposeRecord;
 use super::wire::{Compose, Composer};
@@ -1792,6 +1792,30 @@ impl<'a, Target: Composer + ?Sized> OptBuilder<'a, Target> {
         self.opt_header_mut().set_dnssec_ok(value)
     }
 
+    /// Returns the EDNS parameters of the OPT record.
+    ///
+    /// The rcode is assembled from both the message header and the OPT
+    /// header.
+    #[must_use]
+    pub fn flags(&self) -> OptFlags {
+        OptFlags {
+            udp_payload_size: self.udp_payload_size(),
+            rcode: self.rcode(),
+            version: self.version(),
+            dnssec_ok: self.dnssec_ok(),
+        }
+    }
+
+    /// Sets the EDNS parameters of the OPT record.
+    ///
+    /// The method will update both the message header and the OPT header.
+    pub fn set_flags(&mut self, flags: OptFlags) {
+        self.set_udp_payload_size(flags.udp_payload_size);
+        self.set_rcode(flags.rcode);
+        self.set_version(flags.version);
+        self.set_dnssec_ok(flags.dnssec_ok);
+    }
+
     /// Returns a reference to the full OPT header.
     fn opt_header(&self) -> &OptHeader {
         OptHeader::for_record_slice(&self.target.as_ref()[self.start..])
@@ -2804,6 +2828,51 @@ mod test {
         assert_eq!(opts.next(), Some(Ok(nsid)));
     }
 
+    #[test]
+    fn opt_builder_option_order() {
+        use crate::base::opt::cookie::{ClientCookie, Cookie, ServerCookie};
+        use crate::base::opt::UnknownOptData;
+
+        let mut msg = MessageBuilder::new_vec().additional();
+        let cookie = Cookie::new(
+            ClientCookie::from_octets(*b"clientck"),
+            Some(ServerCookie::from_octets(b"servercookie")),
+        );
+        msg.opt(|o| {
+            o.cookie(cookie)?;
+            o.push(&opt::nsid::Nsid::from_octets(&b"example"[..]).unwrap())?;
+            o.padding(13)?;
+            Ok(())
+        })
+        .unwrap();
+
+        let msg = Message::from_octets(msg.finish()).unwrap();
+        let record = msg.additional().unwrap().next().unwrap().unwrap();
+        let opt = msg.opt().unwrap();
+
+        let options: Vec<_> = opt
+            .opt()
+            .iter::<UnknownOptData<_>>()
+            .map(|option| {
+                let option = option.unwrap();
+                (option.code(), option.as_slice().len())
+            })
+            .collect();
+        assert_eq!(
+            options,
+            [
+                (OptionCode::COOKIE, 20),
+                (OptionCode::NSID, 7),
+                (OptionCode::PADDING, 13),
+            ]
+        );
+
+        // Each option has a four octet header in addition to its data.
+        let sum: usize = options.iter().map(|(_, len)| 4 + len).sum();
+        assert_eq!(usize::from(record.rdlen()), sum);
+        assert_eq!(opt.opt().len(), sum);
+    }
+
     fn create_compressed<T: Composer>(target: T) -> T
     where
         T::AppendError: fmt::Debug,
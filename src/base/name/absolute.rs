@@ -397,6 +397,16 @@ impl<Octs: AsRef<[u8]> + ?Sized> Name<Octs> {
     pub fn fmt_with_dot(&self) -> impl fmt::Display + '_ {
         ToName::fmt_with_dot(self)
     }
+
+    /// Returns an object that displays the name with the given letter case.
+    ///
+    /// Some tools expect names in presentation format to be all lowercase
+    /// while others want the original case preserved. If `lowercase` is
+    /// `true`, all ASCII letters are converted to lowercase. Otherwise the
+    /// name displays exactly like its `Display` implementation.
+    pub fn fmt_with_case(&self, lowercase: bool) -> impl fmt::Display + '_ {
+        ToName::fmt_with_case(self, lowercase)
+    }
 }
 
 /// # Working with Labels
@@ -2013,6 +2023,35 @@ pub(crate) mod test {
         cmp(b"\x07example\x03com\0", "example.com", "example.com.");
     }
 
+    #[test]
+    #[cfg(feature = "std")]
+    fn display_with_case() {
+        use std::string::ToString;
+
+        let name = Name::<Vec<u8>>::from_str("WwW.ExAmple.COM.").unwrap();
+        assert_eq!(
+            format!("{}", name.fmt_with_case(true)),
+            "www.example.com"
+        );
+        assert_eq!(
+            format!("{}", name.fmt_with_case(false)),
+            "WwW.ExAmple.COM"
+        );
+        assert_eq!(
+            format!("{}", name.fmt_with_case(false)),
+            name.to_string()
+        );
+
+        let name = Name::<Vec<u8>>::from_str(r"A\.B\032C.Org").unwrap();
+        assert_eq!(format!("{}", name.fmt_with_case(true)), r"a\.b\ c.org");
+        assert_eq!(format!("{}", name.fmt_with_case(false)), r"A\.B\ C.Org");
+
+        assert_eq!(
+            format!("{}", Name::root_slice().fmt_with_case(true)),
+            "."
+        );
+    }
+
     #[cfg(all(feature = "serde", feature = "std"))]
     #[test]
     fn ser_de() {
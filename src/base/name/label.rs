@@ -433,9 +433,19 @@ impl<'a> IntoIterator for &'a Label {
 
 //--- Display and Debug
 
-impl fmt::Display for Label {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Label {
+    /// Formats the label, converting ASCII letters to lowercase if asked.
+    pub(super) fn fmt_with_case(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        lowercase: bool,
+    ) -> fmt::Result {
         for ch in self.iter() {
+            let ch = if lowercase {
+                ch.to_ascii_lowercase()
+            } else {
+                ch
+            };
             if ch == b' ' || ch == b'.' || ch == b'\\' {
                 write!(f, "\\{}", ch as char)?;
             } else if !(0x20..0x7F).contains(&ch) {
@@ -448,6 +458,12 @@ impl fmt::Display for Label {
     }
 }
 
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with_case(f, false)
+    }
+}
+
 impl fmt::Debug for Label {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("Label(")?;
@@ -1210,5 +1210,33 @@ mod test {
         ne_step(Name::from_slice(b"\x03ww4\x07EXAMPLE\x03com\x00").unwrap());
     }
 
+    #[test]
+    fn eq_owned_name() {
+        // Comparing with an owned name works in both directions and for
+        // compressed names, and ignores ASCII case.
+        let owned =
+            Name::from_slice(b"\x03WWW\x07example\x03COM\x00").unwrap();
+        for parsed in [name!(flat), name!(once), name!(twice)] {
+            assert!(parsed.name_eq(owned));
+            assert!(owned.name_eq(&parsed));
+            assert_eq!(parsed, owned);
+            assert_eq!(*owned, parsed);
+        }
+
+        // Names that only share a prefix or a suffix are different.
+        for other in [
+            b"\x03www\x07example\x00".as_ref(),
+            b"\x07example\x03com\x00".as_ref(),
+            b"\x03www\x07example\x03com\x03net\x00".as_ref(),
+            b"\x00".as_ref(),
+        ] {
+            let other = Name::from_slice(other).unwrap();
+            for parsed in [name!(flat), name!(once), name!(twice)] {
+                assert_ne!(parsed, other);
+                assert_ne!(*other, parsed);
+            }
+        }
+    }
+
     // XXX TODO Test for cmp and hash.
 }
@@ -351,6 +351,20 @@ pub trait ToName: ToLabelIter {
     fn fmt_with_dot(&self) -> DisplayWithDot<'_, Self> {
         DisplayWithDot(self)
     }
+
+    /// Returns an object that displays the name with the given letter case.
+    ///
+    /// If `lowercase` is `true`, all ASCII letters are converted to
+    /// lowercase. Otherwise, the name is displayed with its original case
+    /// preserved, which is what the name’s own `Display` implementation
+    /// does. Either way, the name is displayed without a final dot unless
+    /// it is the root name.
+    fn fmt_with_case(&self, lowercase: bool) -> DisplayWithCase<'_, Self> {
+        DisplayWithCase {
+            name: self,
+            lowercase,
+        }
+    }
 }
 
 pub struct DisplayWithDot<'a, T: ?Sized>(&'a T);
@@ -378,6 +392,36 @@ where
     }
 }
 
+pub struct DisplayWithCase<'a, T: ?Sized> {
+    name: &'a T,
+    lowercase: bool,
+}
+
+impl<T> fmt::Display for DisplayWithCase<'_, T>
+where
+    T: ToLabelIter + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut labels = self.name.iter_labels();
+        let first = match labels.next() {
+            Some(first) => first,
+            None => unreachable!("at least 1 label must be present"),
+        };
+
+        if first.is_root() {
+            return f.write_str(".");
+        }
+        first.fmt_with_case(f, self.lowercase)?;
+        for label in labels {
+            if !label.is_root() {
+                f.write_str(".")?;
+                label.fmt_with_case(f, self.lowercase)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<'a, N: ToName + ?Sized + 'a> ToName for &'a N {}
 
 //------------ ToRelativeName ------------------------------------------------
@@ -34,6 +34,7 @@ pub mod keepalive;
 pub mod keytag;
 pub mod nsid;
 pub mod padding;
+pub mod reportchannel;
 pub mod subnet;
 
 opt_types! {
@@ -46,6 +47,7 @@ opt_types! {
     keytag::{KeyTag<Octs>};
     nsid::{Nsid<Octs>};
     padding::{Padding<Octs>};
+    reportchannel::{ReportChannel<Name>};
     subnet::{ClientSubnet};
 }
 
@@ -267,6 +269,70 @@ impl<Octs: Composer> Opt<Octs> {
     }
 }
 
+impl<Octs: Composer + EmptyBuilder> Opt<Octs> {
+    /// Creates OPT record data by merging the options of two others.
+    ///
+    /// The new data contains the options of `first` followed by those of
+    /// `second`. If an option code appears in both, only the options with
+    /// that code from the side selected by `policy` are kept.
+    pub fn merge<First, Second>(
+        first: &Opt<First>,
+        second: &Opt<Second>,
+        policy: OptMergePolicy,
+    ) -> Result<Self, BuildDataError>
+    where
+        First: AsRef<[u8]> + ?Sized,
+        Second: AsRef<[u8]> + ?Sized,
+    {
+        let first = first.octets.as_ref();
+        let second = second.octets.as_ref();
+        let contains = |octets: &[u8], code: OptionCode| {
+            raw_options(octets).any(|(other, _)| other == code)
+        };
+
+        let mut res = Self::empty();
+        for (code, data) in raw_options(first) {
+            if policy == OptMergePolicy::PreferSecond
+                && contains(second, code)
+            {
+                continue;
+            }
+            res.push_raw_option(code, data.len() as u16, |target| {
+                target.append_slice(data)
+            })?;
+        }
+        for (code, data) in raw_options(second) {
+            if policy == OptMergePolicy::PreferFirst && contains(first, code)
+            {
+                continue;
+            }
+            res.push_raw_option(code, data.len() as u16, |target| {
+                target.append_slice(data)
+            })?;
+        }
+        Ok(res)
+    }
+}
+
+/// Returns an iterator over the code and data of the options in `octets`.
+///
+/// The octets need to contain correctly encoded OPT record data. Iteration
+/// stops at the first malformed option.
+fn raw_options(
+    octets: &[u8],
+) -> impl Iterator<Item = (OptionCode, &[u8])> + '_ {
+    let mut parser = Parser::from_ref(octets);
+    core::iter::from_fn(move || {
+        if parser.remaining() == 0 {
+            return None;
+        }
+        let code = parser.parse_u16_be().ok()?;
+        let len = parser.parse_u16_be().ok()?;
+        let data = parser.parse_octets(usize::from(len)).ok()?;
+        Some((code.into(), data))
+    })
+}
+
 //--- OctetsFrom
 
 impl<Octs, SrcOcts> OctetsFrom<Opt<SrcOcts>> for Opt<Octs>
@@ -642,6 +708,30 @@ impl<Octs> OptRecord<Octs> {
         }
     }
 
+    /// Returns the EDNS parameters of the OPT record.
+    ///
+    /// Some of the bits of the rcode are stored in the regular message
+    /// header. Such a header needs to be passed to the method.
+    pub fn flags(&self, header: Header) -> OptFlags {
+        OptFlags {
+            udp_payload_size: self.udp_payload_size(),
+            rcode: self.rcode(header),
+            version: self.version(),
+            dnssec_ok: self.dnssec_ok(),
+        }
+    }
+
+    /// Sets the EDNS parameters of the OPT record.
+    ///
+    /// This method _only_ sets the upper bits of the rcode. The lower bits
+    /// need to be set in the message header.
+    pub fn set_flags(&mut self, flags: OptFlags) {
+        self.udp_payload_size = flags.udp_payload_size;
+        self.ext_rcode = flags.rcode.ext();
+        self.version = flags.version;
+        self.set_dnssec_ok(flags.dnssec_ok);
+    }
+
     /// Returns a reference to the raw options.
     pub fn opt(&self) -> &Opt<Octs> {
         &self.data
@@ -674,6 +764,34 @@ impl<Octs: Composer> OptRecord<Octs> {
     }
 }
 
+impl<Octs: AsRef<[u8]>> OptRecord<Octs> {
+    /// Merges the options of this and another OPT record into a new record.
+    ///
+    /// The new record keeps the header fields of `self`, i.e., the UDP
+    /// payload size, extended rcode, version, and flags. Its options are
+    /// merged via [`Opt::merge`] with the options of `self` going first.
+    ///
+    /// This is useful for a proxy that needs to combine the options of an
+    /// upstream response with its own, such as cookies or padding.
+    pub fn merge<Other, Target>(
+        &self,
+        other: &OptRecord<Other>,
+        policy: OptMergePolicy,
+    ) -> Result<OptRecord<Target>, BuildDataError>
+    where
+        Other: AsRef<[u8]>,
+        Target: Composer + EmptyBuilder,
+    {
+        Ok(OptRecord {
+            udp_payload_size: self.udp_payload_size,
+            ext_rcode: self.ext_rcode,
+            version: self.version,
+            flags: self.flags,
+            data: Opt::merge(&self.data, &other.data, policy)?,
+        })
+    }
+}
+
 impl<Octs: EmptyBuilder> Default for OptRecord<Octs> {
     fn default() -> Self {
         Self {
@@ -737,6 +855,49 @@ impl<Octs: AsRef<[u8]>> fmt::Debug for OptRecord<Octs> {
     }
 }
 
+//------------ OptFlags ------------------------------------------------------
+
+/// The EDNS parameters conveyed in the header of an OPT record.
+///
+/// This type collects the values that the OPT record stores in the class
+/// and TTL fields of its record header so they can be read or set all at
+/// once via [`OptRecord::flags`] and [`OptBuilder::flags`] and their
+/// setter counterparts.
+///
+/// [`OptBuilder::flags`]: crate::base::message_builder::OptBuilder::flags
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OptFlags {
+    /// The UDP payload size the sender is able to receive.
+    pub udp_payload_size: u16,
+
+    /// The full extended rcode.
+    ///
+    /// The lower four bits of the rcode are kept in the message header.
+    pub rcode: OptRcode,
+
+    /// The EDNS version.
+    ///
+    /// Only EDNS version 0 is currently defined.
+    pub version: u8,
+
+    /// The DNSSEC OK (DO) bit.
+    pub dnssec_ok: bool,
+}
+
+//------------ OptMergePolicy ------------------------------------------------
+
+/// Which options to keep when merging OPT record data.
+///
+/// See [`Opt::merge`] for details.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OptMergePolicy {
+    /// Keep the options of the first OPT record data.
+    PreferFirst,
+
+    /// Keep the options of the second OPT record data.
+    PreferSecond,
+}
+
 //------------ OptionHeader --------------------------------------------------
 
 /// The header of an OPT option.
@@ -1203,6 +1364,39 @@ pub(super) mod test {
         assert!(record.dnssec_ok());
     }
 
+    #[test]
+    fn opt_flags_round_trip() {
+        let flags = OptFlags {
+            udp_payload_size: 1232,
+            rcode: OptRcode::BADCOOKIE,
+            version: 1,
+            dnssec_ok: true,
+        };
+
+        let msg = {
+            let mut mb = MessageBuilder::new_vec().additional();
+            mb.opt(|opt| {
+                opt.set_flags(flags);
+                assert_eq!(opt.flags(), flags);
+                Ok(())
+            })
+            .unwrap();
+            mb.into_message()
+        };
+        let mut opt = msg.opt().unwrap();
+        assert_eq!(opt.flags(msg.header()), flags);
+        assert_eq!(msg.opt_rcode(), OptRcode::BADCOOKIE);
+
+        let other = OptFlags {
+            udp_payload_size: 512,
+            rcode: OptRcode::NOERROR,
+            version: 0,
+            dnssec_ok: false,
+        };
+        opt.set_flags(other);
+        assert_eq!(opt.flags(Header::new()), other);
+    }
+
     #[test]
     fn opt_iter() {
         use self::opt::cookie::{ClientCookie, Cookie};
@@ -1230,6 +1424,70 @@ pub(super) mod test {
         assert_eq!(Some(Ok(cookie)), opt.opt().iter::<opt::Cookie>().next());
     }
 
+    #[test]
+    fn opt_record_merge() {
+        use self::opt::cookie::{ClientCookie, Cookie};
+
+        let nsid = opt::Nsid::from_octets(&b"upstream"[..]).unwrap();
+        let cookie = Cookie::new(
+            ClientCookie::from_octets(1234u64.to_be_bytes()),
+            None,
+        );
+
+        let mut upstream = OptRecord::<Vec<u8>>::default();
+        upstream.set_udp_payload_size(4096);
+        upstream.push(&nsid).unwrap();
+        let mut local = OptRecord::<Vec<u8>>::default();
+        local.set_udp_payload_size(1232);
+        local.push(&cookie).unwrap();
+
+        // Options from both records survive, the header is ours.
+        let merged: OptRecord<Vec<u8>> =
+            local.merge(&upstream, OptMergePolicy::PreferFirst).unwrap();
+        assert_eq!(merged.udp_payload_size(), 1232);
+        assert_eq!(
+            merged.opt().len(),
+            local.opt().len() + upstream.opt().len()
+        );
+        assert_eq!(
+            Some(Ok(nsid)),
+            merged.opt().iter::<opt::Nsid<_>>().next()
+        );
+        assert_eq!(
+            Some(Ok(cookie.clone())),
+            merged.opt().iter::<opt::Cookie>().next()
+        );
+
+        // Options present in both are taken from one side only.
+        let local_nsid = opt::Nsid::from_octets(&b"local"[..]).unwrap();
+        local.push(&local_nsid).unwrap();
+        let merged: OptRecord<Vec<u8>> =
+            local.merge(&upstream, OptMergePolicy::PreferFirst).unwrap();
+        assert_eq!(
+            merged
+                .opt()
+                .iter::<opt::Nsid<_>>()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            [local_nsid]
+        );
+        let merged: OptRecord<Vec<u8>> = local
+            .merge(&upstream, OptMergePolicy::PreferSecond)
+            .unwrap();
+        assert_eq!(
+            merged
+                .opt()
+                .iter::<opt::Nsid<_>>()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            [nsid]
+        );
+        assert_eq!(
+            Some(Ok(cookie)),
+            merged.opt().iter::<opt::Cookie>().next()
+        );
+    }
+
     pub fn test_option_compose_parse<In, F, Out>(data: &In, parse: F)
     where
         In: ComposeOptData + PartialEq<Out> + Debug,
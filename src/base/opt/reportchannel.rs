@@ -0,0 +1,245 @@
+//! EDNS option to signal the agent domain for DNS error reporting.
+//!
+//! The option in this module – [`ReportChannel<Name>`] – allows an
+//! authoritative server to advertise the domain of a monitoring agent to
+//! which a validating resolver can report errors it encountered while
+//! resolving names served by the authoritative server.
+//!
+//! The option is defined in [RFC 9567](https://tools.ietf.org/html/rfc9567).
+
+use super::super::iana::OptionCode;
+use super::super::message_builder::OptBuilder;
+use super::super::name::{Name, ToName};
+use super::super::wire::{Composer, ParseError};
+use super::{ComposeOptData, Opt, OptData, ParseOptData};
+use core::cmp::Ordering;
+use core::{fmt, hash, mem};
+use octseq::builder::OctetsBuilder;
+use octseq::octets::{Octets, OctetsFrom};
+use octseq::parse::Parser;
+
+//------------ ReportChannel -------------------------------------------------
+
+/// Option data for the Report-Channel option.
+///
+/// The Report-Channel option is included by an authoritative server in its
+/// responses. It contains the absolute domain name of the agent domain, the
+/// domain that error reports are sent to. A resolver that encounters an
+/// error while resolving a name can report it by sending a query for a
+/// specially constructed name below the agent domain.
+///
+/// The agent domain is always encoded uncompressed.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[repr(transparent)]
+pub struct ReportChannel<Name: ?Sized> {
+    /// The agent domain.
+    agent_domain: Name,
+}
+
+impl ReportChannel<()> {
+    /// The option code for this option.
+    pub(super) const CODE: OptionCode = OptionCode::REPORT_CHANNEL;
+}
+
+impl<Name: ?Sized> ReportChannel<Name> {
+    /// Creates new Report-Channel option data for the given agent domain.
+    pub fn new(agent_domain: Name) -> Self
+    where
+        Name: Sized,
+    {
+        ReportChannel { agent_domain }
+    }
+
+    /// Creates a reference to Report-Channel option data from a reference
+    /// to the agent domain.
+    pub fn new_ref(agent_domain: &Name) -> &Self {
+        // SAFETY: ReportChannel has repr(transparent)
+        unsafe { mem::transmute(agent_domain) }
+    }
+
+    /// Returns a reference to the agent domain.
+    pub fn agent_domain(&self) -> &Name {
+        &self.agent_domain
+    }
+
+    /// Converts the value into the agent domain.
+    pub fn into_agent_domain(self) -> Name
+    where
+        Name: Sized,
+    {
+        self.agent_domain
+    }
+}
+
+impl<Octs> ReportChannel<Name<Octs>> {
+    /// Parses Report-Channel option data from its wire format.
+    pub fn parse<'a, Src: Octets<Range<'a> = Octs> + ?Sized>(
+        parser: &mut Parser<'a, Src>,
+    ) -> Result<Self, ParseError> {
+        Name::parse(parser).map(Self::new)
+    }
+}
+
+//--- OctetsFrom
+
+impl<Name, SrcName> OctetsFrom<ReportChannel<SrcName>> for ReportChannel<Name>
+where
+    Name: OctetsFrom<SrcName>,
+{
+    type Error = Name::Error;
+
+    fn try_octets_from(
+        src: ReportChannel<SrcName>,
+    ) -> Result<Self, Self::Error> {
+        Name::try_octets_from(src.agent_domain).map(Self::new)
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Name, OtherName> PartialEq<ReportChannel<OtherName>>
+    for ReportChannel<Name>
+where
+    Name: ToName,
+    OtherName: ToName,
+{
+    fn eq(&self, other: &ReportChannel<OtherName>) -> bool {
+        self.agent_domain().name_eq(other.agent_domain())
+    }
+}
+
+impl<Name: ToName> Eq for ReportChannel<Name> {}
+
+//--- PartialOrd and Ord
+
+impl<Name, OtherName> PartialOrd<ReportChannel<OtherName>>
+    for ReportChannel<Name>
+where
+    Name: ToName,
+    OtherName: ToName,
+{
+    fn partial_cmp(
+        &self,
+        other: &ReportChannel<OtherName>,
+    ) -> Option<Ordering> {
+        Some(self.agent_domain().name_cmp(other.agent_domain()))
+    }
+}
+
+impl<Name: ToName> Ord for ReportChannel<Name> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.agent_domain().name_cmp(other.agent_domain())
+    }
+}
+
+//--- Hash
+
+impl<Name: hash::Hash> hash::Hash for ReportChannel<Name> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.agent_domain().hash(state)
+    }
+}
+
+//--- OptData
+
+impl<Name> OptData for ReportChannel<Name> {
+    fn code(&self) -> OptionCode {
+        OptionCode::REPORT_CHANNEL
+    }
+}
+
+impl<'a, Octs> ParseOptData<'a, Octs> for ReportChannel<Name<Octs::Range<'a>>>
+where
+    Octs: Octets,
+{
+    fn parse_option(
+        code: OptionCode,
+        parser: &mut Parser<'a, Octs>,
+    ) -> Result<Option<Self>, ParseError> {
+        if code == OptionCode::REPORT_CHANNEL {
+            Self::parse(parser).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<Name: ToName> ComposeOptData for ReportChannel<Name> {
+    fn compose_len(&self) -> u16 {
+        self.agent_domain.compose_len()
+    }
+
+    fn compose_option<Target: OctetsBuilder + ?Sized>(
+        &self,
+        target: &mut Target,
+    ) -> Result<(), Target::AppendError> {
+        self.agent_domain.compose(target)
+    }
+}
+
+//--- Display and Debug
+
+impl<Name: fmt::Display> fmt::Display for ReportChannel<Name> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.agent_domain)
+    }
+}
+
+impl<Name: fmt::Display> fmt::Debug for ReportChannel<Name> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReportChannel")
+            .field("agent_domain", &format_args!("{}", self.agent_domain))
+            .finish()
+    }
+}
+
+//--- Extended Opt and OptBuilder
+
+impl<Octs: Octets> Opt<Octs> {
+    /// Returns the first Report-Channel option if present.
+    ///
+    /// The Report-Channel option contains the agent domain to which
+    /// resolvers can report errors.
+    pub fn report_channel(
+        &self,
+    ) -> Option<ReportChannel<Name<Octs::Range<'_>>>> {
+        self.first()
+    }
+}
+
+impl<Target: Composer> OptBuilder<'_, Target> {
+    /// Appends the Report-Channel option.
+    ///
+    /// The Report-Channel option advertises `agent_domain` as the domain
+    /// to which resolvers can report errors.
+    pub fn report_channel(
+        &mut self,
+        agent_domain: impl ToName,
+    ) -> Result<(), Target::AppendError> {
+        self.push(&ReportChannel::new(agent_domain))
+    }
+}
+
+//============ Testing ======================================================
+
+#[cfg(test)]
+#[cfg(all(feature = "std", feature = "bytes"))]
+mod test {
+    use super::super::test::test_option_compose_parse;
+    use super::*;
+    use core::str::FromStr;
+    use std::vec::Vec;
+
+    #[test]
+    #[allow(clippy::redundant_closure)] // lifetimes ...
+    fn report_channel_compose_parse() {
+        test_option_compose_parse(
+            &ReportChannel::new(
+                Name::<Vec<u8>>::from_str("a01.agent-domain.example")
+                    .unwrap(),
+            ),
+            |parser| ReportChannel::parse(parser),
+        );
+    }
+}
@@ -7,12 +7,13 @@
 use super::cmp::CanonicalOrd;
 use super::iana::{Class, Rtype};
 use super::name;
-use super::name::{ParsedName, ToName};
+use super::name::{Name, ParsedName, ToName};
 use super::wire::{Composer, ParseError};
 use core::cmp::Ordering;
+use core::convert::Infallible;
 use core::str::FromStr;
 use core::{fmt, hash};
-use octseq::builder::ShortBuf;
+use octseq::builder::{EmptyBuilder, FromBuilder, OctetsBuilder, ShortBuf};
 use octseq::octets::{Octets, OctetsFrom};
 use octseq::parse::Parser;
 
@@ -89,6 +90,28 @@ impl<N: ToName> Question<N> {
     }
 }
 
+/// # Normalization
+///
+impl<N: ToName> Question<N> {
+    /// Returns a copy of the question with the name in canonical form.
+    ///
+    /// The name of the returned question is all lowercase (and, as any
+    /// absolute name, always ends in the root label). It is meant for
+    /// looking up the question internally, e.g., in a zone tree, so that
+    /// names match regardless of the case used by the client. The original
+    /// question should still be used when building the response so that
+    /// the client gets its question echoed back exactly.
+    pub fn to_canonical<Octs>(&self) -> Question<Name<Octs>>
+    where
+        Octs: FromBuilder,
+        <Octs as FromBuilder>::Builder:
+            OctetsBuilder<AppendError = Infallible>,
+        <Octs as FromBuilder>::Builder: EmptyBuilder,
+    {
+        Question::new(self.qname.to_canonical_name(), self.qtype, self.qclass)
+    }
+}
+
 /// # Parsing and Composing
 ///
 impl<Octs> Question<ParsedName<Octs>> {
@@ -11,6 +11,7 @@ use core::fmt;
 use std::error;
 use std::vec::Vec;
 
+use crate::base::iana::SecurityAlgorithm;
 use crate::rdata::Dnskey;
 
 #[cfg(feature = "openssl")]
@@ -162,6 +163,23 @@ impl PublicKey {
     }
 }
 
+/// Returns whether the cryptographic backend supports an algorithm.
+///
+/// If this returns `false`, [`PublicKey::from_dnskey`] fails with
+/// [`AlgorithmError::Unsupported`] for keys using `algorithm`. Some
+/// backends only find out at runtime whether an algorithm is available.
+#[allow(unreachable_code)]
+pub fn supports_algorithm(algorithm: SecurityAlgorithm) -> bool {
+    #[cfg(feature = "ring")]
+    return ring::supports_algorithm(algorithm);
+
+    #[cfg(feature = "openssl")]
+    return openssl::supports_algorithm(algorithm);
+
+    #[cfg(not(any(feature = "ring", feature = "openssl")))]
+    compile_error!("Either feature \"ring\" or \"openssl\" must be enabled for this crate.");
+}
+
 /// Return the RSA exponent and modulus components from DNSKEY record data.
 pub fn rsa_exponent_modulus(
     dnskey: &Dnskey<impl AsRef<[u8]>>,
@@ -288,3 +306,25 @@ impl fmt::Display for FromDnskeyError {
 }
 
 impl error::Error for FromDnskeyError {}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "ring")]
+    fn unsupported_algorithm() {
+        // Ring doesn't implement Ed448.
+        assert!(!supports_algorithm(SecurityAlgorithm::ED448));
+        assert!(supports_algorithm(SecurityAlgorithm::ED25519));
+
+        let dnskey =
+            Dnskey::new(257, 3, SecurityAlgorithm::ED448, [0u8; 57]).unwrap();
+        assert_eq!(
+            PublicKey::from_dnskey(&dnskey).err(),
+            Some(AlgorithmError::Unsupported)
+        );
+    }
+}
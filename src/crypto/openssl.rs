@@ -153,6 +153,30 @@ impl AsRef<[u8]> for Digest {
     }
 }
 
+//----------- supports_algorithm ---------------------------------------------
+
+/// Returns whether OpenSSL supports an algorithm for verifying signatures.
+///
+/// OpenSSL can be built without some elliptic curves, so this checks
+/// whether the curve of an ECDSA algorithm is actually available.
+pub fn supports_algorithm(algorithm: SecurityAlgorithm) -> bool {
+    match algorithm {
+        SecurityAlgorithm::RSASHA1
+        | SecurityAlgorithm::RSASHA1_NSEC3_SHA1
+        | SecurityAlgorithm::RSASHA256
+        | SecurityAlgorithm::RSASHA512
+        | SecurityAlgorithm::ED25519
+        | SecurityAlgorithm::ED448 => true,
+        SecurityAlgorithm::ECDSAP256SHA256 => {
+            EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).is_ok()
+        }
+        SecurityAlgorithm::ECDSAP384SHA384 => {
+            EcGroup::from_curve_name(Nid::SECP384R1).is_ok()
+        }
+        _ => false,
+    }
+}
+
 //----------- PublicKey ------------------------------------------------------
 
 /// A public key for verifying a signature.
@@ -221,8 +245,9 @@ impl PublicKey {
                     _ => unreachable!(),
                 };
 
+                // OpenSSL can be built without some curves.
                 let group = EcGroup::from_curve_name(group_id)
-                    .expect("should not fail");
+                    .map_err(|_| AlgorithmError::Unsupported)?;
                 let mut ctx = BigNumContext::new().expect("should not fail");
 
                 // Add 0x4 identifier to the ECDSA pubkey as expected by openssl.
@@ -133,6 +133,22 @@ impl AsRef<[u8]> for Digest {
     }
 }
 
+//----------- supports_algorithm ---------------------------------------------
+
+/// Returns whether ring supports an algorithm for verifying signatures.
+pub fn supports_algorithm(algorithm: SecurityAlgorithm) -> bool {
+    matches!(
+        algorithm,
+        SecurityAlgorithm::RSASHA1
+            | SecurityAlgorithm::RSASHA1_NSEC3_SHA1
+            | SecurityAlgorithm::RSASHA256
+            | SecurityAlgorithm::RSASHA512
+            | SecurityAlgorithm::ECDSAP256SHA256
+            | SecurityAlgorithm::ECDSAP384SHA384
+            | SecurityAlgorithm::ED25519
+    )
+}
+
 //----------- PublicKey ------------------------------------------------------
 
 /// A public key for verifying a signature.
@@ -329,6 +329,8 @@ impl KeyPair {
                         .key_size()
                         < 2048
                 }
+                // Ring does not support Ed448.
+                SecurityAlgorithm::ED448 => true,
                 _ => false,
             };
 
@@ -412,6 +414,88 @@ pub fn generate(
     Err(GenerateError::UnsupportedAlgorithm)
 }
 
+//----------- generate_key() -------------------------------------------------
+
+/// Generate a new key for the given algorithm, ready for signing.
+///
+/// This is a convenience wrapper around [`generate()`] which also computes
+/// the key tag of the generated key.  The random number generator of the
+/// cryptographic backend is used.
+///
+/// ```
+/// # use domain::crypto::sign::{generate_key, GenerateParams, SignRaw};
+/// let key = generate_key(GenerateParams::Ed25519, 257).unwrap();
+/// assert_eq!(key.key_tag(), key.dnskey().key_tag());
+/// let sig = key.key_pair().unwrap().sign_raw(b"Hello, World!").unwrap();
+/// ```
+pub fn generate_key(
+    params: GenerateParams,
+    flags: u16,
+) -> Result<GeneratedKey, GenerateError> {
+    let (secret_key, dnskey) = generate(params, flags)?;
+    let key_tag = dnskey.key_tag();
+    Ok(GeneratedKey {
+        secret_key,
+        dnskey,
+        key_tag,
+    })
+}
+
+//----------- GeneratedKey ---------------------------------------------------
+
+/// A freshly generated key.
+///
+/// See [`generate_key()`].
+pub struct GeneratedKey {
+    /// The secret key.
+    secret_key: SecretKeyBytes,
+
+    /// The public key as a DNSKEY record.
+    dnskey: Dnskey<Vec<u8>>,
+
+    /// The key tag of the DNSKEY record.
+    key_tag: u16,
+}
+
+impl GeneratedKey {
+    /// The secret key.
+    pub fn secret_key(&self) -> &SecretKeyBytes {
+        &self.secret_key
+    }
+
+    /// The public key as a DNSKEY record.
+    pub fn dnskey(&self) -> &Dnskey<Vec<u8>> {
+        &self.dnskey
+    }
+
+    /// The key tag of the DNSKEY record.
+    pub fn key_tag(&self) -> u16 {
+        self.key_tag
+    }
+
+    /// Load the key into the cryptographic backend for signing.
+    pub fn key_pair(&self) -> Result<KeyPair, FromBytesError> {
+        KeyPair::from_bytes(&self.secret_key, &self.dnskey)
+    }
+
+    /// Split the key into the secret key and the DNSKEY record.
+    pub fn into_parts(self) -> (SecretKeyBytes, Dnskey<Vec<u8>>) {
+        (self.secret_key, self.dnskey)
+    }
+}
+
+//--- Debug
+
+impl fmt::Debug for GeneratedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GeneratedKey")
+            .field("secret_key", &self.secret_key.algorithm())
+            .field("dnskey", &self.dnskey)
+            .field("key_tag", &self.key_tag)
+            .finish()
+    }
+}
+
 //----------- SecretKeyBytes -------------------------------------------------
 
 /// A secret key expressed as raw bytes.
@@ -1059,7 +1143,11 @@ mod tests {
     use std::vec::Vec;
 
     use crate::base::iana::SecurityAlgorithm;
-    use crate::crypto::sign::SecretKeyBytes;
+    use crate::crypto::common::PublicKey;
+    use crate::crypto::sign::{
+        generate_key, GenerateError, GenerateParams, SecretKeyBytes, SignRaw,
+    };
+    use crate::rdata::Dnskey;
 
     const KEYS: &[(SecurityAlgorithm, u16)] = &[
         (SecurityAlgorithm::RSASHA256, 60616),
@@ -1095,4 +1183,55 @@ mod tests {
             assert_eq!(data, same);
         }
     }
+
+    #[test]
+    fn generated_sign_verify() {
+        const DATA: &[u8] = b"Hello, World!";
+
+        for params in [
+            GenerateParams::EcdsaP256Sha256,
+            GenerateParams::EcdsaP384Sha384,
+            GenerateParams::Ed25519,
+            GenerateParams::Ed448,
+        ] {
+            let algorithm = params.algorithm();
+            let key = match generate_key(params, 257) {
+                Ok(key) => key,
+                // Ed448 is only available with OpenSSL.
+                Err(GenerateError::UnsupportedAlgorithm)
+                    if algorithm == SecurityAlgorithm::ED448 =>
+                {
+                    continue
+                }
+                Err(err) => panic!("{algorithm}: {err}"),
+            };
+            assert_eq!(key.secret_key().algorithm(), algorithm);
+            assert_eq!(key.dnskey().algorithm(), algorithm);
+            assert_eq!(key.dnskey().flags(), 257);
+            assert_eq!(key.key_tag(), key.dnskey().key_tag());
+
+            let key_pair = key.key_pair().unwrap();
+            let sig = key_pair.sign_raw(DATA).unwrap();
+
+            assert!(verify(key.dnskey(), DATA, sig.as_ref()));
+            assert!(!verify(key.dnskey(), b"Goodbye!", sig.as_ref()));
+        }
+    }
+
+    /// Verifies a signature with the public key of a DNSKEY record.
+    fn verify(dnskey: &Dnskey<Vec<u8>>, data: &[u8], sig: &[u8]) -> bool {
+        // Ring does not support Ed448, so go to OpenSSL directly.
+        #[cfg(feature = "openssl")]
+        if dnskey.algorithm() == SecurityAlgorithm::ED448 {
+            return crate::crypto::openssl::PublicKey::from_dnskey(dnskey)
+                .unwrap()
+                .verify(data, sig)
+                .is_ok();
+        }
+
+        PublicKey::from_dnskey(dnskey)
+            .unwrap()
+            .verify(data, sig)
+            .is_ok()
+    }
 }
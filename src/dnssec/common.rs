@@ -5,20 +5,27 @@
 #![warn(missing_docs)]
 #![warn(clippy::missing_docs_in_private_items)]
 
-use crate::base::iana::{Class, Nsec3HashAlgorithm};
+use crate::base::iana::{Class, DigestAlgorithm, Nsec3HashAlgorithm};
+use crate::base::message::RecordSection;
+use crate::base::name::ParsedName;
+use crate::base::rdata::ComposeRecordData;
 use crate::base::scan::{IterScanner, Scanner};
-use crate::base::wire::Composer;
+use crate::base::wire::{Composer, ParseError};
 use crate::base::zonefile_fmt::{DisplayKind, ZonefileFmt};
 use crate::base::{Name, Record, Rtype, ToName, Ttl};
-use crate::crypto::common::{DigestBuilder, DigestType};
+use crate::crypto::common::{
+    AlgorithmError, Digest, DigestBuilder, DigestType,
+};
+use crate::dep::octseq::builder::with_infallible;
 use crate::dep::octseq::{
-    EmptyBuilder, FromBuilder, OctetsBuilder, Truncate,
+    EmptyBuilder, FromBuilder, Octets, OctetsBuilder, Truncate,
 };
 use crate::rdata::nsec3::{Nsec3Salt, OwnerHash};
-use crate::rdata::{Dnskey, Nsec3param};
+use crate::rdata::{Cdnskey, Cds, Dnskey, Nsec3param, Rrsig};
 
 use std::error;
 use std::fmt;
+use std::vec::Vec;
 
 //------------ Nsec3HashError -------------------------------------------------
 
@@ -161,6 +168,128 @@ where
     Ok(owner_hash)
 }
 
+//------------ dnskey_digest -------------------------------------------------
+
+/// Calculates the digest of a DNSKEY record.
+///
+/// The digest is calculated over the canonical form of the owner name of
+/// the DNSKEY record followed by its record data, as used by DS and CDS
+/// records, see [RFC 4034, Section 5.1.4].
+///
+/// Returns an error if the digest algorithm is not supported.
+///
+/// [RFC 4034, Section 5.1.4]: https://www.rfc-editor.org/rfc/rfc4034#section-5.1.4
+pub fn dnskey_digest<N, Octs>(
+    owner: &N,
+    dnskey: &Dnskey<Octs>,
+    algorithm: DigestAlgorithm,
+) -> Result<Digest, AlgorithmError>
+where
+    N: ToName + ?Sized,
+    Octs: AsRef<[u8]>,
+{
+    let mut buf: Vec<u8> = Vec::new();
+    with_infallible(|| {
+        owner.compose_canonical(&mut buf)?;
+        dnskey.compose_canonical_rdata(&mut buf)
+    });
+
+    let mut ctx = match algorithm {
+        DigestAlgorithm::SHA1 => DigestBuilder::new(DigestType::Sha1),
+        DigestAlgorithm::SHA256 => DigestBuilder::new(DigestType::Sha256),
+        DigestAlgorithm::SHA384 => DigestBuilder::new(DigestType::Sha384),
+        _ => return Err(AlgorithmError::Unsupported),
+    };
+    ctx.update(&buf);
+    Ok(ctx.finish())
+}
+
+//------------ cdnskey_records -----------------------------------------------
+
+/// Derives CDNSKEY records from a set of DNSKEY records.
+///
+/// Each CDNSKEY record is a copy of one of the given DNSKEY records, see
+/// [RFC 7344, Section 3.2]. The records keep the owner name, class and TTL
+/// of the DNSKEY record they are derived from. The caller selects the keys
+/// the parent should refer to, typically those with the SEP flag set.
+///
+/// [RFC 7344, Section 3.2]: https://www.rfc-editor.org/rfc/rfc7344#section-3.2
+pub fn cdnskey_records<N, Octs>(
+    dnskeys: &[Record<N, Dnskey<Octs>>],
+) -> Vec<Record<N, Cdnskey<Octs>>>
+where
+    N: Clone,
+    Octs: AsRef<[u8]> + Clone,
+{
+    dnskeys
+        .iter()
+        .map(|record| {
+            let key = record.data();
+            // The record data is identical to that of the DNSKEY record and
+            // thus of valid length.
+            let data = Cdnskey::new(
+                key.flags(),
+                key.protocol(),
+                key.algorithm(),
+                key.public_key().clone(),
+            )
+            .expect("DNSKEY record data fits into CDNSKEY");
+            Record::new(
+                record.owner().clone(),
+                record.class(),
+                record.ttl(),
+                data,
+            )
+        })
+        .collect()
+}
+
+//------------ cds_records ---------------------------------------------------
+
+/// Derives CDS records from a set of DNSKEY records.
+///
+/// For each of the given DNSKEY records one CDS record is created for each
+/// of the given digest algorithms, see [RFC 7344, Section 3.1]. The digest
+/// is calculated as for a DS record, see [RFC 4034, Section 5.1.4]. The
+/// records keep the owner name, class and TTL of the DNSKEY record they are
+/// derived from.
+///
+/// Returns an error if one of the digest algorithms is not supported.
+///
+/// [RFC 7344, Section 3.1]: https://www.rfc-editor.org/rfc/rfc7344#section-3.1
+/// [RFC 4034, Section 5.1.4]: https://www.rfc-editor.org/rfc/rfc4034#section-5.1.4
+#[allow(clippy::type_complexity)]
+pub fn cds_records<N, Octs>(
+    dnskeys: &[Record<N, Dnskey<Octs>>],
+    digest_algorithms: &[DigestAlgorithm],
+) -> Result<Vec<Record<N, Cds<Vec<u8>>>>, AlgorithmError>
+where
+    N: ToName + Clone,
+    Octs: AsRef<[u8]>,
+{
+    let mut res = Vec::new();
+    for record in dnskeys {
+        let key = record.data();
+        for &algorithm in digest_algorithms {
+            let digest = dnskey_digest(record.owner(), key, algorithm)?;
+            let data = Cds::new(
+                key.key_tag(),
+                key.algorithm(),
+                algorithm,
+                digest.as_ref().to_vec(),
+            )
+            .expect("digest fits into record data");
+            res.push(Record::new(
+                record.owner().clone(),
+                record.class(),
+                record.ttl(),
+                data,
+            ));
+        }
+    }
+    Ok(res)
+}
+
 //------------ parse_from_bind -----------------------------------------------
 
 /// Parse a DNSSEC key from the conventional format used by BIND.
@@ -283,6 +412,38 @@ where
     Display(record)
 }
 
+//------------ rrsigs_covering -----------------------------------------------
+
+/// Returns an iterator over the RRSIG records covering a record type.
+///
+/// The iterator walks over the remainder of `section` and yields all RRSIG
+/// records whose type covered field is `rtype`. Records of other types and
+/// RRSIG records covering other types are skipped. Records that fail to
+/// parse are returned as errors.
+#[allow(clippy::type_complexity)]
+pub fn rrsigs_covering<'a, Octs>(
+    section: RecordSection<'a, Octs>,
+    rtype: Rtype,
+) -> impl Iterator<
+    Item = Result<
+        Record<
+            ParsedName<Octs::Range<'a>>,
+            Rrsig<Octs::Range<'a>, ParsedName<Octs::Range<'a>>>,
+        >,
+        ParseError,
+    >,
+> + 'a
+where
+    Octs: Octets + ?Sized,
+{
+    section
+        .limit_to::<Rrsig<_, ParsedName<_>>>()
+        .filter(move |rr| match rr {
+            Ok(rr) => rr.data().type_covered() == rtype,
+            Err(_) => true,
+        })
+}
+
 //----------- ParseDnskeyTextError -------------------------------------------
 
 #[derive(Clone, Debug)]
@@ -307,8 +468,15 @@ mod test {
     use std::string::ToString;
     use std::vec::Vec;
 
-    use crate::base::iana::SecurityAlgorithm;
-    use crate::dnssec::common::{display_as_bind, parse_from_bind};
+    use crate::base::iana::{DigestAlgorithm, SecurityAlgorithm};
+    use crate::base::message::RecordSection;
+    use crate::base::{MessageBuilder, Name, Record, Rtype, Ttl};
+    use crate::dnssec::common::{
+        cdnskey_records, cds_records, display_as_bind, parse_from_bind,
+        rrsigs_covering,
+    };
+    use crate::rdata::dnssec::{RtypeBitmapBuilder, Timestamp};
+    use crate::rdata::{Dnskey, Nsec, Rrsig, A};
 
     const KEYS: &[(SecurityAlgorithm, u16, usize)] = &[
         (SecurityAlgorithm::RSASHA1, 439, 2048),
@@ -345,6 +513,63 @@ mod test {
         }
     }
 
+    #[test]
+    fn cdnskey_records_copy_keys() {
+        let keys = load_keys();
+        let cdnskeys = cdnskey_records(&keys);
+        assert_eq!(cdnskeys.len(), keys.len());
+        for (key, cdnskey) in keys.iter().zip(&cdnskeys) {
+            assert_eq!(key.owner(), cdnskey.owner());
+            assert_eq!(key.data().flags(), cdnskey.data().flags());
+            assert_eq!(key.data().algorithm(), cdnskey.data().algorithm());
+            assert_eq!(key.data().public_key(), cdnskey.data().public_key());
+        }
+    }
+
+    #[cfg(feature = "unstable-validator")]
+    #[test]
+    fn cds_records_match_dnskey_digest() {
+        use crate::dnssec::validator::base::DnskeyExt;
+
+        let keys = load_keys();
+        let algorithms = [DigestAlgorithm::SHA256, DigestAlgorithm::SHA384];
+        let cds = cds_records(&keys, &algorithms).unwrap();
+        assert_eq!(cds.len(), keys.len() * algorithms.len());
+
+        let mut cds = cds.iter();
+        for key in &keys {
+            for algorithm in algorithms {
+                let cds = cds.next().unwrap();
+                let digest =
+                    key.data().digest(key.owner(), algorithm).unwrap();
+                assert_eq!(cds.owner(), key.owner());
+                assert_eq!(cds.data().key_tag(), key.data().key_tag());
+                assert_eq!(cds.data().algorithm(), key.data().algorithm());
+                assert_eq!(cds.data().digest_type(), algorithm);
+                assert_eq!(cds.data().digest().as_slice(), digest.as_ref());
+            }
+        }
+    }
+
+    #[test]
+    fn cds_records_unsupported_digest() {
+        let keys = load_keys();
+        assert!(cds_records(&keys, &[DigestAlgorithm::GOST]).is_err());
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn load_keys() -> Vec<Record<Name<Vec<u8>>, Dnskey<Vec<u8>>>> {
+        KEYS.iter()
+            .map(|&(algorithm, key_tag, _)| {
+                let name =
+                    format!("test.+{:03}+{:05}", algorithm.to_int(), key_tag);
+                let path = format!("test-data/dnssec-keys/K{}.key", name);
+                let data = std::fs::read_to_string(path).unwrap();
+                parse_from_bind::<Vec<u8>>(&data).unwrap()
+            })
+            .collect()
+    }
+
     #[test]
     fn bind_format_roundtrip() {
         for &(algorithm, key_tag, _) in KEYS {
@@ -359,4 +584,54 @@ mod test {
             assert_eq!(key, same);
         }
     }
+
+    #[test]
+    fn rrsigs_covering_type() {
+        let owner = Name::vec_from_str("www.example.com.").unwrap();
+        let rrsig = |rtype| {
+            Rrsig::new(
+                rtype,
+                SecurityAlgorithm::ECDSAP256SHA256,
+                3,
+                Ttl::HOUR,
+                Timestamp::from(2),
+                Timestamp::from(1),
+                12345,
+                Name::vec_from_str("example.com.").unwrap(),
+                vec![0; 64],
+            )
+            .unwrap()
+        };
+
+        let mut msg = MessageBuilder::new_vec().answer();
+        msg.push((&owner, 3600, A::from_octets(192, 0, 2, 1)))
+            .unwrap();
+        msg.push((&owner, 3600, rrsig(Rtype::A))).unwrap();
+        let mut msg = msg.authority();
+        let mut types = RtypeBitmapBuilder::new_vec();
+        types.add(Rtype::A).unwrap();
+        types.add(Rtype::RRSIG).unwrap();
+        types.add(Rtype::NSEC).unwrap();
+        let nsec = Nsec::new(
+            Name::vec_from_str("zzz.example.com.").unwrap(),
+            types.finalize(),
+        );
+        msg.push((&owner, 3600, nsec)).unwrap();
+        msg.push((&owner, 3600, rrsig(Rtype::NSEC))).unwrap();
+        msg.push((&owner, 3600, rrsig(Rtype::A))).unwrap();
+        let msg = msg.into_message();
+
+        let covered = |section: RecordSection<'_, Vec<u8>>, rtype| {
+            rrsigs_covering(section, rtype)
+                .map(|rr| rr.unwrap().data().type_covered())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(covered(msg.answer().unwrap(), Rtype::A), [Rtype::A]);
+        assert_eq!(
+            covered(msg.authority().unwrap(), Rtype::NSEC),
+            [Rtype::NSEC]
+        );
+        assert_eq!(covered(msg.authority().unwrap(), Rtype::A), [Rtype::A]);
+        assert!(covered(msg.answer().unwrap(), Rtype::NSEC).is_empty());
+    }
 }
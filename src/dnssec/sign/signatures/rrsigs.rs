@@ -327,11 +327,7 @@ where
     );
 
     scratch.clear();
-
-    rrsig.compose_canonical(scratch).unwrap();
-    for record in rrset.iter() {
-        record.compose_canonical(scratch).unwrap();
-    }
+    compose_signed_data(&rrsig, rrset.iter(), scratch);
     let signature = key.raw_secret_key().sign_raw(&*scratch)?;
     let signature = signature.as_ref().to_vec();
     let Ok(signature) = signature.try_octets_into() else {
@@ -357,6 +353,62 @@ where
     ))
 }
 
+//------------ rrsig_signed_data ---------------------------------------------
+
+/// Returns the data to be signed for an `RRSIG` record.
+///
+/// This produces the exact octets over which the signature of an `RRSIG`
+/// record for `rrset` is calculated as defined in [RFC 4034 section
+/// 3.1.8.1]: the `RRSIG` RDATA without the signature followed by the records
+/// of the RRset in canonical form and order, each with the original TTL of
+/// `rrsig`.
+///
+/// This allows signing with an external signer, e.g. an HSM, that only
+/// needs to be given the data to sign. The resulting signature can be
+/// turned into an `RRSIG` record via [`ProtoRrsig::into_rrsig()`].
+///
+/// The RRset does not need to be sorted in canonical ordering.
+///
+/// [RFC 4034 section 3.1.8.1]:
+///     https://www.rfc-editor.org/rfc/rfc4034.html#section-3.1.8.1
+pub fn rrsig_signed_data<N, D, SN>(
+    rrsig: &ProtoRrsig<SN>,
+    rrset: &Rrset<'_, N, D>,
+) -> Vec<u8>
+where
+    N: ToName,
+    D: RecordData + ComposeRecordData + CanonicalOrd,
+    SN: ToName,
+{
+    let mut records: Vec<_> = rrset.iter().collect();
+    records.sort_by(|a, b| a.data().canonical_cmp(b.data()));
+    let mut signed_data = Vec::new();
+    compose_signed_data(rrsig, records.into_iter(), &mut signed_data);
+    signed_data
+}
+
+/// Appends the data to be signed for an `RRSIG` record to `target`.
+///
+/// The records must be sorted in canonical ordering.
+fn compose_signed_data<'a, N, D, SN>(
+    rrsig: &ProtoRrsig<SN>,
+    records: impl Iterator<Item = &'a Record<N, D>>,
+    target: &mut Vec<u8>,
+) where
+    N: ToName + 'a,
+    D: RecordData + ComposeRecordData + 'a,
+    SN: ToName,
+{
+    rrsig.compose_canonical(target).unwrap();
+    for record in records {
+        record.owner().compose_canonical(target).unwrap();
+        record.rtype().compose(target).unwrap();
+        record.class().compose(target).unwrap();
+        rrsig.original_ttl().compose(target).unwrap();
+        record.data().compose_canonical_len_rdata(target).unwrap();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use bytes::Bytes;
@@ -465,6 +517,43 @@ mod tests {
         assert_eq!(rrsig.labels(), 2);
     }
 
+    #[test]
+    #[cfg(feature = "unstable-validator")]
+    fn rrsig_signed_data_matches_validator() {
+        use crate::base::Ttl;
+        use crate::dnssec::validator::base::RrsigExt;
+        use crate::rdata::A;
+
+        for owner in ["www.example.", "*.example."] {
+            let records: [Record<StoredName, StoredRecordData>; 2] = [
+                mk_record(owner, A::from_str("192.0.2.2").unwrap().into()),
+                mk_record(owner, A::from_str("192.0.2.1").unwrap().into()),
+            ];
+            let rrset = Rrset::new(&records).unwrap();
+
+            // Use an original TTL that differs from that of the records.
+            let rrsig = ProtoRrsig::new(
+                Rtype::A,
+                SecurityAlgorithm::ECDSAP256SHA256,
+                mk_name(owner).rrsig_label_count(),
+                Ttl::from_secs(7200),
+                Timestamp::from(TEST_EXPIRATION),
+                Timestamp::from(TEST_INCEPTION),
+                12345,
+                mk_name("example."),
+            );
+            let signed_data = rrsig_signed_data(&rrsig, &rrset);
+
+            let mut expected = Vec::new();
+            rrsig
+                .into_rrsig(Bytes::new())
+                .unwrap()
+                .signed_data(&mut expected, &mut records.clone())
+                .unwrap();
+            assert_eq!(signed_data, expected);
+        }
+    }
+
     #[test]
     fn sign_rrset_must_not_sign_rrsigs() {
         // RFC 4035
@@ -177,6 +177,11 @@ impl TrustAnchors {
         self.0.push(TrustAnchor::new(rr));
     }
 
+    /// Returns an iterator over the owner names of the anchors.
+    pub(crate) fn owners(&self) -> impl Iterator<Item = Name<Bytes>> + '_ {
+        self.0.iter().map(|ta| ta.owner())
+    }
+
     /// Find the longest matching anchor.
     pub(crate) fn find<TDN: Debug + ToName>(
         &self,
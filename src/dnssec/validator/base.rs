@@ -1,17 +1,20 @@
 //! Base functions for DNSSEC validation.
 
-use crate::base::iana::{DigestAlgorithm, SecurityAlgorithm};
+use crate::base::iana::{Class, DigestAlgorithm, SecurityAlgorithm};
 use crate::base::rdata::ComposeRecordData;
 use crate::base::wire::{Compose, Composer};
-use crate::base::{CanonicalOrd, Name, Record, RecordData, ToName};
+use crate::base::{CanonicalOrd, Name, Record, RecordData, Rtype, ToName};
 use crate::crypto::common::{
-    AlgorithmError, Digest, DigestBuilder, DigestType, PublicKey,
+    supports_algorithm, AlgorithmError, Digest, PublicKey,
 };
-use crate::dep::octseq::builder::with_infallible;
-use crate::rdata::{Dnskey, Rrsig};
+use crate::dnssec::common::{dnskey_digest, nsec3_hash};
+use crate::rdata::dnssec::Timestamp;
+use crate::rdata::{Dnskey, Ds, Nsec3param, Rrsig, ZoneRecordData};
+use crate::utils::base32;
 
 use bytes::Bytes;
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::vec::Vec;
 
 //------------ Dnskey --------------------------------------------------------
@@ -74,23 +77,7 @@ where
         name: &N,
         algorithm: DigestAlgorithm,
     ) -> Result<Digest, AlgorithmError> {
-        let mut buf: Vec<u8> = Vec::new();
-        with_infallible(|| {
-            name.compose_canonical(&mut buf)?;
-            self.compose_canonical_rdata(&mut buf)
-        });
-
-        let mut ctx = match algorithm {
-            DigestAlgorithm::SHA1 => DigestBuilder::new(DigestType::Sha1),
-            DigestAlgorithm::SHA256 => DigestBuilder::new(DigestType::Sha256),
-            DigestAlgorithm::SHA384 => DigestBuilder::new(DigestType::Sha384),
-            _ => {
-                return Err(AlgorithmError::Unsupported);
-            }
-        };
-
-        ctx.update(&buf);
-        Ok(ctx.finish())
+        dnskey_digest(name, self, algorithm)
     }
 
     /// The size of this key, in bits.
@@ -131,6 +118,32 @@ where
     }
 }
 
+//------------ Ds ------------------------------------------------------------
+
+impl Ds<Vec<u8>> {
+    /// Creates the DS record data for a DNSKEY.
+    ///
+    /// The digest is calculated over `owner`, the owner name of the DNSKEY
+    /// record, and the DNSKEY record data using `digest_type`. This can be
+    /// used to create the DS records for a delegation or CDS records.
+    ///
+    /// Returns an error if the digest algorithm is not supported.
+    pub fn from_dnskey<N: ToName, Octs: AsRef<[u8]>>(
+        owner: &N,
+        dnskey: &Dnskey<Octs>,
+        digest_type: DigestAlgorithm,
+    ) -> Result<Self, AlgorithmError> {
+        let digest = dnskey.digest(owner, digest_type)?;
+        Ds::new(
+            dnskey.key_tag(),
+            dnskey.algorithm(),
+            digest_type,
+            digest.as_ref().to_vec(),
+        )
+        .map_err(|_| AlgorithmError::InvalidData)
+    }
+}
+
 /// Return whether a DigestAlgorithm is supported or not.
 // This needs to match the digests supported in digest.
 pub fn supported_digest(d: &DigestAlgorithm) -> bool {
@@ -171,6 +184,19 @@ pub trait RrsigExt {
     where
         N: ToName;
 
+    /// Return whether the labels field of this signature is valid for an
+    /// RRset with the given owner name.
+    ///
+    /// See [RFC 4035, Section 5.3.1](https://tools.ietf.org/html/rfc4035#section-5.3.1):
+    ///
+    /// ```text
+    ///    o  The number of labels in the RRset owner name MUST be greater than
+    ///       or equal to the value in the RRSIG RR's Labels field.
+    /// ```
+    ///
+    /// The root label and a leading wildcard label are not counted.
+    fn labels_valid_for<N: ToName>(&self, owner: &N) -> bool;
+
     /// Attempt to use the cryptographic signature to authenticate the signed data, and thus authenticate the RRSET.
     /// The signed data is expected to be calculated as per [RFC4035, Section 5.3.2](https://tools.ietf.org/html/rfc4035#section-5.3.2).
     ///
@@ -294,6 +320,16 @@ impl<Octets: AsRef<[u8]>, TN: ToName> RrsigExt for Rrsig<Octets, TN> {
         }
     }
 
+    fn labels_valid_for<N: ToName>(&self, owner: &N) -> bool {
+        let mut labels = owner.iter_labels();
+        // Subtract the root label.
+        let mut count = labels.clone().count() - 1;
+        if labels.next().is_some_and(|label| label.is_wildcard()) {
+            count = count.saturating_sub(1);
+        }
+        usize::from(self.labels()) <= count
+    }
+
     fn verify_signed_data(
         &self,
         dnskey: &Dnskey<impl AsRef<[u8]>>,
@@ -313,20 +349,592 @@ impl<Octets: AsRef<[u8]>, TN: ToName> RrsigExt for Rrsig<Octets, TN> {
 }
 
 /// Report whether an algorithm is supported or not.
+///
+/// An algorithm is only supported if the cryptographic backend can
+/// actually verify signatures made with it.
 // This needs to match the algorithms supported in signed_data.
 pub fn supported_algorithm(a: &SecurityAlgorithm) -> bool {
-    *a == SecurityAlgorithm::RSASHA1
+    (*a == SecurityAlgorithm::RSASHA1
         || *a == SecurityAlgorithm::RSASHA1_NSEC3_SHA1
         || *a == SecurityAlgorithm::RSASHA256
         || *a == SecurityAlgorithm::RSASHA512
-        || *a == SecurityAlgorithm::ECDSAP256SHA256
+        || *a == SecurityAlgorithm::ECDSAP256SHA256)
+        && supports_algorithm(*a)
+}
+
+//------------ check_signatures ----------------------------------------------
+
+/// The result of checking a single signature with [`check_signatures`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignatureCheck {
+    /// The key tag of the signature.
+    pub key_tag: u16,
+
+    /// The algorithm of the signature.
+    pub algorithm: SecurityAlgorithm,
+
+    /// The index of the key that validates the signature, if any.
+    pub key: Option<usize>,
+
+    /// The outcome of the check.
+    pub status: SignatureStatus,
+}
+
+/// The outcome of checking a single signature.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SignatureStatus {
+    /// The signature is valid.
+    Valid,
+
+    /// The signature does not cover the RRset.
+    ///
+    /// Either the owner, class or type covered of the signature do not
+    /// match the RRset, the signer is not a parent of the owner, or the
+    /// labels field is larger than the number of labels of the owner.
+    Mismatch,
+
+    /// The expiration time of the signature has passed.
+    Expired,
+
+    /// The inception time of the signature has not been reached yet.
+    NotYetValid,
+
+    /// None of the keys matches the signer, algorithm and key tag.
+    NoKey,
+
+    /// The algorithm or the matching key is not supported.
+    Unsupported,
+
+    /// The cryptographic check failed for all matching keys.
+    Invalid,
+}
+
+/// Check all signatures of an RRset and report the result of each one.
+///
+/// This performs the checks of
+/// [RFC 4035, Section 5.3](https://www.rfc-editor.org/rfc/rfc4035.html#section-5.3)
+/// for every signature in `rrsigs` using the candidate `keys` and returns
+/// one [`SignatureCheck`] per signature in the same order. The field `key`
+/// of a valid signature is the index into `keys` of the key that was
+/// used. Unlike validation, all signatures are checked, which makes this
+/// useful for finding out why an RRset failed to validate.
+#[allow(clippy::type_complexity)]
+pub fn check_signatures<D>(
+    rrset: &[Record<Name<Bytes>, D>],
+    rrsigs: &[Record<Name<Bytes>, Rrsig<Bytes, Name<Bytes>>>],
+    keys: &[Record<Name<Bytes>, Dnskey<Bytes>>],
+) -> Vec<SignatureCheck>
+where
+    D: RecordData + CanonicalOrd + ComposeRecordData + Clone,
+{
+    rrsigs
+        .iter()
+        .map(|sig| {
+            let rrsig = sig.data();
+            let (key, status) = check_signature(rrset, sig, keys);
+            SignatureCheck {
+                key_tag: rrsig.key_tag(),
+                algorithm: rrsig.algorithm(),
+                key,
+                status,
+            }
+        })
+        .collect()
+}
+
+/// Check a single signature for [`check_signatures`].
+fn check_signature<D>(
+    rrset: &[Record<Name<Bytes>, D>],
+    sig: &Record<Name<Bytes>, Rrsig<Bytes, Name<Bytes>>>,
+    keys: &[Record<Name<Bytes>, Dnskey<Bytes>>],
+) -> (Option<usize>, SignatureStatus)
+where
+    D: RecordData + CanonicalOrd + ComposeRecordData + Clone,
+{
+    let rrsig = sig.data();
+    let Some(first) = rrset.first() else {
+        return (None, SignatureStatus::Mismatch);
+    };
+    if let Err(status) = check_rrsig_for_rrset(
+        sig,
+        first.owner(),
+        first.class(),
+        first.rtype(),
+        rrsig.signer_name(),
+        Timestamp::now(),
+    ) {
+        return (None, status);
+    }
+
+    let mut candidates = keys
+        .iter()
+        .enumerate()
+        .filter(|(_, key)| {
+            rrsig_matches_key(
+                rrsig,
+                rrsig.signer_name(),
+                key.data(),
+                key.owner(),
+                key.data().key_tag(),
+            )
+        })
+        .peekable();
+    if candidates.peek().is_none() {
+        return (None, SignatureStatus::NoKey);
+    }
+
+    let mut records = rrset.to_vec();
+    let mut signed_data = Vec::<u8>::new();
+    rrsig
+        .signed_data(&mut signed_data, &mut records)
+        .expect("infallible");
+
+    let mut status = SignatureStatus::Invalid;
+    for (index, key) in candidates {
+        match rrsig.verify_signed_data(key.data(), &signed_data) {
+            Ok(()) => return (Some(index), SignatureStatus::Valid),
+            Err(AlgorithmError::Unsupported) => {
+                status = SignatureStatus::Unsupported
+            }
+            Err(_) => (),
+        }
+    }
+    (None, status)
+}
+
+/// Check that a signature belongs to an RRset and is currently valid.
+///
+/// These are the checks of
+/// [RFC 4035, Section 5.3.1](https://www.rfc-editor.org/rfc/rfc4035.html#section-5.3.1)
+/// that do not involve the key. The RRset is given by its `owner`,
+/// `class` and `rtype`, `signer_name` is the zone assumed to have made
+/// the signature.
+pub(crate) fn check_rrsig_for_rrset(
+    sig: &Record<Name<Bytes>, Rrsig<Bytes, Name<Bytes>>>,
+    owner: &Name<Bytes>,
+    class: Class,
+    rtype: Rtype,
+    signer_name: &Name<Bytes>,
+    now: Timestamp,
+) -> Result<(), SignatureStatus> {
+    let rrsig = sig.data();
+
+    // - The RRSIG RR and the RRset MUST have the same owner name and the
+    //   same class.
+    if sig.owner() != owner || sig.class() != class {
+        return Err(SignatureStatus::Mismatch);
+    }
+
+    // - The RRSIG RR's Signer's Name field MUST be the name of the zone
+    //   that contains the RRset.
+
+    // We don't really know the name of the zone that contains an RRset.
+    // What we can do is check that the signer's name is a suffix of the
+    // owner name. We assume that a zone will not sign things in space that
+    // is delegated (except for the parent side of the delegation).
+    if !owner.ends_with(signer_name) {
+        return Err(SignatureStatus::Mismatch);
+    }
+
+    // - The RRSIG RR's Type Covered field MUST equal the RRset's type.
+    if rrsig.type_covered() != rtype {
+        return Err(SignatureStatus::Mismatch);
+    }
+
+    // - The number of labels in the RRset owner name MUST be greater than
+    //   or equal to the value in the RRSIG RR's Labels field.
+    if !rrsig.labels_valid_for(owner) {
+        return Err(SignatureStatus::Mismatch);
+    }
+
+    // - The validator's notion of the current time MUST be less than or
+    //   equal to the time listed in the RRSIG RR's Expiration field.
+    // - The validator's notion of the current time MUST be greater than or
+    //   equal to the time listed in the RRSIG RR's Inception field.
+    if now.canonical_gt(&rrsig.expiration()) {
+        return Err(SignatureStatus::Expired);
+    }
+    if now.canonical_lt(&rrsig.inception()) {
+        return Err(SignatureStatus::NotYetValid);
+    }
+    Ok(())
+}
+
+/// Check that a signature can have been made by a key.
+///
+/// These are the checks of
+/// [RFC 4035, Section 5.3.1](https://www.rfc-editor.org/rfc/rfc4035.html#section-5.3.1)
+/// that involve the key. The key is given by its `key_name` and
+/// `key_tag` in addition to its data.
+pub(crate) fn rrsig_matches_key(
+    rrsig: &Rrsig<Bytes, Name<Bytes>>,
+    signer_name: &Name<Bytes>,
+    key: &Dnskey<Bytes>,
+    key_name: &Name<Bytes>,
+    key_tag: u16,
+) -> bool {
+    // - The RRSIG RR's Signer's Name, Algorithm, and Key Tag fields MUST
+    //   match the owner name, algorithm, and key tag for some DNSKEY RR in
+    //   the zone's apex DNSKEY RRset.
+    // - The matching DNSKEY RR MUST be present in the zone's apex DNSKEY
+    //   RRset, and MUST have the Zone Flag bit (DNSKEY RDATA Flag bit 7)
+    //   set.
+
+    // We cannot check here if the key is in the zone's apex, that is up to
+    // the caller. Just check the Zone Flag bit.
+    signer_name == key_name
+        && rrsig.algorithm() == key.algorithm()
+        && rrsig.key_tag() == key_tag
+        && key.is_zone_key()
+}
+
+//------------ verify_zone_signatures ----------------------------------------
+
+/// A problem found by [`verify_zone_signatures`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ZoneProblem {
+    /// The zone has no SOA record, so its apex is unknown.
+    MissingSoa,
+
+    /// There is no DNSKEY RRset at the apex.
+    MissingDnskey,
+
+    /// The apex DNSKEY RRset is not signed by one of its own keys.
+    DnskeyNotSelfSigned,
+
+    /// An RRset that needs to be signed has no signatures.
+    MissingRrsig {
+        /// The owner name of the RRset.
+        owner: Name<Bytes>,
+
+        /// The type of the RRset.
+        rtype: Rtype,
+    },
+
+    /// None of the signatures of an RRset is valid.
+    InvalidRrsig {
+        /// The owner name of the RRset.
+        owner: Name<Bytes>,
+
+        /// The type of the RRset.
+        rtype: Rtype,
+
+        /// The result of checking each signature.
+        checks: Vec<SignatureCheck>,
+    },
+
+    /// The zone has neither NSEC records nor an NSEC3PARAM record.
+    MissingDenial,
+
+    /// A name in the zone is not covered by the NSEC or NSEC3 chain.
+    MissingNsec {
+        /// The name without an NSEC or NSEC3 record.
+        owner: Name<Bytes>,
+    },
+
+    /// The next field of an NSEC or NSEC3 record does not refer to the
+    /// next record in the chain.
+    BrokenChain {
+        /// The owner name of the NSEC or NSEC3 record.
+        owner: Name<Bytes>,
+    },
+}
+
+/// The records of a zone by owner name and type.
+type ZoneRrsets = BTreeMap<
+    (Name<Bytes>, Rtype),
+    Vec<Record<Name<Bytes>, ZoneRecordData<Bytes, Name<Bytes>>>>,
+>;
+
+/// Check that a zone is completely and correctly signed.
+///
+/// The zone is given as its records, including the DNSSEC records, in any
+/// order. The function checks that
+///
+/// * every authoritative RRset has a valid signature made with one of the
+///   keys in `dnskeys` that belongs to the apex,
+/// * the apex DNSKEY RRset is signed by one of its own keys, and
+/// * every name in the zone is part of a complete NSEC chain or, if the
+///   zone has an NSEC3PARAM record, has an NSEC3 record in a complete NSEC3
+///   chain.
+///
+/// Records below a zone cut are not authoritative and are ignored. At a
+/// zone cut only DS and NSEC RRsets need to be signed. With NSEC3 opt-out,
+/// insecure delegations don’t need an NSEC3 record.
+///
+/// Signatures are checked against the current time. All problems found are
+/// returned. An empty list means the zone is properly signed.
+#[allow(clippy::type_complexity)]
+pub fn verify_zone_signatures(
+    zone: &[Record<Name<Bytes>, ZoneRecordData<Bytes, Name<Bytes>>>],
+    dnskeys: &[Record<Name<Bytes>, Dnskey<Bytes>>],
+) -> Vec<ZoneProblem> {
+    let mut problems = Vec::new();
+
+    let Some(apex) = zone
+        .iter()
+        .find(|record| record.rtype() == Rtype::SOA)
+        .map(|record| record.owner().clone())
+    else {
+        problems.push(ZoneProblem::MissingSoa);
+        return problems;
+    };
+
+    // Collect the RRsets and their signatures.
+    let mut rrsets = ZoneRrsets::new();
+    let mut rrsigs = BTreeMap::<_, Vec<_>>::new();
+    for record in zone {
+        if !record.owner().ends_with(&apex) {
+            continue;
+        }
+        if let ZoneRecordData::Rrsig(rrsig) = record.data() {
+            rrsigs
+                .entry((record.owner().clone(), rrsig.type_covered()))
+                .or_default()
+                .push(Record::new(
+                    record.owner().clone(),
+                    record.class(),
+                    record.ttl(),
+                    rrsig.clone(),
+                ));
+        } else {
+            rrsets
+                .entry((record.owner().clone(), record.rtype()))
+                .or_default()
+                .push(record.clone());
+        }
+    }
+
+    let cuts: Vec<&Name<Bytes>> = rrsets
+        .keys()
+        .filter(|(owner, rtype)| *rtype == Rtype::NS && *owner != apex)
+        .map(|(owner, _)| owner)
+        .collect();
+    let below_cut = |name: &Name<Bytes>| {
+        cuts.iter().any(|cut| name != *cut && name.ends_with(*cut))
+    };
+
+    // Check the signatures of all authoritative RRsets.
+    let keys: Vec<_> = dnskeys
+        .iter()
+        .filter(|key| *key.owner() == apex)
+        .cloned()
+        .collect();
+    for ((owner, rtype), rrset) in &rrsets {
+        if below_cut(owner)
+            || (cuts.contains(&owner)
+                && *rtype != Rtype::DS
+                && *rtype != Rtype::NSEC)
+        {
+            continue;
+        }
+        let sigs = match rrsigs.get(&(owner.clone(), *rtype)) {
+            Some(sigs) => sigs.as_slice(),
+            None => &[],
+        };
+        if sigs.is_empty() {
+            problems.push(ZoneProblem::MissingRrsig {
+                owner: owner.clone(),
+                rtype: *rtype,
+            });
+            continue;
+        }
+        let checks = check_signatures(rrset, sigs, &keys);
+        if !checks
+            .iter()
+            .any(|check| check.status == SignatureStatus::Valid)
+        {
+            problems.push(ZoneProblem::InvalidRrsig {
+                owner: owner.clone(),
+                rtype: *rtype,
+                checks,
+            });
+        }
+    }
+
+    // Check that the apex DNSKEY RRset is self-signed.
+    match rrsets.get(&(apex.clone(), Rtype::DNSKEY)) {
+        Some(rrset) => {
+            let zone_keys: Vec<_> = rrset
+                .iter()
+                .filter_map(|record| match record.data() {
+                    ZoneRecordData::Dnskey(key) => Some(Record::new(
+                        record.owner().clone(),
+                        record.class(),
+                        record.ttl(),
+                        key.clone(),
+                    )),
+                    _ => None,
+                })
+                .collect();
+            let sigs = match rrsigs.get(&(apex.clone(), Rtype::DNSKEY)) {
+                Some(sigs) => sigs.as_slice(),
+                None => &[],
+            };
+            if !check_signatures(rrset, sigs, &zone_keys)
+                .iter()
+                .any(|check| check.status == SignatureStatus::Valid)
+            {
+                problems.push(ZoneProblem::DnskeyNotSelfSigned);
+            }
+        }
+        None => problems.push(ZoneProblem::MissingDnskey),
+    }
+
+    // Check the NSEC or NSEC3 chain. NSEC3 records have hashed owner names
+    // that aren't part of the zone's names.
+    let names: BTreeSet<Name<Bytes>> = rrsets
+        .keys()
+        .filter(|(owner, rtype)| *rtype != Rtype::NSEC3 && !below_cut(owner))
+        .map(|(owner, _)| owner.clone())
+        .collect();
+    let nsec3param = rrsets
+        .get(&(apex.clone(), Rtype::NSEC3PARAM))
+        .and_then(|rrset| rrset.first())
+        .and_then(|record| match record.data() {
+            ZoneRecordData::Nsec3param(param) => Some(param),
+            _ => None,
+        });
+    if rrsets.keys().any(|(_, rtype)| *rtype == Rtype::NSEC) {
+        check_nsec_chain(&names, &rrsets, &mut problems);
+    } else if let Some(param) = nsec3param {
+        let secure_names = names.iter().filter(|name| {
+            !cuts.contains(name)
+                || rrsets.contains_key(&((*name).clone(), Rtype::DS))
+        });
+        let opt_out = rrsets.iter().any(|((_, rtype), rrset)| {
+            *rtype == Rtype::NSEC3
+                && rrset.iter().any(|record| match record.data() {
+                    ZoneRecordData::Nsec3(nsec3) => nsec3.opt_out(),
+                    _ => false,
+                })
+        });
+        let expected: Vec<&Name<Bytes>> = if opt_out {
+            secure_names.collect()
+        } else {
+            names.iter().collect()
+        };
+        check_nsec3_chain(&apex, &expected, &rrsets, param, &mut problems);
+    } else {
+        problems.push(ZoneProblem::MissingDenial);
+    }
+
+    problems
+}
+
+/// Check the NSEC chain for [`verify_zone_signatures`].
+///
+/// Every name needs an NSEC record that refers to the next name in
+/// canonical order, with the last one referring back to the apex.
+fn check_nsec_chain(
+    names: &BTreeSet<Name<Bytes>>,
+    rrsets: &ZoneRrsets,
+    problems: &mut Vec<ZoneProblem>,
+) {
+    let names: Vec<_> = names.iter().collect();
+    for (i, name) in names.iter().enumerate() {
+        let next = names[(i + 1) % names.len()];
+        let nsec = rrsets
+            .get(&((*name).clone(), Rtype::NSEC))
+            .and_then(|rrset| rrset.first())
+            .and_then(|record| match record.data() {
+                ZoneRecordData::Nsec(nsec) => Some(nsec),
+                _ => None,
+            });
+        match nsec {
+            Some(nsec) => {
+                if nsec.next_name() != next {
+                    problems.push(ZoneProblem::BrokenChain {
+                        owner: (*name).clone(),
+                    });
+                }
+            }
+            None => problems.push(ZoneProblem::MissingNsec {
+                owner: (*name).clone(),
+            }),
+        }
+    }
+}
+
+/// Check the NSEC3 chain for [`verify_zone_signatures`].
+///
+/// The NSEC3 records, ordered by their owner hashes, need to refer to each
+/// other in a loop. The hash of every name in `names` and of the empty
+/// non-terminals above them needs to have an NSEC3 record.
+fn check_nsec3_chain(
+    apex: &Name<Bytes>,
+    names: &[&Name<Bytes>],
+    rrsets: &ZoneRrsets,
+    param: &Nsec3param<Bytes>,
+    problems: &mut Vec<ZoneProblem>,
+) {
+    let mut chain = BTreeMap::new();
+    for ((owner, _), rrset) in rrsets
+        .iter()
+        .filter(|((_, rtype), _)| *rtype == Rtype::NSEC3)
+    {
+        let hash = if owner.parent().as_ref() == Some(apex) {
+            std::str::from_utf8(owner.first().as_slice())
+                .ok()
+                .and_then(|label| base32::decode_hex::<Vec<u8>>(label).ok())
+        } else {
+            None
+        };
+        let nsec3 = rrset.first().and_then(|record| match record.data() {
+            ZoneRecordData::Nsec3(nsec3) => Some(nsec3),
+            _ => None,
+        });
+        match (hash, nsec3) {
+            (Some(hash), Some(nsec3)) => {
+                chain.insert(hash, (owner, nsec3));
+            }
+            _ => problems.push(ZoneProblem::BrokenChain {
+                owner: owner.clone(),
+            }),
+        }
+    }
+
+    let links: Vec<_> = chain.iter().collect();
+    for (i, (_, (owner, nsec3))) in links.iter().enumerate() {
+        let next = links[(i + 1) % links.len()].0;
+        if nsec3.next_owner().as_slice() != next.as_slice() {
+            problems.push(ZoneProblem::BrokenChain {
+                owner: (*owner).clone(),
+            });
+        }
+    }
+
+    // Add the empty non-terminals between the names and the apex.
+    let mut expected = BTreeSet::new();
+    for name in names {
+        let mut name = (*name).clone();
+        while expected.insert(name.clone()) && name != *apex {
+            match name.parent() {
+                Some(parent) => name = parent,
+                None => break,
+            }
+        }
+    }
+
+    for name in expected {
+        let hash = nsec3_hash::<_, _, Vec<u8>>(
+            &name,
+            param.hash_algorithm(),
+            param.iterations(),
+            param.salt(),
+        );
+        match hash {
+            Ok(hash) if chain.contains_key(hash.as_slice()) => (),
+            _ => problems.push(ZoneProblem::MissingNsec { owner: name }),
+        }
+    }
 }
 
 //============ Test ==========================================================
 
 #[cfg(test)]
 #[cfg(feature = "std")]
-mod test {
+pub(super) mod test {
     use super::*;
     use crate::base::iana::{Class, Rtype, SecurityAlgorithm};
     use crate::base::scan::{IterScanner, Scanner};
@@ -334,9 +942,11 @@ mod test {
     use crate::dnssec::common::parse_from_bind;
     use crate::rdata::dnssec::Timestamp;
     use crate::rdata::{Mx, ZoneRecordData};
-    use crate::utils::base64;
+    use crate::utils::{base16, base64};
 
+    use mock_instant::thread_local::MockClock;
     use std::str::FromStr;
+    use std::time::Duration;
 
     type Dnskey = crate::rdata::Dnskey<Vec<u8>>;
     type Ds = crate::rdata::Ds<Vec<u8>>;
@@ -371,6 +981,34 @@ mod test {
         )
     }
 
+    // The signature of the root KSK over the root DNSKEY RRset, valid from
+    // 1558396800 until 1560211200.
+    pub(in crate::dnssec::validator) const ROOT_DNSKEY_SIGNATURE: &str = "otBkINZAQu7AvPKjr/xWIEE7+SoZtKgF8bzVynX6bfJMJuPay8jPvNmwXkZOdSoYlvFp0bk9JWJKCh8y5uoNfMFkN6OSrDkr3t0E+c8c0Mnmwkk5CETH3Gqxthi0yyRX5T4VlHU06/Ks4zI+XAgl3FBpOc554ivdzez8YCjAIGx7XgzzooEb7heMSlLc7S7/HNjw51TPRs4RxrAVcezieKCzPPpeWBhjE6R3oiSwrl0SBD4/yplrDlr7UHs/Atcm3MSgemdyr2sOoOUkVQCVpcj3SQQezoD2tCM7861CXEQdg5fjeHDtz285xHt5HJpA5cOcctRo4ihybfow/+V7AQ==";
+
+    // Returns the root KSK/ZSK as DNSKEY records with a TTL of 172800.
+    pub(in crate::dnssec::validator) fn root_dnskey_records(
+    ) -> Vec<Record<crate::base::Name<Bytes>, crate::rdata::Dnskey<Bytes>>>
+    {
+        let (ksk, zsk) = root_pubkey();
+        [ksk, zsk]
+            .into_iter()
+            .map(|key| {
+                Record::new(
+                    crate::base::Name::root(),
+                    Class::IN,
+                    Ttl::from_secs(172800),
+                    crate::rdata::Dnskey::new(
+                        key.flags(),
+                        key.protocol(),
+                        key.algorithm(),
+                        Bytes::from(key.public_key().clone()),
+                    )
+                    .unwrap(),
+                )
+            })
+            .collect()
+    }
+
     // Returns the current net KSK/ZSK for testing (1024b)
     fn net_pubkey() -> (Dnskey, Dnskey) {
         let ksk = base64::decode::<Vec<u8>>(
@@ -410,6 +1048,30 @@ mod test {
         );
     }
 
+    #[test]
+    fn ds_from_dnskey() {
+        let (dnskey, _) = root_pubkey();
+        let expected = Ds::new(
+            20326,
+            SecurityAlgorithm::RSASHA256,
+            DigestAlgorithm::SHA256,
+            base16::decode_vec(
+                "E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            Ds::from_dnskey(&Name::root(), &dnskey, DigestAlgorithm::SHA256)
+                .unwrap(),
+            expected
+        );
+        assert!(matches!(
+            Ds::from_dnskey(&Name::root(), &dnskey, DigestAlgorithm::GOST),
+            Err(AlgorithmError::Unsupported)
+        ));
+    }
+
     #[test]
     fn rrsig_verify_rsa_sha256() {
         // Test 2048b long key
@@ -423,11 +1085,9 @@ mod test {
             1558396800.into(),
             20326,
             Name::root(),
-            base64::decode::<Vec<u8>>(
-                "otBkINZAQu7AvPKjr/xWIEE7+SoZtKgF8bzVynX6bfJMJuPay8jPvNmwXkZOdSoYlvFp0bk9JWJKCh8y5uoNfMFkN6OSrDkr3t0E+c8c0Mnmwkk5CETH3Gqxthi0yyRX5T4VlHU06/Ks4zI+XAgl3FBpOc554ivdzez8YCjAIGx7XgzzooEb7heMSlLc7S7/HNjw51TPRs4RxrAVcezieKCzPPpeWBhjE6R3oiSwrl0SBD4/yplrDlr7UHs/Atcm3MSgemdyr2sOoOUkVQCVpcj3SQQezoD2tCM7861CXEQdg5fjeHDtz285xHt5HJpA5cOcctRo4ihybfow/+V7AQ==",
-            )
-            .unwrap()
-        ).unwrap();
+            base64::decode::<Vec<u8>>(ROOT_DNSKEY_SIGNATURE).unwrap(),
+        )
+        .unwrap();
         rrsig_verify_dnskey(ksk, zsk, rrsig);
 
         // Test 1024b long key
@@ -748,4 +1408,220 @@ mod test {
             assert_eq!(key_ds, ds);
         }
     }
+
+    #[test]
+    fn check_signatures_per_signature() {
+        MockClock::set_system_time(Duration::from_secs(1559000000));
+
+        let root = crate::base::Name::<Bytes>::root();
+        let ttl = Ttl::from_secs(172800);
+        let keys = root_dnskey_records();
+        let signature =
+            base64::decode::<Bytes>(ROOT_DNSKEY_SIGNATURE).unwrap();
+        let rrsig = |expiration: u32, inception: u32| {
+            Record::new(
+                root.clone(),
+                Class::IN,
+                ttl,
+                crate::rdata::Rrsig::new(
+                    Rtype::DNSKEY,
+                    SecurityAlgorithm::RSASHA256,
+                    0,
+                    ttl,
+                    expiration.into(),
+                    inception.into(),
+                    20326,
+                    root.clone(),
+                    signature.clone(),
+                )
+                .unwrap(),
+            )
+        };
+        let rrsigs =
+            [rrsig(1560211200, 1558396800), rrsig(1558396800, 1556582400)];
+
+        let checks = check_signatures(&keys, &rrsigs, &keys);
+        assert_eq!(
+            checks,
+            [
+                SignatureCheck {
+                    key_tag: 20326,
+                    algorithm: SecurityAlgorithm::RSASHA256,
+                    key: Some(0),
+                    status: SignatureStatus::Valid,
+                },
+                SignatureCheck {
+                    key_tag: 20326,
+                    algorithm: SecurityAlgorithm::RSASHA256,
+                    key: None,
+                    status: SignatureStatus::Expired,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rrsig_labels_exceeding_owner() {
+        MockClock::set_system_time(Duration::from_secs(1559000000));
+
+        let rrsig = |labels: u8| {
+            crate::rdata::Rrsig::new(
+                Rtype::A,
+                SecurityAlgorithm::RSASHA256,
+                labels,
+                Ttl::from_secs(3600),
+                1560211200u32.into(),
+                1558396800u32.into(),
+                20326,
+                Name::root(),
+                Vec::new(),
+            )
+            .unwrap()
+        };
+        let owner = Name::from_str("www.example.").unwrap();
+        let wildcard = Name::from_str("*.example.").unwrap();
+        assert!(rrsig(1).labels_valid_for(&owner));
+        assert!(rrsig(2).labels_valid_for(&owner));
+        assert!(!rrsig(3).labels_valid_for(&owner));
+        assert!(rrsig(1).labels_valid_for(&wildcard));
+        assert!(!rrsig(2).labels_valid_for(&wildcard));
+        assert!(rrsig(0).labels_valid_for(&Name::root()));
+        assert!(!rrsig(1).labels_valid_for(&Name::root()));
+
+        // A signature whose labels exceed those of the owner is rejected
+        // before any key is tried.
+        let root = crate::base::Name::<Bytes>::root();
+        let ttl = Ttl::from_secs(172800);
+        let keys = root_dnskey_records();
+        let rrsigs = [Record::new(
+            root.clone(),
+            Class::IN,
+            ttl,
+            crate::rdata::Rrsig::new(
+                Rtype::DNSKEY,
+                SecurityAlgorithm::RSASHA256,
+                1,
+                ttl,
+                1560211200u32.into(),
+                1558396800u32.into(),
+                20326,
+                root.clone(),
+                Bytes::new(),
+            )
+            .unwrap(),
+        )];
+        let checks = check_signatures(&keys, &rrsigs, &keys);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].status, SignatureStatus::Mismatch);
+    }
+
+    type ZoneRecord = Record<
+        crate::base::Name<Bytes>,
+        ZoneRecordData<Bytes, crate::base::Name<Bytes>>,
+    >;
+
+    /// Loads a zone and returns its records and apex DNSKEY records.
+    #[allow(clippy::type_complexity)]
+    fn load_signed_zone(
+        mut zonefile: &[u8],
+    ) -> (
+        Vec<ZoneRecord>,
+        Vec<Record<crate::base::Name<Bytes>, crate::rdata::Dnskey<Bytes>>>,
+    ) {
+        use crate::base::name::FlattenInto;
+        use crate::zonefile::inplace::{Entry, Zonefile};
+
+        let mut records = Vec::new();
+        for entry in Zonefile::load(&mut zonefile).unwrap() {
+            if let Entry::Record(record) = entry.unwrap() {
+                records.push(record.flatten_into());
+            }
+        }
+        let dnskeys = records
+            .iter()
+            .filter_map(|record: &ZoneRecord| match record.data() {
+                ZoneRecordData::Dnskey(key) => Some(Record::new(
+                    record.owner().clone(),
+                    record.class(),
+                    record.ttl(),
+                    key.clone(),
+                )),
+                _ => None,
+            })
+            .collect();
+        (records, dnskeys)
+    }
+
+    #[test]
+    fn verify_signed_zone() {
+        MockClock::set_system_time(Duration::from_secs(1490000000));
+
+        let (zone, dnskeys) = load_signed_zone(include_bytes!(
+            "../../../test-data/zonefiles/signed-nsec.zone"
+        ));
+        assert_eq!(
+            verify_zone_signatures(&zone, &dnskeys),
+            Vec::<ZoneProblem>::new()
+        );
+
+        let (zone, dnskeys) = load_signed_zone(include_bytes!(
+            "../../../test-data/zonefiles/signed-nsec3.zone"
+        ));
+        assert_eq!(
+            verify_zone_signatures(&zone, &dnskeys),
+            Vec::<ZoneProblem>::new()
+        );
+
+        // Once the signatures have expired, every signed RRset is reported.
+        MockClock::set_system_time(Duration::from_secs(1500000000));
+        let problems = verify_zone_signatures(&zone, &dnskeys);
+        assert!(problems.contains(&ZoneProblem::DnskeyNotSelfSigned));
+        assert!(problems.iter().all(|problem| matches!(
+            problem,
+            ZoneProblem::InvalidRrsig { .. }
+                | ZoneProblem::DnskeyNotSelfSigned
+        )));
+    }
+
+    #[test]
+    fn verify_zone_missing_rrsig() {
+        MockClock::set_system_time(Duration::from_secs(1490000000));
+
+        let www =
+            crate::base::Name::<Bytes>::from_str("www.example.").unwrap();
+        let (mut zone, dnskeys) = load_signed_zone(include_bytes!(
+            "../../../test-data/zonefiles/signed-nsec.zone"
+        ));
+        zone.retain(|record| {
+            !(*record.owner() == www && record.rtype() == Rtype::RRSIG)
+        });
+        assert_eq!(
+            verify_zone_signatures(&zone, &dnskeys),
+            [
+                ZoneProblem::MissingRrsig {
+                    owner: www.clone(),
+                    rtype: Rtype::A,
+                },
+                ZoneProblem::MissingRrsig {
+                    owner: www.clone(),
+                    rtype: Rtype::NSEC,
+                },
+            ]
+        );
+
+        // Without its NSEC record, www.example. breaks the chain.
+        zone.retain(|record| {
+            !(*record.owner() == www && record.rtype() == Rtype::NSEC)
+        });
+        assert_eq!(
+            verify_zone_signatures(&zone, &dnskeys),
+            [
+                ZoneProblem::MissingRrsig {
+                    owner: www.clone(),
+                    rtype: Rtype::A,
+                },
+                ZoneProblem::MissingNsec { owner: www },
+            ]
+        );
+    }
 }
@@ -0,0 +1,91 @@
+//! Limiting the amount of work done for a single validation.
+//!
+//! A carefully crafted response can make a validator do a lot of work:
+//! many signatures to verify, many upstream queries to walk long chains of
+//! delegations and many NSEC3 hashes to compute. To bound this, each
+//! validation runs with a [`Budget`] of work units. Every signature
+//! verification, upstream query and NSEC3 hash that is not served from a
+//! cache costs one unit.
+//!
+//! The budget is kept in a task local variable, so the functions that do
+//! the actual work can charge it through [`charge`] without having to pass
+//! it around. Work done outside of [`Budget::run`] is not limited.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+
+tokio::task_local! {
+    /// The budget of the validation running in the current task.
+    static BUDGET: Arc<Budget>;
+}
+
+//------------ Budget --------------------------------------------------------
+
+/// The work budget of a single validation.
+#[derive(Debug)]
+pub(crate) struct Budget {
+    /// The number of work units left.
+    remaining: AtomicU32,
+
+    /// Whether some work was refused because the budget was used up.
+    exceeded: AtomicBool,
+}
+
+impl Budget {
+    /// Creates a new budget with `units` work units.
+    pub(crate) fn new(units: u32) -> Arc<Self> {
+        Arc::new(Self {
+            remaining: AtomicU32::new(units),
+            exceeded: AtomicBool::new(false),
+        })
+    }
+
+    /// Runs `fut` with this budget.
+    pub(crate) async fn run<F: Future>(
+        self: &Arc<Self>,
+        fut: F,
+    ) -> F::Output {
+        BUDGET.scope(self.clone(), fut).await
+    }
+
+    /// Returns whether some work was refused.
+    pub(crate) fn exceeded(&self) -> bool {
+        self.exceeded.load(Ordering::Relaxed)
+    }
+
+    /// Takes one work unit from the budget.
+    ///
+    /// Returns `false` and marks the budget as exceeded if no units are
+    /// left.
+    fn take(&self) -> bool {
+        let res = self.remaining.fetch_update(
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+            |units| units.checked_sub(1),
+        );
+        if res.is_err() {
+            self.exceeded.store(true, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+}
+
+//------------ Functions -----------------------------------------------------
+
+/// Charges one work unit to the budget of the current validation.
+///
+/// Returns `false` if the work should not be done because the budget is
+/// used up. Returns `true` if there is no budget for the current task.
+pub(crate) fn charge() -> bool {
+    BUDGET.try_with(|budget| budget.take()).unwrap_or(true)
+}
+
+/// Returns whether the budget of the current validation is used up.
+///
+/// Results obtained after the budget ran out are incomplete and should not
+/// be cached.
+pub(crate) fn exceeded() -> bool {
+    BUDGET.try_with(|budget| budget.exceeded()).unwrap_or(false)
+}
@@ -5,7 +5,11 @@
 //! or evaluated results.
 
 use super::anchor::{TrustAnchor, TrustAnchors};
-use super::base::{supported_algorithm, supported_digest, DnskeyExt};
+use super::base::{
+    check_rrsig_for_rrset, rrsig_matches_key, supported_algorithm,
+    supported_digest, DnskeyExt, RrsigExt,
+};
+use super::budget::{self, Budget};
 use super::group::{Group, GroupSet, SigCache, ValidatedGroup};
 use super::nsec::{
     cached_nsec3_hash, nsec3_for_nodata, nsec3_for_nodata_wildcard,
@@ -21,20 +25,23 @@ use super::utilities::{
     get_soa_state, make_ede, map_maybe_secure, rebuild_msg,
     star_closest_encloser, ttl_for_sig,
 };
-use crate::base::iana::{ExtendedErrorCode, OptRcode};
+use crate::base::iana::{
+    DigestAlgorithm, ExtendedErrorCode, OptRcode, SecurityAlgorithm,
+};
 use crate::base::message::ShortMessage;
 use crate::base::name::{Chain, Label};
 use crate::base::opt::ExtendedError;
 use crate::base::{name, wire};
 use crate::base::{
     Message, MessageBuilder, Name, ParsedName, Record, RelativeName, Rtype,
-    ToName,
+    ToName, Ttl,
 };
 use crate::dep::octseq::{Octets, OctetsFrom, OctetsInto};
 use crate::net::client::request::{
     ComposeRequest, RequestMessage, SendRequest,
 };
-use crate::rdata::{AllRecordData, Dnskey, Ds, ZoneRecordData};
+use crate::rdata::dnssec::Timestamp;
+use crate::rdata::{AllRecordData, Cname, Dnskey, Ds, Rrsig, ZoneRecordData};
 use crate::utils::config::DefMinMax;
 use crate::zonefile::inplace;
 use bytes::Bytes;
@@ -47,6 +54,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::vec::Vec;
 use std::{error, fmt};
+use tracing::error;
 
 //----------- Config ---------------------------------------------------------
 
@@ -94,6 +102,14 @@ const MAX_BOGUS_VALIDITY: DefMinMax<Duration> = DefMinMax::new(
 /// most 1 key tag collision and we expect signatures to be valid.
 const MAX_BAD_SIGNATURES: DefMinMax<u8> = DefMinMax::new(1, 1, 8);
 
+/// Limit on the number of signatures that are tried during the validation
+/// of an RRset.
+///
+/// Legitimate RRsets rarely carry more than a few signatures, for example
+/// during an algorithm rollover or with multiple signers. The minimum is 1,
+/// the maximum is 64 and the default is 8.
+const MAX_SIGNATURES: DefMinMax<u8> = DefMinMax::new(8, 1, 64);
+
 /// Number of NSEC3 iterations above which the result is insecure.
 ///
 /// The minimum is 0, the maximum is 500, because
@@ -108,6 +124,13 @@ const NSEC3_ITER_INSECURE: DefMinMax<u16> = DefMinMax::new(100, 0, 500);
 /// for bogus, the default as recommended in RFC 9276 is 500.
 const NSEC3_ITER_BOGUS: DefMinMax<u16> = DefMinMax::new(500, 0, 500);
 
+/// Maximum number of NSEC3 records considered for a denial of existence.
+///
+/// A proof never needs more than three NSEC3 records, but a response can
+/// contain several proofs. The minimum is 3, the maximum is 1,000 and the
+/// default is 20.
+const MAX_NSEC3_RECORDS: DefMinMax<u16> = DefMinMax::new(20, 3, 1000);
+
 /// Maximum number of CNAME and DNAME records that are followed during
 /// validation.
 ///
@@ -115,6 +138,21 @@ const NSEC3_ITER_BOGUS: DefMinMax<u16> = DefMinMax::new(500, 0, 500);
 /// the default as used in unbound is 11.
 const MAX_CNAME_DNAME: DefMinMax<u8> = DefMinMax::new(11, 0, 100);
 
+/// Maximum number of labels below the trust anchor that names are
+/// validated for.
+///
+/// The minimum is 1, the maximum and default is 127, the number of labels
+/// in the longest possible domain name, which effectively means no limit.
+const MAX_DEPTH: DefMinMax<u8> = DefMinMax::new(127, 1, 127);
+
+/// Maximum number of work units spent on validating a single message.
+///
+/// Each signature verification, upstream query and NSEC3 hash that is not
+/// cached costs one unit. The minimum is 10, the maximum is 1,000,000 and
+/// the default is 1,000, which is far more than legitimate validations
+/// need.
+const MAX_WORK: DefMinMax<u32> = DefMinMax::new(1000, 10, 1_000_000);
+
 //------------ Config ---------------------------------------------------------
 
 /// Configuration of a validator.
@@ -141,6 +179,9 @@ pub struct Config {
     /// Limit on the number of signature validation failures
     max_bad_signatures: u8,
 
+    /// Limit on the number of signatures tried for an RRset.
+    max_signatures: u8,
+
     /// NSEC3 interation count above which the NSEC3 hash is not checked
     /// and the validation status is considered insecure.
     nsec3_iter_insecure: u16,
@@ -149,9 +190,38 @@ pub struct Config {
     /// and the validation status is considered bogus.
     nsec3_iter_bogus: u16,
 
+    /// Maximum number of NSEC3 records considered for a denial of
+    /// existence.
+    max_nsec3_records: u16,
+
     /// Maximum number of CNAME and DNAME records that are followed
     /// during validation.
     max_cname_dname: u8,
+
+    /// Maximum number of labels below the trust anchor that names are
+    /// validated for.
+    max_depth: u8,
+
+    /// Maximum number of work units spent on validating a single message.
+    max_work: u32,
+
+    /// What to do with NODATA and NXDOMAIN responses without a SOA record.
+    missing_soa: MissingSoaPolicy,
+
+    /// What to do with RRsets in a secure zone that have no signatures.
+    missing_rrsig: MissingRrsigPolicy,
+
+    /// What to do with responses that have records in the wrong section.
+    misplaced_record: MisplacedRecordPolicy,
+
+    /// Algorithms that are treated as unsupported.
+    disabled_algorithms: Vec<SecurityAlgorithm>,
+
+    /// Called for delegations without supported DS algorithms.
+    unsupported_algorithm_hook: Option<UnsupportedAlgorithmHook>,
+
+    /// Called whenever the validation of a message completes.
+    decision_hook: Option<DecisionHook>,
 }
 
 impl Config {
@@ -244,6 +314,23 @@ impl Config {
         self.max_bad_signatures = MAX_BAD_SIGNATURES.limit(value)
     }
 
+    /// Return the value of max_signatures.
+    pub(crate) fn max_signatures(&self) -> u8 {
+        self.max_signatures
+    }
+
+    /// Set the maximum number of signatures that are tried when validating
+    /// a single RRset.
+    ///
+    /// Signatures beyond this number are ignored. If none of the tried
+    /// signatures validates the RRset, the RRset is bogus.
+    ///
+    /// The value has to be at least one, at most 64 and the default is
+    /// eight.
+    pub fn set_max_signatures(&mut self, value: u8) {
+        self.max_signatures = MAX_SIGNATURES.limit(value)
+    }
+
     /// Return the value of nsec3_iter_insecure.
     pub(crate) fn nsec3_iter_insecure(&self) -> u16 {
         self.nsec3_iter_insecure
@@ -267,9 +354,26 @@ impl Config {
     /// considered bogus.
     ///
     /// The value has to be at least zero, at most five hundred and the
-    /// default is one five hundred.
+    /// default is five hundred.
     pub fn set_nsec3_iter_bogus(&mut self, value: u16) {
-        self.nsec3_iter_bogus = NSEC3_ITER_INSECURE.limit(value)
+        self.nsec3_iter_bogus = NSEC3_ITER_BOGUS.limit(value)
+    }
+
+    /// Return the value of max_nsec3_records.
+    pub(crate) fn max_nsec3_records(&self) -> u16 {
+        self.max_nsec3_records
+    }
+
+    /// Set the maximum number of NSEC3 records in a response that are
+    /// considered when proving the non-existence of a name or type.
+    ///
+    /// Responses with more NSEC3 records are bogus. This limits the
+    /// number of NSEC3 hashes a response can make the validator compute.
+    ///
+    /// The value has to be at least three, at most 1,000 and the default
+    /// is 20.
+    pub fn set_max_nsec3_records(&mut self, value: u16) {
+        self.max_nsec3_records = MAX_NSEC3_RECORDS.limit(value)
     }
 
     /// Return the value of max_cname_dname.
@@ -285,6 +389,149 @@ impl Config {
     pub fn set_max_cname_dname(&mut self, value: u8) {
         self.max_cname_dname = MAX_CNAME_DNAME.limit(value)
     }
+
+    /// Return the value of max_depth.
+    pub(crate) fn max_depth(&self) -> u8 {
+        self.max_depth
+    }
+
+    /// Set the maximum number of labels below the trust anchor that names
+    /// are validated for.
+    ///
+    /// Validating a name further below the trust anchor results in an
+    /// indeterminate state without querying for the delegations in between.
+    /// This limits the work done for deeply nested names.
+    ///
+    /// The value has to be at least one, at most 127 and the default is
+    /// 127, which effectively means no limit.
+    pub fn set_max_depth(&mut self, value: u8) {
+        self.max_depth = MAX_DEPTH.limit(value)
+    }
+
+    /// Return the value of max_work.
+    pub(crate) fn max_work(&self) -> u32 {
+        self.max_work
+    }
+
+    /// Set the maximum amount of work spent on validating a single message.
+    ///
+    /// Each signature verification, upstream query and NSEC3 hash that is
+    /// not served from a cache counts as one unit of work. A message that
+    /// needs more work than this is bogus.
+    ///
+    /// The value has to be at least 10, at most 1,000,000 and the default
+    /// is 1,000.
+    pub fn set_max_work(&mut self, value: u32) {
+        self.max_work = MAX_WORK.limit(value)
+    }
+
+    /// Return the value of missing_soa.
+    pub(crate) fn missing_soa_policy(&self) -> MissingSoaPolicy {
+        self.missing_soa
+    }
+
+    /// Set the validation result of NODATA and NXDOMAIN responses that
+    /// lack a SOA record in the authority section.
+    ///
+    /// The default is [`MissingSoaPolicy::Bogus`].
+    pub fn set_missing_soa_policy(&mut self, value: MissingSoaPolicy) {
+        self.missing_soa = value
+    }
+
+    /// Return the value of missing_rrsig.
+    pub(crate) fn missing_rrsig_policy(&self) -> MissingRrsigPolicy {
+        self.missing_rrsig
+    }
+
+    /// Set the validation result of RRsets in a secure zone that come
+    /// without any signatures.
+    ///
+    /// The default is [`MissingRrsigPolicy::Bogus`].
+    pub fn set_missing_rrsig_policy(&mut self, value: MissingRrsigPolicy) {
+        self.missing_rrsig = value
+    }
+
+    /// Return the value of misplaced_record.
+    pub(crate) fn misplaced_record_policy(&self) -> MisplacedRecordPolicy {
+        self.misplaced_record
+    }
+
+    /// Set the validation result of responses with records in a section
+    /// where they are not allowed.
+    ///
+    /// Such records are an OPT record outside the additional section and a
+    /// SOA record in the answer section of a response to a query for a
+    /// type other than SOA.
+    ///
+    /// The default is [`MisplacedRecordPolicy::Accept`].
+    pub fn set_misplaced_record_policy(
+        &mut self,
+        value: MisplacedRecordPolicy,
+    ) {
+        self.misplaced_record = value
+    }
+
+    /// Return whether an algorithm is supported and enabled.
+    pub(crate) fn algorithm_enabled(&self, alg: &SecurityAlgorithm) -> bool {
+        supported_algorithm(alg) && !self.disabled_algorithms.contains(alg)
+    }
+
+    /// Enable or disable a DNSSEC algorithm.
+    ///
+    /// A disabled algorithm is treated as if it was not supported. DS
+    /// records that use it are disregarded when determining the security
+    /// status of a zone, so a zone that is signed only with disabled
+    /// algorithms is insecure. This allows operators to stop trusting
+    /// deprecated algorithms such as RSASHA1 without rebuilding.
+    ///
+    /// Enabling an algorithm that is not supported by the validator has no
+    /// effect. By default, all supported algorithms are enabled.
+    pub fn set_algorithm_enabled(
+        &mut self,
+        alg: SecurityAlgorithm,
+        enabled: bool,
+    ) {
+        if enabled {
+            self.disabled_algorithms.retain(|item| *item != alg);
+        } else if !self.disabled_algorithms.contains(&alg) {
+            self.disabled_algorithms.push(alg);
+        }
+    }
+
+    /// Set a function that is called for delegations that are treated as
+    /// insecure because none of their DS records use a supported algorithm.
+    ///
+    /// Such delegations are not reported as errors, the extended error of
+    /// the result merely mentions the lack of a supported algorithm. The
+    /// function allows operators to keep track of the zones that cannot be
+    /// validated due to missing algorithm support, for instance during an
+    /// algorithm transition.
+    ///
+    /// By default, no function is set.
+    pub fn set_unsupported_algorithm_hook(
+        &mut self,
+        hook: impl Fn(&UnsupportedDelegation) + Send + Sync + 'static,
+    ) {
+        self.unsupported_algorithm_hook =
+            Some(UnsupportedAlgorithmHook(Arc::new(hook)))
+    }
+
+    /// Set a function that is called whenever the validation of a message
+    /// completes.
+    ///
+    /// The function receives the question of the message together with the
+    /// validation state and the extended error of the result. This allows
+    /// applications to log validation results or collect metrics about
+    /// them. It is not called for messages without a question or if
+    /// validation fails with an error.
+    ///
+    /// By default, no function is set.
+    pub fn set_decision_hook(
+        &mut self,
+        hook: impl Fn(&ValidationDecision) + Send + Sync + 'static,
+    ) {
+        self.decision_hook = Some(DecisionHook(Arc::new(hook)))
+    }
 }
 
 impl Default for Config {
@@ -297,13 +544,142 @@ impl Default for Config {
             max_node_validity: MAX_NODE_VALIDITY.default(),
             max_bogus_validity: MAX_BOGUS_VALIDITY.default(),
             max_bad_signatures: MAX_BAD_SIGNATURES.default(),
+            max_signatures: MAX_SIGNATURES.default(),
             nsec3_iter_insecure: NSEC3_ITER_INSECURE.default(),
             nsec3_iter_bogus: NSEC3_ITER_BOGUS.default(),
+            max_nsec3_records: MAX_NSEC3_RECORDS.default(),
             max_cname_dname: MAX_CNAME_DNAME.default(),
+            max_depth: MAX_DEPTH.default(),
+            max_work: MAX_WORK.default(),
+            missing_soa: MissingSoaPolicy::default(),
+            missing_rrsig: MissingRrsigPolicy::default(),
+            misplaced_record: MisplacedRecordPolicy::default(),
+            disabled_algorithms: Vec::new(),
+            unsupported_algorithm_hook: None,
+            decision_hook: None,
         }
     }
 }
 
+//------------ MissingSoaPolicy ----------------------------------------------
+
+/// How to treat NODATA and NXDOMAIN responses without a SOA record.
+///
+/// The SOA record in the authority section of a negative response tells
+/// the validator which zone the denial of existence records belong to.
+/// Without it, the response cannot be validated.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MissingSoaPolicy {
+    /// The response is bogus.
+    ///
+    /// This is the default.
+    #[default]
+    Bogus,
+
+    /// The response is insecure.
+    ///
+    /// This is more lenient towards broken authoritative servers but
+    /// means that the negative response is accepted without any proof.
+    Insecure,
+}
+
+//------------ MissingRrsigPolicy --------------------------------------------
+
+/// How to treat RRsets in a secure zone that have no signatures.
+///
+/// A secure zone is expected to have signatures on all authoritative
+/// RRsets. Missing signatures are a sign of an attack but can also happen
+/// while signing is being rolled out to the servers of a zone.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MissingRrsigPolicy {
+    /// The RRset is bogus.
+    ///
+    /// This is the default.
+    #[default]
+    Bogus,
+
+    /// The RRset is insecure.
+    ///
+    /// This is more lenient during partial rollouts of signing but means
+    /// that an attacker can strip the signatures from a response.
+    Insecure,
+}
+
+//------------ MisplacedRecordPolicy -----------------------------------------
+
+/// How to treat responses with records in the wrong section.
+///
+/// An OPT record is only allowed in the additional section and a SOA record
+/// only appears in the answer section if it was asked for. Anything else is
+/// a protocol violation by the upstream server.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MisplacedRecordPolicy {
+    /// The misplaced records are ignored.
+    ///
+    /// This is the default.
+    #[default]
+    Accept,
+
+    /// The response is bogus.
+    Bogus,
+}
+
+//------------ UnsupportedDelegation -----------------------------------------
+
+/// A delegation without any DS records using a supported algorithm.
+///
+/// See [`Config::set_unsupported_algorithm_hook`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnsupportedDelegation {
+    /// The name of the delegated zone.
+    pub name: Name<Bytes>,
+
+    /// The key and digest algorithms of the DS records of the delegation.
+    pub algorithms: Vec<(SecurityAlgorithm, DigestAlgorithm)>,
+}
+
+/// The function called for an [`UnsupportedDelegation`].
+#[derive(Clone)]
+struct UnsupportedAlgorithmHook(
+    Arc<dyn Fn(&UnsupportedDelegation) + Send + Sync>,
+);
+
+impl fmt::Debug for UnsupportedAlgorithmHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UnsupportedAlgorithmHook")
+    }
+}
+
+//------------ ValidationDecision --------------------------------------------
+
+/// The outcome of validating a message.
+///
+/// See [`Config::set_decision_hook`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationDecision {
+    /// The query name of the message.
+    pub name: Name<Bytes>,
+
+    /// The query type of the message.
+    pub rtype: Rtype,
+
+    /// The validation state of the message.
+    pub state: ValidationState,
+
+    /// The extended error explaining the state, if any.
+    pub ede: Option<ExtendedError<Vec<u8>>>,
+}
+
+/// The function called with a [`ValidationDecision`].
+#[derive(Clone)]
+struct DecisionHook(Arc<dyn Fn(&ValidationDecision) + Send + Sync>);
+
+impl fmt::Debug for DecisionHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DecisionHook")
+    }
+}
+
 //------------ ValidationContext ---------------------------------------------
 
 /// A DNSSEC validation context.
@@ -353,6 +729,21 @@ impl<Upstream> ValidationContext<Upstream> {
         }
     }
 
+    /// Return a summary of the cached DNSSEC state of a name, if any.
+    ///
+    /// This is meant for inspecting the cache when debugging. Only names
+    /// that are zone cuts, trust anchors or intermediate nodes that were
+    /// evaluated before are in the cache.
+    pub async fn cached_node(
+        &self,
+        name: &Name<Bytes>,
+    ) -> Option<NodeSummary> {
+        self.node_cache
+            .get(name)
+            .await
+            .map(|node| node.debug_summary())
+    }
+
     /// Validate a DNS reply message. An Error value will be returned if the
     /// message cannot be parsed or if there is any other message-related
     /// error.
@@ -375,6 +766,146 @@ impl<Upstream> ValidationContext<Upstream> {
         // Convert to Bytes.
         let bytes = Bytes::copy_from_slice(msg.as_slice());
         let bytes_msg = Message::from_octets(bytes)?;
+        self.validate_parsed_msg(bytes_msg, msg, &mut ProofDetails::default())
+            .await
+    }
+
+    /// Validate a DNS reply message and return details about the result.
+    ///
+    /// This is the same as [`validate_msg`][Self::validate_msg] except
+    /// that the result also describes how the state was reached. See
+    /// [`ValidationResult`] for the details that are provided.
+    pub async fn validate_msg_detailed<'a, MsgOcts, USOcts>(
+        &self,
+        msg: &'a mut Message<MsgOcts>,
+    ) -> Result<ValidationResult, Error>
+    where
+        MsgOcts: Clone + Debug + Octets + OctetsFrom<Vec<u8>> + 'a,
+        <MsgOcts as Octets>::Range<'a>: Debug,
+        USOcts:
+            AsRef<[u8]> + Debug + Octets + OctetsFrom<Vec<u8>> + Send + Sync,
+        Upstream: SendRequest<RequestMessage<USOcts>>,
+    {
+        let bytes = Bytes::copy_from_slice(msg.as_slice());
+        let bytes_msg = Message::from_octets(bytes)?;
+        let mut details = ProofDetails::default();
+        let (state, ede) = self
+            .validate_parsed_msg(bytes_msg, msg, &mut details)
+            .await?;
+        let chain = match &details.signer {
+            Some(signer) => self.zone_chain(signer).await,
+            None => Vec::new(),
+        };
+        Ok(ValidationResult {
+            state,
+            ede,
+            signers: details.signers,
+            wildcard: details.wildcard,
+            closest_encloser: details.closest_encloser,
+            chain,
+            negative_ttl: details.negative_ttl,
+        })
+    }
+
+    /// Validate a DNS reply message that is already backed by [`Bytes`].
+    ///
+    /// This is the same as [`validate_msg`][Self::validate_msg] except
+    /// that the message is not copied before validation. The records in
+    /// the message refer to the original octets instead. This avoids
+    /// temporarily doubling the memory used for large responses.
+    pub async fn validate_bytes_msg<USOcts>(
+        &self,
+        msg: &mut Message<Bytes>,
+    ) -> Result<(ValidationState, Option<ExtendedError<Vec<u8>>>), Error>
+    where
+        USOcts:
+            AsRef<[u8]> + Debug + Octets + OctetsFrom<Vec<u8>> + Send + Sync,
+        Upstream: SendRequest<RequestMessage<USOcts>>,
+    {
+        // Cloning a Bytes value only increments a reference count.
+        let bytes_msg = msg.clone();
+        self.validate_parsed_msg(bytes_msg, msg, &mut ProofDetails::default())
+            .await
+    }
+
+    /// Validate a DNS reply message.
+    ///
+    /// The message is validated using `bytes_msg`. If TTLs need to be
+    /// updated, `msg` is replaced with a rebuilt message. Information about
+    /// how the result was reached is collected in `details`.
+    ///
+    /// The validation is limited to the amount of work configured through
+    /// [`Config::set_max_work`]. If that is exceeded, the message is bogus.
+    async fn validate_parsed_msg<MsgOcts, USOcts>(
+        &self,
+        bytes_msg: Message<Bytes>,
+        msg: &mut Message<MsgOcts>,
+        details: &mut ProofDetails,
+    ) -> Result<(ValidationState, Option<ExtendedError<Vec<u8>>>), Error>
+    where
+        MsgOcts: AsRef<[u8]> + OctetsFrom<Vec<u8>>,
+        USOcts:
+            AsRef<[u8]> + Debug + Octets + OctetsFrom<Vec<u8>> + Send + Sync,
+        Upstream: SendRequest<RequestMessage<USOcts>>,
+    {
+        let question = match self.config.decision_hook {
+            Some(_) => bytes_msg
+                .first_question()
+                .map(|q| (q.qname().to_name::<Bytes>(), q.qtype())),
+            None => None,
+        };
+
+        let budget = Budget::new(self.config.max_work());
+        let mut res = budget
+            .run(self.validate_parsed_msg_budgeted(bytes_msg, msg, details))
+            .await;
+        if budget.exceeded() {
+            *details = ProofDetails::default();
+            res = Ok((
+                ValidationState::Bogus,
+                make_ede(
+                    ExtendedErrorCode::DNSSEC_BOGUS,
+                    "validation budget exceeded",
+                ),
+            ));
+        }
+
+        if let (Some(hook), Some((name, rtype)), Ok((state, ede))) =
+            (&self.config.decision_hook, question, &res)
+        {
+            (hook.0)(&ValidationDecision {
+                name,
+                rtype,
+                state: *state,
+                ede: ede.clone(),
+            });
+        }
+        res
+    }
+
+    /// Validate a DNS reply message within the current work budget.
+    async fn validate_parsed_msg_budgeted<MsgOcts, USOcts>(
+        &self,
+        bytes_msg: Message<Bytes>,
+        msg: &mut Message<MsgOcts>,
+        details: &mut ProofDetails,
+    ) -> Result<(ValidationState, Option<ExtendedError<Vec<u8>>>), Error>
+    where
+        MsgOcts: AsRef<[u8]> + OctetsFrom<Vec<u8>>,
+        USOcts:
+            AsRef<[u8]> + Debug + Octets + OctetsFrom<Vec<u8>> + Send + Sync,
+        Upstream: SendRequest<RequestMessage<USOcts>>,
+    {
+        if self.config.misplaced_record_policy()
+            == MisplacedRecordPolicy::Bogus
+        {
+            if let Some(reason) = misplaced_record(&bytes_msg)? {
+                return Ok((
+                    ValidationState::Bogus,
+                    make_ede(ExtendedErrorCode::DNSSEC_BOGUS, reason),
+                ));
+            }
+        }
 
         // First convert the Answer and Authority sections to lists of RR groups
         let mut answers = GroupSet::new();
@@ -415,6 +946,12 @@ impl<Upstream> ValidationContext<Upstream> {
             VGResult::Err(err) => return Err(err),
         };
 
+        for group in answers.iter() {
+            if group.state() == ValidationState::Secure {
+                details.add_signer(group.signer_name());
+            }
+        }
+
         // We may need to update TTLs of signed RRsets
         if fix_reply {
             *msg = rebuild_msg(&bytes_msg, &answers, &authorities)?;
@@ -428,16 +965,11 @@ impl<Upstream> ValidationContext<Upstream> {
         // (following draft-bellis-dnsop-qdcount-is-one-00)
 
         // Extract Qname, Qclass, Qtype
-        let mut question_section = bytes_msg.question();
-        let question = match question_section.next() {
-            None => {
-                return Err(Error::FormError);
-            }
-            Some(question) => question?,
-        };
-        if question_section.next().is_some() {
+        if bytes_msg.header_counts().qdcount() != 1 {
             return Err(Error::FormError);
         }
+        let question = bytes_msg.sole_question()?;
+        let kind = classify_response(&bytes_msg)?;
         let qname: Name<Bytes> = question.qname().to_name();
         let qclass = question.qclass();
         let qtype = question.qtype();
@@ -447,7 +979,7 @@ impl<Upstream> ValidationContext<Upstream> {
         // and downgrade if required.
         let maybe_secure = ValidationState::Secure;
 
-        let (sname, state, ede) = do_cname_dname(
+        let (sname, state, ede, chain_signer) = do_cname_dname(
             qname,
             qclass,
             qtype,
@@ -463,16 +995,19 @@ impl<Upstream> ValidationContext<Upstream> {
             return Ok((maybe_secure, ede));
         }
 
-        // For NOERROR, check if the answer is positive. Then extract the status
-        // of the group and be done.
+        // For a positive answer, extract the status of the group and be
+        // done.
         // For NODATA first get the SOA, this determines if the proof of a
         // negative result is signed or not.
-        if bytes_msg.opt_rcode() == OptRcode::NOERROR {
+        let noerror =
+            !matches!(kind, ResponseKind::NxDomain | ResponseKind::Other);
+        if kind == ResponseKind::Positive {
             let opt_state =
                 get_answer_state(&sname, qclass, qtype, &mut answers);
             if let Some((state, signer_name, closest_encloser, ede)) =
                 opt_state
             {
+                details.set_signer(signer_name.clone());
                 if state != ValidationState::Secure {
                     // No need to check the wildcard, either because the state
                     // is not secure.
@@ -509,6 +1044,9 @@ impl<Upstream> ValidationContext<Upstream> {
                     return Ok((map_maybe_secure(state, maybe_secure), ede));
                 }
 
+                details.wildcard = true;
+                details.closest_encloser = Some(closest_encloser.clone());
+
                 let (check, state, ede) = check_not_exists_for_wildcard(
                     &sname,
                     &mut authorities,
@@ -528,33 +1066,73 @@ impl<Upstream> ValidationContext<Upstream> {
             }
         }
 
+        // A CNAME or DNAME chain may lead into a different zone for which
+        // the response contains neither an answer nor a SOA record. Nothing
+        // can be said about the target, so the result is the state of the
+        // part of the chain that is in the message. If the target is in the
+        // zone of the last CNAME or DNAME, or its zone cannot be
+        // established, the response has to prove that the target has no
+        // data.
+        if let (ResponseKind::CnameChain, Some(chain_signer)) =
+            (kind, chain_signer)
+        {
+            let other_zone = !sname.ends_with(&chain_signer) || {
+                let node = self.get_node::<USOcts>(&sname).await?;
+                matches!(
+                    node.validation_state(),
+                    ValidationState::Secure | ValidationState::Insecure
+                ) && *node.signer_name() != chain_signer
+            };
+            if other_zone {
+                return Ok((maybe_secure, None));
+            }
+        }
+
         // For both NOERROR/NODATA and for NXDOMAIN we can first look at the SOA
-        // record in the authority section. If there is no SOA, return bogus. If
-        // there is one and the state is not secure, then return the state of the
-        // SOA record.
-        let signer_name =
-            match get_soa_state(&sname, qclass, &mut authorities) {
-                (None, ede) => {
-                    let ede = match ede {
-                        Some(ede) => Some(ede),
-                        None => make_ede(
-                            ExtendedErrorCode::DNSSEC_BOGUS,
-                            "Missing SOA record for NODATA or NXDOMAIN",
-                        ),
-                    };
-                    return Ok((ValidationState::Bogus, ede)); // No SOA, assume the worst.
+        // record in the authority section. If there is no SOA, the result
+        // depends on the configured policy. If there is one and the state is
+        // not secure, then return the state of the SOA record.
+        let signer_name = match get_soa_state(
+            self,
+            &sname,
+            qclass,
+            &mut authorities,
+            &self.config,
+        )
+        .await?
+        {
+            (None, Some(ede)) => {
+                // There is a SOA record but it is wrong.
+                return Ok((ValidationState::Bogus, Some(ede)));
+            }
+            (None, None) => match self.config.missing_soa_policy() {
+                MissingSoaPolicy::Bogus => {
+                    let ede = make_ede(
+                        ExtendedErrorCode::DNSSEC_BOGUS,
+                        "Missing SOA record for NODATA or NXDOMAIN",
+                    );
+                    return Ok((ValidationState::Bogus, ede));
                 }
-                (Some((state, signer_name)), ede) => match state {
-                    ValidationState::Secure => signer_name, // Continue validation.
-                    ValidationState::Insecure
-                    | ValidationState::Bogus
-                    | ValidationState::Indeterminate => {
-                        return Ok((state, ede));
-                    }
-                },
-            };
+                MissingSoaPolicy::Insecure => {
+                    return Ok((ValidationState::Insecure, None));
+                }
+            },
+            (Some((state, signer_name, ttl)), ede) => match state {
+                ValidationState::Secure => {
+                    // Continue validation.
+                    details.negative_ttl = Some(ttl);
+                    signer_name
+                }
+                ValidationState::Insecure
+                | ValidationState::Bogus
+                | ValidationState::Indeterminate => {
+                    return Ok((state, ede));
+                }
+            },
+        };
+        details.set_signer(signer_name.clone());
 
-        if bytes_msg.opt_rcode() == OptRcode::NOERROR {
+        if noerror {
             // Try to prove that the name exists but the qtype doesn't. Start
             // with NSEC and assume the name exists.
             let (state, ede) = nsec_for_nodata(
@@ -586,13 +1164,14 @@ impl<Upstream> ValidationContext<Upstream> {
             );
             match state {
                 NsecState::NoData => {
+                    details.wildcard = true;
                     return Ok((
                         map_maybe_secure(
                             ValidationState::Secure,
                             maybe_secure,
                         ),
                         ede,
-                    ))
+                    ));
                 }
                 NsecState::Nothing => (), // Try something else.
             }
@@ -646,6 +1225,7 @@ impl<Upstream> ValidationContext<Upstream> {
             .await;
             match state {
                 Nsec3State::NoData => {
+                    details.wildcard = true;
                     return Ok((
                         map_maybe_secure(
                             ValidationState::Secure,
@@ -654,10 +1234,13 @@ impl<Upstream> ValidationContext<Upstream> {
                         None,
                     ));
                 }
-                Nsec3State::Nothing =>
-                // totest, missing NSEC3 for wildcard.
-                {
-                    return Ok((ValidationState::Bogus, ede))
+                Nsec3State::Nothing => {
+                    // None of the NSEC or NSEC3 records prove NODATA.
+                    let ede = make_ede(
+                        ExtendedErrorCode::DNSSEC_BOGUS,
+                        "No NSEC3 proof for NODATA",
+                    );
+                    return Ok((ValidationState::Bogus, ede));
                 }
                 Nsec3State::NoDataInsecure => {
                     return Ok((ValidationState::Insecure, ede))
@@ -666,16 +1249,6 @@ impl<Upstream> ValidationContext<Upstream> {
                     return Ok((ValidationState::Bogus, ede))
                 }
             }
-
-            /*
-                    async fn nsec3_for_nodata_wildcard(
-                target: &Name<Bytes>,
-                groups: &mut Vec<ValidatedGroup>,
-                rtype: Rtype,
-                signer_name: &Name<Bytes>,
-                nsec3_cache: &Nsec3Cache,
-            ) -> (Nsec3State, Option<ExtendedError<Vec<u8>>>) {
-            */
         }
 
         // Prove NXDOMAIN.
@@ -686,11 +1259,12 @@ impl<Upstream> ValidationContext<Upstream> {
             NsecNXState::Exists => {
                 return Ok((ValidationState::Bogus, ede));
             }
-            NsecNXState::DoesNotExist(_) => {
+            NsecNXState::DoesNotExist(ce) => {
+                details.closest_encloser = Some(ce);
                 return Ok((
                     map_maybe_secure(ValidationState::Secure, maybe_secure),
                     ede,
-                ))
+                ));
             }
             NsecNXState::Nothing => (), // Try something else.
         }
@@ -705,13 +1279,15 @@ impl<Upstream> ValidationContext<Upstream> {
         )
         .await;
         match state {
-            Nsec3NXState::DoesNotExist(_) => {
+            Nsec3NXState::DoesNotExist(ce) => {
+                details.closest_encloser = Some(ce);
                 return Ok((
                     map_maybe_secure(ValidationState::Secure, maybe_secure),
                     None,
-                ))
+                ));
             }
-            Nsec3NXState::DoesNotExistInsecure(_) => {
+            Nsec3NXState::DoesNotExistInsecure(ce) => {
+                details.closest_encloser = Some(ce);
                 return Ok((ValidationState::Insecure, ede));
             }
             Nsec3NXState::Bogus => return Ok((ValidationState::Bogus, ede)),
@@ -735,6 +1311,62 @@ impl<Upstream> ValidationContext<Upstream> {
         &self,
         name: &Name<Bytes>,
     ) -> Result<Arc<Node>, Error>
+    where
+        Octs:
+            AsRef<[u8]> + Debug + Octets + OctetsFrom<Vec<u8>> + Send + Sync,
+        Upstream: SendRequest<RequestMessage<Octs>>,
+    {
+        self.get_node_traced(name, None).await
+    }
+
+    /// Get the node for validating `name` and the chain of nodes walked.
+    ///
+    /// This is meant for debugging why a name validated as it did. The
+    /// chain starts at the trust anchor or the closest cached node and ends
+    /// with the node for `name`. If the node for `name` was cached or no
+    /// walk was necessary, the chain only contains that node.
+    pub async fn trace_node<Octs>(
+        &self,
+        name: &Name<Bytes>,
+    ) -> Result<(NodeSummary, Vec<WalkedNode>), Error>
+    where
+        Octs:
+            AsRef<[u8]> + Debug + Octets + OctetsFrom<Vec<u8>> + Send + Sync,
+        Upstream: SendRequest<RequestMessage<Octs>>,
+    {
+        let mut chain = Vec::new();
+        let node = self.get_node_traced(name, Some(&mut chain)).await?;
+        Ok((node.debug_summary(), chain))
+    }
+
+    /// Prime the node cache with the nodes of the trust anchors.
+    ///
+    /// This fetches and validates the DNSKEY RRsets of all trust anchors,
+    /// which otherwise happens during the first validation below each
+    /// anchor. Calling this method after creating the context, for instance
+    /// at startup, avoids the extra latency for the first queries.
+    ///
+    /// Priming is optional. A trust anchor whose DNSKEY RRset can't be
+    /// validated results in a cached bogus node, just as it would during
+    /// validation.
+    pub async fn prime<Octs>(&self) -> Result<(), Error>
+    where
+        Octs:
+            AsRef<[u8]> + Debug + Octets + OctetsFrom<Vec<u8>> + Send + Sync,
+        Upstream: SendRequest<RequestMessage<Octs>>,
+    {
+        for owner in self.ta.owners() {
+            self.get_node(&owner).await?;
+        }
+        Ok(())
+    }
+
+    /// Get the node for `name`, optionally recording the walked nodes.
+    async fn get_node_traced<Octs>(
+        &self,
+        name: &Name<Bytes>,
+        mut trace: Option<&mut Vec<WalkedNode>>,
+    ) -> Result<Arc<Node>, Error>
     where
         Octs:
             AsRef<[u8]> + Debug + Octets + OctetsFrom<Vec<u8>> + Send + Sync,
@@ -742,6 +1374,7 @@ impl<Upstream> ValidationContext<Upstream> {
     {
         // Check the cache first
         if let Some(node) = self.cache_lookup(name).await {
+            WalkedNode::record(&mut trace, name, &node);
             return Ok(node);
         }
 
@@ -757,11 +1390,28 @@ impl<Upstream> ValidationContext<Upstream> {
                 self.config.max_node_validity,
             );
             let node = Arc::new(node);
-            self.node_cache.insert(Name::root(), node.clone()).await;
+            self.cache_insert(Name::root(), node.clone()).await;
+            WalkedNode::record(&mut trace, &Name::root(), &node);
             return Ok(node);
         };
 
         let ta_owner = ta.owner();
+
+        // Give up on names too far below the trust anchor.
+        let depth = name.label_count() - ta_owner.label_count();
+        if depth > usize::from(self.config.max_depth()) {
+            let node = Node::indeterminate(
+                name.clone(),
+                make_ede(
+                    ExtendedErrorCode::DNSSEC_INDETERMINATE,
+                    "Name too far below trust anchor.",
+                ),
+                self.config.max_bogus_validity,
+            );
+            WalkedNode::record(&mut trace, name, &node);
+            return Ok(Arc::new(node));
+        }
+
         if ta_owner.name_eq(name) {
             // The trust anchor is the same node we are looking for. Create
             // a node for the trust anchor.
@@ -773,15 +1423,38 @@ impl<Upstream> ValidationContext<Upstream> {
             )
             .await?;
             let node = Arc::new(node);
-            self.node_cache.insert(name.clone(), node.clone()).await;
+            self.cache_insert(name.clone(), node.clone()).await;
+            WalkedNode::record(&mut trace, name, &node);
             return Ok(node);
         }
 
         // Walk from the parent of name back to trust anchor.
         // Keep a list of names we need to walk in the other direction.
-        let (mut node, mut names) =
+        let (node, names) =
             self.find_closest_node(name, ta, ta_owner).await?;
+        if let Some(closest) = names.front().and_then(|name| name.parent()) {
+            WalkedNode::record(&mut trace, &closest, &node);
+        }
+        self.walk_to_name(name, node, names, &mut trace).await
+    }
 
+    /// Walk from `node` down to `name`.
+    ///
+    /// The names of the nodes between `node` and `name`, including `name`
+    /// itself, are taken from the front of `names`. The list must not be
+    /// empty.
+    async fn walk_to_name<Octs>(
+        &self,
+        name: &Name<Bytes>,
+        mut node: Arc<Node>,
+        mut names: VecDeque<Name<Bytes>>,
+        trace: &mut Option<&mut Vec<WalkedNode>>,
+    ) -> Result<Arc<Node>, Error>
+    where
+        Octs:
+            AsRef<[u8]> + Debug + Octets + OctetsFrom<Vec<u8>> + Send + Sync,
+        Upstream: SendRequest<RequestMessage<Octs>>,
+    {
         // Assume that node is not an intermediate node. We have to make sure
         // in find_closest_node.
         let mut signer_node = node.clone();
@@ -800,13 +1473,25 @@ impl<Upstream> ValidationContext<Upstream> {
             }
 
             // Create the child node
-            let child_name = match names.pop_front() {
-                Some(name) => name,
-                None => {
-                    // This should not happen. But the easy way out is to
-                    // just return node.
-                    return Ok(node);
-                }
+            let Some(child_name) = names.pop_front() else {
+                // This should not happen: find_closest_node always returns
+                // at least name and we stop once the list is exhausted.
+                // Returning node would silently give the result for an
+                // ancestor of name, so report the problem instead.
+                error!(
+                    "no names left while walking from {} to {name}",
+                    node.signer_name()
+                );
+                let node = Node::indeterminate(
+                    name.clone(),
+                    make_ede(
+                        ExtendedErrorCode::DNSSEC_INDETERMINATE,
+                        "Internal error while walking to name.",
+                    ),
+                    self.config.max_bogus_validity,
+                );
+                WalkedNode::record(trace, name, &node);
+                return Ok(Arc::new(node));
             };
 
             // If this node is an intermediate node then get the node for
@@ -815,7 +1500,8 @@ impl<Upstream> ValidationContext<Upstream> {
                 self.create_child_node(child_name.clone(), &signer_node)
                     .await?,
             );
-            self.node_cache.insert(child_name, node.clone()).await;
+            WalkedNode::record(trace, &child_name, &node);
+            self.cache_insert(child_name, node.clone()).await;
             if !node.intermediate() {
                 signer_node = node.clone();
             }
@@ -857,7 +1543,7 @@ impl<Upstream> ValidationContext<Upstream> {
                 )
                 .await?;
                 let node = Arc::new(node);
-                self.node_cache.insert(curr, node.clone()).await;
+                self.cache_insert(curr, node.clone()).await;
                 return Ok((node, names));
             }
 
@@ -971,6 +1657,24 @@ impl<Upstream> ValidationContext<Upstream> {
                     ));
                 }
 
+                // A DS RRset for a different name does not answer our
+                // question. Treat it as an attempt to confuse us.
+                if answers
+                    .iter()
+                    .any(|g| g.rtype() == Rtype::DS && g.owner() != name)
+                {
+                    return Ok(Node::new_delegation(
+                        name,
+                        ValidationState::Bogus,
+                        Vec::new(),
+                        make_ede(
+                            ExtendedErrorCode::DNSSEC_BOGUS,
+                            "DS owner mismatch",
+                        ),
+                        self.config.max_bogus_validity,
+                    ));
+                }
+
                 // Verify proof that DS doesn't exist for this name.
                 let (state, ttl, ede) = nsec_for_ds(
                     &name,
@@ -1119,7 +1823,7 @@ impl<Upstream> ValidationContext<Upstream> {
         // authenticated DS records using unknown or unsupported message
         // digest algorithms.
         let mut tmp_group = ds_group.clone();
-        let valid_algs = tmp_group
+        let algorithms: Vec<_> = tmp_group
             .rr_iter()
             .map(|r| {
                 if let AllRecordData::Ds(ds) = r.data() {
@@ -1128,11 +1832,19 @@ impl<Upstream> ValidationContext<Upstream> {
                     panic!("DS record expected");
                 }
             })
-            .any(|(alg, dig)| {
-                supported_algorithm(&alg) && supported_digest(&dig)
-            });
+            .collect();
+        let valid_algs = algorithms.iter().any(|(alg, dig)| {
+            self.config.algorithm_enabled(alg) && supported_digest(dig)
+        });
 
         if !valid_algs {
+            if let Some(hook) = &self.config.unsupported_algorithm_hook {
+                (hook.0)(&UnsupportedDelegation {
+                    name: name.clone(),
+                    algorithms,
+                });
+            }
+
             // Delegation is insecure
             let ede = make_ede(
                 ExtendedErrorCode::OTHER,
@@ -1167,19 +1879,10 @@ impl<Upstream> ValidationContext<Upstream> {
             match answers.iter().find(|g| g.rtype() == Rtype::DNSKEY) {
                 Some(g) => g,
                 None => {
-                    // totest, no DNSKEY in reply to DNSKEY request
                     // No DNSKEY RRset, set validation state to bogus.
-                    let ede = make_ede(
-                        ExtendedErrorCode::DNSSEC_BOGUS,
-                        "No DNSKEY found",
+                    return Ok(
+                        self.lame_delegation(name, "No DNSKEY RRset found")
                     );
-                    return Ok(Node::new_delegation(
-                        name,
-                        ValidationState::Bogus,
-                        Vec::new(),
-                        ede,
-                        self.config.max_bogus_validity,
-                    ));
                 }
             };
 
@@ -1191,8 +1894,14 @@ impl<Upstream> ValidationContext<Upstream> {
         // Try to find one DNSKEY record that matches a DS record and that
         // can be used to validate the DNSKEY RRset.
 
+        // The key tags of the keys that signed the DNSKEY RRset.
+        let sig_tags =
+            dnskey_group.sig_key_tags(self.config.max_signatures.into());
+
         let mut bad_sigs = 0;
         let mut ede = None;
+        let mut found_key = false;
+        let mut found_sig = false;
         for ds in tmp_group
             .rr_iter()
             .map(|r| {
@@ -1203,7 +1912,7 @@ impl<Upstream> ValidationContext<Upstream> {
                 }
             })
             .filter(|ds| {
-                supported_algorithm(&ds.algorithm())
+                self.config.algorithm_enabled(&ds.algorithm())
                     && supported_digest(&ds.digest_type())
             })
         {
@@ -1211,6 +1920,7 @@ impl<Upstream> ValidationContext<Upstream> {
                 None => continue,
                 Some(r) => r,
             };
+            found_key = true;
             let dnskey =
                 if let AllRecordData::Dnskey(dnskey) = r_dnskey.data() {
                     dnskey
@@ -1218,8 +1928,18 @@ impl<Upstream> ValidationContext<Upstream> {
                     panic!("expected DNSKEY");
                 };
             let key_tag = dnskey.key_tag();
+            if !sig_tags.contains(&key_tag) {
+                // The key matches a DS record but did not sign the DNSKEY
+                // RRset.
+                continue;
+            }
+            found_sig = true;
             let key_name = r_dnskey.owner().to_name();
-            for sig in (*dnskey_group).clone().sig_iter() {
+            for sig in (*dnskey_group)
+                .clone()
+                .sig_iter()
+                .take(self.config.max_signatures.into())
+            {
                 if sig.data().key_tag() != key_tag {
                     continue; // Signature from wrong key
                 }
@@ -1305,6 +2025,26 @@ impl<Upstream> ValidationContext<Upstream> {
             // from this key on the DNSKEY RRset. Try the next key.
         }
 
+        if !found_key {
+            // None of the DNSKEY records matches a DS record.
+            return Ok(self.lame_delegation(name, "No DNSKEY matches DS"));
+        }
+
+        if !found_sig {
+            // None of the keys that match a DS record signed the DNSKEY
+            // RRset.
+            return Ok(Node::new_delegation(
+                name,
+                ValidationState::Bogus,
+                Vec::new(),
+                make_ede(
+                    ExtendedErrorCode::RRSIGS_MISSING,
+                    "No signature for DNSKEY from key matching DS",
+                ),
+                self.config.max_bogus_validity,
+            ));
+        }
+
         // totest, no DNSKEY without signatures
         // totest, DNSKEY with 1 failing signatures
         if ede.is_none() {
@@ -1319,6 +2059,22 @@ impl<Upstream> ValidationContext<Upstream> {
         ))
     }
 
+    /// Create a bogus node for a lame secure delegation.
+    ///
+    /// A delegation is lame if the parent has a DS RRset for `name` but
+    /// the child either has no DNSKEY RRset or none of its keys matches a
+    /// DS record. This is reported with a DNSKEY Missing EDE to tell it
+    /// apart from other failures.
+    fn lame_delegation(&self, name: Name<Bytes>, reason: &str) -> Node {
+        Node::new_delegation(
+            name,
+            ValidationState::Bogus,
+            Vec::new(),
+            make_ede(ExtendedErrorCode::DNSKEY_MISSING, reason),
+            self.config.max_bogus_validity,
+        )
+    }
+
     /// Try to look up a name in the cache.
     async fn cache_lookup(&self, name: &Name<Bytes>) -> Option<Arc<Node>> {
         let ce = self.node_cache.get(name).await?;
@@ -1328,6 +2084,41 @@ impl<Upstream> ValidationContext<Upstream> {
         Some(ce)
     }
 
+    /// Add a node to the cache.
+    ///
+    /// Nodes created after the work budget of the current validation ran
+    /// out may be incomplete and are not cached.
+    async fn cache_insert(&self, name: Name<Bytes>, node: Arc<Node>) {
+        if budget::exceeded() {
+            return;
+        }
+        self.node_cache.insert(name, node).await;
+    }
+
+    /// Return the zones from the trust anchor down to `signer`.
+    ///
+    /// The zones are taken from the node cache, so this is only complete
+    /// directly after validating something signed by `signer`.
+    async fn zone_chain(&self, signer: &Name<Bytes>) -> Vec<Name<Bytes>> {
+        let Some(ta) = self.ta.find(signer) else {
+            return Vec::new();
+        };
+        let ta_owner = ta.owner();
+        let mut chain = Vec::new();
+        for name in signer.iter_suffixes() {
+            if let Some(node) = self.cache_lookup(&name).await {
+                if !node.intermediate() && *node.signer_name() == name {
+                    chain.push(name.clone());
+                }
+            }
+            if name == ta_owner {
+                break;
+            }
+        }
+        chain.reverse();
+        chain
+    }
+
     /// Return a reference to the NSEC3 cache.
     pub(crate) fn nsec3_cache(&self) -> &Nsec3Cache {
         &self.nsec3_cache
@@ -1403,6 +2194,199 @@ pub enum ValidationState {
     Indeterminate,
 }
 
+//--- Display
+
+impl fmt::Display for ValidationState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ValidationState::Secure => "secure",
+            ValidationState::Insecure => "insecure",
+            ValidationState::Bogus => "bogus",
+            ValidationState::Indeterminate => "indeterminate",
+        })
+    }
+}
+
+//------------ ValidationResult ----------------------------------------------
+
+/// The detailed result of validating a message.
+///
+/// See [`ValidationContext::validate_msg_detailed`]. The details are only
+/// filled in as far as validation got, so for instance a bogus answer may
+/// lack a signer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationResult {
+    /// The validation state of the message.
+    pub state: ValidationState,
+
+    /// The extended error explaining the state, if any.
+    pub ede: Option<ExtendedError<Vec<u8>>>,
+
+    /// The zones that signed the answer or the proof of non-existence.
+    ///
+    /// There is more than one zone if a CNAME or DNAME chain crosses zone
+    /// boundaries.
+    pub signers: Vec<Name<Bytes>>,
+
+    /// Whether the answer or the proof of non-existence involved a wildcard.
+    pub wildcard: bool,
+
+    /// The closest encloser proven for a wildcard answer or a non-existent
+    /// name, if any.
+    pub closest_encloser: Option<Name<Bytes>>,
+
+    /// The zones from the trust anchor down to the zone that signed the
+    /// final answer or proof of non-existence.
+    pub chain: Vec<Name<Bytes>>,
+
+    /// The TTL for which a negative answer can be cached.
+    ///
+    /// This is derived from the SOA record of a NODATA or NXDOMAIN response
+    /// and its signature. It is only present if the SOA record is secure.
+    pub negative_ttl: Option<Ttl>,
+}
+
+//------------ ResponseKind --------------------------------------------------
+
+/// The kind of a response as determined by [`classify_response`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ResponseKind {
+    /// The response answers the question, possibly via a CNAME chain.
+    Positive,
+
+    /// The name exists but there is no data of the requested type.
+    NoData,
+
+    /// The name does not exist.
+    NxDomain,
+
+    /// The response delegates to the servers of a child zone.
+    Referral,
+
+    /// The answer section contains a CNAME chain that needs to be followed
+    /// further to get an answer.
+    CnameChain,
+
+    /// The response has an rcode other than NOERROR or NXDOMAIN.
+    Other,
+}
+
+/// Determines the kind of a response without validating it.
+///
+/// The answer section is searched for CNAME records leading from the
+/// question name to the final name. DNAME records are not followed
+/// themselves but through the CNAME records synthesized from them. A
+/// NOERROR response is positive if the answer section has records of the
+/// requested type for the final name. Otherwise, it is NODATA if the
+/// authority section has a SOA record, a CNAME chain if a chain was
+/// followed, a referral if the authority section has NS records and NODATA
+/// in all other cases.
+///
+/// Returns an error if the message does not have exactly one question or
+/// cannot be parsed.
+pub fn classify_response<Octs: Octets + ?Sized>(
+    msg: &Message<Octs>,
+) -> Result<ResponseKind, wire::ParseError> {
+    let question = msg.sole_question()?;
+    let qtype = question.qtype();
+    let mut sname: Name<Vec<u8>> = question.qname().to_name();
+
+    // Follow the CNAME chain. Every step uses up a record, so the number of
+    // steps is limited by the number of answers.
+    let mut chain = false;
+    if qtype != Rtype::CNAME {
+        for _ in 0..msg.header_counts().ancount() {
+            let mut next = None;
+            for record in msg.answer()?.limit_to::<Cname<ParsedName<_>>>() {
+                let record = record?;
+                if record.owner().name_eq(&sname) {
+                    next = Some(record.data().cname().to_name());
+                    break;
+                }
+            }
+            match next {
+                Some(target) => {
+                    sname = target;
+                    chain = true;
+                }
+                None => break,
+            }
+        }
+    }
+
+    let rcode = msg.opt_rcode();
+    if rcode == OptRcode::NXDOMAIN {
+        return Ok(ResponseKind::NxDomain);
+    }
+    if rcode != OptRcode::NOERROR {
+        return Ok(ResponseKind::Other);
+    }
+
+    for record in msg.answer()? {
+        let record = record?;
+        if record.owner().name_eq(&sname)
+            && (record.rtype() == qtype || qtype == Rtype::ANY)
+        {
+            return Ok(ResponseKind::Positive);
+        }
+    }
+
+    let mut has_soa = false;
+    let mut has_ns = false;
+    for record in msg.authority()? {
+        match record?.rtype() {
+            Rtype::SOA => has_soa = true,
+            Rtype::NS => has_ns = true,
+            _ => {}
+        }
+    }
+    Ok(if has_soa {
+        ResponseKind::NoData
+    } else if chain {
+        ResponseKind::CnameChain
+    } else if has_ns {
+        ResponseKind::Referral
+    } else {
+        ResponseKind::NoData
+    })
+}
+
+//------------ ProofDetails --------------------------------------------------
+
+/// Information collected while validating a message.
+#[derive(Debug, Default)]
+struct ProofDetails {
+    /// The zones that signed the answer or the proof of non-existence.
+    signers: Vec<Name<Bytes>>,
+
+    /// The zone that signed the final answer or proof of non-existence.
+    signer: Option<Name<Bytes>>,
+
+    /// Whether a wildcard was involved.
+    wildcard: bool,
+
+    /// The proven closest encloser, if any.
+    closest_encloser: Option<Name<Bytes>>,
+
+    /// The TTL of a negative answer as derived from its SOA record.
+    negative_ttl: Option<Ttl>,
+}
+
+impl ProofDetails {
+    /// Add a signer if it isn't known already.
+    fn add_signer(&mut self, signer: Name<Bytes>) {
+        if !self.signers.contains(&signer) {
+            self.signers.push(signer);
+        }
+    }
+
+    /// Set the signer of the final answer or proof of non-existence.
+    fn set_signer(&mut self, signer: Name<Bytes>) {
+        self.add_signer(signer.clone());
+        self.signer = Some(signer);
+    }
+}
+
 //------------ Node ----------------------------------------------------------
 
 /// Node represent the DNSSEC state of a DNS name.
@@ -1499,9 +2483,21 @@ impl Node {
         let mut bad_sigs = 0;
         let mut opt_ede: Option<ExtendedError<Vec<u8>>> = None;
 
+        // Trust anchors for keys that did not sign the DNSKEY RRset can be
+        // skipped without looking for the key.
+        let sig_tags = dnskeys.sig_key_tags(config.max_signatures.into());
+
         // Try to find one trust anchor key that can be used to validate
         // the DNSKEY RRset.
         for ta_rr in (*ta).clone().iter() {
+            let ta_tag = match ta_rr.data() {
+                ZoneRecordData::Dnskey(dnskey) => dnskey.key_tag(),
+                ZoneRecordData::Ds(ds) => ds.key_tag(),
+                _ => continue,
+            };
+            if !sig_tags.contains(&ta_tag) {
+                continue; // No signature from this key.
+            }
             let opt_dnskey_rr = if ta_rr.rtype() == Rtype::DNSKEY {
                 has_key(dnskeys, ta_rr)
             } else if ta_rr.rtype() == Rtype::DS {
@@ -1528,7 +2524,11 @@ impl Node {
                 };
             let key_tag = dnskey.key_tag();
             let key_name = dnskey_rr.owner().to_name();
-            for sig in (*dnskeys).clone().sig_iter() {
+            for sig in (*dnskeys)
+                .clone()
+                .sig_iter()
+                .take(config.max_signatures.into())
+            {
                 if sig.data().key_tag() != key_tag {
                     continue; // Signature from wrong key
                 }
@@ -1685,8 +2685,81 @@ impl Node {
     }
 
     /// Return the remaining time to live.
+    ///
+    /// Returns zero if the node has expired.
     pub fn ttl(&self) -> Duration {
-        self.valid_for - self.created_at.elapsed()
+        self.valid_for.saturating_sub(self.created_at.elapsed())
+    }
+
+    /// Return a summary of the node for diagnostics.
+    pub fn debug_summary(&self) -> NodeSummary {
+        NodeSummary {
+            state: self.state,
+            signer_name: self.signer_name.clone(),
+            key_tags: self.keys.iter().map(|key| key.key_tag()).collect(),
+            intermediate: self.intermediate,
+            ttl: self.ttl(),
+        }
+    }
+}
+
+//------------ NodeSummary ---------------------------------------------------
+
+/// A summary of the DNSSEC state of a name as kept in the node cache.
+///
+/// See [`ValidationContext::cached_node`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeSummary {
+    /// The validation state of the name.
+    pub state: ValidationState,
+
+    /// The signer name, the name of the zone the name belongs to.
+    pub signer_name: Name<Bytes>,
+
+    /// The key tags of the validated DNSKEY records of the zone.
+    ///
+    /// This is empty for intermediate nodes and for nodes that are not
+    /// secure.
+    pub key_tags: Vec<u16>,
+
+    /// Whether the name is an intermediate node rather than a zone cut.
+    pub intermediate: bool,
+
+    /// The time the cache entry remains valid.
+    pub ttl: Duration,
+}
+
+//------------ WalkedNode ----------------------------------------------------
+
+/// A node walked while looking up the DNSSEC state of a name.
+///
+/// See [`ValidationContext::trace_node`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct WalkedNode {
+    /// The name of the node.
+    pub name: Name<Bytes>,
+
+    /// The validation state of the node.
+    pub state: ValidationState,
+
+    /// The extended error explaining the state, if any.
+    pub ede: Option<ExtendedError<Vec<u8>>>,
+}
+
+impl WalkedNode {
+    /// Add `node` to `trace` if tracing is enabled.
+    fn record(
+        trace: &mut Option<&mut Vec<WalkedNode>>,
+        name: &Name<Bytes>,
+        node: &Node,
+    ) {
+        if let Some(trace) = trace.as_mut() {
+            trace.push(WalkedNode {
+                name: name.clone(),
+                state: node.validation_state(),
+                ede: node.extended_error(),
+            });
+        }
     }
 }
 
@@ -1712,7 +2785,7 @@ fn has_key(
         let AllRecordData::Dnskey(key_dnskey) = key.data() else {
             continue;
         };
-        if tkey.owner().to_name::<Bytes>() != key.owner() {
+        if !tkey.owner().name_eq(key.owner()) {
             continue;
         }
         if tkey.class() != key.class() {
@@ -1782,6 +2855,144 @@ fn find_key_for_ds(
     None
 }
 
+/// Verify a signed DNSKEY RRset against a DS RRset.
+///
+/// This is the step that connects a zone to its parent or to a DS trust
+/// anchor. A DNSKEY record that matches one of the DS records in
+/// `ds_set` has to have a valid signature in `rrsigs` over the complete
+/// DNSKEY RRset in `dnskeys`. The signatures are checked at the current
+/// time.
+///
+/// The result is [`ValidationState::Secure`] if such a signature is found
+/// and [`ValidationState::Bogus`] otherwise. In the latter case, the
+/// extended error describes why validation failed.
+#[allow(clippy::type_complexity)]
+pub fn verify_dnskey_rrset(
+    dnskeys: &[Record<Name<Bytes>, Dnskey<Bytes>>],
+    rrsigs: &[Record<Name<Bytes>, Rrsig<Bytes, Name<Bytes>>>],
+    ds_set: &[Record<Name<Bytes>, Ds<Bytes>>],
+) -> (ValidationState, Option<ExtendedError<Vec<u8>>>) {
+    let Some(first) = dnskeys.first() else {
+        return (
+            ValidationState::Bogus,
+            make_ede(ExtendedErrorCode::DNSKEY_MISSING, "No DNSKEY"),
+        );
+    };
+    let owner = first.owner();
+
+    let mut bad_sigs = 0;
+    let mut ede = None;
+    let mut found_key = false;
+    for ds in ds_set
+        .iter()
+        .filter(|r| r.owner() == owner)
+        .map(|r| r.data())
+        .filter(|ds| {
+            supported_algorithm(&ds.algorithm())
+                && supported_digest(&ds.digest_type())
+        })
+    {
+        let Some(dnskey) = dnskeys
+            .iter()
+            .map(|r| r.data())
+            .filter(|key| {
+                key.algorithm() == ds.algorithm()
+                    && key.key_tag() == ds.key_tag()
+            })
+            .find(|key| {
+                key.digest(owner, ds.digest_type())
+                    .is_ok_and(|d| ds.digest() == d.as_ref())
+            })
+        else {
+            continue;
+        };
+        found_key = true;
+        let key_tag = dnskey.key_tag();
+        for sig in rrsigs
+            .iter()
+            .filter(|sig| sig.data().key_tag() == key_tag)
+            .take(MAX_SIGNATURES.default().into())
+        {
+            if check_dnskey_sig(sig, dnskeys, owner, dnskey) {
+                return (ValidationState::Secure, None);
+            }
+
+            // See create_child_node for why we tolerate so few failures.
+            bad_sigs += 1;
+            if bad_sigs > MAX_BAD_SIGNATURES.default() {
+                return (
+                    ValidationState::Bogus,
+                    make_ede(
+                        ExtendedErrorCode::DNSSEC_BOGUS,
+                        "too many bad signatures for DNSKEY",
+                    ),
+                );
+            }
+            if ede.is_none() {
+                ede = make_ede(
+                    ExtendedErrorCode::DNSSEC_BOGUS,
+                    "bad signature for DNSKEY",
+                );
+            }
+        }
+    }
+
+    if !found_key {
+        return (
+            ValidationState::Bogus,
+            make_ede(
+                ExtendedErrorCode::DNSKEY_MISSING,
+                "No DNSKEY matches DS",
+            ),
+        );
+    }
+    if ede.is_none() {
+        ede = make_ede(ExtendedErrorCode::DNSSEC_BOGUS, "No signature");
+    }
+    (ValidationState::Bogus, ede)
+}
+
+/// Check a signature over a DNSKEY RRset made by a key from the RRset.
+///
+/// This is `Group::check_sig` for the special case where the signer is
+/// the owner of the RRset.
+fn check_dnskey_sig(
+    sig: &Record<Name<Bytes>, Rrsig<Bytes, Name<Bytes>>>,
+    dnskeys: &[Record<Name<Bytes>, Dnskey<Bytes>>],
+    owner: &Name<Bytes>,
+    key: &Dnskey<Bytes>,
+) -> bool {
+    let rrsig = sig.data();
+
+    if dnskeys
+        .iter()
+        .any(|r| r.owner() != owner || r.class() != sig.class())
+        || rrsig.signer_name() != owner
+    {
+        return false;
+    }
+    if check_rrsig_for_rrset(
+        sig,
+        owner,
+        sig.class(),
+        Rtype::DNSKEY,
+        owner,
+        Timestamp::now(),
+    )
+    .is_err()
+        || !rrsig_matches_key(rrsig, owner, key, owner, key.key_tag())
+    {
+        return false;
+    }
+
+    let mut rr_set = dnskeys.to_vec();
+    let mut signed_data = Vec::<u8>::new();
+    rrsig
+        .signed_data(&mut signed_data, &mut rr_set)
+        .expect("infallible");
+    rrsig.verify_signed_data(key, &signed_data).is_ok()
+}
+
 /// The result of trying to prove using NSEC or NSEC3 records that a DS
 /// record does not exist for a certain name.
 #[derive(Debug)]
@@ -1849,7 +3060,6 @@ async fn nsec_for_ds(
             // Rule out wildcard. The NS record cannot be the result of a
             // wildcard expansion. So the NSEC cannot either.
             if wildcard.is_some() {
-                // totest, NSEC that proves no DS is a wildcard.
                 let ede = make_ede(
                     ExtendedErrorCode::DNSSEC_BOGUS,
                     "NSEC for DS is wildcard",
@@ -1862,7 +3072,6 @@ async fn nsec_for_ds(
 
             // Check for DS.
             if types.contains(Rtype::DS) {
-                // totest, no DS but NSEC proves DS
                 // We didn't get a DS RRset but the NSEC record proves there
                 // is one. Complain.
                 let ede = make_ede(
@@ -1874,7 +3083,6 @@ async fn nsec_for_ds(
 
             // Check for SOA.
             if types.contains(Rtype::SOA) {
-                // totest, NSEC for DS from apex
                 // This is an NSEC record from the APEX. Complain.
                 let ede = make_ede(
                     ExtendedErrorCode::DNSSEC_BOGUS,
@@ -1926,7 +3134,6 @@ async fn nsec_for_ds(
             // - that the nsec does not have DNAME
             // - that if the nsec has NS, it also has SOA
             if types.contains(Rtype::DNAME) {
-                // totest, prefix NSEC for DS is DNAME
                 // We should not be here. Return failure.
                 let ede = make_ede(
                     ExtendedErrorCode::DNSSEC_BOGUS,
@@ -1935,7 +3142,6 @@ async fn nsec_for_ds(
                 return (CNsecState::Bogus, config.max_bogus_validity, ede);
             }
             if types.contains(Rtype::NS) && !types.contains(Rtype::SOA) {
-                // totest, prefix NSEC for DS is delegation
                 // We got a delegation NSEC. Return failure.
                 let ede = make_ede(
                     ExtendedErrorCode::DNSSEC_BOGUS,
@@ -2030,9 +3236,12 @@ async fn nsec3_for_ds(
         if iterations > config.nsec3_iter_insecure
             || iterations > config.nsec3_iter_bogus
         {
-            // totest, NSEC3 for DS with very high iteration count
-            // totest, NSEC3 for DS with high iteration count
-            // High iteration count, verify the signature and abort.
+            // High iteration count, verify the signature and abort. We
+            // do not compute the hash, so we cannot tell whether the NSEC3
+            // record covers target (and has opt-out set) or matches it.
+            // A valid signature is enough to treat the delegation as
+            // insecure, unless the iteration count is also above the bogus
+            // limit.
 
             let (state, _wildcard, ede, ttl, _) =
                 g.validate_with_node(node, sig_cache, config).await;
@@ -2056,7 +3265,7 @@ async fn nsec3_for_ds(
                 return (CNsecState::Bogus, ede, ttl);
             }
             let ede = make_ede(
-                ExtendedErrorCode::DNSSEC_BOGUS,
+                ExtendedErrorCode::OTHER,
                 "NSEC3 with too high iteration count",
             );
             return (CNsecState::InsecureDelegation, ede, ttl);
@@ -2205,6 +3414,18 @@ where
     Octs: AsRef<[u8]> + Debug + Octets + OctetsFrom<Vec<u8>> + Send + Sync,
     Upstream: SendRequest<RequestMessage<Octs>>,
 {
+    if !budget::charge() {
+        // Out of budget. Pretend that the request failed.
+        return Ok((
+            GroupSet::new(),
+            GroupSet::new(),
+            make_ede(
+                ExtendedErrorCode::DNSSEC_BOGUS,
+                "validation budget exceeded",
+            ),
+        ));
+    }
+
     let mut msg = MessageBuilder::new_vec();
     msg.header_mut().set_cd(true);
     msg.header_mut().set_rd(true);
@@ -2282,6 +3503,33 @@ where
     Ok((answers, authorities, ede))
 }
 
+/// Checks a response for records in a section where they are not allowed.
+///
+/// Returns a description of the first misplaced record found, if any.
+fn misplaced_record(
+    msg: &Message<Bytes>,
+) -> Result<Option<&'static str>, Error> {
+    // RFC 6891, Section 6.1.1: "If an OPT record is found in any other
+    // section, it MUST be treated as malformed."
+    for rr in msg.answer()?.chain(msg.authority()?) {
+        if rr?.rtype() == Rtype::OPT {
+            return Ok(Some("OPT record outside additional section"));
+        }
+    }
+
+    // A SOA record in the answer section only makes sense if it was asked
+    // for.
+    let qtype = msg.sole_question().map_err(|_| Error::FormError)?.qtype();
+    if qtype != Rtype::SOA && qtype != Rtype::ANY {
+        for rr in msg.answer()? {
+            if rr?.rtype() == Rtype::SOA {
+                return Ok(Some("SOA record in answer section"));
+            }
+        }
+    }
+    Ok(None)
+}
+
 //----------- Error ----------------------------------------------------------
 
 /// Various errors that can be returned by function in the
@@ -2297,6 +3545,9 @@ pub enum Error {
     /// Error parsing trust anchors.
     InplaceError(inplace::Error),
 
+    /// Records of a single RRset have different classes.
+    MixedClass,
+
     /// Cannot convert one type of octets into another.
     OctetsConversion,
 
@@ -2349,14 +3600,17 @@ impl From<ShortMessage> for Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Error::FormError => write!(f, "FormError"),
-            Error::InplaceError(_) => write!(f, "InplaceError"),
-            Error::OctetsConversion => write!(f, "OctetsConversion"),
-            Error::ParseError => write!(f, "ParseError"),
-            Error::PushError => write!(f, "PushError"),
-            Error::PushNameError => write!(f, "PushNameError"),
-            Error::ReadError(_) => write!(f, "FormError"),
-            Error::ShortMessage => write!(f, "ShortMEssage"),
+            Error::FormError => write!(f, "form error"),
+            Error::InplaceError(err) => {
+                write!(f, "error parsing trust anchors: {err}")
+            }
+            Error::MixedClass => write!(f, "RRset with mixed classes"),
+            Error::OctetsConversion => write!(f, "octets conversion failed"),
+            Error::ParseError => write!(f, "parse error"),
+            Error::PushError => write!(f, "push error"),
+            Error::PushNameError => write!(f, "push name error"),
+            Error::ReadError(err) => write!(f, "read error: {err}"),
+            Error::ShortMessage => write!(f, "short message"),
         }
     }
 }
@@ -2366,6 +3620,7 @@ impl error::Error for Error {
         match self {
             Error::FormError => None,
             Error::InplaceError(err) => Some(err),
+            Error::MixedClass => None,
             Error::OctetsConversion => None,
             Error::ParseError => None,
             Error::PushError => None,
@@ -2375,3 +3630,404 @@ impl error::Error for Error {
         }
     }
 }
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::iana::{Class, DigestAlgorithm, SecurityAlgorithm};
+    use crate::base::Ttl;
+    use crate::dnssec::validator::base::test::{
+        root_dnskey_records, ROOT_DNSKEY_SIGNATURE,
+    };
+    use crate::net::client::request::GetResponse;
+    use crate::utils::base64;
+    use core::str::FromStr;
+    use mock_instant::thread_local::MockClock;
+    use std::boxed::Box;
+    use std::vec;
+
+    #[test]
+    fn display_validation_state() {
+        assert_eq!(ValidationState::Secure.to_string(), "secure");
+        assert_eq!(ValidationState::Insecure.to_string(), "insecure");
+        assert_eq!(ValidationState::Bogus.to_string(), "bogus");
+        assert_eq!(
+            ValidationState::Indeterminate.to_string(),
+            "indeterminate"
+        );
+    }
+
+    #[test]
+    fn display_error() {
+        fn is_error<E: error::Error>(_: &E) {}
+
+        let io_err =
+            std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        let cases = [
+            (Error::FormError, "form error"),
+            (Error::MixedClass, "RRset with mixed classes"),
+            (Error::OctetsConversion, "octets conversion failed"),
+            (Error::ParseError, "parse error"),
+            (Error::PushError, "push error"),
+            (Error::PushNameError, "push name error"),
+            (Error::ReadError(Arc::new(io_err)), "read error: gone"),
+            (Error::ShortMessage, "short message"),
+        ];
+        for (err, text) in cases {
+            is_error(&err);
+            assert_eq!(err.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn classify_responses() {
+        use crate::base::iana::Rcode;
+        use crate::base::Serial;
+        use crate::rdata::{Ns, Soa, A};
+
+        type Data = AllRecordData<Vec<u8>, Name<Vec<u8>>>;
+
+        fn name(s: &str) -> Name<Vec<u8>> {
+            Name::from_str(s).unwrap()
+        }
+
+        // Builds a response to www.example. A with the given rcode and
+        // records.
+        fn response(
+            rcode: Rcode,
+            answer: &[(&str, Data)],
+            authority: &[(&str, Data)],
+        ) -> Message<Vec<u8>> {
+            let mut msg = MessageBuilder::new_vec();
+            msg.header_mut().set_qr(true);
+            msg.header_mut().set_rcode(rcode);
+            let mut msg = msg.question();
+            msg.push((name("www.example."), Rtype::A)).unwrap();
+            let mut msg = msg.answer();
+            for (owner, data) in answer {
+                msg.push((name(owner), 3600, data)).unwrap();
+            }
+            let mut msg = msg.authority();
+            for (owner, data) in authority {
+                msg.push((name(owner), 3600, data)).unwrap();
+            }
+            msg.into_message()
+        }
+
+        let soa: Data = Soa::new(
+            name("ns.example."),
+            name("hostmaster.example."),
+            Serial(1),
+            Ttl::from_secs(3600),
+            Ttl::from_secs(300),
+            Ttl::from_secs(604800),
+            Ttl::from_secs(300),
+        )
+        .into();
+        let a: Data = A::from_octets(192, 0, 2, 1).into();
+        let ns: Data = Ns::new(name("ns.www.example.")).into();
+        let cname: Data = Cname::new(name("www.example.net.")).into();
+
+        let msg =
+            response(Rcode::NOERROR, &[("www.example.", a.clone())], &[]);
+        assert_eq!(classify_response(&msg), Ok(ResponseKind::Positive));
+
+        let msg = response(Rcode::NOERROR, &[], &[("example.", soa.clone())]);
+        assert_eq!(classify_response(&msg), Ok(ResponseKind::NoData));
+
+        let msg = response(Rcode::NXDOMAIN, &[], &[("example.", soa)]);
+        assert_eq!(classify_response(&msg), Ok(ResponseKind::NxDomain));
+
+        let msg = response(Rcode::NOERROR, &[], &[("www.example.", ns)]);
+        assert_eq!(classify_response(&msg), Ok(ResponseKind::Referral));
+
+        let msg =
+            response(Rcode::NOERROR, &[("www.example.", cname.clone())], &[]);
+        assert_eq!(classify_response(&msg), Ok(ResponseKind::CnameChain));
+
+        // A chain that ends in an answer is positive.
+        let msg = response(
+            Rcode::NOERROR,
+            &[("www.example.", cname), ("www.example.net.", a)],
+            &[],
+        );
+        assert_eq!(classify_response(&msg), Ok(ResponseKind::Positive));
+    }
+
+    #[tokio::test]
+    async fn max_depth_is_indeterminate() {
+        let ta = TrustAnchors::from_u8(
+            b". 172800 IN DS 20326 8 2 \
+            E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D",
+        )
+        .unwrap();
+        let mut config = Config::new();
+        config.set_max_depth(4);
+        let vc = ValidationContext::with_config(ta, NoUpstream, config);
+
+        let name = Name::<Bytes>::from_str("a.b.c.d.e.f.g.h.").unwrap();
+        let node = vc.get_node::<Vec<u8>>(&name).await.unwrap();
+        assert_eq!(node.validation_state(), ValidationState::Indeterminate);
+        assert_eq!(node.signer_name(), &name);
+    }
+
+    #[test]
+    fn expired_node_ttl_is_zero() {
+        let node = Node::new_delegation(
+            Name::root(),
+            ValidationState::Insecure,
+            Vec::new(),
+            None,
+            Duration::ZERO,
+        );
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(node.expired());
+        assert_eq!(node.ttl(), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn below_insecure_cut_is_insecure() {
+        let ta = TrustAnchors::from_u8(
+            b". 172800 IN DS 20326 8 2 \
+            E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D",
+        )
+        .unwrap();
+        let mut config = Config::new();
+        config.set_max_depth(4);
+        let vc = ValidationContext::with_config(ta, NoUpstream, config);
+
+        // Pretend that insecure.example has been proven insecure.
+        let cut = Name::<Bytes>::from_str("insecure.example.").unwrap();
+        let node = Node::new_delegation(
+            cut.clone(),
+            ValidationState::Insecure,
+            Vec::new(),
+            None,
+            Duration::from_secs(3600),
+        );
+        vc.node_cache.insert(cut.clone(), Arc::new(node)).await;
+
+        // Names below the cut must not cause an upstream query.
+        for name in ["www.insecure.example.", "a.b.insecure.example."] {
+            let name = Name::<Bytes>::from_str(name).unwrap();
+            let node = vc.get_node::<Vec<u8>>(&name).await.unwrap();
+            assert_eq!(node.validation_state(), ValidationState::Insecure);
+            assert_eq!(node.signer_name(), &cut);
+        }
+
+        // The maximum depth still applies.
+        let name =
+            Name::<Bytes>::from_str("a.b.c.d.insecure.example.").unwrap();
+        let node = vc.get_node::<Vec<u8>>(&name).await.unwrap();
+        assert_eq!(node.validation_state(), ValidationState::Indeterminate);
+    }
+
+    #[tokio::test]
+    async fn walk_without_names_is_indeterminate() {
+        let ta = TrustAnchors::from_u8(
+            b". 172800 IN DS 20326 8 2 \
+            E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D",
+        )
+        .unwrap();
+        let vc = ValidationContext::new(ta, NoUpstream);
+
+        // A secure closest node with nothing left to walk. This can't
+        // happen via find_closest_node but must not return the node for
+        // the ancestor.
+        let parent = Name::<Bytes>::from_str("example.").unwrap();
+        let name = Name::<Bytes>::from_str("www.example.").unwrap();
+        let node = Arc::new(Node::new_delegation(
+            parent,
+            ValidationState::Secure,
+            Vec::new(),
+            None,
+            Duration::from_secs(3600),
+        ));
+        let mut chain = Vec::new();
+        let node = vc
+            .walk_to_name::<Vec<u8>>(
+                &name,
+                node,
+                VecDeque::new(),
+                &mut Some(&mut chain),
+            )
+            .await
+            .unwrap();
+        assert_eq!(node.validation_state(), ValidationState::Indeterminate);
+        assert_eq!(node.signer_name(), &name);
+        assert!(node.extended_error().is_some());
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain[0].name, name);
+
+        // The result must not have been cached.
+        assert!(vc.cache_lookup(&name).await.is_none());
+    }
+
+    /// An upstream that must not be queried.
+    #[derive(Debug)]
+    struct NoUpstream;
+
+    impl SendRequest<RequestMessage<Vec<u8>>> for NoUpstream {
+        fn send_request(
+            &self,
+            _request_msg: RequestMessage<Vec<u8>>,
+        ) -> Box<dyn GetResponse + Send + Sync> {
+            panic!("unexpected upstream query");
+        }
+    }
+
+    #[test]
+    fn verify_dnskey_rrset_valid() {
+        set_root_sig_time();
+        let (dnskeys, rrsigs, ds_set) = root_dnskey_rrset();
+        let (state, ede) = verify_dnskey_rrset(&dnskeys, &rrsigs, &ds_set);
+        assert_eq!(state, ValidationState::Secure);
+        assert!(ede.is_none());
+    }
+
+    #[test]
+    fn verify_dnskey_rrset_ds_mismatch() {
+        set_root_sig_time();
+        let (dnskeys, rrsigs, ds_set) = root_dnskey_rrset();
+        let ds = ds_set[0].data();
+        let mut digest = ds.digest().to_vec();
+        digest[0] ^= 0xff;
+        let ds_set = [Record::new(
+            Name::root(),
+            Class::IN,
+            Ttl::DAY,
+            Ds::new(
+                ds.key_tag(),
+                ds.algorithm(),
+                ds.digest_type(),
+                Bytes::from(digest),
+            )
+            .unwrap(),
+        )];
+
+        let (state, ede) = verify_dnskey_rrset(&dnskeys, &rrsigs, &ds_set);
+        assert_eq!(state, ValidationState::Bogus);
+        assert_eq!(ede.unwrap().code(), ExtendedErrorCode::DNSKEY_MISSING);
+    }
+
+    #[test]
+    fn verify_dnskey_rrset_bad_signature() {
+        set_root_sig_time();
+        let (dnskeys, rrsigs, ds_set) = root_dnskey_rrset();
+        let rrsig = rrsigs[0].data();
+        let mut signature = rrsig.signature().to_vec();
+        signature[0] ^= 0xff;
+        let rrsigs = [Record::new(
+            Name::root(),
+            Class::IN,
+            Ttl::DAY,
+            Rrsig::new(
+                rrsig.type_covered(),
+                rrsig.algorithm(),
+                rrsig.labels(),
+                rrsig.original_ttl(),
+                rrsig.expiration(),
+                rrsig.inception(),
+                rrsig.key_tag(),
+                rrsig.signer_name().clone(),
+                Bytes::from(signature),
+            )
+            .unwrap(),
+        )];
+
+        let (state, ede) = verify_dnskey_rrset(&dnskeys, &rrsigs, &ds_set);
+        assert_eq!(state, ValidationState::Bogus);
+        assert_eq!(ede.unwrap().code(), ExtendedErrorCode::DNSSEC_BOGUS);
+    }
+
+    /// Sets the clock to a time within the validity of the signature
+    /// returned by [`root_dnskey_rrset`].
+    fn set_root_sig_time() {
+        MockClock::set_system_time(Duration::from_secs(1559000000));
+    }
+
+    /// Returns the root DNSKEY RRset of May 2019 with the KSK's signature
+    /// and the DS record of the KSK.
+    #[allow(clippy::type_complexity)]
+    fn root_dnskey_rrset() -> (
+        Vec<Record<Name<Bytes>, Dnskey<Bytes>>>,
+        Vec<Record<Name<Bytes>, Rrsig<Bytes, Name<Bytes>>>>,
+        Vec<Record<Name<Bytes>, Ds<Bytes>>>,
+    ) {
+        let signature =
+            base64::decode::<Bytes>(ROOT_DNSKEY_SIGNATURE).unwrap();
+        let digest = base64::decode::<Bytes>(
+            "4G1EuAuPHTmpXAsNfGXQhFjogECbvGg0VxBCN8f47I0=",
+        )
+        .unwrap();
+
+        let ttl = Ttl::from_secs(172800);
+        let dnskeys = root_dnskey_records();
+        let rrsigs = vec![Record::new(
+            Name::root(),
+            Class::IN,
+            ttl,
+            Rrsig::new(
+                Rtype::DNSKEY,
+                SecurityAlgorithm::RSASHA256,
+                0,
+                ttl,
+                1560211200.into(),
+                1558396800.into(),
+                20326,
+                Name::root(),
+                signature,
+            )
+            .unwrap(),
+        )];
+        let ds_set = vec![Record::new(
+            Name::root(),
+            Class::IN,
+            ttl,
+            Ds::new(
+                20326,
+                SecurityAlgorithm::RSASHA256,
+                DigestAlgorithm::SHA256,
+                digest,
+            )
+            .unwrap(),
+        )];
+        (dnskeys, rrsigs, ds_set)
+    }
+
+    #[test]
+    fn node_debug_summary() {
+        let ksk = Dnskey::new(
+            257,
+            3,
+            SecurityAlgorithm::ECDSAP256SHA256,
+            Bytes::from_static(b"ksk"),
+        )
+        .unwrap();
+        let zsk = Dnskey::new(
+            256,
+            3,
+            SecurityAlgorithm::ECDSAP256SHA256,
+            Bytes::from_static(b"zsk"),
+        )
+        .unwrap();
+        let name = Name::<Bytes>::from_str("example.").unwrap();
+        let node = Node::new_delegation(
+            name.clone(),
+            ValidationState::Secure,
+            vec![ksk.clone(), zsk.clone()],
+            None,
+            Duration::from_secs(3600),
+        );
+
+        let summary = node.debug_summary();
+        assert_eq!(summary.state, ValidationState::Secure);
+        assert_eq!(summary.signer_name, name);
+        assert_eq!(summary.key_tags, vec![ksk.key_tag(), zsk.key_tag()]);
+        assert!(!summary.intermediate);
+        assert!(summary.ttl <= Duration::from_secs(3600));
+        assert!(summary.ttl > Duration::from_secs(3500));
+    }
+}
@@ -6,11 +6,12 @@
 //! Name suggested by Yorgos: SignedRrset. Problem, sometimes there are no
 //! signatures, sometimes there is a signature but no RRset.
 
+use super::budget;
 use super::context::{
-    Config, Error, Node, ValidationContext, ValidationState,
+    Config, Error, MissingRrsigPolicy, Node, ValidationContext,
+    ValidationState,
 };
 use super::utilities::{make_ede, map_dname, ttl_for_sig};
-use crate::base::cmp::CanonicalOrd;
 use crate::base::iana::class::Class;
 use crate::base::iana::ExtendedErrorCode;
 use crate::base::name::ToName;
@@ -20,7 +21,9 @@ use crate::base::{Name, ParsedName, ParsedRecord, Record, Rtype, Ttl};
 use crate::crypto::common::{DigestBuilder, DigestType};
 use crate::dep::octseq::builder::with_infallible;
 use crate::dep::octseq::{Octets, OctetsFrom};
-use crate::dnssec::validator::base::RrsigExt;
+use crate::dnssec::validator::base::{
+    check_rrsig_for_rrset, rrsig_matches_key, RrsigExt,
+};
 use crate::net::client::request::{RequestMessage, SendRequest};
 use crate::rdata::dnssec::Timestamp;
 use crate::rdata::{AllRecordData, Dnskey, Rrsig};
@@ -122,7 +125,7 @@ impl Group {
     fn add(&mut self, rr: &ParsedRecord<'_, Bytes>) -> Result<(), ()> {
         // First check owner.
         if let Some(frr) = self.rr_set.first() {
-            if frr.owner() != &rr.owner().to_name::<Bytes>() {
+            if *frr.owner() != rr.owner() {
                 return Err(());
             }
         } else if *self.sig_set[0].owner() != rr.owner() {
@@ -215,6 +218,33 @@ impl Group {
         Err(())
     }
 
+    /// Check whether a record belongs to the same RRset as this group
+    /// except for its class.
+    ///
+    /// For signatures, the type covered is compared with the type of the
+    /// group.
+    fn class_conflict(&self, rr: &ParsedRecord<'_, Bytes>) -> bool {
+        let (curr_owner, curr_class, curr_rtype) =
+            if let Some(frr) = self.rr_set.first() {
+                (frr.owner(), frr.class(), frr.rtype())
+            } else {
+                (
+                    self.sig_set[0].owner(),
+                    self.sig_set[0].class(),
+                    self.sig_set[0].data().type_covered(),
+                )
+            };
+        if *curr_owner != rr.owner() || curr_class == rr.class() {
+            return false;
+        }
+        let rtype = match rr.to_record::<Rrsig<_, _>>() {
+            Ok(Some(record)) => record.data().type_covered(),
+            Ok(None) => rr.rtype(),
+            Err(_) => return false,
+        };
+        rtype == curr_rtype
+    }
+
     /// Add extra records that are associated with a group.
     ///
     /// The main use at the moment is to store the courtesy CNAME that comes
@@ -305,6 +335,18 @@ impl Group {
         self.sig_set.len()
     }
 
+    /// Return the key tags of at most `max` signature records in a group.
+    ///
+    /// Keys and DS records with a key tag that is not in this list cannot
+    /// be used to validate the group.
+    pub fn sig_key_tags(&self, max: usize) -> Vec<u16> {
+        self.sig_set
+            .iter()
+            .take(max)
+            .map(|sig| sig.data().key_tag())
+            .collect()
+    }
+
     /// Return an iterator over the signature records in a group.
     pub fn sig_iter(&mut self) -> Iter<'_, SigType> {
         self.sig_set.iter()
@@ -443,15 +485,31 @@ impl Group {
         let group_dur = group_ttl.into_duration();
         let ttl = min(ttl, group_dur);
 
+        if self.sig_set_len() == 0
+            && config.missing_rrsig_policy() == MissingRrsigPolicy::Insecure
+        {
+            let ede =
+                make_ede(ExtendedErrorCode::RRSIGS_MISSING, "No signature");
+            return (ValidationState::Insecure, None, ede, ttl, None);
+        }
+
+        // Compute the key tags once. Signatures with a key tag that matches
+        // none of the keys are skipped without trying to verify them.
+        let key_tags: Vec<u16> =
+            keys.iter().map(|key| key.key_tag()).collect();
+
         let mut bad_sigs = 0;
-        for sig_rec in self.clone().sig_iter() {
+        let max_sigs = usize::from(config.max_signatures());
+        for sig_rec in self.clone().sig_iter().take(max_sigs) {
             let sig = sig_rec.data();
-            for key in keys {
+            if !key_tags.contains(&sig.key_tag()) {
+                continue; // No key for this signature.
+            }
+            for (key, &key_tag) in keys.iter().zip(key_tags.iter()) {
                 // See if this key matches the sig.
                 if key.algorithm() != sig.algorithm() {
                     continue;
                 }
-                let key_tag = key.key_tag();
                 if key_tag != sig.key_tag() {
                     continue;
                 }
@@ -529,7 +587,14 @@ impl Group {
             }
         }
 
-        if opt_ede.is_none() {
+        if self.sig_set_len() > max_sigs {
+            // Signatures were ignored. Report that instead of what happened
+            // with the signatures that were tried.
+            opt_ede = make_ede(
+                ExtendedErrorCode::DNSSEC_BOGUS,
+                "too many signatures",
+            );
+        } else if opt_ede.is_none() {
             opt_ede =
                 make_ede(ExtendedErrorCode::DNSSEC_BOGUS, "No signature");
         }
@@ -553,77 +618,23 @@ impl Group {
         key_name: &Name<Bytes>,
         key_tag: u16,
     ) -> bool {
-        let ts_now = Timestamp::now();
-        let rtype = self.rtype();
-        let owner = self.owner();
-        let labels = owner.iter().count() - 1;
         let rrsig = sig.data();
 
-        // RFC 4035, Section 5.3.1:
-        // - The RRSIG RR and the RRset MUST have the same owner name and the same
-        //   class.
-        if !sig.owner().name_eq(&owner) || sig.class() != self.class() {
-            return false;
-        }
-
-        // RFC 4035, Section 5.3.1:
-        // - The RRSIG RR's Signer's Name field MUST be the name of the zone that
-        //   contains the RRset.
-
-        // We don't really know the name of the zone that contains an RRset. What
-        // we can do is that the signer's name is a prefix of the owner name.
-        // We assume that a zone will not sign things in space that is delegated
-        // (except for the parent side of the delegation)
-        if !owner.ends_with(&signer_name) {
-            return false;
-        }
-
-        // RFC 4035, Section 5.3.1:
-        // - The RRSIG RR's Type Covered field MUST equal the RRset's type.
-        if rrsig.type_covered() != rtype {
-            return false;
-        }
-
-        // RFC 4035, Section 5.3.1:
-        // - The number of labels in the RRset owner name MUST be greater than
-        //   or equal to the value in the RRSIG RR's Labels field.
-        if labels < rrsig.labels() as usize {
-            return false;
-        }
-
-        // RFC 4035, Section 5.3.1:
-        // - The validator's notion of the current time MUST be less than or
-        //   equal to the time listed in the RRSIG RR's Expiration field.
-        // - The validator's notion of the current time MUST be greater than or
-        //   equal to the time listed in the RRSIG RR's Inception field.
-        if ts_now.canonical_gt(&rrsig.expiration())
-            || ts_now.canonical_lt(&rrsig.inception())
-        {
-            return false;
-        }
-
-        // RFC 4035, Section 5.3.1:
-        // - The RRSIG RR's Signer's Name, Algorithm, and Key Tag fields MUST
-        //   match the owner name, algorithm, and key tag for some DNSKEY RR in
-        //   the zone's apex DNSKEY RRset.
-        if signer_name != key_name
-            || rrsig.algorithm() != key.algorithm()
-            || rrsig.key_tag() != key_tag
+        // RFC 4035, Section 5.3.1.
+        if check_rrsig_for_rrset(
+            sig,
+            &self.owner(),
+            self.class(),
+            self.rtype(),
+            signer_name,
+            Timestamp::now(),
+        )
+        .is_err()
+            || !rrsig_matches_key(rrsig, signer_name, key, key_name, key_tag)
         {
             return false;
         }
 
-        // RFC 4035, Section 5.3.1:
-        // - The matching DNSKEY RR MUST be present in the zone's apex DNSKEY
-        //   RRset, and MUST have the Zone Flag bit (DNSKEY RDATA Flag bit 7)
-        //   set.
-
-        // We cannot check here if the key is in the zone's apex, that is up to
-        // the caller. Just check the Zone Flag bit.
-        if !key.is_zone_key() {
-            return false;
-        }
-
         //signature
         let mut signed_data = Vec::<u8>::new();
         rrsig
@@ -670,6 +681,10 @@ impl Group {
         if let Some(ce) = cache.cache.get(&cache_key).await {
             return ce;
         }
+        if !budget::charge() {
+            // Out of budget. Fail without caching the result.
+            return false;
+        }
         let res = self.check_sig(sig, signer_name, key, key_name, key_tag);
         cache.cache.insert(cache_key, res).await;
         res
@@ -749,6 +764,12 @@ impl GroupSet {
             }
         }
 
+        // The record does not fit in any group. If that is only because
+        // of its class then the section mixes classes for one RRset.
+        if self.0.iter().any(|g| g.class_conflict(&rr)) {
+            return Err(Error::MixedClass);
+        }
+
         // Add a new group.
         self.0.push(Group::new(rr)?);
         Ok(())
@@ -827,7 +848,7 @@ impl GroupSet {
                         panic!("DNAME expected");
                     };
                 if let AllRecordData::Cname(cname) = cname_rr.data() {
-                    if cname.cname().to_name::<Bytes>() == result_name {
+                    if *cname.cname() == result_name {
                         g.add_extra(cname_rr);
                         g.found_duplicate |= found_duplicate;
                         return true;
@@ -992,6 +1013,17 @@ impl ValidatedGroup {
     pub fn found_duplicate(&self) -> bool {
         self.found_duplicate
     }
+
+    /// Return the records of the group as a group that can be validated
+    /// again, for instance against a specific node.
+    pub(crate) fn to_group(&self) -> Group {
+        Group {
+            rr_set: self.rr_set.clone(),
+            sig_set: self.sig_set.clone(),
+            extra_set: self.extra_set.clone(),
+            found_duplicate: self.found_duplicate,
+        }
+    }
 }
 
 //----------- Helper functions -----------------------------------------------
@@ -1033,3 +1065,149 @@ impl SigCache {
         }
     }
 }
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::iana::SecurityAlgorithm;
+    use crate::base::{Message, MessageBuilder};
+    use crate::rdata::{Txt, A};
+    use core::str::FromStr;
+    use std::vec;
+
+    #[tokio::test]
+    async fn unknown_key_tags_are_not_verified() {
+        let key = Dnskey::new(
+            256,
+            3,
+            SecurityAlgorithm::ECDSAP256SHA256,
+            Bytes::from_static(b"key"),
+        )
+        .unwrap();
+        let key_tag = key.key_tag();
+        let zone = Name::<Bytes>::from_str("example.").unwrap();
+        let owner = Name::<Bytes>::from_str("www.example.").unwrap();
+
+        // An A record with signatures that all refer to absent keys.
+        let mut msg = MessageBuilder::new_vec().answer();
+        msg.push((&owner, 3600, A::from_octets(192, 0, 2, 1)))
+            .unwrap();
+        for i in 1..=3 {
+            let rrsig = Rrsig::new(
+                Rtype::A,
+                SecurityAlgorithm::ECDSAP256SHA256,
+                2,
+                Ttl::from_secs(3600),
+                Timestamp::from(2_000_000_000),
+                Timestamp::from(1_000_000_000),
+                key_tag.wrapping_add(i),
+                zone.clone(),
+                Bytes::from_static(&[0; 64]),
+            )
+            .unwrap();
+            msg.push((&owner, 3600, rrsig)).unwrap();
+        }
+        let msg = Message::from_octets(Bytes::from(msg.finish())).unwrap();
+        let mut groups = GroupSet::new();
+        for rr in msg.answer().unwrap() {
+            groups.add(rr.unwrap()).unwrap();
+        }
+        let group = groups.iter().next().unwrap();
+        assert_eq!(
+            group.sig_key_tags(8),
+            vec![
+                key_tag.wrapping_add(1),
+                key_tag.wrapping_add(2),
+                key_tag.wrapping_add(3)
+            ]
+        );
+        assert_eq!(group.sig_key_tags(1), vec![key_tag.wrapping_add(1)]);
+
+        let node = Node::new_delegation(
+            zone,
+            ValidationState::Secure,
+            vec![key],
+            None,
+            Duration::from_secs(3600),
+        );
+        let sig_cache = SigCache::new(100);
+        let (state, _, ede, _, _) = group
+            .validate_with_node(&node, &sig_cache, &Config::new())
+            .await;
+
+        // With the default limit of one bad signature, verifying the
+        // signatures would have resulted in "too many bad signatures".
+        assert_eq!(state, ValidationState::Bogus);
+        assert_eq!(
+            ede,
+            make_ede(ExtendedErrorCode::DNSSEC_BOGUS, "No signature")
+        );
+        sig_cache.cache.run_pending_tasks().await;
+        assert_eq!(sig_cache.cache.entry_count(), 0);
+    }
+
+    #[test]
+    fn add_duplicate_records() {
+        let owner = Name::<Bytes>::from_str("www.example.").unwrap();
+        let mut msg = MessageBuilder::new_vec().answer();
+        for _ in 0..2 {
+            msg.push((&owner, 3600, A::from_octets(192, 0, 2, 1)))
+                .unwrap();
+        }
+        msg.push((&owner, 3600, A::from_octets(192, 0, 2, 2)))
+            .unwrap();
+        let msg = Message::from_octets(Bytes::from(msg.finish())).unwrap();
+        let mut groups = GroupSet::new();
+        for rr in msg.answer().unwrap() {
+            groups.add(rr.unwrap()).unwrap();
+        }
+        let mut iter = groups.iter();
+        let group = iter.next().unwrap();
+        assert!(iter.next().is_none());
+        assert_eq!(group.rr_set().len(), 2);
+        assert!(group.found_duplicate);
+    }
+
+    #[test]
+    fn add_mixed_class_records() {
+        let owner = Name::<Bytes>::from_str("www.example.").unwrap();
+        let mut msg = MessageBuilder::new_vec().answer();
+        msg.push((&owner, 3600, A::from_octets(192, 0, 2, 1)))
+            .unwrap();
+        msg.push(Record::new(
+            &owner,
+            Class::CH,
+            Ttl::from_secs(3600),
+            A::from_octets(192, 0, 2, 2),
+        ))
+        .unwrap();
+        let msg = Message::from_octets(Bytes::from(msg.finish())).unwrap();
+        let mut answer = msg.answer().unwrap();
+        let mut groups = GroupSet::new();
+        groups.add(answer.next().unwrap().unwrap()).unwrap();
+        assert!(matches!(
+            groups.add(answer.next().unwrap().unwrap()),
+            Err(Error::MixedClass)
+        ));
+
+        // A different class with a different type is a separate RRset.
+        let mut msg = MessageBuilder::new_vec().answer();
+        msg.push((&owner, 3600, A::from_octets(192, 0, 2, 1)))
+            .unwrap();
+        msg.push(Record::new(
+            &owner,
+            Class::CH,
+            Ttl::from_secs(3600),
+            Txt::<Vec<u8>>::build_from_slice(b"text").unwrap(),
+        ))
+        .unwrap();
+        let msg = Message::from_octets(Bytes::from(msg.finish())).unwrap();
+        let mut groups = GroupSet::new();
+        for rr in msg.answer().unwrap() {
+            groups.add(rr.unwrap()).unwrap();
+        }
+        assert_eq!(groups.iter().count(), 2);
+    }
+}
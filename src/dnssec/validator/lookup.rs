@@ -0,0 +1,185 @@
+//! Looking up and validating a single name.
+//!
+//! This module provides the building blocks for a `delv`-like tool: the
+//! function [`lookup()`] sends a query for a name and record type to a
+//! resolver, validates the reply using a [`ValidationContext`] and returns
+//! a [`Lookup`] with the result. The [`Display`][fmt::Display] impl of
+//! [`Lookup`] prints the validation state, the extended error if any, and
+//! the records in the answer section.
+//!
+//! # Example
+//! ```no_run
+//! # use domain::base::{Name, Rtype};
+//! # use domain::dnssec::validator::anchor::TrustAnchors;
+//! # use domain::dnssec::validator::context::ValidationContext;
+//! # use domain::dnssec::validator::lookup::lookup;
+//! # use domain::net::client::dgram_stream;
+//! # use domain::net::client::protocol::{TcpConnect, UdpConnect};
+//! # use std::net::{IpAddr, SocketAddr};
+//! # use std::str::FromStr;
+//! #
+//! # async fn f(ta: TrustAnchors) {
+//! #     let server_addr = SocketAddr::new(IpAddr::from_str("::1").unwrap(), 53);
+//! #     let udp_connect = UdpConnect::new(server_addr);
+//! #     let tcp_connect = TcpConnect::new(server_addr);
+//! #     let (conn, transport) = dgram_stream::Connection::new(udp_connect, tcp_connect);
+//! #     tokio::spawn(async move {
+//! #         transport.run().await;
+//! #     });
+//!     let vc = ValidationContext::new(ta, conn.clone());
+//!     let qname = Name::from_str("example.com").unwrap();
+//!     let res = lookup(&conn, &vc, &qname, Rtype::AAAA).await.unwrap();
+//!     print!("{res}");
+//! # }
+//! ```
+
+use super::context::{
+    Error as ValidatorError, ValidationContext, ValidationState,
+};
+use crate::base::iana::Rtype;
+use crate::base::opt::ExtendedError;
+use crate::base::{Message, MessageBuilder, Name, ParsedName};
+use crate::dep::octseq::{Octets, OctetsFrom};
+use crate::net::client::request::{
+    ComposeRequest, Error as RequestError, RequestMessage, SendRequest,
+};
+use crate::rdata::AllRecordData;
+use bytes::Bytes;
+use std::error;
+use std::fmt::{self, Debug};
+use std::vec::Vec;
+
+//------------ lookup --------------------------------------------------------
+
+/// Looks up and validates a name and record type.
+///
+/// The query is sent with the RD and DO bits set through `conn`, which
+/// typically is a connection to a recursive resolver. The reply is then
+/// validated with `vc`. The upstream of `vc` can be the same connection.
+pub async fn lookup<Conn, Upstream, USOcts>(
+    conn: &Conn,
+    vc: &ValidationContext<Upstream>,
+    qname: &Name<Bytes>,
+    qtype: Rtype,
+) -> Result<Lookup, LookupError>
+where
+    Conn: SendRequest<RequestMessage<Vec<u8>>>,
+    USOcts: AsRef<[u8]> + Debug + Octets + OctetsFrom<Vec<u8>> + Send + Sync,
+    Upstream: SendRequest<RequestMessage<USOcts>>,
+{
+    let mut msg = MessageBuilder::new_vec();
+    msg.header_mut().set_rd(true);
+    let mut msg = msg.question();
+    msg.push((qname, qtype)).expect("should not fail");
+    let mut req = RequestMessage::new(msg)?;
+    req.set_dnssec_ok(true);
+
+    let mut reply = conn.send_request(req).get_response().await?;
+    let (state, ede) = vc.validate_bytes_msg(&mut reply).await?;
+    Ok(Lookup { state, ede, reply })
+}
+
+//------------ Lookup --------------------------------------------------------
+
+/// The result of validating the reply to a query.
+///
+/// See [`lookup()`].
+#[derive(Clone, Debug)]
+pub struct Lookup {
+    /// The validation state of the reply.
+    state: ValidationState,
+
+    /// The extended error explaining the state, if any.
+    ede: Option<ExtendedError<Vec<u8>>>,
+
+    /// The reply message.
+    reply: Message<Bytes>,
+}
+
+impl Lookup {
+    /// Returns the validation state of the reply.
+    pub fn state(&self) -> ValidationState {
+        self.state
+    }
+
+    /// Returns the extended error provided by the validator, if any.
+    pub fn extended_error(&self) -> Option<&ExtendedError<Vec<u8>>> {
+        self.ede.as_ref()
+    }
+
+    /// Returns the reply message.
+    ///
+    /// If the validator had to lower TTLs, the message is the rebuilt one.
+    pub fn reply(&self) -> &Message<Bytes> {
+        &self.reply
+    }
+}
+
+impl fmt::Display for Lookup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.state {
+            ValidationState::Secure => writeln!(f, "; fully validated")?,
+            ValidationState::Insecure => writeln!(f, "; unsigned answer")?,
+            ValidationState::Bogus => writeln!(f, "; validation failed")?,
+            ValidationState::Indeterminate => {
+                writeln!(f, "; validation indeterminate")?
+            }
+        }
+        if let Some(ede) = &self.ede {
+            writeln!(f, "; EDE: {ede}")?;
+        }
+        writeln!(f, "; rcode: {}", self.reply.opt_rcode())?;
+        if let Ok(answer) = self.reply.answer() {
+            for rr in answer.flatten() {
+                if let Ok(Some(rr)) =
+                    rr.into_record::<AllRecordData<_, ParsedName<_>>>()
+                {
+                    writeln!(f, "{rr}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+//------------ LookupError ---------------------------------------------------
+
+/// An error happened while looking up or validating a name.
+#[derive(Clone, Debug)]
+pub enum LookupError {
+    /// Sending the query or receiving the reply failed.
+    Request(RequestError),
+
+    /// The reply could not be validated.
+    Validation(ValidatorError),
+}
+
+impl From<RequestError> for LookupError {
+    fn from(err: RequestError) -> Self {
+        Self::Request(err)
+    }
+}
+
+impl From<ValidatorError> for LookupError {
+    fn from(err: ValidatorError) -> Self {
+        Self::Validation(err)
+    }
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Request(err) => write!(f, "request failed: {err}"),
+            Self::Validation(err) => write!(f, "validation failed: {err}"),
+        }
+    }
+}
+
+impl error::Error for LookupError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Request(err) => Some(err),
+            Self::Validation(err) => Some(err),
+        }
+    }
+}
@@ -31,6 +31,9 @@
 //! Low-level operations for computing the hash of a DNSKEY or verifying an
 //! RRSIG record are provided by the module [`base`].
 //!
+//! The module [`lookup`] combines sending a query and validating the reply
+//! for tools that look up and validate a single name.
+//!
 //! # Caching
 //! The validator has four caches:
 //! 1) A `node` cache that caches the DNSSEC status and (if needed) DNSKEY
@@ -120,7 +123,11 @@
 
 pub mod anchor;
 pub mod base;
+mod budget;
 pub mod context;
 mod group;
+pub mod lookup;
 mod nsec;
 mod utilities;
+
+pub use nsec::{nsec3_closest_encloser, Nsec3ClosestEncloser};
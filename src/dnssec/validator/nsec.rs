@@ -17,6 +17,7 @@ use crate::dnssec::common::nsec3_hash;
 use crate::rdata::nsec3::{Nsec3Salt, OwnerHash};
 use crate::rdata::{AllRecordData, Nsec, Nsec3};
 
+use super::budget;
 use super::context::{Config, ValidationState};
 use super::group::ValidatedGroup;
 use super::utilities::{make_ede, star_closest_encloser};
@@ -416,6 +417,9 @@ pub async fn nsec3_for_nodata(
     nsec3_cache: &Nsec3Cache,
     config: &Config,
 ) -> (Nsec3State, Option<ExtendedError<Vec<u8>>>) {
+    if let Err(ede) = check_nsec3_count(groups, config) {
+        return (Nsec3State::Bogus, ede);
+    }
     if rtype == Rtype::DS {
         // RFC 5155, Section 6 (Opt-Out):
         // An Opt-Out NSEC3 RR does not assert the existence or non-existence
@@ -657,6 +661,10 @@ pub async fn nsec3_for_not_exists(
     nsec3_cache: &Nsec3Cache,
     config: &Config,
 ) -> (Nsec3NXState, Option<ExtendedError<Vec<u8>>>) {
+    if let Err(ede) = check_nsec3_count(groups, config) {
+        return (Nsec3NXState::Bogus, ede);
+    }
+
     // We assume the target does not exist and the signer_name does exist.
     // Starting from signer_name and going towards target we check if a name
     // exists or not. We assume signer_name exists. If we find a name that
@@ -810,6 +818,10 @@ pub async fn nsec3_for_not_exists_no_ce(
     nsec3_cache: &Nsec3Cache,
     config: &Config,
 ) -> (Nsec3NXStateNoCE, Option<ExtendedError<Vec<u8>>>) {
+    if let Err(ede) = check_nsec3_count(groups, config) {
+        return (Nsec3NXStateNoCE::Bogus, ede);
+    }
+
     // Check whether the name exists, or is proven to not exist.
     for g in groups.iter() {
         let res_opt_nsec3_hash = get_checked_nsec3(g, signer_name, config);
@@ -906,6 +918,41 @@ pub async fn nsec3_for_nxdomain(
             return (Nsec3NXState::Bogus, ede);
         }
     };
+
+    // Hash all names of the proof with the parameters of each NSEC3 record
+    // in one go. All but the wildcard have been hashed while looking for the
+    // closest encloser and are taken from the cache. The wildcard check
+    // below then finds the hash of the wildcard in the cache, too.
+    let next_closer = target
+        .iter_suffixes()
+        .find(|n| n.label_count() == ce.label_count() + 1)
+        .unwrap_or_else(|| target.clone());
+    let names = [target.clone(), ce.clone(), next_closer, star_name.clone()];
+    let mut params = Vec::new();
+    for g in groups.iter() {
+        let Ok(Some((nsec3, _))) = get_checked_nsec3(g, signer_name, config)
+        else {
+            continue;
+        };
+        let param = (
+            nsec3.hash_algorithm(),
+            nsec3.iterations(),
+            nsec3.salt().clone(),
+        );
+        if params.contains(&param) {
+            continue;
+        }
+        let _ = cached_nsec3_hashes(
+            &names,
+            param.0,
+            param.1,
+            &param.2,
+            nsec3_cache,
+        )
+        .await;
+        params.push(param);
+    }
+
     let (state, new_ede) = nsec3_for_not_exists_no_ce(
         &star_name,
         groups,
@@ -934,6 +981,112 @@ pub async fn nsec3_for_nxdomain(
     }
 }
 
+/// A closest encloser proof using NSEC3 records.
+///
+/// See RFC 5155, Section 7.2.1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Nsec3ClosestEncloser {
+    /// The closest encloser, the longest existing ancestor of the name.
+    pub closest_encloser: Name<Bytes>,
+
+    /// The next closer name, the closest encloser prepended with the next
+    /// label of the name.
+    pub next_closer: Name<Bytes>,
+
+    /// Whether the NSEC3 record that covers the next closer name has the
+    /// opt-out flag set.
+    pub opt_out: bool,
+}
+
+/// Find the closest encloser of target and its next closer name.
+///
+/// The nsec3s argument contains NSEC3 records as pairs of owner name and
+/// record data. The records are expected to be validated and to belong to
+/// the zone with the apex zone. Records with an unsupported hash algorithm
+/// or a malformed owner name are ignored. The caller is responsible for
+/// rejecting records with a too high iteration count.
+///
+/// This implements the algorithm in RFC 5155, Section 8.3: the closest
+/// encloser is the longest ancestor of target that is matched by an NSEC3
+/// record and the next closer name needs to be covered by an NSEC3 record.
+/// Return None if no such proof exists, including when target itself is
+/// matched and thus exists.
+pub fn nsec3_closest_encloser(
+    target: &Name<Bytes>,
+    zone: &Name<Bytes>,
+    nsec3s: &[(Name<Bytes>, Nsec3<Bytes>)],
+) -> Option<Nsec3ClosestEncloser> {
+    let mut next_closer: Option<Name<Bytes>> = None;
+    for n in target.iter_suffixes() {
+        if !n.ends_with(zone) {
+            break;
+        }
+
+        let matching = nsec3s.iter().find_map(|(owner, nsec3)| {
+            let (hash, ownerhash) = nsec3_hashes(&n, owner, zone, nsec3)?;
+            (hash == ownerhash).then_some(nsec3)
+        });
+        let Some(matching) = matching else {
+            next_closer = Some(n);
+            continue;
+        };
+
+        // RFC 5155, Section 8.3, Point 3: the DNAME type bit must not be
+        // set and the NS type bit may only be set if the SOA type bit is
+        // set.
+        let types = matching.types();
+        if types.contains(Rtype::DNAME)
+            || (types.contains(Rtype::NS) && !types.contains(Rtype::SOA))
+        {
+            return None;
+        }
+
+        // If target itself is matched there is no next closer name.
+        let next_closer = next_closer?;
+        let covering = nsec3s.iter().find(|(owner, nsec3)| {
+            nsec3_hashes(&next_closer, owner, zone, nsec3).is_some_and(
+                |(hash, ownerhash)| {
+                    nsec3_in_range(&hash, &ownerhash, nsec3.next_owner())
+                },
+            )
+        })?;
+        return Some(Nsec3ClosestEncloser {
+            closest_encloser: n,
+            next_closer,
+            opt_out: covering.1.opt_out(),
+        });
+    }
+    None
+}
+
+/// Return the NSEC3 hash of name and the owner hash of an NSEC3 record.
+/// Return None if the record cannot be used.
+#[allow(clippy::type_complexity)]
+fn nsec3_hashes(
+    name: &Name<Bytes>,
+    owner: &Name<Bytes>,
+    zone: &Name<Bytes>,
+    nsec3: &Nsec3<Bytes>,
+) -> Option<(OwnerHash<Vec<u8>>, OwnerHash<Vec<u8>>)> {
+    if !supported_nsec3_hash(nsec3.hash_algorithm())
+        || owner.parent().as_ref() != Some(zone)
+    {
+        return None;
+    }
+    let ownerhash = nsec3_label_to_hash(owner.first()).ok()?;
+    if ownerhash.as_slice().len() != nsec3.next_owner().as_slice().len() {
+        return None;
+    }
+    let hash = nsec3_hash(
+        name,
+        nsec3.hash_algorithm(),
+        nsec3.iterations(),
+        nsec3.salt(),
+    )
+    .ok()?;
+    Some((hash, ownerhash))
+}
+
 /// The key of the NSEC3 cache. The name that needs to be hash, together
 /// with the hash algorithm, the number of iterations and the salt.
 #[derive(Eq, Hash, PartialEq)]
@@ -973,12 +1126,50 @@ pub async fn cached_nsec3_hash(
     if let Some(ce) = cache.cache.get(&key).await {
         return ce;
     }
+    if !budget::charge() {
+        // Out of budget. Return an empty hash, which does not match any
+        // NSEC3 owner. The validation result is discarded anyway.
+        return Arc::new(
+            OwnerHash::from_octets(Vec::new()).expect("should not fail"),
+        );
+    }
     let hash = nsec3_hash(owner, algorithm, iterations, salt).unwrap();
     let hash = Arc::new(hash);
     cache.cache.insert(key, hash.clone()).await;
     hash
 }
 
+/// Return the NSEC3 hashes of several names using a cache.
+///
+/// This is the same as calling [`cached_nsec3_hash`] for each name but
+/// names that appear more than once are only looked up and hashed once.
+/// This helps NXDOMAIN proofs that need the hashes of several related
+/// names with the same parameters, such as the closest encloser, the next
+/// closer name, and the wildcard.
+///
+/// The hashes are returned in the order of `owners`.
+pub async fn cached_nsec3_hashes(
+    owners: &[Name<Bytes>],
+    algorithm: Nsec3HashAlgorithm,
+    iterations: u16,
+    salt: &Nsec3Salt<Bytes>,
+    cache: &Nsec3Cache,
+) -> Vec<Arc<OwnerHash<Vec<u8>>>> {
+    let mut hashes: Vec<Arc<OwnerHash<Vec<u8>>>> =
+        Vec::with_capacity(owners.len());
+    for (i, owner) in owners.iter().enumerate() {
+        let hash = match owners[..i].iter().position(|n| n == owner) {
+            Some(pos) => hashes[pos].clone(),
+            None => {
+                cached_nsec3_hash(owner, algorithm, iterations, salt, cache)
+                    .await
+            }
+        };
+        hashes.push(hash);
+    }
+    hashes
+}
+
 /// Convert a label to an NSEC3 hash value.
 pub fn nsec3_label_to_hash(
     label: &Label,
@@ -1007,6 +1198,28 @@ where
     }
 }
 
+/// Check that there are not too many NSEC3 records in groups.
+///
+/// Every NSEC3 record may require computing a hash, so a response with a
+/// large number of them is rejected as bogus before any work is done.
+fn check_nsec3_count(
+    groups: &[ValidatedGroup],
+    config: &Config,
+) -> Result<(), Option<ExtendedError<Vec<u8>>>> {
+    let count: usize = groups
+        .iter()
+        .filter(|g| g.rtype() == Rtype::NSEC3)
+        .map(|g| g.rr_set().len())
+        .sum();
+    if count > config.max_nsec3_records().into() {
+        return Err(make_ede(
+            ExtendedErrorCode::DNSSEC_BOGUS,
+            "too many NSEC3 records",
+        ));
+    }
+    Ok(())
+}
+
 /// Return an NSEC3 record from a group also with it's owner hash value.
 /// Check if the NSEC3 record is valid. Return None if the checks fail.
 /// Return the validation state and optional extended error if the number of
@@ -1094,3 +1307,164 @@ fn get_checked_nsec3(
     // All check pass, return the NSEC3 record and the owner hash.
     Ok(Some((nsec3.clone(), ownerhash)))
 }
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod tests {
+    use std::{format, vec};
+
+    use super::*;
+    use crate::rdata::dnssec::RtypeBitmap;
+
+    /// The NSEC3 records from RFC 5155, Appendix B.1, which prove that
+    /// a.c.x.w.example does not exist.
+    fn rfc5155_b1() -> Vec<(Name<Bytes>, Nsec3<Bytes>)> {
+        vec![
+            nsec3(
+                "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom",
+                "2t7b4g4vsa5smi47k61mv5bv1a22bojr",
+                &[
+                    Rtype::NS,
+                    Rtype::SOA,
+                    Rtype::MX,
+                    Rtype::RRSIG,
+                    Rtype::DNSKEY,
+                    Rtype::NSEC3PARAM,
+                ],
+            ),
+            nsec3(
+                "b4um86eghhds6nea196smvmlo4ors995",
+                "gjeqe526plbf1g8mklp59enfd789njgi",
+                &[Rtype::MX, Rtype::RRSIG],
+            ),
+            nsec3(
+                "35mthgpgcu1qg68fab165klnsnk3dpvl",
+                "b4um86eghhds6nea196smvmlo4ors995",
+                &[Rtype::NS, Rtype::DS, Rtype::RRSIG],
+            ),
+        ]
+    }
+
+    #[test]
+    fn closest_encloser_rfc5155_name_error() {
+        let ce = nsec3_closest_encloser(
+            &n("a.c.x.w.example."),
+            &n("example."),
+            &rfc5155_b1(),
+        )
+        .unwrap();
+        assert_eq!(ce.closest_encloser, n("x.w.example."));
+        assert_eq!(ce.next_closer, n("c.x.w.example."));
+        assert!(ce.opt_out);
+    }
+
+    #[test]
+    fn closest_encloser_of_existing_name() {
+        // x.w.example is matched by an NSEC3 record, so it exists.
+        assert_eq!(
+            nsec3_closest_encloser(
+                &n("x.w.example."),
+                &n("example."),
+                &rfc5155_b1()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn closest_encloser_without_covering_nsec3() {
+        // Without the record covering c.x.w.example there is no proof.
+        let mut nsec3s = rfc5155_b1();
+        nsec3s.remove(0);
+        assert_eq!(
+            nsec3_closest_encloser(
+                &n("a.c.x.w.example."),
+                &n("example."),
+                &nsec3s
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn closest_encloser_wrong_zone() {
+        // The records do not belong to example.org.
+        assert_eq!(
+            nsec3_closest_encloser(
+                &n("a.c.x.w.example.org."),
+                &n("example.org."),
+                &rfc5155_b1()
+            ),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn batched_hashes_equal_individual_hashes() {
+        let names = [
+            n("a.c.x.w.example."),
+            n("c.x.w.example."),
+            n("x.w.example."),
+            n("*.x.w.example."),
+            n("X.W.example."),
+        ];
+        let salt = Nsec3Salt::from_str("aabbccdd").unwrap();
+
+        let batched = cached_nsec3_hashes(
+            &names,
+            Nsec3HashAlgorithm::SHA1,
+            12,
+            &salt,
+            &Nsec3Cache::new(100),
+        )
+        .await;
+        assert_eq!(batched.len(), names.len());
+
+        let cache = Nsec3Cache::new(100);
+        for (name, hash) in names.iter().zip(&batched) {
+            let single = cached_nsec3_hash(
+                name,
+                Nsec3HashAlgorithm::SHA1,
+                12,
+                &salt,
+                &cache,
+            )
+            .await;
+            assert_eq!(single, *hash);
+        }
+
+        // RFC 5155, Appendix B.1: x.w.example matches this NSEC3 record.
+        assert_eq!(
+            *batched[2],
+            OwnerHash::<Vec<u8>>::from_str(
+                "b4um86eghhds6nea196smvmlo4ors995"
+            )
+            .unwrap()
+        );
+    }
+
+    fn n(name: &str) -> Name<Bytes> {
+        Name::from_str(name).unwrap()
+    }
+
+    fn nsec3(
+        owner: &str,
+        next: &str,
+        types: &[Rtype],
+    ) -> (Name<Bytes>, Nsec3<Bytes>) {
+        let mut bitmap = RtypeBitmap::<Bytes>::builder();
+        for rtype in types {
+            bitmap.add(*rtype).unwrap();
+        }
+        let data = Nsec3::new(
+            Nsec3HashAlgorithm::SHA1,
+            1,
+            12,
+            Nsec3Salt::from_str("aabbccdd").unwrap(),
+            OwnerHash::from_str(next).unwrap(),
+            bitmap.finalize(),
+        );
+        (n(&format!("{owner}.example.")), data)
+    }
+}
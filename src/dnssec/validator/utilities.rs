@@ -1,7 +1,9 @@
 //! A collection of utility functions.
 
-use super::context::{Config, Error, ValidationState};
-use super::group::ValidatedGroup;
+use super::context::{
+    Config, Error, Node, ValidationContext, ValidationState,
+};
+use super::group::{SigCache, ValidatedGroup};
 use super::nsec::{nsec3_for_not_exists_no_ce, nsec_for_not_exists};
 use super::nsec::{Nsec3Cache, Nsec3NXStateNoCE, NsecNXState};
 use crate::base::iana::{Class, ExtendedErrorCode};
@@ -13,11 +15,13 @@ use crate::base::{
     Message, MessageBuilder, Name, NameBuilder, ParsedName, Record,
     RecordSectionBuilder, Rtype, StaticCompressor, ToName, Ttl,
 };
-use crate::dep::octseq::{OctetsFrom, OctetsInto};
+use crate::dep::octseq::{Octets, OctetsFrom, OctetsInto};
+use crate::net::client::request::{RequestMessage, SendRequest};
 use crate::rdata::dnssec::Timestamp;
 use crate::rdata::{AllRecordData, Dname, Rrsig};
 use bytes::Bytes;
 use std::cmp::min;
+use std::fmt::Debug;
 use std::vec::Vec;
 
 //----------- Helper functions -----------------------------------------------
@@ -25,6 +29,12 @@ use std::vec::Vec;
 /// Go through the answer section and resolve qname as much as possible
 /// using the available CNAME and DNAME records. Return the final name
 /// and validation state and well as an optional extended error.
+///
+/// The chain is only followed as far as the records are present in
+/// `answers`. If a target is not in the message, it is returned as the
+/// final name and the state covers only the records that were followed.
+/// The last element of the result is the signer name of the last CNAME
+/// or DNAME that was followed, if any.
 pub async fn do_cname_dname(
     qname: Name<Bytes>,
     qclass: Class,
@@ -33,8 +43,14 @@ pub async fn do_cname_dname(
     authorities: &mut [ValidatedGroup],
     nsec3_cache: &Nsec3Cache,
     config: &Config,
-) -> (Name<Bytes>, ValidationState, Option<ExtendedError<Vec<u8>>>) {
+) -> (
+    Name<Bytes>,
+    ValidationState,
+    Option<ExtendedError<Vec<u8>>>,
+    Option<Name<Bytes>>,
+) {
     let mut name = qname;
+    let mut signer = None;
     let mut count = 0;
     let mut maybe_secure = ValidationState::Secure;
     'name_loop: loop {
@@ -77,18 +93,19 @@ pub async fn do_cname_dname(
                         // totest, CNAME from wildcard with bad non-existance
                         // proof
                         // Report failure
-                        return (name, ValidationState::Bogus, ede);
+                        return (name, ValidationState::Bogus, ede, None);
                     }
                 }
                 name = cname.cname().to_name();
                 maybe_secure = map_maybe_secure(g.state(), maybe_secure);
+                signer = Some(g.signer_name());
                 count += 1;
                 if count > config.max_cname_dname() {
                     let ede = make_ede(
                         ExtendedErrorCode::DNSSEC_BOGUS,
                         "too many DNAME/CNAME records in sequence",
                     );
-                    return (name, ValidationState::Bogus, ede);
+                    return (name, ValidationState::Bogus, ede, None);
                 }
                 continue 'name_loop;
             }
@@ -111,7 +128,7 @@ pub async fn do_cname_dname(
                         ExtendedErrorCode::DNSSEC_BOGUS,
                         "DNAME from wildcard",
                     );
-                    return (owner, ValidationState::Bogus, ede);
+                    return (owner, ValidationState::Bogus, ede, None);
                 }
 
                 name = match map_dname(&owner, dname, &name) {
@@ -121,10 +138,11 @@ pub async fn do_cname_dname(
                             ExtendedErrorCode::DNSSEC_BOGUS,
                             "Failed to expand DNAME",
                         );
-                        return (owner, ValidationState::Bogus, ede);
+                        return (owner, ValidationState::Bogus, ede, None);
                     }
                 };
                 maybe_secure = map_maybe_secure(g.state(), maybe_secure);
+                signer = Some(g.signer_name());
                 count += 1;
                 if count > config.max_cname_dname() {
                     // totest, loop with too many DNAME records
@@ -132,7 +150,7 @@ pub async fn do_cname_dname(
                         ExtendedErrorCode::DNSSEC_BOGUS,
                         "too many DNAME/CNAME records in sequence",
                     );
-                    return (owner, ValidationState::Bogus, ede);
+                    return (owner, ValidationState::Bogus, ede, None);
                 }
                 continue 'name_loop;
             }
@@ -144,7 +162,7 @@ pub async fn do_cname_dname(
         break;
     }
 
-    (name, maybe_secure, None)
+    (name, maybe_secure, None, signer)
 }
 
 /// Apply a DNAME to a target name.
@@ -216,17 +234,28 @@ pub fn get_answer_state(
 }
 
 /// Try to find a SOA record that is a parent of  qname and qclass. Return None
-/// is nothing was found. Otherwise, return the validation state,
-/// and the signer name. Optionally return an extended error.
+/// is nothing was found. Otherwise, validate the SOA RRset against the node
+/// of its signer and return the validation state, the signer name, and the
+/// TTL for which the negative answer can be cached. Optionally return an
+/// extended error.
 #[allow(clippy::type_complexity)]
-pub fn get_soa_state(
+pub async fn get_soa_state<Upstream, Octs>(
+    vc: &ValidationContext<Upstream>,
     qname: &Name<Bytes>,
     qclass: Class,
     groups: &mut [ValidatedGroup],
-) -> (
-    Option<(ValidationState, Name<Bytes>)>,
-    Option<ExtendedError<Vec<u8>>>,
-) {
+    config: &Config,
+) -> Result<
+    (
+        Option<(ValidationState, Name<Bytes>, Ttl)>,
+        Option<ExtendedError<Vec<u8>>>,
+    ),
+    Error,
+>
+where
+    Octs: AsRef<[u8]> + Debug + Octets + OctetsFrom<Vec<u8>> + Send + Sync,
+    Upstream: SendRequest<RequestMessage<Octs>>,
+{
     let mut ede = None;
     for g in groups.iter() {
         if g.rtype() != Rtype::SOA {
@@ -250,9 +279,59 @@ pub fn get_soa_state(
             );
             continue;
         }
-        return (Some((g.state(), g.signer_name())), g.ede());
+        let signer_name = g.signer_name();
+        let node = vc.get_node(&signer_name).await?;
+        let (state, ttl, ede) =
+            soa_state(g, &node, vc.usig_cache(), config).await;
+        return Ok((Some((state, signer_name, ttl)), ede));
+    }
+    Ok((None, ede))
+}
+
+/// Validate the SOA RRset from the authority section of a NODATA or
+/// NXDOMAIN response against the node of its signer. Return the
+/// validation state and the TTL for which the negative answer can be
+/// cached. Optionally return an extended error.
+///
+/// The SOA RRset is what the proof of non-existence is anchored in, so it
+/// has to validate by itself. A SOA RRset cannot be the result of wildcard
+/// expansion, so a signature that indicates an expanded wildcard makes the
+/// SOA RRset bogus.
+///
+/// The TTL is the minimum of the TTL of the SOA record, its MINIMUM field
+/// (see [RFC 2308, Section 5]), and the TTL of the signature that
+/// validated the RRset.
+///
+/// [RFC 2308, Section 5]: https://www.rfc-editor.org/rfc/rfc2308#section-5
+pub(crate) async fn soa_state(
+    group: &ValidatedGroup,
+    node: &Node,
+    sig_cache: &SigCache,
+    config: &Config,
+) -> (ValidationState, Ttl, Option<ExtendedError<Vec<u8>>>) {
+    let mut ttl = Ttl::MAX;
+    for rr in group.rr_set() {
+        ttl = min(ttl, rr.ttl());
+        if let AllRecordData::Soa(soa) = rr.data() {
+            ttl = min(ttl, soa.minimum());
+        }
+    }
+
+    let (state, wildcard, ede, _, adjust_ttl) = group
+        .to_group()
+        .validate_with_node(node, sig_cache, config)
+        .await;
+    if let Some(adjust_ttl) = adjust_ttl {
+        ttl = min(ttl, adjust_ttl);
+    }
+    if state == ValidationState::Secure && wildcard.is_some() {
+        let ede = make_ede(
+            ExtendedErrorCode::DNSSEC_BOGUS,
+            "SOA from wildcard expansion",
+        );
+        return (ValidationState::Bogus, ttl, ede);
     }
-    (None, ede)
+    (state, ttl, ede)
 }
 
 /// Keep track if the current validation result is still secure or if it
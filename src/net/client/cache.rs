@@ -974,28 +974,10 @@ fn validity(
         }
     }
 
-    let msg = msg.question();
-    let mut msg = msg.answer()?;
-    for rr in &mut msg {
-        let rr = rr?;
-        min_val =
-            min(min_val, Duration::from_secs(rr.ttl().as_secs() as u64));
-    }
-
-    let mut msg = msg.next_section()?.expect("section should be present");
-    for rr in &mut msg {
-        let rr = rr?;
-        min_val =
-            min(min_val, Duration::from_secs(rr.ttl().as_secs() as u64));
-    }
-
-    let msg = msg.next_section()?.expect("section should be present");
-    for rr in msg {
-        let rr = rr?;
-        if rr.rtype() != Rtype::OPT {
-            min_val =
-                min(min_val, Duration::from_secs(rr.ttl().as_secs() as u64));
-        }
+    // Messages without records and negative responses without a SOA
+    // record have no cache TTL and are limited by the above only.
+    if let Some(ttl) = msg.cache_ttl() {
+        min_val = min(min_val, ttl.into_duration());
     }
 
     Ok(min_val)
@@ -3,6 +3,11 @@
 //! This module implements a DNS client for use with datagram protocols, i.e.,
 //! message-oriented, connection-less, unreliable network protocols. In
 //! practice, this is pretty much exclusively UDP.
+//!
+//! Responses with the TC bit set are returned as they are. Use the
+//! [dgram_stream][super::dgram_stream] transport to automatically retry
+//! truncated responses over a stream transport, or set
+//! [`Config::set_truncated_is_error`] to receive an error instead.
 
 #![warn(missing_docs)]
 
@@ -73,6 +78,9 @@ pub struct Config {
 
     /// Receive buffer size.
     recv_size: usize,
+
+    /// Whether a truncated response is an error.
+    truncated_is_error: bool,
 }
 
 impl Config {
@@ -160,6 +168,24 @@ impl Config {
     pub fn recv_size(&self) -> usize {
         self.recv_size
     }
+
+    /// Sets whether a truncated response is reported as an error.
+    ///
+    /// If this is `true`, a response with the TC bit set results in a
+    /// [`QueryError`] of kind [`QueryErrorKind::Truncated`] so that the
+    /// caller can retry the request over a stream transport. Otherwise,
+    /// which is the default, the truncated response is returned.
+    ///
+    /// The [dgram_stream][super::dgram_stream] transport retries truncated
+    /// responses over a stream transport by itself and does not need this.
+    pub fn set_truncated_is_error(&mut self, value: bool) {
+        self.truncated_is_error = value
+    }
+
+    /// Returns whether a truncated response is reported as an error.
+    pub fn truncated_is_error(&self) -> bool {
+        self.truncated_is_error
+    }
 }
 
 impl Default for Config {
@@ -170,6 +196,7 @@ impl Default for Config {
             max_retries: MAX_RETRIES.default(),
             udp_payload_size: Some(DEF_UDP_PAYLOAD_SIZE),
             recv_size: DEF_RECV_SIZE,
+            truncated_is_error: false,
         }
     }
 }
@@ -308,6 +335,13 @@ where
                     continue;
                 }
 
+                if answer.header().tc()
+                    && self.state.config.truncated_is_error
+                {
+                    trace!("Received message is truncated");
+                    return Err(QueryError::truncated().into());
+                }
+
                 trace!("Received message is accepted");
                 return Ok(answer.octets_into());
             }
@@ -423,6 +457,14 @@ impl QueryError {
     fn receive(io: io::Error) -> Self {
         Self::new(QueryErrorKind::Receive, io)
     }
+
+    /// Create a new truncated response error.
+    fn truncated() -> Self {
+        Self::new(
+            QueryErrorKind::Truncated,
+            io::Error::other("response truncated"),
+        )
+    }
 }
 
 impl QueryError {
@@ -467,6 +509,12 @@ pub enum QueryErrorKind {
 
     /// Failed to read the response.
     Receive,
+
+    /// The response was truncated.
+    ///
+    /// This is only reported if [`Config::set_truncated_is_error`] is
+    /// enabled.
+    Truncated,
 }
 
 impl QueryErrorKind {
@@ -476,6 +524,7 @@ impl QueryErrorKind {
             Self::Connect => "connecting failed",
             Self::Send => "sending request failed",
             Self::Timeout | Self::Receive => "reading response failed",
+            Self::Truncated => "response truncated",
         }
     }
 }
@@ -487,6 +536,67 @@ impl fmt::Display for QueryErrorKind {
             Self::Send => "sending request failed",
             Self::Timeout => "request timeout",
             Self::Receive => "reading response failed",
+            Self::Truncated => "response truncated",
         })
     }
 }
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{MessageBuilder, Name, Rtype};
+    use crate::net::client::protocol::UdpConnect;
+    use crate::net::client::request::RequestMessage;
+    use std::vec;
+    use tokio::net::UdpSocket;
+
+    #[tokio::test]
+    async fn truncated_response() {
+        // A server that answers every request with an empty, truncated
+        // response.
+        let sock = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = sock.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = vec![0; 512];
+            loop {
+                let (len, peer) = sock.recv_from(&mut buf).await.unwrap();
+                let mut reply =
+                    Message::from_octets(buf[..len].to_vec()).unwrap();
+                reply.header_mut().set_qr(true);
+                reply.header_mut().set_tc(true);
+                sock.send_to(reply.as_slice(), peer).await.unwrap();
+            }
+        });
+
+        let mk_request = || {
+            let mut msg = MessageBuilder::new_vec().question();
+            msg.push((Name::<Vec<u8>>::root(), Rtype::SOA)).unwrap();
+            RequestMessage::new(msg).unwrap()
+        };
+
+        // By default, the truncated response is returned.
+        let conn = Connection::new(UdpConnect::new(addr));
+        let response = conn
+            .send_request(mk_request())
+            .get_response()
+            .await
+            .unwrap();
+        assert!(response.header().tc());
+
+        // Optionally, it is reported as an error.
+        let mut config = Config::new();
+        config.set_truncated_is_error(true);
+        let conn = Connection::with_config(UdpConnect::new(addr), config);
+        let err = conn
+            .send_request(mk_request())
+            .get_response()
+            .await
+            .unwrap_err();
+        let Error::Dgram(err) = err else {
+            panic!("unexpected error {err}");
+        };
+        assert!(matches!(err.kind(), QueryErrorKind::Truncated));
+    }
+}
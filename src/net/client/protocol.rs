@@ -7,7 +7,7 @@ use std::io;
 use std::net::SocketAddr;
 use std::task::{Context, Poll};
 use tokio::io::ReadBuf;
-use tokio::net::{TcpStream, UdpSocket};
+use tokio::net::{TcpSocket, TcpStream, UdpSocket};
 
 /// How many times do we try a new random port if we get ‘address in use.’
 const RETRY_RANDOM_PORT: usize = 10;
@@ -37,6 +37,9 @@ pub trait AsyncConnect {
 pub struct TcpConnect {
     /// Remote address to connect to.
     addr: SocketAddr,
+
+    /// Whether to try TCP Fast Open.
+    fast_open: bool,
 }
 
 impl TcpConnect {
@@ -44,7 +47,30 @@ impl TcpConnect {
     ///
     /// addr is the destination address to connect to.
     pub fn new(addr: SocketAddr) -> Self {
-        Self { addr }
+        Self {
+            addr,
+            fast_open: false,
+        }
+    }
+
+    /// Returns whether TCP Fast Open is tried for new connections.
+    pub fn fast_open(&self) -> bool {
+        self.fast_open
+    }
+
+    /// Sets whether TCP Fast Open should be tried for new connections.
+    ///
+    /// With TCP Fast Open ([RFC 7413]), the first query on a connection is
+    /// sent together with the SYN, saving a round trip if the server has
+    /// handed out a cookie before. If the operating system does not support
+    /// it or the server does not accept it, a regular TCP handshake is
+    /// done instead. Currently, TCP Fast Open is only used on Linux.
+    ///
+    /// The default is `false`.
+    ///
+    /// [RFC 7413]: https://datatracker.ietf.org/doc/html/rfc7413
+    pub fn set_fast_open(&mut self, value: bool) {
+        self.fast_open = value
     }
 }
 
@@ -59,7 +85,7 @@ impl AsyncConnect for TcpConnect {
     >;
 
     fn connect(&self) -> Self::Fut {
-        Box::pin(TcpStream::connect(self.addr))
+        Box::pin(connect_tcp(self.addr, self.fast_open))
     }
 }
 
@@ -77,6 +103,9 @@ pub struct TlsConnect {
 
     /// Remote address to connect to.
     addr: SocketAddr,
+
+    /// Whether to try TCP Fast Open.
+    fast_open: bool,
 }
 
 #[cfg(feature = "tokio-rustls")]
@@ -94,8 +123,23 @@ impl TlsConnect {
             client_config: client_config.into(),
             server_name,
             addr,
+            fast_open: false,
         }
     }
+
+    /// Returns whether TCP Fast Open is tried for new connections.
+    pub fn fast_open(&self) -> bool {
+        self.fast_open
+    }
+
+    /// Sets whether TCP Fast Open should be tried for new connections.
+    ///
+    /// If enabled, the TLS client hello is sent together with the SYN.
+    /// See [`TcpConnect::set_fast_open`] for details. The default is
+    /// `false`.
+    pub fn set_fast_open(&mut self, value: bool) {
+        self.fast_open = value
+    }
 }
 
 #[cfg(feature = "tokio-rustls")]
@@ -114,14 +158,73 @@ impl AsyncConnect for TlsConnect {
             tokio_rustls::TlsConnector::from(self.client_config.clone());
         let server_name = self.server_name.clone();
         let addr = self.addr;
+        let fast_open = self.fast_open;
         Box::pin(async move {
             let box_connection = Box::new(tls_connection);
-            let tcp = TcpStream::connect(addr).await?;
+            let tcp = connect_tcp(addr, fast_open).await?;
             box_connection.connect(server_name, tcp).await
         })
     }
 }
 
+//------------ connect_tcp ---------------------------------------------------
+
+/// Connects to a TCP server, trying TCP Fast Open if requested.
+///
+/// Failing to enable TCP Fast Open on the socket is not an error. The
+/// connection is then established the usual way.
+async fn connect_tcp(
+    addr: SocketAddr,
+    fast_open: bool,
+) -> Result<TcpStream, io::Error> {
+    if !fast_open {
+        return TcpStream::connect(addr).await;
+    }
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    let _ = enable_fast_open(&socket);
+    socket.connect(addr).await
+}
+
+/// Enables TCP Fast Open for connecting the socket.
+///
+/// With `TCP_FASTOPEN_CONNECT`, `connect` returns right away and the data
+/// of the first write is sent with the SYN. The kernel falls back to a
+/// regular handshake if the server does not support Fast Open.
+#[cfg(target_os = "linux")]
+fn enable_fast_open(socket: &TcpSocket) -> Result<(), io::Error> {
+    use std::os::fd::AsRawFd;
+
+    let enable: libc::c_int = 1;
+    // SAFETY: The option value is a c_int living for the whole call and
+    //         its size is passed along.
+    let res = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_FASTOPEN_CONNECT,
+            &enable as *const libc::c_int as *const libc::c_void,
+            core::mem::size_of_val(&enable) as libc::socklen_t,
+        )
+    };
+    if res == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Enables TCP Fast Open for connecting the socket.
+///
+/// This is not supported on this platform.
+#[cfg(not(target_os = "linux"))]
+fn enable_fast_open(_socket: &TcpSocket) -> Result<(), io::Error> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
 //------------ UdpConnect --------------------------------------------------
 
 /// Create new UDP connections.
@@ -1,4 +1,11 @@
 //! A transport that multiplexes requests over multiple redundant transports.
+//!
+//! Normally, [`Connection::new`] returns a [`Transport`] whose
+//! [`run`][Transport::run] method has to be spawned as a separate task. As
+//! an alternative, [`Connection::new_inline`] creates a connection that
+//! does all its bookkeeping while the requests are polled. This is useful
+//! in single-threaded or test contexts where spawning a task is
+//! inconvenient.
 
 use bytes::Bytes;
 
@@ -14,6 +21,7 @@ use std::cmp::Ordering;
 use std::fmt::{Debug, Formatter};
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 use std::vec::Vec;
 
 use tokio::sync::{mpsc, oneshot};
@@ -67,6 +75,9 @@ pub struct Config {
 
     /// Defer replies that report ServFail.
     defer_servfail: bool,
+
+    /// How requests are distributed over the transports.
+    mode: Mode,
 }
 
 impl Config {
@@ -99,6 +110,43 @@ impl Config {
     pub fn set_defer_servfail(&mut self, value: bool) {
         self.defer_servfail = value
     }
+
+    /// Return the value of the mode configuration variable.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Set the value of the mode configuration variable.
+    pub fn set_mode(&mut self, value: Mode) {
+        self.mode = value
+    }
+}
+
+//------------ Mode -----------------------------------------------------------
+
+/// How a request is distributed over the transports.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Mode {
+    /// Start with the transport with the lowest expected response time.
+    ///
+    /// If no reply arrives within the expected response time, the request
+    /// is also sent to the next transport. Occasionally a slower transport
+    /// is tried first to update its estimate.
+    #[default]
+    Adaptive,
+
+    /// Send the request to all transports at once and use the first
+    /// reply that arrives.
+    ///
+    /// Transport errors are always deferred in this mode.
+    Parallel,
+
+    /// Try the transports one by one in the order they were added.
+    ///
+    /// The next transport is only tried once the previous one has failed,
+    /// no matter how long that takes. Transport errors are always deferred
+    /// in this mode.
+    Failover,
 }
 
 //------------ Connection -----------------------------------------------------
@@ -113,11 +161,14 @@ where
     config: Config,
 
     /// To send a request to the runner.
-    sender: mpsc::Sender<ChanReq<Req>>,
+    sender: Sender<Req>,
 }
 
 impl<Req: Clone + Debug + Send + Sync + 'static> Connection<Req> {
     /// Create a new connection.
+    ///
+    /// The returned [`Transport`] has to be run, typically by spawning
+    /// its [`run`][Transport::run] method as a separate task.
     pub fn new() -> (Self, Transport<Req>) {
         Self::with_config(Default::default())
     }
@@ -125,7 +176,33 @@ impl<Req: Clone + Debug + Send + Sync + 'static> Connection<Req> {
     /// Create a new connection with a given config.
     pub fn with_config(config: Config) -> (Self, Transport<Req>) {
         let (sender, receiver) = mpsc::channel(DEF_CHAN_CAP);
-        (Self { config, sender }, Transport::new(receiver))
+        (
+            Self {
+                config,
+                sender: Sender::Channel(sender),
+            },
+            Transport::new(receiver),
+        )
+    }
+
+    /// Create a new connection without a background task.
+    ///
+    /// The connections and their response time estimates are kept by the
+    /// connection itself and are updated while requests are being polled,
+    /// so no [`Transport`] needs to be run.
+    pub fn new_inline() -> Self {
+        Self::with_config_inline(Default::default())
+    }
+
+    /// Create a new connection without a background task with a given
+    /// config.
+    ///
+    /// See [`new_inline`][Self::new_inline] for details.
+    pub fn with_config_inline(config: Config) -> Self {
+        Self {
+            config,
+            sender: Sender::Inline(Arc::new(Mutex::new(RunState::new()))),
+        }
     }
 
     /// Add a transport connection.
@@ -244,7 +321,7 @@ where
     conn_rt: Vec<ConnRT>,
 
     /// Channel to send requests to the run function.
-    sender: mpsc::Sender<ChanReq<Req>>,
+    sender: Sender<Req>,
 
     /// List of futures for outstanding requests.
     fut_list: FuturesUnordered<
@@ -404,13 +481,19 @@ impl<Req: Clone + Send + Sync + 'static> Query<Req> {
         config: Config,
         request_msg: Req,
         mut conn_rt: Vec<ConnRT>,
-        sender: mpsc::Sender<ChanReq<Req>>,
+        sender: Sender<Req>,
     ) -> Self {
         let conn_rt_len = conn_rt.len();
-        conn_rt.sort_unstable_by(conn_rt_cmp);
+
+        // Only the adaptive mode orders the transports by their expected
+        // response time. Failover keeps the order in which they were added.
+        let adaptive = config.mode == Mode::Adaptive;
+        if adaptive {
+            conn_rt.sort_unstable_by(conn_rt_cmp);
+        }
 
         // Do we want to probe a less performant upstream?
-        if conn_rt_len > 1 && random::<f64>() < PROBE_P {
+        if adaptive && conn_rt_len > 1 && random::<f64>() < PROBE_P {
             let index: usize = 1 + random::<usize>() % (conn_rt_len - 1);
 
             // Give the probe some head start. We may need a separate
@@ -445,18 +528,18 @@ impl<Req: Clone + Send + Sync + 'static> Query<Req> {
                     if self.conn_rt.is_empty() {
                         return Err(Error::NoTransportAvailable);
                     }
-                    self.state = QueryState::Probe(0);
+                    if self.config.mode == Mode::Parallel {
+                        for ind in 0..self.conn_rt.len() {
+                            self.start(ind);
+                        }
+                        self.state = QueryState::Wait;
+                    } else {
+                        self.state = QueryState::Probe(0);
+                    }
                     continue;
                 }
                 QueryState::Probe(ind) => {
-                    self.conn_rt[ind].start = Some(Instant::now());
-                    let fut = start_request(
-                        ind,
-                        self.conn_rt[ind].id,
-                        self.sender.clone(),
-                        self.request_msg.clone(),
-                    );
-                    self.fut_list.push(Box::pin(fut));
+                    self.start(ind);
                     let timeout = Instant::now() + self.conn_rt[ind].est_rt;
                     loop {
                         tokio::select! {
@@ -464,7 +547,7 @@ impl<Req: Clone + Send + Sync + 'static> Query<Req> {
                                 let res = res.expect("res should not be empty");
                                 match res.1 {
                                     Err(ref err) => {
-                                        if self.config.defer_transport_error {
+                                        if self.defer_transport_error() {
                                             if self.deferred_transport_error.is_none() {
                                                 self.deferred_transport_error = Some(err.clone());
                                             }
@@ -520,7 +603,9 @@ impl<Req: Clone + Send + Sync + 'static> Query<Req> {
                                 // Break out of receive loop
                                 break;
                             }
-                            _ = sleep_until(timeout) => {
+                            _ = sleep_until(timeout),
+                                if self.config.mode != Mode::Failover =>
+                            {
                                 // Move to the next Probe state if there
                                 // are more upstreams to try, otherwise
                                 // move to the Wait state.
@@ -598,7 +683,7 @@ impl<Req: Clone + Send + Sync + 'static> Query<Req> {
                         let res = res.expect("res should not be empty");
                         match res.1 {
                             Err(ref err) => {
-                                if self.config.defer_transport_error {
+                                if self.defer_transport_error() {
                                     if self.deferred_transport_error.is_none()
                                     {
                                         self.deferred_transport_error =
@@ -634,6 +719,24 @@ impl<Req: Clone + Send + Sync + 'static> Query<Req> {
             }
         }
     }
+
+    /// Start the request on the connection at index `ind`.
+    fn start(&mut self, ind: usize) {
+        self.conn_rt[ind].start = Some(Instant::now());
+        let fut = start_request(
+            ind,
+            self.conn_rt[ind].id,
+            self.sender.clone(),
+            self.request_msg.clone(),
+        );
+        self.fut_list.push(Box::pin(fut));
+    }
+
+    /// Returns whether a transport error should be deferred.
+    fn defer_transport_error(&self) -> bool {
+        self.config.defer_transport_error
+            || self.config.mode != Mode::Adaptive
+    }
 }
 
 //------------ Transport -----------------------------------------------------
@@ -656,103 +759,183 @@ impl<Req: Clone + Send + Sync + 'static> Transport<Req> {
 
     /// Run method.
     pub async fn run(mut self) {
-        let mut next_id: u64 = 10;
-        let mut conn_stats: Vec<ConnStats> = Vec::new();
-        let mut conn_rt: Vec<ConnRT> = Vec::new();
-        let mut conns: Vec<Box<dyn SendRequest<Req> + Send + Sync>> =
-            Vec::new();
+        let mut state = RunState::new();
+        while let Some(req) = self.receiver.recv().await {
+            state.handle(req);
+        }
+        // All references to connection objects are dropped. Shutdown.
+    }
+}
 
-        loop {
-            let req = match self.receiver.recv().await {
-                Some(req) => req,
-                None => break, // All references to connection objects are
-                               // dropped. Shutdown.
-            };
-            match req {
-                ChanReq::Add(add_req) => {
-                    let id = next_id;
-                    next_id += 1;
-                    conn_stats.push(ConnStats {
-                        mean: (DEFAULT_RT_MS as f64) / 1000.,
-                        mean_sq: 0.,
-                    });
-                    conn_rt.push(ConnRT {
-                        id,
-                        est_rt: DEFAULT_RT,
-                        start: None,
-                    });
-                    conns.push(add_req.conn);
-
-                    // Don't care if send fails
-                    let _ = add_req.tx.send(Ok(()));
-                }
-                ChanReq::GetRT(rt_req) => {
-                    // Don't care if send fails
-                    let _ = rt_req.tx.send(Ok(conn_rt.clone()));
-                }
-                ChanReq::Query(request_req) => {
-                    let opt_ind =
-                        conn_rt.iter().position(|e| e.id == request_req.id);
-                    match opt_ind {
-                        Some(ind) => {
-                            let query = conns[ind]
-                                .send_request(request_req.request_msg);
-                            // Don't care if send fails
-                            let _ = request_req.tx.send(Ok(query));
-                        }
-                        None => {
-                            // Don't care if send fails
-                            let _ = request_req
-                                .tx
-                                .send(Err(Error::RedundantTransportNotFound));
-                        }
+//------------ RunState ------------------------------------------------------
+
+/// The state kept by the run function.
+///
+/// This is owned by [`Transport::run`] or, for inline connections, shared
+/// by all clones of the connection.
+struct RunState<Req>
+where
+    Req: Send + Sync,
+{
+    /// The identifier for the next connection that is added.
+    next_id: u64,
+
+    /// Response time statistics for each connection.
+    conn_stats: Vec<ConnStats>,
+
+    /// The estimated response time for each connection.
+    conn_rt: Vec<ConnRT>,
+
+    /// The connections.
+    conns: Vec<Box<dyn SendRequest<Req> + Send + Sync>>,
+}
+
+impl<Req: Clone + Send + Sync + 'static> RunState<Req> {
+    /// Creates a new state without any connections.
+    fn new() -> Self {
+        Self {
+            next_id: 10,
+            conn_stats: Vec::new(),
+            conn_rt: Vec::new(),
+            conns: Vec::new(),
+        }
+    }
+
+    /// Handles a single command.
+    fn handle(&mut self, req: ChanReq<Req>) {
+        match req {
+            ChanReq::Add(add_req) => {
+                let id = self.next_id;
+                self.next_id += 1;
+                self.conn_stats.push(ConnStats {
+                    mean: (DEFAULT_RT_MS as f64) / 1000.,
+                    mean_sq: 0.,
+                });
+                self.conn_rt.push(ConnRT {
+                    id,
+                    est_rt: DEFAULT_RT,
+                    start: None,
+                });
+                self.conns.push(add_req.conn);
+
+                // Don't care if send fails
+                let _ = add_req.tx.send(Ok(()));
+            }
+            ChanReq::GetRT(rt_req) => {
+                // Don't care if send fails
+                let _ = rt_req.tx.send(Ok(self.conn_rt.clone()));
+            }
+            ChanReq::Query(request_req) => {
+                let opt_ind =
+                    self.conn_rt.iter().position(|e| e.id == request_req.id);
+                match opt_ind {
+                    Some(ind) => {
+                        let query = self.conns[ind]
+                            .send_request(request_req.request_msg);
+                        // Don't care if send fails
+                        let _ = request_req.tx.send(Ok(query));
                     }
-                }
-                ChanReq::Report(time_report) => {
-                    let opt_ind =
-                        conn_rt.iter().position(|e| e.id == time_report.id);
-                    if let Some(ind) = opt_ind {
-                        let elapsed = time_report.elapsed.as_secs_f64();
-                        conn_stats[ind].mean +=
-                            (elapsed - conn_stats[ind].mean) / SMOOTH_N;
-                        let elapsed_sq = elapsed * elapsed;
-                        conn_stats[ind].mean_sq +=
-                            (elapsed_sq - conn_stats[ind].mean_sq) / SMOOTH_N;
-                        let mean = conn_stats[ind].mean;
-                        let var = conn_stats[ind].mean_sq - mean * mean;
-                        let std_dev =
-                            if var < 0. { 0. } else { f64::sqrt(var) };
-                        let est_rt = mean + 3. * std_dev;
-                        conn_rt[ind].est_rt = Duration::from_secs_f64(est_rt);
+                    None => {
+                        // Don't care if send fails
+                        let _ = request_req
+                            .tx
+                            .send(Err(Error::RedundantTransportNotFound));
                     }
                 }
-                ChanReq::Failure(time_report) => {
-                    let opt_ind =
-                        conn_rt.iter().position(|e| e.id == time_report.id);
-                    if let Some(ind) = opt_ind {
-                        let elapsed = time_report.elapsed.as_secs_f64();
-                        if elapsed < conn_stats[ind].mean {
-                            // Do not update the mean if a
-                            // failure took less time than the
-                            // current mean.
-                            continue;
-                        }
-                        conn_stats[ind].mean +=
-                            (elapsed - conn_stats[ind].mean) / SMOOTH_N;
-                        let elapsed_sq = elapsed * elapsed;
-                        conn_stats[ind].mean_sq +=
-                            (elapsed_sq - conn_stats[ind].mean_sq) / SMOOTH_N;
-                        let mean = conn_stats[ind].mean;
-                        let var = conn_stats[ind].mean_sq - mean * mean;
-                        let std_dev =
-                            if var < 0. { 0. } else { f64::sqrt(var) };
-                        let est_rt = mean + 3. * std_dev;
-                        conn_rt[ind].est_rt = Duration::from_secs_f64(est_rt);
+            }
+            ChanReq::Report(time_report) => {
+                let opt_ind =
+                    self.conn_rt.iter().position(|e| e.id == time_report.id);
+                if let Some(ind) = opt_ind {
+                    self.update_rt(ind, time_report.elapsed);
+                }
+            }
+            ChanReq::Failure(time_report) => {
+                let opt_ind =
+                    self.conn_rt.iter().position(|e| e.id == time_report.id);
+                if let Some(ind) = opt_ind {
+                    let elapsed = time_report.elapsed.as_secs_f64();
+                    if elapsed < self.conn_stats[ind].mean {
+                        // Do not update the mean if a
+                        // failure took less time than the
+                        // current mean.
+                        return;
                     }
+                    self.update_rt(ind, time_report.elapsed);
                 }
             }
         }
     }
+
+    /// Updates the estimated response time of the connection at `ind`.
+    fn update_rt(&mut self, ind: usize, elapsed: Duration) {
+        let stats = &mut self.conn_stats[ind];
+        let elapsed = elapsed.as_secs_f64();
+        stats.mean += (elapsed - stats.mean) / SMOOTH_N;
+        let elapsed_sq = elapsed * elapsed;
+        stats.mean_sq += (elapsed_sq - stats.mean_sq) / SMOOTH_N;
+        let mean = stats.mean;
+        let var = stats.mean_sq - mean * mean;
+        let std_dev = if var < 0. { 0. } else { f64::sqrt(var) };
+        let est_rt = mean + 3. * std_dev;
+        self.conn_rt[ind].est_rt = Duration::from_secs_f64(est_rt);
+    }
+}
+
+//------------ Sender --------------------------------------------------------
+
+/// The way commands reach the run function.
+enum Sender<Req>
+where
+    Req: Send + Sync,
+{
+    /// Commands are sent to [`Transport::run`] via a channel.
+    Channel(mpsc::Sender<ChanReq<Req>>),
+
+    /// Commands are handled directly by the caller.
+    Inline(Arc<Mutex<RunState<Req>>>),
+}
+
+impl<Req: Clone + Send + Sync + 'static> Sender<Req> {
+    /// Sends a command to the run function.
+    async fn send(
+        &self,
+        req: ChanReq<Req>,
+    ) -> Result<(), mpsc::error::SendError<ChanReq<Req>>> {
+        match self {
+            Sender::Channel(sender) => sender.send(req).await,
+            Sender::Inline(state) => {
+                state.lock().expect("poisoned lock").handle(req);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<Req> Clone for Sender<Req>
+where
+    Req: Send + Sync,
+{
+    fn clone(&self) -> Self {
+        match self {
+            Sender::Channel(sender) => Sender::Channel(sender.clone()),
+            Sender::Inline(state) => Sender::Inline(state.clone()),
+        }
+    }
+}
+
+impl<Req> Debug for Sender<Req>
+where
+    Req: Send + Sync,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sender::Channel(sender) => {
+                f.debug_tuple("Channel").field(sender).finish()
+            }
+            Sender::Inline(_) => f.write_str("Inline"),
+        }
+    }
 }
 
 //------------ Utility --------------------------------------------------------
@@ -763,11 +946,11 @@ impl<Req: Clone + Send + Sync + 'static> Transport<Req> {
 async fn start_request<Req>(
     index: usize,
     id: u64,
-    sender: mpsc::Sender<ChanReq<Req>>,
+    sender: Sender<Req>,
     request_msg: Req,
 ) -> (usize, Result<Message<Bytes>, Error>)
 where
-    Req: Send + Sync,
+    Req: Clone + Send + Sync + 'static,
 {
     let (tx, rx) = oneshot::channel();
     sender
@@ -814,3 +997,143 @@ fn skip<Octs: Octets>(msg: &Message<Octs>, config: &Config) -> bool {
 
     false
 }
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::MessageBuilder;
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+    use std::sync::Arc;
+
+    #[tokio::test(start_paused = true)]
+    async fn failover_waits_for_failure() {
+        // A slow but working first transport is never given up on.
+        let (conn, first, second) =
+            setup(Mode::Failover, Duration::from_secs(5), false).await;
+        conn.send_request(()).get_response().await.unwrap();
+        assert_eq!(first.load(AtomicOrdering::SeqCst), 1);
+        assert_eq!(second.load(AtomicOrdering::SeqCst), 0);
+
+        // A failing first transport makes us move on to the second one.
+        let (conn, first, second) =
+            setup(Mode::Failover, Duration::from_secs(5), true).await;
+        conn.send_request(()).get_response().await.unwrap();
+        assert_eq!(first.load(AtomicOrdering::SeqCst), 1);
+        assert_eq!(second.load(AtomicOrdering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn parallel_contacts_all() {
+        let (conn, first, second) =
+            setup(Mode::Parallel, Duration::from_secs(5), false).await;
+        conn.send_request(()).get_response().await.unwrap();
+        assert_eq!(first.load(AtomicOrdering::SeqCst), 1);
+        assert_eq!(second.load(AtomicOrdering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn inline_without_task() {
+        // No transport task is spawned for an inline connection.
+        let conn = Connection::new_inline();
+        let first = MockConn::new(Duration::from_secs(5), true);
+        let first_count = first.count.clone();
+        conn.add(Box::new(first)).await.unwrap();
+        let second = MockConn::new(Duration::ZERO, false);
+        let second_count = second.count.clone();
+        conn.add(Box::new(second)).await.unwrap();
+
+        conn.send_request(()).get_response().await.unwrap();
+        assert!(first_count.load(AtomicOrdering::SeqCst) <= 1);
+        assert_eq!(second_count.load(AtomicOrdering::SeqCst), 1);
+
+        // A connection without any transports fails cleanly.
+        let conn = Connection::<()>::new_inline();
+        assert!(conn.send_request(()).get_response().await.is_err());
+    }
+
+    //--- Helpers
+
+    /// Creates a redundant connection with two mock transports.
+    ///
+    /// The first transport replies after `delay` or fails if `fail` is
+    /// true. The second one replies immediately. Returns the connection
+    /// and the number of requests each transport received.
+    async fn setup(
+        mode: Mode,
+        delay: Duration,
+        fail: bool,
+    ) -> (Connection<()>, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        let mut config = Config::default();
+        config.set_mode(mode);
+        let (conn, transport) = Connection::with_config(config);
+        tokio::spawn(transport.run());
+
+        let first = MockConn::new(delay, fail);
+        let first_count = first.count.clone();
+        conn.add(Box::new(first)).await.unwrap();
+        let second = MockConn::new(Duration::ZERO, false);
+        let second_count = second.count.clone();
+        conn.add(Box::new(second)).await.unwrap();
+
+        (conn, first_count, second_count)
+    }
+
+    #[derive(Debug)]
+    struct MockConn {
+        delay: Duration,
+        fail: bool,
+        count: Arc<AtomicUsize>,
+    }
+
+    impl MockConn {
+        fn new(delay: Duration, fail: bool) -> Self {
+            Self {
+                delay,
+                fail,
+                count: Default::default(),
+            }
+        }
+    }
+
+    impl SendRequest<()> for MockConn {
+        fn send_request(&self, _: ()) -> Box<dyn GetResponse + Send + Sync> {
+            self.count.fetch_add(1, AtomicOrdering::SeqCst);
+            Box::new(MockRequest {
+                delay: self.delay,
+                fail: self.fail,
+            })
+        }
+    }
+
+    #[derive(Debug)]
+    struct MockRequest {
+        delay: Duration,
+        fail: bool,
+    }
+
+    impl GetResponse for MockRequest {
+        fn get_response(
+            &mut self,
+        ) -> Pin<
+            Box<
+                dyn Future<Output = Result<Message<Bytes>, Error>>
+                    + Send
+                    + Sync
+                    + '_,
+            >,
+        > {
+            let delay = self.delay;
+            let fail = self.fail;
+            Box::pin(async move {
+                tokio::time::sleep(delay).await;
+                if fail {
+                    Err(Error::ConnectionClosed)
+                } else {
+                    Ok(MessageBuilder::new_bytes().into_message())
+                }
+            })
+        }
+    }
+}
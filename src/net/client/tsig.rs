@@ -12,6 +12,10 @@
 //! 3. [Send a request][Connection::send_request] using the connection.
 //! 4. [Receive the response][GetResponse] or responses.
 //!
+//! The transport isn't limited to zone transfers. Any request can be sent
+//! through it, for instance regular queries or [RFC 2136] UPDATE messages
+//! that the receiving server authorizes based on the key.
+//!
 //! # How it works
 //!
 //! Requests are automatically signed with the given key and response
@@ -43,6 +47,8 @@
 //! requests this will occur at the receiving server. For responses this will
 //! result in [`GetResponse`] returning
 //! [Error::ValidationError][crate::net::client::request::Error].
+//!
+//! [RFC 2136]: https://www.rfc-editor.org/rfc/rfc2136.html
 #![cfg(all(feature = "tsig", feature = "unstable-client-transport"))]
 #![warn(missing_docs)]
 #![warn(clippy::missing_docs_in_private_items)]
@@ -721,7 +727,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::base::iana::Rcode;
+    use crate::base::iana::{Opcode, Rcode};
     use crate::base::message_builder::QuestionBuilder;
     use crate::base::{MessageBuilder, Name, Rtype};
     use crate::tsig::{
@@ -775,6 +781,43 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn signed_update_via_connection() {
+        do_update_via_connection(false).await;
+    }
+
+    #[tokio::test]
+    async fn signed_update_via_connection_invalid_response() {
+        do_update_via_connection(true).await;
+    }
+
+    async fn do_update_via_connection(invalidate_signature: bool) {
+        // Make an RFC 2136 UPDATE message. The zone section has the same
+        // layout as the question section.
+        let mut msg = MessageBuilder::new_vec();
+        msg.header_mut().set_opcode(Opcode::UPDATE);
+        let mut msg = msg.question();
+        msg.push((Name::vec_from_str("example.com").unwrap(), Rtype::SOA))
+            .unwrap();
+        let req =
+            crate::net::client::request::RequestMessage::new(msg).unwrap();
+
+        // Send it through the public transport. The mock upstream verifies
+        // the signature of the request before signing its response.
+        let key = mk_tsig_key();
+        let upstream = MockUpstream::new(key.clone(), invalidate_signature);
+        let conn = Connection::new(key, upstream);
+        let res = conn.send_request(req).get_response().await;
+
+        if invalidate_signature {
+            assert!(matches!(res, Err(Error::Authentication(_))));
+        } else {
+            let res = res.unwrap();
+            assert_eq!(res.header().opcode(), Opcode::UPDATE);
+            assert_eq!(res.header_counts().arcount(), 0);
+        }
+    }
+
     #[tokio::test]
     async fn multiple_signed_valid_responses() {
         do_multiple_responses(false, false).await
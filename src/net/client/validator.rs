@@ -122,8 +122,11 @@ use std::vec::Vec;
 //------------ Config ---------------------------------------------------------
 
 /// Configuration of a validator.
-#[derive(Clone, Default, Debug)]
-pub struct Config {}
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Whether to pass through responses to requests with the CD flag.
+    honor_cd: bool,
+}
 
 impl Config {
     /// Creates a new config with default values.
@@ -132,6 +135,25 @@ impl Config {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Sets whether the CD flag in requests is honored.
+    ///
+    /// If the CD (Checking Disabled) flag is set in a request and this
+    /// option is enabled, the response from upstream is passed through to
+    /// the client without validation. If the option is disabled,
+    /// responses are always validated and a request with the CD flag is
+    /// treated like one without.
+    ///
+    /// The default is `true`.
+    pub fn set_honor_cd(&mut self, value: bool) {
+        self.honor_cd = value;
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { honor_cd: true }
+    }
 }
 
 //------------ Connection -----------------------------------------------------
@@ -232,7 +254,7 @@ where
     vc: Arc<ValidationContext<VCUpstream>>,
 
     /// The configuration of the connection.
-    _config: Config,
+    config: Config,
 
     /// valid of the cd flag in the request.
     cd: bool,
@@ -261,7 +283,7 @@ where
             request_msg,
             upstream,
             vc,
-            _config: config,
+            config,
             cd: false,
             dnssec_ok: false,
             _phantom: PhantomData,
@@ -306,7 +328,9 @@ where
                 RequestState::GetResponse(request) => {
                     let response_msg = request.get_response().await?;
 
-                    if self.cd {
+                    // If we don't honor the CD flag, we validate as if it
+                    // was clear but still echo it in the response.
+                    if self.cd && self.config.honor_cd {
                         if self.dnssec_ok {
                             // Clear the AD flag if it is clear. Check if CD
                             // is set. If either AD is set or CD is clear then
@@ -340,7 +364,7 @@ where
                 }
 
                 RequestState::Validate(response_msg) => {
-                    let res = self.vc.validate_msg(response_msg).await;
+                    let res = self.vc.validate_bytes_msg(response_msg).await;
                     return match res {
                         Err(err) => Err(Error::Validation(err)),
                         Ok((state, opt_ede)) => {
@@ -350,9 +374,9 @@ where
                                     // if we have to strip DNSSEC records. Set
                                     // the AD flag if it is not set and either
                                     // AD or DO is set in the request.
-                                    // We always have to clear CD.
+                                    // CD is copied from the request.
                                     if self.dnssec_ok {
-                                        // Set AD and clear CD.
+                                        // Set AD and copy CD.
                                         let mut response_msg =
                                             Message::from_octets(
                                                 response_msg
@@ -364,7 +388,7 @@ where
                                             .set_ad(true);
                                         response_msg
                                             .header_mut()
-                                            .set_cd(false);
+                                            .set_cd(self.cd);
                                         let response_msg =
                                             Message::<Bytes>::from_octets(
                                                 response_msg
@@ -377,13 +401,13 @@ where
                                         let msg = remove_dnssec(
                                             response_msg,
                                             self.request_msg.header().ad(),
-                                            false,
+                                            self.cd,
                                         );
                                         msg
                                     }
                                 }
                                 ValidationState::Bogus => {
-                                    serve_fail(response_msg, opt_ede)
+                                    serve_fail(response_msg, self.cd, opt_ede)
                                 }
                                 ValidationState::Insecure
                                 | ValidationState::Indeterminate => {
@@ -395,10 +419,10 @@ where
                                     };
                                     // Check the state of the DO flag to see
                                     // if we have to strip DNSSEC records.
-                                    // Clear the AD flag if it is set. Always
-                                    // clear CD.
+                                    // Clear the AD flag if it is set. CD is
+                                    // copied from the request.
                                     if self.dnssec_ok {
-                                        // Clear AD if it is set. Clear CD.
+                                        // Clear AD if it is set. Copy CD.
                                         let mut response_msg =
                                             Message::from_octets(
                                                 response_msg
@@ -410,7 +434,7 @@ where
                                             .set_ad(false);
                                         response_msg
                                             .header_mut()
-                                            .set_cd(false);
+                                            .set_cd(self.cd);
                                         let response_msg =
                                             Message::<Bytes>::from_octets(
                                                 response_msg
@@ -422,7 +446,7 @@ where
                                         remove_dnssec(
                                             &response_msg,
                                             false,
-                                            false,
+                                            self.cd,
                                         )
                                     }
                                 }
@@ -667,6 +691,7 @@ fn add_opt(
 /// Generate a SERVFAIL reply message.
 fn serve_fail(
     msg: &Message<Bytes>,
+    cd: bool,
     opt_ede: Option<ExtendedError<Vec<u8>>>,
 ) -> Result<Message<Bytes>, Error> {
     let mut target =
@@ -678,6 +703,7 @@ fn serve_fail(
     *target.header_mut() = msg.header();
     target.header_mut().set_rcode(Rcode::SERVFAIL);
     target.header_mut().set_ad(false);
+    target.header_mut().set_cd(cd);
 
     let source = source.question();
     let mut target = target.question();
@@ -2,9 +2,12 @@
 
 #![cfg(test)]
 
+use std::boxed::Box;
 use std::fs::File;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::string::ToString;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::Duration;
@@ -21,11 +24,23 @@ use rstest::rstest;
 use tracing::instrument;
 
 // use domain::net::client::clock::{Clock, FakeClock};
+use crate::base::iana::{
+    Class, DigestAlgorithm, Rcode, Rtype, SecurityAlgorithm,
+};
+use crate::base::opt::Opt;
 use crate::base::scan::IterScanner;
+use crate::base::{Message, MessageBuilder, Name, ParsedName, Serial, Ttl};
 use crate::dnssec::validator::anchor::TrustAnchors;
-use crate::dnssec::validator::context::ValidationContext;
+use crate::dnssec::validator::context::{
+    self, MisplacedRecordPolicy, MissingRrsigPolicy, MissingSoaPolicy,
+    UnsupportedDelegation, ValidationContext, ValidationResult,
+    ValidationState,
+};
+use crate::dnssec::validator::lookup::lookup;
+use crate::net::client::request::{GetResponse, RequestMessage, SendRequest};
 use crate::net::client::{multi_stream, validator};
 use crate::rdata::dnssec::Timestamp;
+use crate::rdata::{AllRecordData, Soa, A};
 
 use lazy_static::lazy_static;
 
@@ -40,7 +55,8 @@ async fn async_test_validator(filename: &str) {
     let file = File::open(filename).unwrap();
     let stelline = parse_file(&file, filename);
 
-    let ta = parse_server_config(&stelline.config);
+    let (ta, config, validator_config) =
+        parse_server_config(&stelline.config);
 
     let step_value = Arc::new(CurrStepValue::new());
     let multi_conn = Connect::new(stelline.clone(), step_value.clone());
@@ -49,10 +65,11 @@ async fn async_test_validator(filename: &str) {
         ms_tran.run().await;
     });
 
-    let vc = Arc::new(ValidationContext::new(ta, ms.clone()));
+    let vc = Arc::new(ValidationContext::with_config(ta, ms.clone(), config));
 
     // let clock = FakeClock::new();
-    let validator = validator::Connection::new(ms, vc); //_with_time(ms, clock.clone());
+    let validator =
+        validator::Connection::with_config(ms, vc, validator_config); //_with_time(ms, clock.clone());
 
     do_client_simple(&stelline, &step_value, validator /*, &clock*/).await;
 }
@@ -66,9 +83,465 @@ async fn validator_test_all(
     async_test_validator(rpl_file.to_str().unwrap()).await;
 }
 
-fn parse_server_config(config: &Config) -> TrustAnchors {
+#[allow(clippy::await_holding_lock)]
+#[tokio::test(start_paused = true)]
+async fn validate_bytes_msg_matches_validate_msg() {
+    let _locked = LOCK.lock().unwrap();
+
+    let filename = "test-data/validator/val_large_rrset.rpl";
+    let (ta, config, ms) = load_scenario(filename);
+
+    let vc = ValidationContext::with_config(ta, ms.clone(), config);
+
+    // Fetch the unvalidated reply with the large RRset.
+    let mut msg = MessageBuilder::new_vec().question();
+    msg.push((
+        Name::<Vec<u8>>::from_str("www.example.").unwrap(),
+        Rtype::TXT,
+    ))
+    .unwrap();
+    let req = RequestMessage::new(msg).unwrap();
+    let reply = ms.send_request(req).get_response().await.unwrap();
+    assert_eq!(reply.answer().unwrap().count(), 101);
+
+    let mut copied = Message::from_octets(reply.as_slice().to_vec()).unwrap();
+    let copied_res =
+        vc.validate_msg::<_, Vec<u8>>(&mut copied).await.unwrap();
+
+    let mut shared = reply.clone();
+    let shared_res =
+        vc.validate_bytes_msg::<Vec<u8>>(&mut shared).await.unwrap();
+
+    assert_eq!(copied_res.0, ValidationState::Secure);
+    assert_eq!(copied_res, shared_res);
+    assert_eq!(copied.as_slice(), shared.as_slice());
+}
+
+#[allow(clippy::await_holding_lock)]
+#[tokio::test(start_paused = true)]
+async fn trace_node_records_chain() {
+    let _locked = LOCK.lock().unwrap();
+
+    let filename = "test-data/validator/val_cname_new_signer.rpl";
+    let (ta, config, ms) = load_scenario(filename);
+
+    let vc = ValidationContext::with_config(ta, ms.clone(), config);
+    let name = Name::from_str("sub.example.com.").unwrap();
+    let (node, chain) = vc.trace_node::<Vec<u8>>(&name).await.unwrap();
+
+    assert_eq!(node.state, ValidationState::Secure);
+    assert_eq!(node.signer_name, name);
+    let walked: Vec<_> = chain
+        .iter()
+        .map(|walked| (walked.name.clone(), walked.state))
+        .collect();
+    assert_eq!(
+        walked,
+        [
+            (
+                Name::from_str("example.com.").unwrap(),
+                ValidationState::Secure
+            ),
+            (name.clone(), ValidationState::Secure),
+        ]
+    );
+
+    // The node is cached now, so the chain only contains the node itself.
+    let (_, chain) = vc.trace_node::<Vec<u8>>(&name).await.unwrap();
+    assert_eq!(chain.len(), 1);
+    assert_eq!(chain[0].name, name);
+}
+
+#[rstest]
+#[case(
+    "test-data/validator/val_rrsig_some.rpl",
+    "www.example.",
+    Rtype::A,
+    ValidationState::Secure,
+    "; fully validated\n; rcode: NOERROR\nwww.example. 3600 IN A 192.0.2.1\n"
+)]
+#[case(
+    "test-data/validator/val_nodata_nosoa.rpl",
+    "www.example.",
+    Rtype::TXT,
+    ValidationState::Bogus,
+    "; validation failed\n\
+     ; EDE: DNSSEC Bogus Missing SOA record for NODATA or NXDOMAIN\n\
+     ; rcode: NOERROR\n"
+)]
+#[tokio::test(start_paused = true)]
+async fn lookup_prints_state(
+    #[case] filename: &str,
+    #[case] qname: &str,
+    #[case] qtype: Rtype,
+    #[case] state: ValidationState,
+    #[case] expected: &str,
+) {
+    async_test_lookup(filename, qname, qtype, state, expected).await;
+}
+
+#[allow(clippy::await_holding_lock)]
+async fn async_test_lookup(
+    filename: &str,
+    qname: &str,
+    qtype: Rtype,
+    state: ValidationState,
+    expected: &str,
+) {
+    let _locked = LOCK.lock().unwrap();
+
+    let (ta, config, ms) = load_scenario(filename);
+
+    let vc = ValidationContext::with_config(ta, ms.clone(), config);
+    let qname = Name::from_str(qname).unwrap();
+    let res = lookup(&ms, &vc, &qname, qtype).await.unwrap();
+
+    assert_eq!(res.state(), state);
+    let printed = res.to_string();
+    assert!(
+        printed.starts_with(expected),
+        "unexpected output: {printed}"
+    );
+}
+
+#[allow(clippy::await_holding_lock)]
+#[tokio::test(start_paused = true)]
+async fn decision_hook() {
+    let _locked = LOCK.lock().unwrap();
+
+    let filename = "test-data/validator/val_rrsig_some.rpl";
+    let (ta, mut config, ms) = load_scenario(filename);
+    let decisions = Arc::new(Mutex::new(Vec::new()));
+    let hook_decisions = decisions.clone();
+    config.set_decision_hook(move |decision| {
+        hook_decisions.lock().unwrap().push(decision.clone())
+    });
+
+    let vc = ValidationContext::with_config(ta, ms.clone(), config);
+    let qname = Name::from_str("www.example.").unwrap();
+    let res = lookup(&ms, &vc, &qname, Rtype::A).await.unwrap();
+    assert_eq!(res.state(), ValidationState::Secure);
+
+    let decisions = decisions.lock().unwrap();
+    assert_eq!(decisions.len(), 1);
+    assert_eq!(decisions[0].name, qname);
+    assert_eq!(decisions[0].rtype, Rtype::A);
+    assert_eq!(decisions[0].state, ValidationState::Secure);
+}
+
+/// An upstream that counts the requests sent through it.
+#[derive(Clone, Debug)]
+struct CountingUpstream<Upstream> {
+    inner: Upstream,
+    count: Arc<AtomicUsize>,
+}
+
+impl<Upstream> SendRequest<RequestMessage<Vec<u8>>>
+    for CountingUpstream<Upstream>
+where
+    Upstream: SendRequest<RequestMessage<Vec<u8>>>,
+{
+    fn send_request(
+        &self,
+        request_msg: RequestMessage<Vec<u8>>,
+    ) -> Box<dyn GetResponse + Send + Sync> {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.inner.send_request(request_msg)
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[tokio::test(start_paused = true)]
+async fn prime_caches_trust_anchor_nodes() {
+    let _locked = LOCK.lock().unwrap();
+
+    let filename = "test-data/validator/val_large_rrset.rpl";
+    let (ta, config, ms) = load_scenario(filename);
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let upstream = CountingUpstream {
+        inner: ms,
+        count: count.clone(),
+    };
+    let vc = ValidationContext::with_config(ta, upstream, config);
+
+    vc.prime::<Vec<u8>>().await.unwrap();
+    let primed = count.load(Ordering::Relaxed);
+    assert!(primed > 0);
+
+    // The root node now comes from the cache.
+    let node = vc.get_node::<Vec<u8>>(&Name::root()).await.unwrap();
+    assert_eq!(node.validation_state(), ValidationState::Secure);
+    assert_eq!(count.load(Ordering::Relaxed), primed);
+}
+
+#[allow(clippy::await_holding_lock)]
+#[tokio::test(start_paused = true)]
+async fn unsupported_algorithm_hook() {
+    let _locked = LOCK.lock().unwrap();
+
+    let filename = "test-data/validator/val_ds_unsupported_digest.rpl";
+    let (ta, mut config, ms) = load_scenario(filename);
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let hook_reported = reported.clone();
+    config.set_unsupported_algorithm_hook(move |delegation| {
+        hook_reported.lock().unwrap().push(delegation.clone())
+    });
+
+    let vc = ValidationContext::with_config(ta, ms.clone(), config);
+    let qname = Name::from_str("www.example.").unwrap();
+    let res = lookup(&ms, &vc, &qname, Rtype::A).await.unwrap();
+
+    // The only DS record uses GOST which isn't supported, so the
+    // delegation is insecure and reported.
+    assert_eq!(res.state(), ValidationState::Insecure);
+    assert_eq!(
+        *reported.lock().unwrap(),
+        [UnsupportedDelegation {
+            name: qname,
+            algorithms: vec![(
+                SecurityAlgorithm::ECDSAP256SHA256,
+                DigestAlgorithm::GOST
+            )],
+        }]
+    );
+}
+
+#[allow(clippy::await_holding_lock)]
+#[tokio::test(start_paused = true)]
+async fn disabled_algorithm() {
+    let _locked = LOCK.lock().unwrap();
+
+    let filename = "test-data/validator/iter_validate_child_zone_noaddr.rpl";
+    let (ta, mut config, ms) = load_scenario(filename);
+    config.set_algorithm_enabled(SecurityAlgorithm::RSASHA1, false);
+
+    let vc = ValidationContext::with_config(ta, ms.clone(), config);
+    let qname = Name::from_str("nic.cz.").unwrap();
+    let res = lookup(&ms, &vc, &qname, Rtype::MX).await.unwrap();
+
+    // nic.cz. is signed with RSASHA1 only, so disabling the algorithm
+    // makes the zone insecure.
+    assert_eq!(res.state(), ValidationState::Insecure);
+}
+
+#[allow(clippy::await_holding_lock)]
+#[tokio::test(start_paused = true)]
+async fn cname_target_not_in_message() {
+    let _locked = LOCK.lock().unwrap();
+
+    let filename = "test-data/validator/fwd_val_cname_sibling.rpl";
+    let (ta, config, ms) = load_scenario(filename);
+
+    let vc = ValidationContext::with_config(ta, ms.clone(), config);
+
+    let qname = Name::<Vec<u8>>::from_str("rate.dns-oarc.net.").unwrap();
+    let mut msg = MessageBuilder::new_vec().question();
+    msg.push((&qname, Rtype::A)).unwrap();
+    let req = RequestMessage::new(msg).unwrap();
+    let reply = ms.send_request(req).get_response().await.unwrap();
+
+    // Keep only the signed CNAME. Its target lives in a different zone
+    // and is not part of the message.
+    let mut msg = MessageBuilder::new_vec()
+        .start_answer(&reply, Rcode::NOERROR)
+        .unwrap();
+    for rr in reply.answer().unwrap() {
+        let rr = rr
+            .unwrap()
+            .into_record::<AllRecordData<_, ParsedName<_>>>()
+            .unwrap()
+            .unwrap();
+        if *rr.owner() == qname {
+            msg.push(rr).unwrap();
+        }
+    }
+    let mut msg = msg.into_message();
+    assert_eq!(msg.header_counts().ancount(), 2);
+
+    let (state, ede) = vc.validate_msg::<_, Vec<u8>>(&mut msg).await.unwrap();
+    assert_eq!(state, ValidationState::Secure);
+    assert_eq!(ede, None);
+}
+
+#[allow(clippy::await_holding_lock)]
+#[tokio::test(start_paused = true)]
+async fn validate_msg_detailed_wildcard() {
+    let _locked = LOCK.lock().unwrap();
+
+    let filename = "test-data/validator/val_wild_expanded.rpl";
+    let (ta, config, ms) = load_scenario(filename);
+
+    let vc = ValidationContext::with_config(ta, ms.clone(), config);
+
+    let mut msg = MessageBuilder::new_vec().question();
+    msg.push((Name::<Vec<u8>>::from_str("www.example.").unwrap(), Rtype::A))
+        .unwrap();
+    let req = RequestMessage::new(msg).unwrap();
+    let mut reply = ms.send_request(req).get_response().await.unwrap();
+
+    let res = vc
+        .validate_msg_detailed::<_, Vec<u8>>(&mut reply)
+        .await
+        .unwrap();
+    let example = Name::from_str("example.").unwrap();
+    assert_eq!(
+        res,
+        ValidationResult {
+            state: ValidationState::Secure,
+            ede: None,
+            signers: vec![example.clone()],
+            wildcard: true,
+            closest_encloser: Some(example.clone()),
+            chain: vec![Name::root(), example],
+            negative_ttl: None,
+        }
+    );
+}
+
+#[rstest]
+#[case(
+    "test-data/validator/val_nodata_soa_secure.rpl",
+    ValidationState::Secure,
+    Some(Ttl::from_secs(300))
+)]
+#[case(
+    "test-data/validator/val_nodata_soa_bad_sig.rpl",
+    ValidationState::Bogus,
+    None
+)]
+#[tokio::test(start_paused = true)]
+async fn validate_msg_detailed_negative_ttl(
+    #[case] filename: &str,
+    #[case] state: ValidationState,
+    #[case] negative_ttl: Option<Ttl>,
+) {
+    async_test_negative_ttl(filename, state, negative_ttl).await;
+}
+
+#[allow(clippy::await_holding_lock)]
+async fn async_test_negative_ttl(
+    filename: &str,
+    state: ValidationState,
+    negative_ttl: Option<Ttl>,
+) {
+    let _locked = LOCK.lock().unwrap();
+
+    let (ta, config, ms) = load_scenario(filename);
+    let vc = ValidationContext::with_config(ta, ms.clone(), config);
+
+    let mut msg = MessageBuilder::new_vec().question();
+    msg.push((
+        Name::<Vec<u8>>::from_str("www.example.").unwrap(),
+        Rtype::TXT,
+    ))
+    .unwrap();
+    let req = RequestMessage::new(msg).unwrap();
+    let mut reply = ms.send_request(req).get_response().await.unwrap();
+
+    // For a secure SOA, the TTL is limited by its MINIMUM field.
+    let res = vc
+        .validate_msg_detailed::<_, Vec<u8>>(&mut reply)
+        .await
+        .unwrap();
+    assert_eq!(res.state, state);
+    assert_eq!(res.negative_ttl, negative_ttl);
+}
+
+#[allow(clippy::await_holding_lock)]
+#[tokio::test(start_paused = true)]
+async fn misplaced_records_are_bogus() {
+    let _locked = LOCK.lock().unwrap();
+
+    let filename = "test-data/validator/val_wild_expanded.rpl";
+    let (ta, mut config, ms) = load_scenario(filename);
+    config.set_misplaced_record_policy(MisplacedRecordPolicy::Bogus);
+
+    let vc = ValidationContext::with_config(ta, ms.clone(), config);
+
+    let name = Name::<Vec<u8>>::from_str("www.example.").unwrap();
+    let zone = Name::<Vec<u8>>::from_str("example.").unwrap();
+    let soa = Soa::new(
+        zone.clone(),
+        zone.clone(),
+        Serial(1),
+        Ttl::from_secs(3600),
+        Ttl::from_secs(300),
+        Ttl::from_secs(604800),
+        Ttl::from_secs(300),
+    );
+
+    // An OPT record in the authority section.
+    let mut msg = MessageBuilder::new_vec();
+    msg.header_mut().set_qr(true);
+    let mut msg = msg.question();
+    msg.push((&name, Rtype::A)).unwrap();
+    let mut msg = msg.answer();
+    msg.push((&name, 3600, A::from_octets(10, 0, 0, 1)))
+        .unwrap();
+    let mut msg = msg.authority();
+    msg.push((
+        Name::root_slice(),
+        Class::from_int(1232),
+        0,
+        Opt::<Vec<u8>>::empty(),
+    ))
+    .unwrap();
+    let mut reply = msg.into_message();
+    let res = vc
+        .validate_msg_detailed::<_, Vec<u8>>(&mut reply)
+        .await
+        .unwrap();
+    assert_eq!(res.state, ValidationState::Bogus);
+
+    // A SOA record in the answer section of a positive response.
+    let mut msg = MessageBuilder::new_vec();
+    msg.header_mut().set_qr(true);
+    let mut msg = msg.question();
+    msg.push((&name, Rtype::A)).unwrap();
+    let mut msg = msg.answer();
+    msg.push((&name, 3600, A::from_octets(10, 0, 0, 1)))
+        .unwrap();
+    msg.push((&zone, 3600, soa)).unwrap();
+    let mut reply = msg.into_message();
+    let res = vc
+        .validate_msg_detailed::<_, Vec<u8>>(&mut reply)
+        .await
+        .unwrap();
+    assert_eq!(res.state, ValidationState::Bogus);
+}
+
+/// The upstream connection of a scenario.
+type Upstream = multi_stream::Connection<RequestMessage<Vec<u8>>>;
+
+/// Loads the scenario in `filename` and connects to its upstream.
+///
+/// Returns the trust anchors and validation context configuration of the
+/// scenario together with the connection.
+fn load_scenario(
+    filename: &str,
+) -> (TrustAnchors, context::Config, Upstream) {
+    let file = File::open(filename).unwrap();
+    let stelline = parse_file(&file, filename);
+
+    let (ta, config, _) = parse_server_config(&stelline.config);
+
+    let step_value = Arc::new(CurrStepValue::new());
+    let multi_conn = Connect::new(stelline.clone(), step_value.clone());
+    let (ms, ms_tran) = Upstream::new(multi_conn);
+    tokio::spawn(async move {
+        ms_tran.run().await;
+    });
+    (ta, config, ms)
+}
+
+fn parse_server_config(
+    config: &Config,
+) -> (TrustAnchors, context::Config, validator::Config) {
     let mut in_server_block = false;
     let mut ta = TrustAnchors::empty();
+    let mut vc_config = context::Config::new();
+    let mut validator_config = validator::Config::new();
 
     for line in config.lines() {
         if line.starts_with("server:") {
@@ -107,6 +580,44 @@ fn parse_server_config(config: &Config) -> TrustAnchors {
                     ("trust-anchor", a) => {
                         ta.add_u8(a.trim_matches('"').as_bytes()).unwrap();
                     }
+                    ("val-missing-soa", "bogus") => {
+                        vc_config
+                            .set_missing_soa_policy(MissingSoaPolicy::Bogus);
+                    }
+                    ("val-missing-soa", "insecure") => {
+                        vc_config.set_missing_soa_policy(
+                            MissingSoaPolicy::Insecure,
+                        );
+                    }
+                    ("val-missing-rrsig", "bogus") => {
+                        vc_config.set_missing_rrsig_policy(
+                            MissingRrsigPolicy::Bogus,
+                        );
+                    }
+                    ("val-missing-rrsig", "insecure") => {
+                        vc_config.set_missing_rrsig_policy(
+                            MissingRrsigPolicy::Insecure,
+                        );
+                    }
+                    ("val-misplaced-record", "accept") => {
+                        vc_config.set_misplaced_record_policy(
+                            MisplacedRecordPolicy::Accept,
+                        );
+                    }
+                    ("val-misplaced-record", "bogus") => {
+                        vc_config.set_misplaced_record_policy(
+                            MisplacedRecordPolicy::Bogus,
+                        );
+                    }
+                    ("val-max-work", v) => {
+                        vc_config.set_max_work(v.parse().unwrap());
+                    }
+                    ("ignore-cd-flag", "yes") => {
+                        validator_config.set_honor_cd(false);
+                    }
+                    ("ignore-cd-flag", "no") => {
+                        validator_config.set_honor_cd(true);
+                    }
                     _ => {
                         eprintln!("Ignoring unknown server setting '{setting}' with value: {value:?}");
                     }
@@ -115,5 +626,5 @@ fn parse_server_config(config: &Config) -> TrustAnchors {
         }
     }
 
-    ta
+    (ta, vc_config, validator_config)
 }
@@ -34,15 +34,21 @@
 //! 4. Call `CallbackBatcher::finish()` to ensure that a last partial response
 //!    is also completely handled.
 //!
+//! If you'd rather pull the completed messages than be called back, use
+//! [`RrBatcher`] instead. It is not tied to zone transfers and can be used
+//! by any service that needs to spread records over multiple responses.
+//!
 //! [RFC 1995]: https://www.rfc-editor.org/info/rfc1995
 //! [RFC 5936]: https://www.rfc-editor.org/info/rfc5936
 //! [ResourceRecordBatcher]: ResourceRecordBatcher
 //! [CallbackBatcher]: CallbackBatcher  
 use core::marker::PhantomData;
 
+use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::sync::Arc;
 
+use octseq::builder::ShortBuf;
 use octseq::Octets;
 use tracing::trace;
 
@@ -50,10 +56,15 @@ use crate::base::iana::Rcode;
 use crate::base::message_builder::{AnswerBuilder, PushError};
 use crate::base::record::ComposeRecord;
 use crate::base::wire::Composer;
-use crate::base::{Message, StreamTarget};
+use crate::base::{Message, MessageBuilder, StreamTarget, TreeCompressor};
 
 use super::util::mk_builder_for_target;
 
+//------------ Constants -----------------------------------------------------
+
+/// The maximum length of any DNS message.
+const MAX_MSG_BYTE_LEN: usize = u16::MAX as usize;
+
 //----------- PushResult ------------------------------------------------------
 
 /// The result of attempting to push a resource record into a
@@ -333,11 +344,209 @@ where
     }
 }
 
+//------------ RrBatcher ------------------------------------------------------
+
+/// Splits a stream of resource records into response messages.
+///
+/// Records are added one at a time via [`push()`][Self::push]. They are
+/// placed in the answer section of a response to the request message given
+/// when creating the batcher. Once a response is full, it is queued and a
+/// new one is started. Completed responses are removed from the queue in
+/// order via [`take()`][Self::take]. After the last record has been pushed,
+/// [`finish()`][Self::finish] queues the last, partially filled response.
+///
+/// A response is considered full if either of two limits is reached:
+///
+/// * The soft byte limit is the maximum length a response may grow to. A
+///   record that would make the response exceed the limit is added to the
+///   next response instead. Responses never exceed 65,535 bytes, whether a
+///   soft byte limit is set or not.
+/// * The hard RR limit is the maximum number of records in a response.
+///
+/// A single record that doesn't fit into an otherwise empty response
+/// results in an error.
+///
+/// The `Target` is the octets builder responses are composed into. Use
+/// [`new()`][Self::new] for uncompressed responses in a [`StreamTarget`]
+/// and [`new_compressed()`][Self::new_compressed] for responses using name
+/// compression. In the latter case, the soft byte limit applies to the
+/// compressed length of a response, so more records will fit if their
+/// names compress well.
+pub struct RrBatcher<RequestOctets, Target> {
+    /// The request message being responded to.
+    req_msg: Arc<Message<RequestOctets>>,
+
+    /// Creates the target for a new response.
+    mk_target: fn() -> Target,
+
+    /// The maximum length of a response in bytes.
+    soft_byte_limit: Option<usize>,
+
+    /// The maximum number of records in a response.
+    hard_rr_limit: Option<u16>,
+
+    /// The response currently being filled.
+    current: Option<AnswerBuilder<Target>>,
+
+    /// The completed responses not yet taken.
+    ready: VecDeque<AnswerBuilder<Target>>,
+}
+
+impl<RequestOctets, Target> RrBatcher<RequestOctets, StreamTarget<Target>>
+where
+    RequestOctets: Octets,
+    Target: Composer + Default,
+    Target::AppendError: Into<ShortBuf>,
+{
+    /// Creates a new batcher for responses to the given request.
+    ///
+    /// Names in the responses are not compressed. Initially, no limits
+    /// other than the maximum message size apply.
+    pub fn new(req_msg: Arc<Message<RequestOctets>>) -> Self {
+        Self::with_target(req_msg, mk_stream_target)
+    }
+}
+
+impl<RequestOctets, Target>
+    RrBatcher<RequestOctets, TreeCompressor<StreamTarget<Target>>>
+where
+    RequestOctets: Octets,
+    Target: Composer + Default,
+    Target::AppendError: Into<ShortBuf>,
+{
+    /// Creates a new batcher for responses using name compression.
+    ///
+    /// Initially, no limits other than the maximum message size apply.
+    pub fn new_compressed(req_msg: Arc<Message<RequestOctets>>) -> Self {
+        Self::with_target(req_msg, || TreeCompressor::new(mk_stream_target()))
+    }
+}
+
+impl<RequestOctets, Target> RrBatcher<RequestOctets, Target>
+where
+    RequestOctets: Octets,
+    Target: Composer,
+{
+    /// Creates a new batcher using the given function to create targets.
+    ///
+    /// A new target is created for every response. Since the size of a
+    /// response is determined by looking at its target after adding a
+    /// record, a target that compresses names leads to responses being
+    /// filled according to their compressed size.
+    pub fn with_target(
+        req_msg: Arc<Message<RequestOctets>>,
+        mk_target: fn() -> Target,
+    ) -> Self {
+        Self {
+            req_msg,
+            mk_target,
+            soft_byte_limit: None,
+            hard_rr_limit: None,
+            current: None,
+            ready: VecDeque::new(),
+        }
+    }
+
+    /// Sets the maximum length of a response in bytes.
+    ///
+    /// The limit applies to responses started after this call.
+    pub fn set_soft_byte_limit(&mut self, limit: Option<usize>) {
+        self.soft_byte_limit = limit;
+    }
+
+    /// Sets the maximum number of records in a response.
+    ///
+    /// The limit also applies to the response currently being filled.
+    pub fn set_hard_rr_limit(&mut self, limit: Option<u16>) {
+        self.hard_rr_limit = limit;
+    }
+
+    /// Adds a record to the current response.
+    ///
+    /// If the record doesn't fit into the current response, that response
+    /// is queued and the record is added to a new one. If the response
+    /// reaches the hard RR limit with this record, it is queued right away.
+    ///
+    /// Returns an error if the record doesn't even fit into an empty
+    /// response.
+    pub fn push(
+        &mut self,
+        record: impl ComposeRecord,
+    ) -> Result<(), PushError> {
+        let mut answer = match self.current.take() {
+            Some(answer) => answer,
+            None => self.start()?,
+        };
+        if let Err(err) = answer.push_ref(&record) {
+            if answer.counts().ancount() == 0 {
+                return Err(err);
+            }
+            self.ready.push_back(answer);
+            answer = self.start()?;
+            answer.push_ref(&record)?;
+        }
+        if self
+            .hard_rr_limit
+            .is_some_and(|limit| answer.counts().ancount() >= limit)
+        {
+            self.ready.push_back(answer);
+        } else {
+            self.current = Some(answer);
+        }
+        Ok(())
+    }
+
+    /// Queues the current response even if it isn't full yet.
+    ///
+    /// Call this after the last record has been pushed. Nothing is queued
+    /// if there are no records in the current response.
+    pub fn finish(&mut self) {
+        if let Some(answer) = self.current.take() {
+            self.ready.push_back(answer);
+        }
+    }
+
+    /// Removes the oldest completed response from the queue.
+    ///
+    /// Returns `None` if no response has been completed since the last
+    /// call.
+    pub fn take(&mut self) -> Option<AnswerBuilder<Target>> {
+        self.ready.pop_front()
+    }
+
+    /// Starts a new response observing the soft byte limit.
+    fn start(&self) -> Result<AnswerBuilder<Target>, PushError> {
+        let mut builder = MessageBuilder::from_target((self.mk_target)())
+            .map_err(|_| PushError::ShortBuf)?;
+        builder.set_push_limit(
+            self.soft_byte_limit.map_or(MAX_MSG_BYTE_LEN, |limit| {
+                limit.min(MAX_MSG_BYTE_LEN)
+            }),
+        );
+        builder.start_answer(&self.req_msg, Rcode::NOERROR)
+    }
+}
+
+//------------ mk_stream_target -----------------------------------------------
+
+/// Creates a new, empty stream target.
+fn mk_stream_target<Target>() -> StreamTarget<Target>
+where
+    Target: Composer + Default,
+{
+    StreamTarget::new(Target::default())
+        .map_err(|_| ())
+        .expect("Internal error: Unable to create new target.")
+}
+
+//============ Tests =========================================================
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::base::{MessageBuilder, Name};
+    use crate::base::Name;
     use crate::rdata::Txt;
+    use core::str::FromStr;
     use core::sync::atomic::{AtomicU64, Ordering};
     use std::vec::Vec;
 
@@ -389,6 +598,130 @@ mod tests {
         batcher.callback_state().assert_eq(0, 2, 2);
     }
 
+    #[test]
+    fn rr_batcher_hard_rr_limit() {
+        let mut batcher = mk_rr_batcher();
+        batcher.set_hard_rr_limit(Some(3));
+        for _ in 0..7 {
+            batcher.push(mk_dummy_rr(&[0; 10])).unwrap();
+        }
+        assert_eq!(take_ancounts(&mut batcher), [3, 3]);
+        batcher.finish();
+        assert_eq!(take_ancounts(&mut batcher), [1]);
+    }
+
+    #[test]
+    fn rr_batcher_hard_rr_limit_reached_exactly() {
+        let mut batcher = mk_rr_batcher();
+        batcher.set_hard_rr_limit(Some(3));
+        for _ in 0..3 {
+            batcher.push(mk_dummy_rr(&[0; 10])).unwrap();
+        }
+        assert_eq!(take_ancounts(&mut batcher), [3]);
+        batcher.finish();
+        assert!(take_ancounts(&mut batcher).is_empty());
+    }
+
+    #[test]
+    fn rr_batcher_soft_byte_limit() {
+        // Three records of a bit over 1,000 bytes fit, four don't.
+        let mut batcher = mk_rr_batcher();
+        batcher.set_soft_byte_limit(Some(4000));
+        for _ in 0..10 {
+            batcher.push(mk_dummy_rr(&[0; 1000])).unwrap();
+        }
+        assert_eq!(take_ancounts(&mut batcher), [3, 3, 3]);
+        batcher.finish();
+        assert_eq!(take_ancounts(&mut batcher), [1]);
+    }
+
+    #[test]
+    fn rr_batcher_both_limits() {
+        // Whichever limit is reached first ends a response.
+        let mut batcher = mk_rr_batcher();
+        batcher.set_soft_byte_limit(Some(4000));
+        batcher.set_hard_rr_limit(Some(2));
+        for _ in 0..5 {
+            batcher.push(mk_dummy_rr(&[0; 1000])).unwrap();
+        }
+        batcher.set_hard_rr_limit(Some(10));
+        for _ in 0..4 {
+            batcher.push(mk_dummy_rr(&[0; 1000])).unwrap();
+        }
+        batcher.finish();
+        assert_eq!(take_ancounts(&mut batcher), [2, 2, 3, 2]);
+    }
+
+    #[test]
+    fn rr_batcher_max_message_size() {
+        // Without a soft byte limit messages are still limited to 64k.
+        let mut batcher = mk_rr_batcher();
+        batcher.set_soft_byte_limit(Some(usize::MAX));
+        batcher.push(mk_dummy_rr(&vec![0; 65000])).unwrap();
+        batcher.push(mk_dummy_rr(&vec![0; 1000])).unwrap();
+        batcher.finish();
+        assert_eq!(take_ancounts(&mut batcher), [1, 1]);
+    }
+
+    #[test]
+    fn rr_batcher_record_too_large() {
+        let mut batcher = mk_rr_batcher();
+        batcher.set_soft_byte_limit(Some(500));
+        batcher.push(mk_dummy_rr(&[0; 10])).unwrap();
+        assert!(batcher.push(mk_dummy_rr(&[0; 1000])).is_err());
+        batcher.finish();
+        assert_eq!(take_ancounts(&mut batcher), [1]);
+    }
+
+    #[test]
+    fn rr_batcher_compressed_size() {
+        // With compression, the soft byte limit applies to the compressed
+        // length and more records with similar names fit.
+        let req = Arc::new(MessageBuilder::new_vec().into_message());
+        let mut plain =
+            RrBatcher::<_, StreamTarget<Vec<u8>>>::new(req.clone());
+        let mut compressed = RrBatcher::<
+            _,
+            TreeCompressor<StreamTarget<Vec<u8>>>,
+        >::new_compressed(req);
+        plain.set_soft_byte_limit(Some(1000));
+        compressed.set_soft_byte_limit(Some(1000));
+        let owner = Name::<Vec<u8>>::from_str(
+            "a-rather-long-label.in.a-rather-long-zone.example.com.",
+        )
+        .unwrap();
+        for _ in 0..100 {
+            let rr =
+                (&owner, 0, Txt::<Vec<u8>>::build_from_slice(b"x").unwrap());
+            plain.push(&rr).unwrap();
+            compressed.push(&rr).unwrap();
+        }
+        plain.finish();
+        compressed.finish();
+
+        let plain = take_ancounts(&mut plain);
+        let compressed = take_ancounts(&mut compressed);
+        assert!(compressed[0] > plain[0]);
+        assert!(compressed.len() < plain.len());
+        assert_eq!(plain.iter().sum::<u16>(), 100);
+        assert_eq!(compressed.iter().sum::<u16>(), 100);
+    }
+
+    fn mk_rr_batcher() -> RrBatcher<Vec<u8>, StreamTarget<Vec<u8>>> {
+        let req = Arc::new(MessageBuilder::new_vec().into_message());
+        RrBatcher::new(req)
+    }
+
+    fn take_ancounts<Target: Composer>(
+        batcher: &mut RrBatcher<Vec<u8>, Target>,
+    ) -> Vec<u16> {
+        let mut res = Vec::new();
+        while let Some(answer) = batcher.take() {
+            res.push(answer.counts().ancount());
+        }
+        res
+    }
+
     fn mk_counting_batcher(
     ) -> CallbackBatcher<Vec<u8>, Vec<u8>, BatchCounter, Arc<TestCounters>>
     {
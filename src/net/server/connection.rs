@@ -578,6 +578,11 @@ where
     }
 
     /// Write a response back to the caller over the network stream.
+    ///
+    /// The response is written straight from the buffer it was composed
+    /// into, including the two octet length prefix kept by [`StreamTarget`],
+    /// so no intermediate copy is made. As a stream message is limited to
+    /// 65,535 octets, a single response never needs more than that.
     async fn write_response_to_stream(
         &mut self,
         msg: StreamTarget<Svc::Target>,
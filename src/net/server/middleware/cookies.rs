@@ -83,9 +83,28 @@ impl<RequestOctets, NextSvc, RequestMeta>
         }
     }
 
+    /// Creates an instance of this middleware service with a random server
+    /// secret.
+    ///
+    /// The secret is generated using the thread-local random number
+    /// generator.
+    #[must_use]
     pub fn with_random_secret(next_svc: NextSvc) -> Self {
+        Self::with_rng(next_svc, &mut rand::thread_rng())
+    }
+
+    /// Creates an instance of this middleware service with a server secret
+    /// taken from the given random number generator.
+    ///
+    /// Passing a seeded generator results in a reproducible server secret
+    /// and thus in reproducible server cookies, e.g. for testing.
+    #[must_use]
+    pub fn with_rng<R: RngCore + ?Sized>(
+        next_svc: NextSvc,
+        rng: &mut R,
+    ) -> Self {
         let mut server_secret = [0u8; 16];
-        rand::thread_rng().fill_bytes(&mut server_secret);
+        rng.fill_bytes(&mut server_secret);
         Self::new(next_svc, server_secret)
     }
 
@@ -497,17 +516,23 @@ where
 #[cfg(test)]
 mod tests {
     use bytes::Bytes;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
     use std::vec::Vec;
     use tokio::time::Instant;
     use tokio_stream::StreamExt;
 
+    use crate::base::iana::Rcode;
     use crate::base::opt::cookie::ClientCookie;
     use crate::base::opt::Cookie;
-    use crate::base::{Message, MessageBuilder, Name, Rtype};
+    use crate::base::{Message, MessageBuilder, Name, Rtype, Serial};
     use crate::net::server::message::{Request, UdpTransportContext};
     use crate::net::server::middleware::cookies::CookiesMiddlewareSvc;
     use crate::net::server::service::{CallResult, Service, ServiceResult};
-    use crate::net::server::util::service_fn;
+    use crate::net::server::tests::util::{
+        mk_non_udp_request, mk_udp_request,
+    };
+    use crate::net::server::util::{mk_builder_for_target, service_fn};
 
     #[tokio::test]
     async fn dont_add_cookie_twice() {
@@ -582,4 +607,80 @@ mod tests {
             "There should only be one COOKIE option"
         );
     }
+
+    #[tokio::test]
+    async fn deny_list_only_applies_to_udp() {
+        // Build a dummy DNS query without a cookie.
+        let query = MessageBuilder::new_vec();
+        let mut query = query.question();
+        query.push((Name::<Bytes>::root(), Rtype::A)).unwrap();
+        let message = query.into_message();
+
+        fn my_service(
+            req: Request<Vec<u8>>,
+            _meta: (),
+        ) -> ServiceResult<Vec<u8>> {
+            let builder = mk_builder_for_target();
+            let answer =
+                builder.start_answer(req.message(), Rcode::NOERROR)?;
+            Ok(CallResult::new(answer.additional()))
+        }
+
+        let my_svc = service_fn(my_service, ());
+        let middleware_svc = CookiesMiddlewareSvc::new(my_svc, [0; 16])
+            .with_denied_ips(["127.0.0.1".parse().unwrap()]);
+
+        let svc = &middleware_svc;
+        let process = move |request: Request<Vec<u8>>| async move {
+            let mut stream = svc.call(request).await;
+            let call_result: CallResult<Vec<u8>> =
+                stream.next().await.unwrap().unwrap();
+            let (response, _feedback) = call_result.into_inner();
+            let response = response.unwrap().finish();
+            Message::from_octets(response.as_dgram_slice().to_vec()).unwrap()
+        };
+
+        // Over UDP the client is told to retry over TCP.
+        let response = process(mk_udp_request(message.clone(), None)).await;
+        assert_eq!(response.header().rcode(), Rcode::REFUSED);
+        assert!(response.header().tc());
+
+        // Over TCP the request is passed on to the next service.
+        let response = process(mk_non_udp_request(message, None)).await;
+        assert_eq!(response.header().rcode(), Rcode::NOERROR);
+        assert!(!response.header().tc());
+    }
+
+    #[test]
+    fn seeded_rng_gives_reproducible_cookies() {
+        fn my_service(
+            _req: Request<Vec<u8>>,
+            _meta: (),
+        ) -> ServiceResult<Vec<u8>> {
+            todo!()
+        }
+
+        let mk_svc = |seed| {
+            CookiesMiddlewareSvc::<Vec<u8>, _, ()>::with_rng(
+                service_fn(my_service, ()),
+                &mut StdRng::seed_from_u64(seed),
+            )
+        };
+        let svc1 = mk_svc(42);
+        let svc2 = mk_svc(42);
+        let svc3 = mk_svc(43);
+
+        let client_cookie = ClientCookie::new_random();
+        let client_ip = "127.0.0.1".parse().unwrap();
+        let mk_cookie = |svc: &CookiesMiddlewareSvc<_, _, _>| {
+            Cookie::new(client_cookie, None).create_response(
+                Serial(1_700_000_000),
+                client_ip,
+                &svc.server_secret,
+            )
+        };
+
+        assert_eq!(mk_cookie(&svc1), mk_cookie(&svc2));
+        assert_ne!(mk_cookie(&svc1), mk_cookie(&svc3));
+    }
 }
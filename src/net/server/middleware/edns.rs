@@ -3,6 +3,7 @@ use core::future::{ready, Ready};
 use core::marker::PhantomData;
 use core::ops::ControlFlow;
 
+use bytes::Bytes;
 use futures_util::stream::{once, Once, Stream};
 use log::{log_enabled, Level};
 use octseq::Octets;
@@ -11,7 +12,9 @@ use tracing::{debug, error, trace, warn};
 use crate::base::iana::OptRcode;
 use crate::base::message_builder::AdditionalBuilder;
 use crate::base::opt::keepalive::IdleTimeout;
-use crate::base::opt::{ComposeOptData, Opt, OptRecord, TcpKeepalive};
+use crate::base::opt::{
+    ComposeOptData, Opt, OptRecord, ReportChannel, TcpKeepalive,
+};
 use crate::base::wire::Composer;
 use crate::base::{Message, Name, StreamTarget};
 use crate::net::server::message::{Request, TransportSpecificContext};
@@ -42,10 +45,12 @@ const EDNS_VERSION_ZERO: u8 = 0;
 /// | [6891] | TBD     |
 /// | [7828] | TBD     |
 /// | [9210] | TBD     |
+/// | [9567] | Partial |
 ///
 /// [6891]: https://datatracker.ietf.org/doc/html/rfc6891
 /// [7828]: https://datatracker.ietf.org/doc/html/rfc7828
 /// [9210]: https://datatracker.ietf.org/doc/html/rfc9210
+/// [9567]: https://datatracker.ietf.org/doc/html/rfc9567
 #[derive(Clone, Debug, Default)]
 pub struct EdnsMiddlewareSvc<RequestOctets, NextSvc, RequestMeta> {
     /// The upstream [`Service`] to pass requests to and receive responses
@@ -58,6 +63,21 @@ pub struct EdnsMiddlewareSvc<RequestOctets, NextSvc, RequestMeta> {
     /// responses through unmodified.
     enabled: bool,
 
+    /// Should requests with an unsupported EDNS version be rejected?
+    ///
+    /// Defaults to true. If true, requests with an EDNS version higher than
+    /// the highest version supported by this service will be answered with
+    /// BADVERS per [RFC 6891 section 6.1.3].
+    ///
+    /// [RFC 6891 section 6.1.3]: https://datatracker.ietf.org/doc/html/rfc6891#section-6.1.3
+    check_version: bool,
+
+    /// The agent domain to advertise for DNS error reporting.
+    ///
+    /// Defaults to None. If set, responses to requests with an OPT record
+    /// include an RFC 9567 Report-Channel option with this domain.
+    report_channel: Option<Name<Bytes>>,
+
     _phantom: PhantomData<(RequestOctets, RequestMeta)>,
 }
 
@@ -70,14 +90,48 @@ impl<RequestOctets, NextSvc, RequestMeta>
         Self {
             next_svc,
             enabled: true,
+            check_version: true,
+            report_channel: None,
             _phantom: PhantomData,
         }
     }
 
+    /// Enables or disables this middleware service.
     pub fn enable(mut self, enabled: bool) -> Self {
         self.enabled = enabled;
         self
     }
+
+    /// Enables or disables the rejection of unsupported EDNS versions.
+    ///
+    /// When enabled (the default), a request whose OPT record indicates an
+    /// EDNS version higher than 0 is answered with an extended RCODE of
+    /// BADVERS and an OPT record advertising version 0, the highest version
+    /// implemented by this service.
+    pub fn check_version(mut self, enabled: bool) -> Self {
+        self.check_version = enabled;
+        self
+    }
+
+    /// Sets the agent domain to advertise for DNS error reporting.
+    ///
+    /// When set, responses to requests that include an OPT record carry
+    /// an [RFC 9567] Report-Channel option with the given agent domain,
+    /// which resolvers can use to report errors they encountered. The
+    /// option is not included in responses for names at or below the agent
+    /// domain itself, so that reports are not made about the reporting
+    /// channel.
+    ///
+    /// Defaults to None, i.e. no option is included.
+    ///
+    /// [RFC 9567]: https://datatracker.ietf.org/doc/html/rfc9567
+    pub fn report_channel(
+        mut self,
+        agent_domain: Option<Name<Bytes>>,
+    ) -> Self {
+        self.report_channel = agent_domain;
+        self
+    }
 }
 
 impl<RequestOctets, NextSvc, RequestMeta>
@@ -126,13 +180,25 @@ where
                 // https://datatracker.ietf.org/doc/html/rfc6891#section-6.1.3
                 // 6.1.3. OPT Record TTL Field Use
                 //   "If a responder does not implement the VERSION level of
-                //    the request, then it MUST respond with RCODE=BADVERS."
-                if opt_rec.version() > EDNS_VERSION_ZERO {
+                //    the request, then it MUST respond with RCODE=BADVERS.
+                //    All responses MUST be limited in format to the VERSION
+                //    level of the request, but the VERSION of each response
+                //    SHOULD be the highest implementation level of the
+                //    responder."
+                if self.check_version && opt_rec.version() > EDNS_VERSION_ZERO
+                {
                     debug!("RFC 6891 6.1.3 violation: request EDNS version {} > 0", opt_rec.version());
-                    return ControlFlow::Break(mk_error_response(
+                    let mut response = mk_error_response(
                         request.message(),
                         OptRcode::BADVERS,
-                    ));
+                    );
+                    if let Err(err) = add_edns_options(&mut response, |b| {
+                        b.set_version(EDNS_VERSION_ZERO);
+                        Ok(())
+                    }) {
+                        warn!("Cannot set EDNS version in BADVERS response: {err}");
+                    }
+                    return ControlFlow::Break(response);
                 }
 
                 match request.transport_ctx() {
@@ -208,7 +274,7 @@ where
 
                         ctx.set_max_response_size_hint(Some(negotiated_hint));
 
-                        Self::reserve_space_for_opt(request, false);
+                        self.reserve_space_for_opt(request, false);
                     }
 
                     TransportSpecificContext::NonUdp(_ctx) => {
@@ -230,7 +296,7 @@ where
                             }
                         }
 
-                        Self::reserve_space_for_opt(request, true);
+                        self.reserve_space_for_opt(request, true);
                     }
                 }
             }
@@ -242,6 +308,7 @@ where
     fn postprocess(
         request: &Request<RequestOctets, RequestMeta>,
         response: &mut AdditionalBuilder<StreamTarget<NextSvc::Target>>,
+        report_channel: &Option<Name<Bytes>>,
     ) {
         // https://www.rfc-editor.org/rfc/rfc6891.html#section-7
         // 7: Transport considerations
@@ -319,6 +386,20 @@ where
             }
         }
 
+        // https://datatracker.ietf.org/doc/html/rfc9567#section-5
+        // The Report-Channel option tells resolvers that error reporting is
+        // supported and which agent domain to send reports to.
+        if let Some(agent_domain) =
+            Self::report_channel_for(request, report_channel)
+        {
+            response.clear_push_limit();
+            if let Err(err) = add_edns_options(response, |builder| {
+                builder.push(&ReportChannel::new(agent_domain))
+            }) {
+                warn!("Cannot add RFC 9567 Report-Channel option to response: {err}");
+            }
+        }
+
         // https://www.rfc-editor.org/rfc/rfc6891.html#section-6.1.1
         // 6.1.1: Basic Elements
         // ...
@@ -342,6 +423,7 @@ where
     }
 
     fn reserve_space_for_opt(
+        &self,
         request: &mut Request<RequestOctets, RequestMeta>,
         is_tcp: bool,
     ) {
@@ -373,6 +455,15 @@ where
             0
         };
 
+        let report_channel_option_len =
+            match Self::report_channel_for(request, &self.report_channel) {
+                Some(agent_domain) => {
+                    // OPTION-CODE + OPTION-LENGTH + OPTION-DATA
+                    2 + 2 + ReportChannel::new(agent_domain).compose_len()
+                }
+                None => 0,
+            };
+
         let root_name_len = Name::root_ref().compose_len();
 
         // See:
@@ -387,19 +478,37 @@ where
             + 2 // CLASS
             + 4 // TTL
             + 2 // RDLEN
-            + keep_alive_option_len; // OPTION-DATA
+            + keep_alive_option_len // OPTION-DATA
+            + report_channel_option_len; // OPTION-DATA
 
         request.reserve_bytes(wire_opt_len);
     }
 
+    /// Returns the agent domain to advertise in the response to `request`.
+    ///
+    /// This is `report_channel` if the request has an OPT record and its
+    /// question is not for a name at or below the agent domain.
+    fn report_channel_for<'a>(
+        request: &Request<RequestOctets, RequestMeta>,
+        report_channel: &'a Option<Name<Bytes>>,
+    ) -> Option<&'a Name<Bytes>> {
+        let agent_domain = report_channel.as_ref()?;
+        let msg = request.message();
+        msg.opt()?;
+        match msg.first_question() {
+            Some(q) if q.qname().ends_with(agent_domain) => None,
+            _ => Some(agent_domain),
+        }
+    }
+
     fn map_stream_item(
         request: Request<RequestOctets, RequestMeta>,
         mut stream_item: ServiceResult<NextSvc::Target>,
-        _pp_meta: &mut (),
+        report_channel: &mut Option<Name<Bytes>>,
     ) -> ServiceResult<NextSvc::Target> {
         if let Ok(cr) = &mut stream_item {
             if let Some(response) = cr.response_mut() {
-                Self::postprocess(&request, response);
+                Self::postprocess(&request, response, report_channel);
             }
         }
         stream_item
@@ -426,7 +535,7 @@ where
             NextSvc::Future,
             NextSvc::Stream,
             RequestMeta,
-            (),
+            Option<Name<Bytes>>,
         >,
         Once<Ready<<NextSvc::Stream as Stream>::Item>>,
         <NextSvc::Stream as Stream>::Item,
@@ -448,13 +557,17 @@ where
                 let map = PostprocessingStream::new(
                     svc_call_fut,
                     request,
-                    (),
+                    self.report_channel.clone(),
                     Self::map_stream_item,
                 );
                 ready(MiddlewareStream::Map(map))
             }
             ControlFlow::Break(mut response) => {
-                Self::postprocess(&request, &mut response);
+                Self::postprocess(
+                    &request,
+                    &mut response,
+                    &self.report_channel,
+                );
                 ready(MiddlewareStream::Result(once(ready(Ok(
                     CallResult::new(response),
                 )))))
@@ -465,6 +578,7 @@ where
 
 #[cfg(test)]
 mod tests {
+    use core::str::FromStr;
     use std::vec::Vec;
 
     use bytes::Bytes;
@@ -476,9 +590,10 @@ mod tests {
         Request, TransportSpecificContext, UdpTransportContext,
     };
 
-    use crate::base::iana::Rcode;
+    use crate::base::iana::{OptRcode, Rcode};
     use crate::net::server::middleware::mandatory::MINIMUM_RESPONSE_BYTE_LEN;
     use crate::net::server::service::{CallResult, Service, ServiceResult};
+    use crate::net::server::tests::util::mk_udp_request;
     use crate::net::server::util::{mk_builder_for_target, service_fn};
 
     use super::EdnsMiddlewareSvc;
@@ -529,8 +644,128 @@ mod tests {
         assert_eq!(process(HUGE, HUGE).await, HUGE);
     }
 
+    #[tokio::test]
+    async fn unsupported_edns_version_gets_badvers() {
+        // An EDNS version 1 request should be rejected with BADVERS and an
+        // OPT record advertising EDNS version 0.
+        let response = process_version(1, true).await;
+        assert_eq!(response.opt_rcode(), OptRcode::BADVERS);
+        assert_eq!(response.opt().unwrap().version(), 0);
+
+        // An EDNS version 0 request should reach the upstream service.
+        let response = process_version(0, true).await;
+        assert_eq!(response.opt_rcode(), OptRcode::NXDOMAIN);
+
+        // With the version check disabled an EDNS version 1 request should
+        // also reach the upstream service.
+        let response = process_version(1, false).await;
+        assert_eq!(response.opt_rcode(), OptRcode::NXDOMAIN);
+    }
+
+    #[tokio::test]
+    async fn report_channel_is_advertised() {
+        let agent = Name::<Bytes>::from_str("agent.example").unwrap();
+
+        // A request with an OPT record gets the Report-Channel option.
+        let response = process_report_channel("www.example.com", true).await;
+        let opt = response.opt().unwrap();
+        let rc = opt.opt().report_channel().unwrap();
+        assert_eq!(rc.agent_domain(), &agent);
+
+        // Requests for the agent domain don't.
+        let response =
+            process_report_channel("_er.0.a.0._er.agent.example", true).await;
+        assert!(response.opt().unwrap().opt().report_channel().is_none());
+
+        // Neither do requests without an OPT record.
+        let response = process_report_channel("www.example.com", false).await;
+        assert!(response.opt().is_none());
+    }
+
     //------------ Helper functions ------------------------------------------
 
+    async fn process_report_channel(
+        qname: &str,
+        with_opt: bool,
+    ) -> Message<Vec<u8>> {
+        let query = MessageBuilder::new_vec();
+        let mut query = query.question();
+        query
+            .push((Name::<Bytes>::from_str(qname).unwrap(), Rtype::A))
+            .unwrap();
+        let message = if with_opt {
+            let mut additional = query.additional();
+            additional.opt(|_| Ok(())).unwrap();
+            additional.into_message()
+        } else {
+            query.into_message()
+        };
+
+        let request = mk_udp_request(message, None);
+
+        fn my_service(
+            req: Request<Vec<u8>>,
+            _meta: (),
+        ) -> ServiceResult<Vec<u8>> {
+            let builder = mk_builder_for_target();
+            let answer =
+                builder.start_answer(req.message(), Rcode::NXDOMAIN)?;
+            Ok(CallResult::new(answer.additional()))
+        }
+
+        let my_svc = service_fn(my_service, ());
+        let middleware_svc = EdnsMiddlewareSvc::new(my_svc)
+            .report_channel(Some(Name::from_str("agent.example").unwrap()));
+        let mut stream = middleware_svc.call(request).await;
+        let call_result: CallResult<Vec<u8>> =
+            stream.next().await.unwrap().unwrap();
+        let (response, _feedback) = call_result.into_inner();
+
+        let response = response.unwrap().finish();
+        Message::from_octets(response.as_dgram_slice().to_vec()).unwrap()
+    }
+
+    async fn process_version(
+        edns_version: u8,
+        check_version: bool,
+    ) -> Message<Vec<u8>> {
+        // Build a dummy DNS query with an OPT record of the given version.
+        let query = MessageBuilder::new_vec();
+        let mut query = query.question();
+        query.push((Name::<Bytes>::root(), Rtype::A)).unwrap();
+        let mut additional = query.additional();
+        additional
+            .opt(|builder| {
+                builder.set_version(edns_version);
+                Ok(())
+            })
+            .unwrap();
+        let message = additional.into_message();
+
+        let request = mk_udp_request(message, None);
+
+        fn my_service(
+            req: Request<Vec<u8>>,
+            _meta: (),
+        ) -> ServiceResult<Vec<u8>> {
+            let builder = mk_builder_for_target();
+            let answer =
+                builder.start_answer(req.message(), Rcode::NXDOMAIN)?;
+            Ok(CallResult::new(answer.additional()))
+        }
+
+        let my_svc = service_fn(my_service, ());
+        let middleware_svc =
+            EdnsMiddlewareSvc::new(my_svc).check_version(check_version);
+        let mut stream = middleware_svc.call(request).await;
+        let call_result: CallResult<Vec<u8>> =
+            stream.next().await.unwrap().unwrap();
+        let (response, _feedback) = call_result.into_inner();
+
+        let response = response.unwrap().finish();
+        Message::from_octets(response.as_dgram_slice().to_vec()).unwrap()
+    }
+
     async fn process(
         client_value: Option<u16>,
         server_value: Option<u16>,
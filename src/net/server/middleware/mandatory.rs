@@ -4,12 +4,13 @@ use core::marker::PhantomData;
 use core::ops::ControlFlow;
 
 use std::fmt::Display;
+use std::vec::Vec;
 
 use futures_util::stream::{once, Once, Stream};
 use octseq::Octets;
 use tracing::{debug, error, trace, warn};
 
-use crate::base::iana::{Opcode, OptRcode};
+use crate::base::iana::{Opcode, OptRcode, Rtype};
 use crate::base::message_builder::{AdditionalBuilder, PushError};
 use crate::base::wire::{Composer, ParseError};
 use crate::base::{Message, StreamTarget};
@@ -50,6 +51,9 @@ pub struct MandatoryMiddlewareSvc<RequestOctets, NextSvc, RequestMeta> {
     /// responses.
     strict: bool,
 
+    /// The opcodes to accept, or `None` to accept all opcodes.
+    opcodes: Option<Vec<Opcode>>,
+
     _phantom: PhantomData<(RequestOctets, RequestMeta)>,
 }
 
@@ -63,6 +67,7 @@ impl<RequestOctets, NextSvc, RequestMeta>
     pub fn new(next_svc: NextSvc) -> Self {
         Self {
             strict: true,
+            opcodes: None,
             next_svc,
             _phantom: PhantomData,
         }
@@ -75,10 +80,23 @@ impl<RequestOctets, NextSvc, RequestMeta>
     pub fn relaxed(next_svc: NextSvc) -> Self {
         Self {
             strict: false,
+            opcodes: None,
             next_svc,
             _phantom: PhantomData,
         }
     }
+
+    /// Only accepts requests with one of the given opcodes.
+    ///
+    /// Requests with any other opcode are answered with NOTIMP. This makes
+    /// it possible to enable opcodes such as NOTIFY or UPDATE only when the
+    /// services further down the chain support them. By default requests
+    /// are accepted whatever their opcode, except for IQUERY in strict mode.
+    #[must_use]
+    pub fn with_opcodes<T: Into<Vec<Opcode>>>(mut self, opcodes: T) -> Self {
+        self.opcodes = Some(opcodes.into());
+        self
+    }
 }
 
 impl<RequestOctets, NextSvc, RequestMeta>
@@ -212,6 +230,20 @@ where
             ));
         }
 
+        // https://datatracker.ietf.org/doc/html/rfc1035#section-4.1.1
+        //   "RCODE ... 4 Not Implemented - The name server does not support
+        //    the requested kind of query."
+        if let Some(opcodes) = &self.opcodes {
+            let opcode = msg.header().opcode();
+            if !opcodes.contains(&opcode) {
+                debug!("Request opcode {opcode} is not supported.");
+                return ControlFlow::Break(mk_error_response(
+                    msg,
+                    OptRcode::NOTIMP,
+                ));
+            }
+        }
+
         // https://datatracker.ietf.org/doc/html/rfc9619#section-4
         // 4. Updates to RFC 1035
         //   ...
@@ -231,6 +263,19 @@ where
             ));
         }
 
+        // https://datatracker.ietf.org/doc/html/rfc6891#section-6.1.1
+        // 6.1.1. Basic Elements
+        //   ...
+        //   "If an OPT record is found in any other section, it MUST be
+        //    treated as malformed."
+        if self.strict && has_misplaced_opt(msg) {
+            debug!("RFC 6891 violation: OPT record outside the additional section.");
+            return ControlFlow::Break(mk_error_response(
+                msg,
+                OptRcode::FORMERR,
+            ));
+        }
+
         ControlFlow::Continue(())
     }
 
@@ -350,6 +395,21 @@ where
     }
 }
 
+//------------ Helper functions ----------------------------------------------
+
+/// Returns whether the message has an OPT record outside the additional
+/// section.
+///
+/// Records that fail to parse are skipped.
+fn has_misplaced_opt<Octs: Octets>(msg: &Message<Octs>) -> bool {
+    let (Ok(answer), Ok(authority)) = (msg.answer(), msg.authority()) else {
+        return false;
+    };
+    answer
+        .chain(authority)
+        .any(|rr| matches!(rr, Ok(rr) if rr.rtype() == Rtype::OPT))
+}
+
 //------------ TruncateError -------------------------------------------------
 
 /// An error occured during oversize response truncation.
@@ -395,10 +455,12 @@ mod tests {
     use futures_util::StreamExt;
     use tokio::time::Instant;
 
-    use crate::base::iana::Rcode;
+    use crate::base::iana::{Class, Opcode, Rcode};
+    use crate::base::opt::Opt;
     use crate::base::{MessageBuilder, Name, Rtype};
     use crate::net::server::message::{Request, UdpTransportContext};
     use crate::net::server::service::{CallResult, Service, ServiceResult};
+    use crate::net::server::tests::util::mk_udp_request;
     use crate::net::server::util::{mk_builder_for_target, service_fn};
 
     use super::{MandatoryMiddlewareSvc, MINIMUM_RESPONSE_BYTE_LEN};
@@ -426,8 +488,86 @@ mod tests {
         assert!(process(Some(HUGE)).await <= Some(HUGE as usize));
     }
 
+    #[tokio::test]
+    async fn unsupported_opcode_is_not_implemented() {
+        let rcode = process_opcode(Opcode::UPDATE).await;
+        assert_eq!(rcode, Rcode::NOTIMP);
+    }
+
+    #[tokio::test]
+    async fn enabled_opcode_is_passed_on() {
+        let rcode = process_opcode(Opcode::NOTIFY).await;
+        assert_eq!(rcode, Rcode::NXDOMAIN);
+    }
+
+    #[tokio::test]
+    async fn misplaced_opt_is_formerr() {
+        let mut query = MessageBuilder::new_vec().question();
+        query.push((Name::<Bytes>::root(), Rtype::SOA)).unwrap();
+        let mut query = query.authority();
+        query
+            .push((
+                Name::root_slice(),
+                Class::from_int(1232),
+                0,
+                Opt::<Vec<u8>>::empty(),
+            ))
+            .unwrap();
+        let message = query.into_message();
+
+        let request = mk_udp_request(message, None);
+
+        // Strict mode rejects the request.
+        let my_svc = service_fn(nxdomain_service, ());
+        let middleware_svc = MandatoryMiddlewareSvc::new(my_svc);
+        let mut stream = middleware_svc.call(request.clone()).await;
+        let call_result: CallResult<Vec<u8>> =
+            stream.next().await.unwrap().unwrap();
+        let (response, _feedback) = call_result.into_inner();
+        assert_eq!(response.unwrap().header().rcode(), Rcode::FORMERR);
+
+        // Relaxed mode passes it on.
+        let my_svc = service_fn(nxdomain_service, ());
+        let middleware_svc = MandatoryMiddlewareSvc::relaxed(my_svc);
+        let mut stream = middleware_svc.call(request).await;
+        let call_result: CallResult<Vec<u8>> =
+            stream.next().await.unwrap().unwrap();
+        let (response, _feedback) = call_result.into_inner();
+        assert_eq!(response.unwrap().header().rcode(), Rcode::NXDOMAIN);
+    }
+
     //------------ Helper functions ------------------------------------------
 
+    // Returns the rcode of the response to a request with the given opcode
+    // passed through the middleware accepting only QUERY and NOTIFY.
+    async fn process_opcode(opcode: Opcode) -> Rcode {
+        let mut query = MessageBuilder::new_vec();
+        query.header_mut().set_opcode(opcode);
+        let mut query = query.question();
+        query.push((Name::<Bytes>::root(), Rtype::SOA)).unwrap();
+        let message = query.into_message();
+
+        let request = mk_udp_request(message, None);
+
+        let my_svc = service_fn(nxdomain_service, ());
+        let middleware_svc = MandatoryMiddlewareSvc::new(my_svc)
+            .with_opcodes([Opcode::QUERY, Opcode::NOTIFY]);
+        let mut stream = middleware_svc.call(request).await;
+        let call_result: CallResult<Vec<u8>> =
+            stream.next().await.unwrap().unwrap();
+        let (response, _feedback) = call_result.into_inner();
+        response.unwrap().header().rcode()
+    }
+
+    fn nxdomain_service(
+        req: Request<Vec<u8>>,
+        _meta: (),
+    ) -> ServiceResult<Vec<u8>> {
+        let builder = mk_builder_for_target();
+        let answer = builder.start_answer(req.message(), Rcode::NXDOMAIN)?;
+        Ok(CallResult::new(answer.additional()))
+    }
+
     // Returns Some(n) if truncation occurred where n is the size after
     // truncation.
     async fn process(max_response_size_hint: Option<u16>) -> Option<usize> {
@@ -56,6 +56,7 @@ use futures_util::stream::{once, Once, Stream};
 use octseq::Octets;
 use tracing::{error, info, warn};
 
+use crate::base::header::HeaderFlags;
 use crate::base::iana::{Class, Opcode, OptRcode, Rcode};
 use crate::base::message::CopyRecordsError;
 use crate::base::message_builder::AdditionalBuilder;
@@ -233,8 +234,7 @@ where
                 let response_hdr = additional.header_mut();
                 response_hdr.set_opcode(Opcode::NOTIFY);
                 response_hdr.set_rcode(Rcode::NOERROR);
-                response_hdr.set_qr(true);
-                response_hdr.set_aa(true);
+                HeaderFlags::new().qr(true).aa(true).apply(response_hdr);
 
                 let res = once(ready(Ok(CallResult::new(additional))));
                 ControlFlow::Break(res)
@@ -6,6 +6,7 @@ use octseq::Octets;
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::trace;
 
+use crate::base::header::HeaderFlags;
 use crate::base::iana::{Opcode, Rcode};
 use crate::base::message_builder::{
     AdditionalBuilder, AnswerBuilder, PushError,
@@ -18,6 +19,11 @@ use crate::net::server::batcher::{
 use crate::net::server::service::{CallResult, ServiceResult};
 use crate::net::server::util::mk_builder_for_target;
 
+//------------ Constants -----------------------------------------------------
+
+/// The maximum length of any DNS message.
+const MAX_MSG_BYTE_LEN: usize = u16::MAX as usize;
+
 //------------ BatchReadyError ------------------------------------------------
 
 #[derive(Clone, Copy, Debug)]
@@ -124,16 +130,18 @@ where
         // MandatoryMiddlewareSvc we do it anyway to try harder to conform to
         // the RFC.
         header.set_id(msg.header().id());
-        header.set_qr(true);
-
         header.set_opcode(Opcode::QUERY);
-        header.set_aa(true);
-        header.set_tc(false);
-        header.set_rd(msg.header().rd());
-        header.set_ra(false);
-        header.set_z(false);
-        header.set_ad(false);
-        header.set_cd(false);
+
+        HeaderFlags::new()
+            .qr(true)
+            .aa(true)
+            .tc(false)
+            .rd(msg.header().rd())
+            .ra(false)
+            .z(false)
+            .ad(false)
+            .cd(false)
+            .apply(header);
     }
 }
 
@@ -154,7 +162,9 @@ where
     ) -> Result<AnswerBuilder<StreamTarget<Target>>, PushError> {
         let mut builder = mk_builder_for_target();
         if let Some(limit) = cb_state.soft_byte_limit {
-            builder.set_push_limit(limit);
+            // No DNS message can be longer than 65,535 bytes, whatever the
+            // caller asked for.
+            builder.set_push_limit(limit.min(MAX_MSG_BYTE_LEN));
         }
         let answer = builder.start_answer(msg, Rcode::NOERROR)?;
         Ok(answer)
@@ -1,3 +1,6 @@
+use core::cmp::Ordering;
+use core::fmt;
+
 use std::vec::Vec;
 
 use bytes::Bytes;
@@ -6,9 +9,134 @@ use tokio::sync::mpsc::Sender;
 use tracing::error;
 
 use crate::base::iana::OptRcode;
-use crate::base::{Name, Rtype};
+use crate::base::{Name, Rtype, Serial};
+use crate::rdata::ZoneRecordData;
 use crate::zonetree::{SharedRrset, StoredName, ZoneDiff, ZoneDiffItem};
 
+//------------ check_serial_progression ----------------------------------------
+
+/// Checks that the SOA serials of a sequence of diffs progress correctly.
+///
+/// RFC 1995 requires each difference sequence to start with the older SOA
+/// in the deleted RRs and the newer SOA in the added RRs, and the sequences
+/// to be ordered oldest first. This checks that each diff has both SOAs at
+/// the apex, that its serial increases, that the diffs connect to each
+/// other, and that together they lead from `query_serial` to
+/// `zone_serial`.
+///
+/// Malformed diffs would otherwise only be noticed after part of the
+/// response has already been streamed to the client, if at all.
+pub async fn check_serial_progression<Diff: ZoneDiff>(
+    qname: &StoredName,
+    query_serial: Serial,
+    zone_serial: Serial,
+    diffs: &[Diff],
+) -> Result<(), SerialProgressionError> {
+    let mut expected = query_serial;
+
+    for (idx, diff) in diffs.iter().enumerate() {
+        let Some(old) =
+            rrset_serial(diff.get_removed(qname, Rtype::SOA).await)
+        else {
+            return Err(SerialProgressionError::MissingOldSoa(idx));
+        };
+        let Some(new) = rrset_serial(diff.get_added(qname, Rtype::SOA).await)
+        else {
+            return Err(SerialProgressionError::MissingNewSoa(idx));
+        };
+        if old != expected {
+            return Err(SerialProgressionError::Gap {
+                idx,
+                expected,
+                found: old,
+            });
+        }
+        if new.partial_cmp(&old) != Some(Ordering::Greater) {
+            return Err(SerialProgressionError::NotIncreasing {
+                idx,
+                old,
+                new,
+            });
+        }
+        expected = new;
+    }
+
+    if expected != zone_serial {
+        return Err(SerialProgressionError::WrongEnd {
+            expected: zone_serial,
+            found: expected,
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns the serial of the first record in an SOA RRset.
+fn rrset_serial(rrset: Option<&SharedRrset>) -> Option<Serial> {
+    match rrset?.first()?.data() {
+        ZoneRecordData::Soa(soa) => Some(soa.serial()),
+        _ => None,
+    }
+}
+
+//------------ SerialProgressionError ------------------------------------------
+
+/// The SOA serials of a sequence of diffs do not progress correctly.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SerialProgressionError {
+    /// The diff with the given index has no SOA in its deleted RRs.
+    MissingOldSoa(usize),
+
+    /// The diff with the given index has no SOA in its added RRs.
+    MissingNewSoa(usize),
+
+    /// A diff does not start where the previous one ended.
+    Gap {
+        idx: usize,
+        expected: Serial,
+        found: Serial,
+    },
+
+    /// The serial of a diff does not increase.
+    NotIncreasing {
+        idx: usize,
+        old: Serial,
+        new: Serial,
+    },
+
+    /// The diffs do not end at the current serial of the zone.
+    WrongEnd { expected: Serial, found: Serial },
+}
+
+impl fmt::Display for SerialProgressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingOldSoa(idx) => {
+                write!(f, "diff {idx} lacks the old SOA")
+            }
+            Self::MissingNewSoa(idx) => {
+                write!(f, "diff {idx} lacks the new SOA")
+            }
+            Self::Gap {
+                idx,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "diff {idx} starts at serial {found} instead of {expected}"
+                )
+            }
+            Self::NotIncreasing { idx, old, new } => {
+                write!(f, "diff {idx} goes from serial {old} to {new}")
+            }
+            Self::WrongEnd { expected, found } => {
+                write!(f, "diffs end at serial {found} instead of {expected}")
+            }
+        }
+    }
+}
+
 //------------ DiffFunneler ----------------------------------------------------
 
 pub struct DiffFunneler<Diff> {
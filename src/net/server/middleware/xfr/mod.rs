@@ -54,7 +54,7 @@ pub mod data_provider;
 pub mod service;
 
 pub use data_provider::{XfrData, XfrDataProvider, XfrDataProviderError};
-pub use service::XfrMiddlewareSvc;
+pub use service::{XfrMiddlewareSvc, XfrRateLimiter};
 
 #[cfg(test)]
 mod tests;
@@ -9,7 +9,7 @@ use tracing::{debug, error};
 use crate::base::iana::OptRcode;
 use crate::base::rdata::RecordData;
 use crate::base::wire::Composer;
-use crate::base::{Message, Name, Rtype};
+use crate::base::{Message, Name, Rtype, Ttl};
 use crate::net::server::batcher::ResourceRecordBatcher;
 use crate::net::server::middleware::xfr::util::add_to_stream;
 use crate::net::server::service::ServiceResult;
@@ -29,6 +29,7 @@ pub struct BatchingRrResponder<RequestOctets, Target> {
     soft_byte_limit: usize,
     must_fit_in_single_message: bool,
     batcher_semaphore: Arc<Semaphore>,
+    max_ttl: Option<Ttl>,
 }
 
 impl<RequestOctets, Target> BatchingRrResponder<RequestOctets, Target>
@@ -46,6 +47,7 @@ where
         soft_byte_limit: usize,
         must_fit_in_single_message: bool,
         batcher_semaphore: Arc<Semaphore>,
+        max_ttl: Option<Ttl>,
     ) -> Self {
         Self {
             msg,
@@ -56,6 +58,7 @@ where
             soft_byte_limit,
             must_fit_in_single_message,
             batcher_semaphore,
+            max_ttl,
         }
     }
 
@@ -67,9 +70,13 @@ where
             return Err(OptRcode::SERVFAIL);
         }
 
-        // SAFETY: msg.sole_question() was already checked in
-        // get_relevant_question().
-        let qclass = self.msg.sole_question().unwrap().qclass();
+        // Note: msg.sole_question() was already checked in
+        // get_relevant_question(), so this should not fail.
+        let Ok(question) = self.msg.sole_question() else {
+            error!("Internal error: XFR request lacks a single question");
+            return Err(OptRcode::SERVFAIL);
+        };
+        let qclass = question.qclass();
 
         // Note: NSD apparently uses name compresson on AXFR responses
         // because AXFR responses they typically contain lots of
@@ -99,11 +106,16 @@ where
         let mut last_rr_rtype = None;
 
         while let Some((owner, rrset)) = self.batcher_rx.recv().await {
+            let ttl = match self.max_ttl {
+                Some(max_ttl) => rrset.ttl().min(max_ttl),
+                None => rrset.ttl(),
+            };
+
             for rr in rrset.data() {
                 last_rr_rtype = Some(rr.rtype());
 
                 if let Err(err) =
-                    batcher.push((owner.clone(), qclass, rrset.ttl(), rr))
+                    batcher.push((owner.clone(), qclass, ttl, rr))
                 {
                     match err {
                         BatchReadyError::MustFitInSingleMessage => {
@@ -148,12 +160,16 @@ where
             return Err(OptRcode::SERVFAIL);
         }
 
-        if last_rr_rtype != Some(Rtype::SOA) {
-            error!(
-                "Internal error: Last RR was {}, expected SOA",
-                last_rr_rtype.unwrap()
-            );
-            return Err(OptRcode::SERVFAIL);
+        match last_rr_rtype {
+            Some(Rtype::SOA) => {}
+            Some(rtype) => {
+                error!("Internal error: Last RR was {rtype}, expected SOA");
+                return Err(OptRcode::SERVFAIL);
+            }
+            None => {
+                error!("Internal error: No RRs received, expected SOA");
+                return Err(OptRcode::SERVFAIL);
+            }
         }
 
         Ok(())
@@ -1,32 +1,45 @@
+use core::fmt;
 use core::future::{ready, Future, Ready};
 use core::marker::PhantomData;
 use core::ops::ControlFlow;
 
+use core::time::Duration;
+
 use std::boxed::Box;
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::net::IpAddr;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::string::ToString;
+use std::sync::{Arc, Mutex};
 use std::vec::Vec;
 
 use futures_util::stream::{once, Once, Stream};
 use octseq::Octets;
 use tokio::sync::mpsc::unbounded_channel;
 use tokio::sync::Semaphore;
+use tokio::time::Instant;
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::{debug, error, info, trace, warn};
 
-use crate::base::iana::{Opcode, OptRcode};
+use crate::base::iana::{ExtendedErrorCode, Opcode, OptRcode};
 use crate::base::wire::Composer;
-use crate::base::{Message, ParsedName, Question, Rtype, Serial, ToName};
+use crate::base::{
+    Message, ParsedName, Question, Rtype, Serial, ToName, Ttl,
+};
 use crate::net::server::message::{Request, TransportSpecificContext};
 use crate::net::server::middleware::stream::MiddlewareStream;
 use crate::net::server::middleware::xfr::axfr::ZoneFunneler;
 use crate::net::server::middleware::xfr::data_provider::XfrDataProvider;
 use crate::net::server::middleware::xfr::data_provider::XfrDataProviderError;
-use crate::net::server::middleware::xfr::ixfr::DiffFunneler;
+use crate::net::server::middleware::xfr::ixfr::{
+    check_serial_progression, DiffFunneler,
+};
 use crate::net::server::middleware::xfr::responder::BatchingRrResponder;
 use crate::net::server::service::{CallResult, Service, ServiceFeedback};
-use crate::net::server::util::{mk_builder_for_target, mk_error_response};
+use crate::net::server::util::{
+    mk_builder_for_target, mk_error_response, mk_error_response_with_ede,
+};
 use crate::rdata::{Soa, ZoneRecordData};
 use crate::zonetree::{
     Answer, AnswerContent, ReadableZone, SharedRrset, StoredName,
@@ -72,6 +85,12 @@ pub struct XfrMiddlewareSvc<RequestOctets, NextSvc, RequestMeta, XDP> {
     /// may run concurrently.
     batcher_semaphore: Arc<Semaphore>,
 
+    /// An optional limit on the number of full zone transfers per client.
+    rate_limiter: Option<Arc<XfrRateLimiter>>,
+
+    /// An optional upper bound for the TTLs of transferred records.
+    max_ttl: Option<Ttl>,
+
     _phantom: PhantomData<(RequestOctets, RequestMeta)>,
 }
 
@@ -102,9 +121,40 @@ where
             xfr_data_provider,
             zone_walking_semaphore,
             batcher_semaphore,
+            rate_limiter: None,
+            max_ttl: None,
             _phantom: PhantomData,
         }
     }
+
+    /// Limits the number of full zone transfers per client IP address.
+    ///
+    /// Requests for a full zone transfer, i.e. AXFR requests and IXFR
+    /// requests that fall back to AXFR, that exceed the limit are answered
+    /// with REFUSED.
+    ///
+    /// The limiter can be shared between multiple instances of this
+    /// middleware.
+    #[must_use]
+    pub fn with_rate_limiter(
+        mut self,
+        rate_limiter: Arc<XfrRateLimiter>,
+    ) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Caps the TTLs of records in AXFR and IXFR responses.
+    ///
+    /// Records with a TTL above `max_ttl` are transferred with a TTL of
+    /// `max_ttl` instead. This can be used to make secondaries refresh
+    /// their data sooner than the TTLs in the zone would suggest. The zone
+    /// itself is not modified.
+    #[must_use]
+    pub fn with_max_ttl(mut self, max_ttl: Ttl) -> Self {
+        self.max_ttl = Some(max_ttl);
+        self
+    }
 }
 
 impl<RequestOctets, NextSvc, RequestMeta, XDP>
@@ -130,6 +180,8 @@ where
     pub async fn preprocess(
         zone_walking_semaphore: Arc<Semaphore>,
         batcher_semaphore: Arc<Semaphore>,
+        rate_limiter: Option<&XfrRateLimiter>,
+        max_ttl: Option<Ttl>,
         req: &Request<RequestOctets, RequestMeta>,
         xfr_data_provider: XDP,
     ) -> Result<
@@ -155,41 +207,44 @@ where
         //    query, but with the query type being IXFR and the authority
         //    section containing the SOA record of client's version of the
         //    zone."
-        let ixfr_query_serial = if let Ok(Some(Ok(query_soa))) = msg
-            .authority()
-            .map(|section| section.limit_to::<Soa<ParsedName<_>>>().next())
-        {
-            Some(query_soa.data().serial())
+        let ixfr_query_serial = if q.qtype() == Rtype::IXFR {
+            match Self::get_ixfr_query_serial(msg) {
+                Ok(serial) => Some(serial),
+                Err(err) => {
+                    warn!(
+                        "{} for {} from {} refused: {err}",
+                        q.qtype(),
+                        q.qname(),
+                        req.client_addr()
+                    );
+                    return Ok(Self::mk_ede_response(
+                        msg,
+                        OptRcode::FORMERR,
+                        ExtendedErrorCode::OTHER,
+                        &err.to_string(),
+                    ));
+                }
+            }
         } else {
             None
         };
 
-        if q.qtype() == Rtype::IXFR && ixfr_query_serial.is_none() {
-            warn!(
-                "{} for {} from {} refused: IXFR request lacks authority section SOA",
-                q.qtype(),
-                q.qname(),
-                req.client_addr()
-            );
-            return Err(OptRcode::FORMERR);
-        }
-
         // Is transfer allowed for the requested zone for this requestor?
-        let xfr_data = xfr_data_provider
-            .request(req, ixfr_query_serial)
-            .await
-            .map_err(|err| match err {
-                XfrDataProviderError::ParseError(err) => {
+        let xfr_data =
+            match xfr_data_provider.request(req, ixfr_query_serial).await {
+                Ok(xfr_data) => xfr_data,
+
+                Err(XfrDataProviderError::ParseError(err)) => {
                     debug!(
                         "{} for {} from {} refused: parse error: {err}",
                         q.qtype(),
                         q.qname(),
                         req.client_addr()
                     );
-                    OptRcode::FORMERR
+                    return Err(OptRcode::FORMERR);
                 }
 
-                XfrDataProviderError::UnknownZone => {
+                Err(XfrDataProviderError::UnknownZone) => {
                     // https://datatracker.ietf.org/doc/html/rfc5936#section-2.2.1
                     // 2.2.1 Header Values
                     //   "If a server is not authoritative for the queried
@@ -200,34 +255,53 @@ where
                         q.qname(),
                         req.client_addr()
                     );
-                    OptRcode::NOTAUTH
+                    return Ok(Self::mk_ede_response(
+                        msg,
+                        OptRcode::NOTAUTH,
+                        ExtendedErrorCode::NOT_AUTHORITATIVE,
+                        "unknown zone",
+                    ));
                 }
 
-                XfrDataProviderError::TemporarilyUnavailable => {
+                Err(XfrDataProviderError::TemporarilyUnavailable) => {
                     // The zone is not yet loaded or has expired, both of
                     // which are presumably transient conditions and thus
                     // SERVFAIL is the appropriate response, not NOTAUTH, as
                     // we know we are supposed to be authoritative for the
                     // zone but we just don't have the data right now.
                     warn!(
-                        "{} for {} from {} refused: zone not currently available",
+                        "{} for {} from {} refused: zone not currently \
+                         available",
                         q.qtype(),
                         q.qname(),
                         req.client_addr()
                     );
-                    OptRcode::SERVFAIL
+                    return Ok(Self::mk_ede_response(
+                        msg,
+                        OptRcode::SERVFAIL,
+                        ExtendedErrorCode::NOT_READY,
+                        "zone not currently available",
+                    ));
                 }
 
-                XfrDataProviderError::Refused => {
+                Err(XfrDataProviderError::Refused) => {
+                    // The XFR data provider decides based on the request
+                    // metadata, i.e. typically the TSIG key used to sign the
+                    // request, whether transfer is allowed.
                     warn!(
                         "{} for {} from {} refused: access denied",
                         q.qtype(),
                         q.qname(),
                         req.client_addr()
                     );
-                    OptRcode::REFUSED
+                    return Ok(Self::mk_ede_response(
+                        msg,
+                        OptRcode::REFUSED,
+                        ExtendedErrorCode::PROHIBITED,
+                        "transfer not permitted for this client or TSIG key",
+                    ));
                 }
-            })?;
+            };
 
         // Read the zone SOA RR
         let read = xfr_data.zone().read();
@@ -239,7 +313,12 @@ where
                 q.qname(),
                 req.client_addr()
             );
-            return Err(OptRcode::SERVFAIL);
+            return Ok(Self::mk_ede_response(
+                msg,
+                OptRcode::SERVFAIL,
+                ExtendedErrorCode::NOT_AUTHORITATIVE,
+                "not authoritative for the requested name",
+            ));
         };
 
         match q.qtype() {
@@ -290,6 +369,19 @@ where
                         req.client_addr()
                     );
                 }
+
+                if let Some(rate_limiter) = rate_limiter {
+                    if !rate_limiter.try_acquire(req.client_addr().ip()) {
+                        warn!(
+                            "{} for {} from {} refused: too many full zone transfers",
+                            q.qtype(),
+                            q.qname(),
+                            req.client_addr()
+                        );
+                        return Err(OptRcode::REFUSED);
+                    }
+                }
+
                 let stream = Self::respond_to_axfr_query(
                     zone_walking_semaphore,
                     batcher_semaphore,
@@ -298,6 +390,7 @@ where
                     &zone_soa_answer,
                     read,
                     xfr_data.compatibility_mode(),
+                    max_ttl,
                 )
                 .await?;
 
@@ -329,6 +422,7 @@ where
                     q.qname().to_name(),
                     &zone_soa_answer,
                     xfr_data.into_diffs(),
+                    max_ttl,
                 )
                 .await?;
 
@@ -343,6 +437,10 @@ where
     }
 
     /// Generate and send an AXFR response for a given request and zone.
+    ///
+    /// The zone is walked via `read`, which provides a snapshot of the zone
+    /// as it was when the SOA was read. Thus the transferred data matches
+    /// the serial in the SOA even if the zone is updated during the walk.
     #[allow(clippy::too_many_arguments)]
     async fn respond_to_axfr_query<T>(
         zone_walk_semaphore: Arc<Semaphore>,
@@ -352,6 +450,7 @@ where
         zone_soa_answer: &Answer,
         read: Box<dyn ReadableZone>,
         compatibility_mode: bool,
+        max_ttl: Option<Ttl>,
     ) -> Result<
         XfrMiddlewareStream<
             NextSvc::Future,
@@ -369,6 +468,16 @@ where
             );
             return Err(OptRcode::SERVFAIL);
         };
+        if !matches!(
+            zone_soa_rrset.data().first(),
+            Some(ZoneRecordData::Soa(_))
+        ) {
+            error!(
+                "AXFR for {qname} from {} refused: zone SOA RRset is empty",
+                req.client_addr()
+            );
+            return Err(OptRcode::SERVFAIL);
+        }
 
         if compatibility_mode {
             trace!(
@@ -451,6 +560,7 @@ where
             soft_byte_limit,
             must_fit_in_single_message,
             batcher_semaphore,
+            max_ttl,
         );
 
         let cloned_msg = msg.clone();
@@ -505,6 +615,7 @@ where
         qname: StoredName,
         zone_soa_answer: &Answer,
         diffs: Vec<XDP::Diff>,
+        max_ttl: Option<Ttl>,
     ) -> Result<
         XfrMiddlewareStream<
             NextSvc::Future,
@@ -554,6 +665,37 @@ where
             return Ok(MiddlewareStream::Map(once(ready(res))));
         }
 
+        // Make sure the diffs form a valid sequence before we start
+        // streaming them as we can't take back what we sent.
+        //
+        // The futures for accessing the diffs aren't Sync, so they can't be
+        // awaited here directly. Do the check in a separate task instead.
+        let zone_serial = soa.serial();
+        let check_qname = qname.clone();
+        let check = tokio::spawn(async move {
+            let res = check_serial_progression(
+                &check_qname,
+                query_serial,
+                zone_serial,
+                &diffs,
+            )
+            .await;
+            (diffs, res)
+        });
+        let diffs = match check.await {
+            Ok((diffs, Ok(()))) => diffs,
+            Ok((_, Err(err))) => {
+                error!(
+                    "Refusing to send malformed IXFR diffs for {qname}: {err}"
+                );
+                return Err(OptRcode::SERVFAIL);
+            }
+            Err(err) => {
+                error!("Internal error: Failed to check IXFR diffs: {err}");
+                return Err(OptRcode::SERVFAIL);
+            }
+        };
+
         // TODO: Add something like the Bind `max-ixfr-ratio` option that
         // "sets the size threshold (expressed as a percentage of the size of
         // the full zone) beyond which named chooses to use an AXFR response
@@ -597,6 +739,7 @@ where
             soft_byte_limit,
             must_fit_in_single_message,
             batcher_semaphore,
+            max_ttl,
         );
 
         let cloned_msg = msg.clone();
@@ -643,6 +786,25 @@ where
         Ok(MiddlewareStream::Result(stream))
     }
 
+    /// Creates a single error response explaining the error via an EDE.
+    #[allow(clippy::type_complexity)]
+    fn mk_ede_response(
+        msg: &Message<RequestOctets>,
+        rcode: OptRcode,
+        code: ExtendedErrorCode,
+        text: &str,
+    ) -> ControlFlow<
+        XfrMiddlewareStream<
+            NextSvc::Future,
+            NextSvc::Stream,
+            <NextSvc::Stream as Stream>::Item,
+        >,
+    > {
+        let response = mk_error_response_with_ede(msg, rcode, code, text);
+        let res = Ok(CallResult::new(response));
+        ControlFlow::Break(MiddlewareStream::Map(once(ready(res))))
+    }
+
     /// Is this message for us?
     ///
     /// Returns `Some(Question)` if the given query uses OPCODE QUERYY and has
@@ -661,20 +823,60 @@ where
         None
     }
 
+    /// Extracts the client SOA serial from an IXFR query.
+    ///
+    /// The authority section of an IXFR query must contain exactly one SOA
+    /// record holding the client's version of the zone.
+    fn get_ixfr_query_serial(
+        msg: &Message<RequestOctets>,
+    ) -> Result<Serial, IxfrQuerySoaError> {
+        let section =
+            msg.authority().map_err(|_| IxfrQuerySoaError::Malformed)?;
+        let mut soas = section.limit_to::<Soa<ParsedName<_>>>();
+
+        let serial = match soas.next() {
+            None => return Err(IxfrQuerySoaError::Missing),
+            Some(Err(_)) => return Err(IxfrQuerySoaError::Malformed),
+            Some(Ok(soa)) => soa.data().serial(),
+        };
+
+        match soas.next() {
+            None => Ok(serial),
+            Some(Err(_)) => Err(IxfrQuerySoaError::Malformed),
+            Some(Ok(_)) => Err(IxfrQuerySoaError::Multiple),
+        }
+    }
+
     fn calc_msg_bytes_available<T>(req: &Request<RequestOctets, T>) -> usize {
-        let bytes_available = match req.transport_ctx() {
+        let max_msg_size = match req.transport_ctx() {
             TransportSpecificContext::Udp(ctx) => {
-                let max_msg_size = ctx
-                    .max_response_size_hint()
-                    .unwrap_or(MAX_UDP_MSG_BYTE_LEN);
-                max_msg_size - req.num_reserved_bytes()
-            }
-            TransportSpecificContext::NonUdp(_) => {
-                MAX_TCP_MSG_BYTE_LEN - req.num_reserved_bytes()
+                // https://datatracker.ietf.org/doc/html/rfc6891#section-6.2.5
+                // 6.2.5. Payload Size Selection
+                //   "Values lower than 512 MUST be treated as equal to 512."
+                //
+                // A requestor without EDNS can only receive 512 bytes and
+                // one with EDNS no more than its advertised payload size.
+                // The size hint may come from the server configuration
+                // rather than from the EDNS middleware, so it is limited to
+                // what the requestor can receive here as well.
+                let requestor_max = req
+                    .message()
+                    .opt()
+                    .map_or(MAX_UDP_MSG_BYTE_LEN, |opt| {
+                        opt.udp_payload_size()
+                    })
+                    .max(MAX_UDP_MSG_BYTE_LEN);
+                ctx.max_response_size_hint().map_or(requestor_max, |hint| {
+                    hint.clamp(MAX_UDP_MSG_BYTE_LEN, requestor_max)
+                })
             }
+            // The EDNS payload size only applies to UDP, see RFC 6891
+            // section 6.2.3.
+            TransportSpecificContext::NonUdp(_) => MAX_TCP_MSG_BYTE_LEN,
         };
 
-        bytes_available as usize
+        usize::from(max_msg_size)
+            .saturating_sub(usize::from(req.num_reserved_bytes()))
     }
 }
 
@@ -711,10 +913,14 @@ where
         let xfr_data_provider = self.xfr_data_provider.clone();
         let zone_walking_semaphore = self.zone_walking_semaphore.clone();
         let batcher_semaphore = self.batcher_semaphore.clone();
+        let rate_limiter = self.rate_limiter.clone();
+        let max_ttl = self.max_ttl;
         Box::pin(async move {
             match Self::preprocess(
                 zone_walking_semaphore,
                 batcher_semaphore,
+                rate_limiter.as_deref(),
+                max_ttl,
                 &request,
                 xfr_data_provider,
             )
@@ -739,6 +945,96 @@ where
     }
 }
 
+//------------ XfrRateLimiter -------------------------------------------------
+
+/// A limit on the number of full zone transfers per client IP address.
+///
+/// At most `max_transfers` full zone transfers are permitted per client IP
+/// address in each window of the given duration. The window of a client
+/// starts with its first transfer.
+///
+/// See [`XfrMiddlewareSvc::with_rate_limiter`].
+#[derive(Debug)]
+pub struct XfrRateLimiter {
+    /// The number of transfers permitted per window.
+    max_transfers: usize,
+
+    /// The length of a window.
+    window: Duration,
+
+    /// The start of the current window and the number of transfers in it
+    /// for each client that transferred recently.
+    clients: Mutex<HashMap<IpAddr, (Instant, usize)>>,
+}
+
+impl XfrRateLimiter {
+    /// Creates a new limiter.
+    #[must_use]
+    pub fn new(max_transfers: usize, window: Duration) -> Self {
+        Self {
+            max_transfers,
+            window,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a transfer for the given client if it is within the limit.
+    ///
+    /// Returns whether the transfer is permitted.
+    pub fn try_acquire(&self, client: IpAddr) -> bool {
+        let now = Instant::now();
+        let mut clients = self.clients.lock().unwrap();
+        if !clients.contains_key(&client) {
+            // Forget about clients whose window has ended so that the map
+            // doesn't grow without bounds.
+            clients.retain(|_, (start, _)| {
+                now.duration_since(*start) < self.window
+            });
+        }
+        let (start, count) = clients.entry(client).or_insert((now, 0));
+        if now.duration_since(*start) >= self.window {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= self.max_transfers {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+//------------ IxfrQuerySoaError ----------------------------------------------
+
+/// Why the client SOA of an IXFR query could not be determined.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum IxfrQuerySoaError {
+    /// The authority section contains no SOA record.
+    Missing,
+
+    /// The authority section contains more than one SOA record.
+    Multiple,
+
+    /// The authority section or an SOA record in it could not be parsed.
+    Malformed,
+}
+
+impl fmt::Display for IxfrQuerySoaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => {
+                f.write_str("IXFR request lacks authority section SOA")
+            }
+            Self::Multiple => f.write_str(
+                "IXFR request has more than one authority section SOA",
+            ),
+            Self::Malformed => {
+                f.write_str("IXFR request has malformed authority section")
+            }
+        }
+    }
+}
+
 //------------ XfrMapStream ---------------------------------------------------
 
 pub type XfrResultStream<StreamItem> = UnboundedReceiverStream<StreamItem>;
@@ -4,9 +4,13 @@ use core::pin::Pin;
 use core::str::FromStr;
 use core::sync::atomic::{AtomicBool, Ordering};
 
+use core::time::Duration;
+
 use std::borrow::ToOwned;
 use std::boxed::Box;
 use std::fmt::Debug;
+use std::net::SocketAddr;
+use std::string::String;
 use std::sync::Arc;
 use std::vec::Vec;
 
@@ -18,11 +22,13 @@ use tokio::sync::Semaphore;
 use tokio::time::Instant;
 
 use crate::base::iana::{
-    Class, DigestAlgorithm, OptRcode, Rcode, SecurityAlgorithm,
+    Class, DigestAlgorithm, ExtendedErrorCode, OptRcode, Rcode,
+    SecurityAlgorithm,
 };
 use crate::base::{
     Message, MessageBuilder, Name, ParsedName, Rtype, Serial, ToName, Ttl,
 };
+use crate::net::server::batcher::ResourceRecordBatcher;
 use crate::net::server::message::{
     NonUdpTransportContext, Request, TransportSpecificContext,
     UdpTransportContext,
@@ -33,6 +39,7 @@ use crate::net::server::middleware::xfr::data_provider::{
 use crate::net::server::service::{
     CallResult, Service, ServiceError, ServiceFeedback, ServiceResult,
 };
+use crate::net::server::tests::util::mk_udp_request;
 use crate::rdata::{
     Aaaa, AllRecordData, Cname, Ds, Mx, Ns, Soa, Txt, ZoneRecordData, A,
 };
@@ -41,10 +48,12 @@ use crate::zonefile::inplace::Zonefile;
 use crate::zonetree::types::{EmptyZoneDiff, Rrset};
 use crate::zonetree::{
     AnswerContent, InMemoryZoneDiff, InMemoryZoneDiffBuilder, SharedRrset,
-    Zone,
+    Zone, ZoneBuilder,
 };
 
-use super::service::{XfrMiddlewareStream, XfrMiddlewareSvc};
+use super::batcher::XfrRrBatcher;
+use super::ixfr::{check_serial_progression, SerialProgressionError};
+use super::service::{XfrMiddlewareStream, XfrMiddlewareSvc, XfrRateLimiter};
 use super::util::read_soa;
 
 //------------ ExpectedRecords ------------------------------------------------
@@ -220,6 +229,185 @@ async fn axfr_not_allowed_over_udp() {
     assert_eq!(resp.header().rcode(), Rcode::NOTIMP);
 }
 
+#[tokio::test]
+async fn axfr_rate_limited_per_client() {
+    tokio::time::pause();
+
+    let zone = load_zone(include_bytes!(
+        "../../../../../test-data/zonefiles/nsd-example.txt"
+    ));
+    let limiter = XfrRateLimiter::new(2, Duration::from_secs(60));
+
+    let first_client = "127.0.0.1:12345".parse().unwrap();
+    let second_client = "127.0.0.2:12345".parse().unwrap();
+
+    let preprocess = |client_addr: SocketAddr| {
+        let req = mk_axfr_request_from(zone.apex_name(), client_addr);
+        let zone = zone.clone();
+        let limiter = &limiter;
+        async move {
+            XfrMiddlewareSvc::<Vec<u8>, TestNextSvc, (), Zone>::preprocess(
+                Arc::new(Semaphore::new(1)),
+                Arc::new(Semaphore::new(1)),
+                Some(limiter),
+                None,
+                &req,
+                zone,
+            )
+            .await
+        }
+    };
+
+    // The first two transfers are permitted, the third is refused.
+    for _ in 0..2 {
+        let res = preprocess(first_client).await;
+        assert!(matches!(res, Ok(ControlFlow::Break(_))));
+    }
+    let res = preprocess(first_client).await;
+    assert!(matches!(res, Err(OptRcode::REFUSED)));
+
+    // Another client is not affected.
+    let res = preprocess(second_client).await;
+    assert!(matches!(res, Ok(ControlFlow::Break(_))));
+
+    // Once the window has passed, the first client may transfer again.
+    tokio::time::advance(Duration::from_secs(60)).await;
+    let res = preprocess(first_client).await;
+    assert!(matches!(res, Ok(ControlFlow::Break(_))));
+}
+
+#[tokio::test]
+async fn axfr_with_max_ttl() {
+    let zone = load_zone(include_bytes!(
+        "../../../../../test-data/zonefiles/nsd-example.txt"
+    ));
+
+    let req = mk_axfr_request(zone.apex_name(), ());
+
+    let res = XfrMiddlewareSvc::<Vec<u8>, TestNextSvc, (), Zone>::preprocess(
+        Arc::new(Semaphore::new(1)),
+        Arc::new(Semaphore::new(1)),
+        None,
+        Some(Ttl::from_secs(300)),
+        &req,
+        zone.clone(),
+    )
+    .await
+    .unwrap();
+
+    let ControlFlow::Break(mut stream) = res else {
+        panic!("AXFR failed");
+    };
+
+    // All records in the zone have a TTL of 86400 seconds, the transferred
+    // ones must carry the capped TTL.
+    let mut num_records = 0;
+    while let Some(msg) = stream.next().await {
+        let msg = msg.unwrap();
+        if matches!(msg.feedback(), Some(ServiceFeedback::EndTransaction)) {
+            break;
+        }
+        let Some(resp_builder) = msg.into_inner().0 else {
+            continue;
+        };
+        let resp = resp_builder.as_message();
+        for rec in resp.answer().unwrap() {
+            assert_eq!(rec.unwrap().ttl(), Ttl::from_secs(300));
+            num_records += 1;
+        }
+    }
+    assert_eq!(num_records, 13);
+}
+
+#[tokio::test]
+async fn axfr_is_consistent_under_concurrent_update() {
+    let zone = load_zone(include_bytes!(
+        "../../../../../test-data/zonefiles/nsd-example.txt"
+    ));
+    let serial = zone.current_serial().unwrap();
+
+    let req = mk_axfr_request(zone.apex_name(), ());
+
+    let res = do_preprocess(zone.clone(), &req).await.unwrap();
+
+    let ControlFlow::Break(mut stream) = res else {
+        panic!("AXFR failed");
+    };
+
+    // Change the address of the apex while the transfer is in progress.
+    let mut write = zone.write().await;
+    let apex = write.open(false).await.unwrap();
+    let mut rrset = Rrset::new(Rtype::A, Ttl::from_secs(86400));
+    rrset.push_data(A::new(p("192.0.2.99")).into());
+    apex.update_rrset(rrset.into_shared()).await.unwrap();
+    drop(apex);
+    write.commit(true).await.unwrap();
+    assert_eq!(zone.current_serial(), Some(serial.add(1)));
+
+    // The transfer must contain the zone as it was when it started.
+    let mut soa_serials = Vec::new();
+    let mut addrs = Vec::new();
+    while let Some(msg) = stream.next().await {
+        let msg = msg.unwrap();
+        if matches!(msg.feedback(), Some(ServiceFeedback::EndTransaction)) {
+            break;
+        }
+        let Some(resp_builder) = msg.into_inner().0 else {
+            continue;
+        };
+        let resp = resp_builder.as_message();
+        for rec in resp.answer().unwrap() {
+            let rec = rec
+                .unwrap()
+                .into_record::<AllRecordData<_, ParsedName<_>>>()
+                .unwrap()
+                .unwrap();
+            match rec.data() {
+                AllRecordData::Soa(soa) => soa_serials.push(soa.serial()),
+                AllRecordData::A(a)
+                    if rec.owner().to_name::<Bytes>()
+                        == *zone.apex_name() =>
+                {
+                    addrs.push(a.addr())
+                }
+                _ => {}
+            }
+        }
+    }
+    assert_eq!(soa_serials, [serial, serial]);
+    assert_eq!(addrs, [p::<std::net::Ipv4Addr>("192.0.2.1")]);
+}
+
+#[tokio::test]
+async fn axfr_for_zone_without_soa_is_servfail() {
+    let apex = n("example.com");
+    let mut a = Rrset::new(Rtype::A, Ttl::from_secs(3600));
+    a.push_data(A::new(p("192.0.2.1")).into());
+    let a = a.into_shared();
+
+    // A zone without any SOA RRset.
+    let mut builder = ZoneBuilder::new(apex.clone(), Class::IN);
+    builder.insert_rrset(&apex, a.clone()).unwrap();
+    let zone = builder.build();
+    let req = mk_axfr_request(zone.apex_name(), ());
+    let res = do_preprocess(zone, &req).await;
+    assert!(matches!(res, Err(OptRcode::SERVFAIL)));
+
+    // A zone with an empty SOA RRset.
+    let mut builder = ZoneBuilder::new(apex.clone(), Class::IN);
+    builder.insert_rrset(&apex, a).unwrap();
+    builder
+        .insert_rrset(
+            &apex,
+            Rrset::new(Rtype::SOA, Ttl::from_secs(3600)).into_shared(),
+        )
+        .unwrap();
+    let zone = builder.build();
+    let req = mk_axfr_request(zone.apex_name(), ());
+    let res = do_preprocess(zone, &req).await;
+    assert!(matches!(res, Err(OptRcode::SERVFAIL)));
+}
+
 #[tokio::test]
 async fn ixfr_rfc1995_section7_full_zone_reply() {
     // Based on https://datatracker.ietf.org/doc/html/rfc1995#section-7
@@ -475,6 +663,61 @@ JAIN-BB.JAIN.AD.JP. IN A   192.41.197.2
     ));
 }
 
+#[tokio::test]
+async fn ixfr_serial_progression_well_formed() {
+    let zone = load_zone(include_bytes!(
+        "../../../../../test-data/zonefiles/nsd-example.txt"
+    ));
+    let serial3 = get_zone_soa(&zone).await.serial();
+    let serial2 = Serial(serial3.0 - 1);
+    let serial1 = Serial(serial3.0 - 2);
+    let apex = zone.apex_name().clone();
+    let diffs = vec![
+        mk_soa_diff(&apex, serial1, serial2),
+        mk_soa_diff(&apex, serial2, serial3),
+    ];
+
+    let res = check_serial_progression(&apex, serial1, serial3, &diffs).await;
+    assert_eq!(res, Ok(()));
+
+    let zone_with_diffs = ZoneWithDiffs::new(zone.clone(), diffs);
+    let req = mk_ixfr_request(&apex, serial1, ());
+    let res = do_preprocess(zone_with_diffs, &req).await;
+    assert!(matches!(res, Ok(ControlFlow::Break(_))));
+}
+
+#[tokio::test]
+async fn ixfr_serial_progression_out_of_order() {
+    let zone = load_zone(include_bytes!(
+        "../../../../../test-data/zonefiles/nsd-example.txt"
+    ));
+    let serial3 = get_zone_soa(&zone).await.serial();
+    let serial2 = Serial(serial3.0 - 1);
+    let serial1 = Serial(serial3.0 - 2);
+    let apex = zone.apex_name().clone();
+
+    // The newer diff comes first.
+    let diffs = vec![
+        mk_soa_diff(&apex, serial2, serial3),
+        mk_soa_diff(&apex, serial1, serial2),
+    ];
+
+    let res = check_serial_progression(&apex, serial2, serial3, &diffs).await;
+    assert_eq!(
+        res,
+        Err(SerialProgressionError::Gap {
+            idx: 1,
+            expected: serial3,
+            found: serial1,
+        })
+    );
+
+    let zone_with_diffs = ZoneWithDiffs::new(zone.clone(), diffs);
+    let req = mk_ixfr_request(&apex, serial2, ());
+    let res = do_preprocess(zone_with_diffs, &req).await;
+    assert!(matches!(res, Err(OptRcode::SERVFAIL)));
+}
+
 #[tokio::test]
 async fn ixfr_rfc1995_section7_udp_packet_overflow() {
     // Based on https://datatracker.ietf.org/doc/html/rfc1995#section-7
@@ -498,9 +741,192 @@ async fn ixfr_rfc1995_section7_udp_packet_overflow() {
     assert_stream_eq(req.message(), &mut stream, &mut expected_records).await;
 }
 
+#[tokio::test]
+async fn ixfr_udp_size_hint_below_minimum() {
+    let zone = load_zone(include_bytes!(
+        "../../../../../test-data/zonefiles/nsd-example.txt"
+    ));
+
+    // A client cannot ask for less than 512 bytes, so a smaller size hint
+    // is treated as 512 bytes.
+    let req = mk_ixfr_request_for_transport(
+        zone.apex_name(),
+        Serial(0),
+        (),
+        TransportSpecificContext::Udp(UdpTransportContext::new(Some(100))),
+    );
+
+    let res = do_preprocess(zone.clone(), &req).await.unwrap();
+
+    let ControlFlow::Break(mut stream) = res else {
+        panic!("IXFR failed");
+    };
+
+    let msg = stream.next().await.unwrap().unwrap();
+    let builder = msg.into_inner().0.unwrap();
+    let resp = builder.as_message();
+    assert!(resp.is_answer(req.message()));
+    assert!(resp.as_slice().len() <= 512);
+
+    let zone_soa = get_zone_soa(&zone).await;
+    let first = resp
+        .answer()
+        .unwrap()
+        .limit_to::<Soa<_>>()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.data().serial(), zone_soa.serial());
+}
+
+#[tokio::test]
+async fn ixfr_udp_requestor_payload_size() {
+    // A zone that fits into the server's size hint but not into the
+    // payload size advertised by the requestor.
+    let mut zone_text = String::from(
+        "$ORIGIN example.com.\n\
+         $TTL 3600\n\
+         @ IN SOA ns.example.com. hostmaster.example.com. \
+         1 7200 3600 1209600 3600\n\
+         @ NS ns.example.com.\n",
+    );
+    for i in 1..=40 {
+        zone_text.push_str(&format!("host-{i} A 192.0.2.{i}\n"));
+    }
+    let zone = load_zone(zone_text.as_bytes());
+
+    let msg = MessageBuilder::new_vec();
+    let mut msg = msg.question();
+    msg.push((zone.apex_name(), Rtype::IXFR)).unwrap();
+    let mut msg = msg.authority();
+    let ttl = Ttl::from_secs(0);
+    let soa = Soa::new(n("name"), n("rname"), Serial(0), ttl, ttl, ttl, ttl);
+    msg.push((zone.apex_name(), Class::IN, ttl, soa)).unwrap();
+    let mut msg = msg.additional();
+    msg.opt(|opt| {
+        opt.set_udp_payload_size(600);
+        Ok(())
+    })
+    .unwrap();
+    let req = mk_udp_request(msg.into_message(), Some(4096));
+
+    let res = do_preprocess(zone.clone(), &req).await.unwrap();
+
+    let ControlFlow::Break(mut stream) = res else {
+        panic!("IXFR failed");
+    };
+
+    // The zone doesn't fit into 600 bytes, so only the zone SOA is sent.
+    let msg = stream.next().await.unwrap().unwrap();
+    let builder = msg.into_inner().0.unwrap();
+    let resp = builder.as_message();
+    assert!(resp.is_answer(req.message()));
+    assert!(resp.as_slice().len() <= 600);
+    assert_eq!(resp.header_counts().ancount(), 1);
+
+    let zone_soa = get_zone_soa(&zone).await;
+    let first = resp
+        .answer()
+        .unwrap()
+        .limit_to::<Soa<_>>()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.data().serial(), zone_soa.serial());
+}
+
+#[test]
+fn batcher_clamps_soft_byte_limit() {
+    let req = mk_axfr_request(n("example.com"), ());
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut batcher = XfrRrBatcher::<Vec<u8>, Vec<u8>>::build(
+        req.message().clone(),
+        sender,
+        Some(100_000),
+        None,
+        false,
+    );
+
+    let txt = Txt::<Vec<u8>>::build_from_slice(&[b'x'; 200]).unwrap();
+    for i in 0..1000 {
+        batcher
+            .push((
+                n(&format!("host-{i}.example.com")),
+                Class::IN,
+                Ttl::from_secs(0),
+                txt.clone(),
+            ))
+            .unwrap();
+    }
+    batcher.finish().unwrap();
+    drop(batcher);
+
+    let mut num_msgs = 0;
+    let mut num_records = 0;
+    while let Ok(msg) = receiver.try_recv() {
+        let builder = msg.unwrap().into_inner().0.unwrap();
+        let resp = builder.as_message();
+        assert!(resp.as_slice().len() <= usize::from(u16::MAX));
+        num_records += resp.header_counts().ancount();
+        num_msgs += 1;
+    }
+    assert_eq!(num_records, 1000);
+    assert!(num_msgs > 1);
+}
+
 #[tokio::test]
 async fn ixfr_multi_response_tcp() {}
 
+#[tokio::test]
+async fn ixfr_authority_soa_count() {
+    // https://datatracker.ietf.org/doc/html/rfc1995#section-3
+    let zone = load_zone(include_bytes!(
+        "../../../../../test-data/zonefiles/nsd-example.txt"
+    ));
+    let zone_soa = get_zone_soa(&zone).await;
+    let transport = || {
+        TransportSpecificContext::NonUdp(NonUdpTransportContext::new(None))
+    };
+
+    // No SOA in the authority section.
+    let req =
+        mk_ixfr_request_with_serials(zone.apex_name(), &[], (), transport());
+    let res = do_preprocess(zone.clone(), &req).await;
+    assert_ede_response(
+        res,
+        Rcode::FORMERR,
+        ExtendedErrorCode::OTHER,
+        "IXFR request lacks authority section SOA",
+    )
+    .await;
+
+    // Exactly one SOA in the authority section.
+    let req = mk_ixfr_request_with_serials(
+        zone.apex_name(),
+        &[zone_soa.serial()],
+        (),
+        transport(),
+    );
+    let res = do_preprocess(zone.clone(), &req).await;
+    assert!(matches!(res, Ok(ControlFlow::Break(_))));
+
+    // Two SOAs in the authority section.
+    let req = mk_ixfr_request_with_serials(
+        zone.apex_name(),
+        &[zone_soa.serial(), zone_soa.serial().add(1)],
+        (),
+        transport(),
+    );
+    let res = do_preprocess(zone, &req).await;
+    assert_ede_response(
+        res,
+        Rcode::FORMERR,
+        ExtendedErrorCode::OTHER,
+        "IXFR request has more than one authority section SOA",
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn axfr_with_tsig_key() {
     // Define an XfrDataProvider that expects to receive a Request that is
@@ -567,6 +993,68 @@ async fn axfr_with_tsig_key() {
     assert!(checked.load(Ordering::SeqCst));
 }
 
+#[tokio::test]
+async fn xfr_unknown_zone_has_ede() {
+    let zone = load_zone(include_bytes!(
+        "../../../../../test-data/zonefiles/nsd-example.txt"
+    ));
+    let req = mk_axfr_request(n("example.net"), ());
+    let res = do_preprocess(zone, &req).await;
+    assert_ede_response(
+        res,
+        Rcode::NOTAUTH,
+        ExtendedErrorCode::NOT_AUTHORITATIVE,
+        "unknown zone",
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn xfr_name_outside_zone_has_ede() {
+    let zone = load_zone(include_bytes!(
+        "../../../../../test-data/zonefiles/nsd-example.txt"
+    ));
+    let req = mk_axfr_request(n("example.net"), ());
+    let res = do_preprocess(FixedXfrDataProvider(Ok(zone)), &req).await;
+    assert_ede_response(
+        res,
+        Rcode::SERVFAIL,
+        ExtendedErrorCode::NOT_AUTHORITATIVE,
+        "not authoritative for the requested name",
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn xfr_refused_has_ede() {
+    let req = mk_axfr_request(n("example.com"), ());
+    let xdp = FixedXfrDataProvider(Err(XfrDataProviderError::Refused));
+    let res = do_preprocess(xdp, &req).await;
+    assert_ede_response(
+        res,
+        Rcode::REFUSED,
+        ExtendedErrorCode::PROHIBITED,
+        "transfer not permitted for this client or TSIG key",
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn xfr_temporarily_unavailable_has_ede() {
+    let req = mk_axfr_request(n("example.com"), ());
+    let xdp = FixedXfrDataProvider(Err(
+        XfrDataProviderError::TemporarilyUnavailable,
+    ));
+    let res = do_preprocess(xdp, &req).await;
+    assert_ede_response(
+        res,
+        Rcode::SERVFAIL,
+        ExtendedErrorCode::NOT_READY,
+        "zone not currently available",
+    )
+    .await;
+}
+
 //------------ Helper functions -------------------------------------------
 
 fn n(name: &str) -> Name<Bytes> {
@@ -639,6 +1127,24 @@ fn mk_axfr_request_for_transport<T>(
     Request::new(client_addr, received_at, msg, transport_specific, metadata)
 }
 
+fn mk_axfr_request_from(
+    qname: impl ToName,
+    client_addr: SocketAddr,
+) -> Request<Vec<u8>, ()> {
+    let msg = MessageBuilder::new_vec();
+    let mut msg = msg.question();
+    msg.push((qname, Rtype::AXFR)).unwrap();
+    let msg = msg.into_message();
+
+    Request::new(
+        client_addr,
+        Instant::now(),
+        msg,
+        TransportSpecificContext::NonUdp(NonUdpTransportContext::new(None)),
+        (),
+    )
+}
+
 fn mk_ixfr_request<T>(
     qname: impl ToName + Clone,
     serial: Serial,
@@ -670,6 +1176,21 @@ fn mk_ixfr_request_for_transport<T>(
     serial: Serial,
     metadata: T,
     transport_specific: TransportSpecificContext,
+) -> Request<Vec<u8>, T> {
+    mk_ixfr_request_with_serials(
+        qname,
+        &[serial],
+        metadata,
+        transport_specific,
+    )
+}
+
+/// Makes an IXFR request with one authority section SOA per serial.
+fn mk_ixfr_request_with_serials<T>(
+    qname: impl ToName + Clone,
+    serials: &[Serial],
+    metadata: T,
+    transport_specific: TransportSpecificContext,
 ) -> Request<Vec<u8>, T> {
     let client_addr = "127.0.0.1:12345".parse().unwrap();
     let received_at = Instant::now();
@@ -679,14 +1200,44 @@ fn mk_ixfr_request_for_transport<T>(
 
     let mut msg = msg.authority();
     let ttl = Ttl::from_secs(0);
-    let soa = Soa::new(n("name"), n("rname"), serial, ttl, ttl, ttl, ttl);
-    msg.push((qname, Class::IN, Ttl::from_secs(0), soa))
-        .unwrap();
+    for serial in serials {
+        let soa =
+            Soa::new(n("name"), n("rname"), *serial, ttl, ttl, ttl, ttl);
+        msg.push((qname.clone(), Class::IN, Ttl::from_secs(0), soa))
+            .unwrap();
+    }
     let msg = msg.into_message();
 
     Request::new(client_addr, received_at, msg, transport_specific, metadata)
 }
 
+fn mk_soa_diff(
+    apex: &Name<Bytes>,
+    start: Serial,
+    end: Serial,
+) -> InMemoryZoneDiff {
+    let soa = |serial| {
+        let mut rrset = Rrset::new(Rtype::SOA, Ttl::from_secs(0));
+        rrset.push_data(
+            Soa::new(
+                n("ns.example.com."),
+                n("hostmaster.example.com."),
+                serial,
+                Ttl::from_secs(600),
+                Ttl::from_secs(600),
+                Ttl::from_secs(3600000),
+                Ttl::from_secs(604800),
+            )
+            .into(),
+        );
+        SharedRrset::new(rrset)
+    };
+    let mut diff = InMemoryZoneDiffBuilder::new();
+    diff.remove(apex.clone(), Rtype::SOA, soa(start));
+    diff.add(apex.clone(), Rtype::SOA, soa(end));
+    diff.build().unwrap()
+}
+
 async fn do_preprocess<RequestMeta, XDP: XfrDataProvider<RequestMeta>>(
     zone: XDP,
     req: &Request<Vec<u8>, RequestMeta>,
@@ -706,6 +1257,8 @@ where
     XfrMiddlewareSvc::<Vec<u8>, TestNextSvc, RequestMeta, XDP>::preprocess(
         Arc::new(Semaphore::new(1)),
         Arc::new(Semaphore::new(1)),
+        None,
+        None,
         req,
         zone,
     )
@@ -770,6 +1323,36 @@ async fn assert_stream_eq<
     stream
 }
 
+#[allow(clippy::type_complexity)]
+async fn assert_ede_response(
+    res: Result<
+        ControlFlow<
+            XfrMiddlewareStream<
+                <TestNextSvc as Service>::Future,
+                <TestNextSvc as Service>::Stream,
+                <<TestNextSvc as Service>::Stream as Stream>::Item,
+            >,
+        >,
+        OptRcode,
+    >,
+    rcode: Rcode,
+    code: ExtendedErrorCode,
+    text: &str,
+) {
+    let Ok(ControlFlow::Break(mut stream)) = res else {
+        panic!("expected an error response");
+    };
+    let msg = stream.next().await.unwrap().unwrap();
+    let resp_builder = msg.into_inner().0.unwrap();
+    let resp = resp_builder.as_message();
+    assert_eq!(resp.header().rcode(), rcode);
+    let opt = resp.opt().unwrap();
+    let ede = opt.opt().extended_error().unwrap();
+    assert_eq!(ede.code(), code);
+    assert_eq!(ede.text_slice(), Some(text.as_bytes()));
+    assert!(stream.next().await.is_none());
+}
+
 #[derive(Clone)]
 struct TestNextSvc;
 
@@ -849,3 +1432,32 @@ impl XfrDataProvider for ZoneWithDiffs {
         Box::pin(ready(res))
     }
 }
+
+/// An XFR data provider returning the same result for every request.
+struct FixedXfrDataProvider(Result<Zone, XfrDataProviderError>);
+
+impl XfrDataProvider for FixedXfrDataProvider {
+    type Diff = EmptyZoneDiff;
+    fn request<Octs>(
+        &self,
+        _req: &Request<Octs, ()>,
+        _diff_from: Option<Serial>,
+    ) -> Pin<
+        Box<
+            dyn Future<
+                    Output = Result<
+                        XfrData<Self::Diff>,
+                        XfrDataProviderError,
+                    >,
+                > + Sync
+                + Send,
+        >,
+    >
+    where
+        Octs: Octets + Send + Sync,
+    {
+        let res =
+            self.0.clone().map(|zone| XfrData::new(zone, vec![], false));
+        Box::pin(ready(res))
+    }
+}
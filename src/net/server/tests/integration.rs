@@ -14,6 +14,7 @@ use std::sync::Arc;
 use std::time::Duration;
 use std::vec::Vec;
 
+use bytes::Bytes;
 use ring::test::rand::FixedByteRandom;
 use rstest::rstest;
 use tracing::instrument;
@@ -23,8 +24,8 @@ use crate::base::iana::{Class, Rcode};
 use crate::base::name::ToName;
 use crate::base::net::IpAddr;
 use crate::base::wire::Composer;
-use crate::base::Name;
 use crate::base::Rtype;
+use crate::base::{MessageBuilder, Name};
 use crate::logging::init_logging;
 use crate::net::client::request::{RequestMessage, RequestMessageMulti};
 use crate::net::client::{dgram, stream, tsig};
@@ -42,6 +43,7 @@ use crate::net::server::middleware::tsig::TsigMiddlewareSvc;
 use crate::net::server::middleware::xfr::XfrMiddlewareSvc;
 use crate::net::server::service::{CallResult, Service, ServiceResult};
 use crate::net::server::stream::StreamServer;
+use crate::net::server::tests::util::mk_udp_request;
 use crate::net::server::util::{mk_builder_for_target, service_fn};
 use crate::stelline::channel::ClientServerChannel;
 use crate::stelline::client::{
@@ -53,6 +55,7 @@ use crate::stelline::simple_dgram_client;
 use crate::tsig::{Algorithm, Key, KeyName, KeyStore};
 use crate::utils::base16;
 use crate::zonefile::inplace::Zonefile;
+use crate::zonetree::test_util::load_zone;
 use crate::zonetree::{Answer, Zone};
 use crate::zonetree::{StoredName, ZoneBuilder, ZoneTree};
 
@@ -407,12 +410,15 @@ fn test_service<RequestMeta>(
     request: Request<Vec<u8>, RequestMeta>,
     zones: Arc<ZoneTree>,
 ) -> ServiceResult<Vec<u8>> {
+    // Look up the question in canonical form. The response echoes the
+    // question as received.
     let question = request.message().sole_question().unwrap();
+    let question = question.to_canonical::<Bytes>();
 
     let answer = match zones.find_zone(question.qname(), question.qclass()) {
         Some(zone) => {
             let readable_zone = zone.read();
-            let qname = question.qname().to_bytes();
+            let qname = question.qname().clone();
             let qtype = question.qtype();
             readable_zone.query(qname, qtype).unwrap()
         }
@@ -424,6 +430,36 @@ fn test_service<RequestMeta>(
     Ok(CallResult::new(additional))
 }
 
+#[test]
+fn test_service_mixed_case_query() {
+    let mut zones = ZoneTree::new();
+    zones
+        .insert_zone(load_zone(include_bytes!(
+            "../../../../test-data/zonefiles/rfc1034-6-1-root.zone"
+        )))
+        .unwrap();
+
+    let mut query = MessageBuilder::new_vec().question();
+    let qname = Name::<Bytes>::from_str("Sri-Nic.arpa.").unwrap();
+    query.push((&qname, Rtype::A)).unwrap();
+    let request = mk_udp_request(query.into_message(), None);
+
+    // The zone and the records are found despite the differing case ...
+    let call_result = test_service(request, Arc::new(zones)).unwrap();
+    let (response, _feedback) = call_result.into_inner();
+    let response = response.unwrap();
+    let response = response.as_message();
+    assert_eq!(response.header().rcode(), Rcode::NOERROR);
+    assert_eq!(response.header_counts().ancount(), 2);
+
+    // ... while the response echoes the question as received.
+    let question = response.sole_question().unwrap();
+    assert_eq!(question.qname().to_string(), "Sri-Nic.arpa");
+    for rr in response.answer().unwrap() {
+        assert_eq!(rr.unwrap().owner().to_string(), "Sri-Nic.arpa");
+    }
+}
+
 //----------- Stelline config block parsing -----------------------------------
 
 #[derive(Default)]
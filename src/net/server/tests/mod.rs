@@ -1,3 +1,4 @@
 #![cfg(all(feature = "net", test))]
 mod integration;
 mod unit;
+pub(crate) mod util;
@@ -11,11 +11,17 @@ use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::vec::Vec;
 
+use futures_util::stream::{once, Once};
+use octseq::Octets;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::time::sleep;
 use tokio::time::Instant;
 use tracing::trace;
 
+use crate::base::iana::Rcode;
+use crate::base::message_builder::AdditionalBuilder;
+use crate::base::wire::Composer;
+use crate::base::Message;
 use crate::base::MessageBuilder;
 use crate::base::Name;
 use crate::base::Rtype;
@@ -30,6 +36,7 @@ use crate::net::server::service::{
 };
 use crate::net::server::sock::AsyncAccept;
 use crate::net::server::stream::StreamServer;
+use crate::rdata::Txt;
 
 /// Mock I/O which supplies a sequence of mock messages to the server at a
 /// defined rate.
@@ -49,6 +56,9 @@ struct MockStream {
 
     /// Disconnect while one or more responses are pending?
     disconnect_with_pending_responses: bool,
+
+    /// Where to record the bytes written by the server, if anywhere.
+    written: Option<Arc<Mutex<Vec<u8>>>>,
 }
 
 impl MockStream {
@@ -56,6 +66,7 @@ impl MockStream {
         messages_to_read: VecDeque<Vec<u8>>,
         new_message_every: Duration,
         disconnect_with_pending_responses: bool,
+        written: Option<Arc<Mutex<Vec<u8>>>>,
     ) -> Self {
         let pending_responses = messages_to_read.len();
         Self {
@@ -64,6 +75,7 @@ impl MockStream {
             new_message_every,
             pending_responses,
             disconnect_with_pending_responses,
+            written,
         }
     }
 }
@@ -136,6 +148,9 @@ impl AsyncWrite for MockStream {
         if self.pending_responses > 0 {
             self.pending_responses -= 1;
         }
+        if let Some(written) = &self.written {
+            written.lock().unwrap().extend_from_slice(buf);
+        }
         Poll::Ready(Ok(buf.len()))
     }
 
@@ -162,6 +177,7 @@ struct MockClientConfig {
     pub messages: VecDeque<Vec<u8>>,
     pub client_port: u16,
     pub disconnect_with_pending_responses: bool,
+    pub written: Option<Arc<Mutex<Vec<u8>>>>,
 }
 
 /// A mock TCP connection acceptor with a fixed rate at which (mock) client
@@ -236,6 +252,7 @@ impl AsyncAccept for MockListener {
                         messages,
                         client_port,
                         disconnect_with_pending_responses,
+                        written,
                     }) = streams_to_read.pop_front()
                     {
                         last_accept.replace(Instant::now());
@@ -244,6 +261,7 @@ impl AsyncAccept for MockListener {
                                 messages,
                                 new_message_every,
                                 disconnect_with_pending_responses,
+                                written,
                             ))),
                             format!("192.168.0.1:{}", client_port)
                                 .parse()
@@ -348,6 +366,43 @@ impl Service<Vec<u8>> for MyService {
     }
 }
 
+/// A mock service that answers every query with a large TXT RRset.
+struct LargeTxtService;
+
+impl Service<Vec<u8>> for LargeTxtService {
+    type Target = Vec<u8>;
+    type Stream = Once<Ready<Result<CallResult<Vec<u8>>, ServiceError>>>;
+    type Future = Ready<Self::Stream>;
+
+    fn call(&self, request: Request<Vec<u8>>) -> Self::Future {
+        let response = mk_large_txt_response(
+            MessageBuilder::new_stream_vec(),
+            request.message(),
+        );
+        ready(once(ready(Ok(CallResult::new(response)))))
+    }
+}
+
+/// The number of TXT records in the answer of a large response.
+const LARGE_RRSET_LEN: usize = 200;
+
+/// Compose an answer to `request` holding a TXT RRset of close to the
+/// maximum size of a stream message.
+fn mk_large_txt_response<Target: Composer, Octs: Octets>(
+    builder: MessageBuilder<Target>,
+    request: &Message<Octs>,
+) -> AdditionalBuilder<Target> {
+    let mut answer = builder.start_answer(request, Rcode::NOERROR).unwrap();
+    let qname = request.sole_question().unwrap().into_qname();
+    for i in 0..LARGE_RRSET_LEN {
+        let txt =
+            Txt::<Vec<u8>>::build_from_slice(&[b'a' + (i % 26) as u8; 255])
+                .unwrap();
+        answer.push((&qname, 3600, txt)).unwrap();
+    }
+    answer.additional()
+}
+
 /// Create a mock DNS client request.
 fn mk_query() -> StreamTarget<Vec<u8>> {
     let mut msg = MessageBuilder::from_target(StaticCompressor::new(
@@ -392,6 +447,7 @@ async fn tcp_service_test() {
             ]),
             client_port: 1,
             disconnect_with_pending_responses: false,
+            written: None,
         };
         let slow_client = MockClientConfig {
             new_message_every: Duration::from_millis(3000),
@@ -401,6 +457,7 @@ async fn tcp_service_test() {
             ]),
             client_port: 2,
             disconnect_with_pending_responses: false,
+            written: None,
         };
         let num_messages =
             fast_client.messages.len() + slow_client.messages.len();
@@ -484,6 +541,7 @@ async fn tcp_client_disconnect_test() {
             ]),
             client_port: 1,
             disconnect_with_pending_responses: true,
+            written: None,
         };
         let slow_client = MockClientConfig {
             new_message_every: Duration::from_millis(3000),
@@ -493,6 +551,7 @@ async fn tcp_client_disconnect_test() {
             ]),
             client_port: 2,
             disconnect_with_pending_responses: false,
+            written: None,
         };
         let num_messages =
             fast_client.messages.len() + slow_client.messages.len();
@@ -558,3 +617,54 @@ async fn tcp_client_disconnect_test() {
     // Terminate the task that periodically prints the server status
     server_status_printer_handle.abort();
 }
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn tcp_large_response_test() {
+    init_logging();
+
+    let query = mk_query().as_dgram_slice().to_vec();
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let client = MockClientConfig {
+        new_message_every: Duration::from_millis(100),
+        messages: VecDeque::from([query.clone()]),
+        client_port: 1,
+        disconnect_with_pending_responses: false,
+        written: Some(written.clone()),
+    };
+    let listener = MockListener::new(
+        VecDeque::from([client]),
+        Duration::from_millis(100),
+    );
+    let ready_flag = listener.get_ready_flag();
+
+    let srv = Arc::new(StreamServer::new(
+        listener,
+        MockBufSource,
+        Arc::new(LargeTxtService),
+    ));
+    let spawned_srv = srv.clone();
+    let srv_handle = tokio::spawn(async move { spawned_srv.run().await });
+
+    ready_flag.store(true, Ordering::Relaxed);
+    sleep(Duration::from_secs(5)).await;
+
+    assert_eq!(srv.metrics().num_sent_responses(), 1);
+    srv.shutdown().unwrap();
+    let _ = srv_handle.await;
+
+    // The stream path must produce exactly the octets that a plain
+    // message builder composes, preceded by the two octet length.
+    let request = Message::from_octets(query).unwrap();
+    let expected =
+        mk_large_txt_response(MessageBuilder::new_vec(), &request).finish();
+    assert!(expected.len() > 50_000);
+
+    let written = written.lock().unwrap();
+    let (len, msg) = written.split_at(2);
+    assert_eq!(usize::from(u16::from_be_bytes([len[0], len[1]])), msg.len());
+    assert_eq!(msg, expected.as_slice());
+    assert_eq!(
+        Message::from_octets(msg).unwrap().header_counts().ancount(),
+        LARGE_RRSET_LEN as u16
+    );
+}
@@ -0,0 +1,184 @@
+//! Helpers for testing message processing.
+use core::time::Duration;
+
+use std::vec::Vec;
+
+use octseq::Octets;
+use tokio::time::Instant;
+
+use crate::base::message::RecordSection;
+use crate::base::wire::ParseError;
+use crate::base::{Message, ParsedName, Record};
+use crate::net::server::message::{
+    NonUdpTransportContext, Request, UdpTransportContext,
+};
+use crate::rdata::AllRecordData;
+
+/// A record of a message section with its data fully parsed.
+type SectionRecord<'a, Octs> = Record<
+    ParsedName<<Octs as Octets>::Range<'a>>,
+    AllRecordData<
+        <Octs as Octets>::Range<'a>,
+        ParsedName<<Octs as Octets>::Range<'a>>,
+    >,
+>;
+
+/// Returns whether two messages are semantically equivalent.
+///
+/// The message ID is ignored. The opcode, response code, flags and question
+/// section have to be identical, while the answer, authority and additional
+/// sections are compared as multisets of records, i.e., the order of the
+/// records within a section does not matter. Records are compared by owner,
+/// class, TTL and record data, so names are compared case-insensitively and
+/// name compression is irrelevant.
+///
+/// A message that fails to parse is not equivalent to any message.
+pub(crate) fn messages_equivalent<A: Octets, B: Octets>(
+    a: &Message<A>,
+    b: &Message<B>,
+) -> bool {
+    let (header_a, header_b) = (a.header(), b.header());
+    if header_a.opcode() != header_b.opcode()
+        || header_a.rcode() != header_b.rcode()
+        || header_a.flags() != header_b.flags()
+    {
+        return false;
+    }
+
+    let questions_a: Result<Vec<_>, _> = a.question().collect();
+    let questions_b: Result<Vec<_>, _> = b.question().collect();
+    match (questions_a, questions_b) {
+        (Ok(questions_a), Ok(questions_b)) if questions_a == questions_b => {}
+        _ => return false,
+    }
+
+    sections_equivalent(a.answer(), b.answer())
+        && sections_equivalent(a.authority(), b.authority())
+        && sections_equivalent(a.additional(), b.additional())
+}
+
+/// Returns whether two sections contain the same records in any order.
+fn sections_equivalent<A: Octets, B: Octets>(
+    a: Result<RecordSection<'_, A>, ParseError>,
+    b: Result<RecordSection<'_, B>, ParseError>,
+) -> bool {
+    let (Some(records_a), Some(mut records_b)) =
+        (section_records(a), section_records(b))
+    else {
+        return false;
+    };
+    if records_a.len() != records_b.len() {
+        return false;
+    }
+    for record in records_a {
+        let pos = records_b.iter().position(|other| {
+            record == *other && record.ttl() == other.ttl()
+        });
+        match pos {
+            Some(pos) => {
+                records_b.swap_remove(pos);
+            }
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Parses all records of a section.
+fn section_records<'a, Octs: Octets>(
+    section: Result<RecordSection<'a, Octs>, ParseError>,
+) -> Option<Vec<SectionRecord<'a, Octs>>> {
+    section
+        .ok()?
+        .map(|record| record.ok()?.into_record().ok()?)
+        .collect()
+}
+
+//------------ Requests ------------------------------------------------------
+
+/// The client address used for requests created by the helpers below.
+pub(crate) const CLIENT_ADDR: &str = "127.0.0.1:12345";
+
+/// Creates a request for `message` as if it was received over UDP.
+pub(crate) fn mk_udp_request<Octs>(
+    message: Message<Octs>,
+    max_response_size_hint: Option<u16>,
+) -> Request<Octs>
+where
+    Octs: AsRef<[u8]> + Send + Sync,
+{
+    let ctx = UdpTransportContext::new(max_response_size_hint);
+    Request::new(
+        CLIENT_ADDR.parse().unwrap(),
+        Instant::now(),
+        message,
+        ctx.into(),
+        (),
+    )
+}
+
+/// Creates a request for `message` as if it was received over a non-UDP
+/// transport such as TCP or TLS.
+pub(crate) fn mk_non_udp_request<Octs>(
+    message: Message<Octs>,
+    idle_timeout: Option<Duration>,
+) -> Request<Octs>
+where
+    Octs: AsRef<[u8]> + Send + Sync,
+{
+    let ctx = NonUdpTransportContext::new(idle_timeout);
+    Request::new(
+        CLIENT_ADDR.parse().unwrap(),
+        Instant::now(),
+        message,
+        ctx.into(),
+        (),
+    )
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[test]
+fn messages_equivalent_ignores_id_and_order() {
+    use core::str::FromStr;
+
+    use crate::base::iana::{Class, Rcode};
+    use crate::base::{MessageBuilder, Name, Rtype, Ttl};
+    use crate::rdata::A;
+
+    fn mk_msg(id: u16, addrs: &[&str], rcode: Rcode) -> Message<Vec<u8>> {
+        let name = Name::<Vec<u8>>::from_str("example.com.").unwrap();
+        let mut msg = MessageBuilder::new_vec();
+        msg.header_mut().set_id(id);
+        msg.header_mut().set_qr(true);
+        msg.header_mut().set_rcode(rcode);
+        let mut msg = msg.question();
+        msg.push((&name, Rtype::A)).unwrap();
+        let mut msg = msg.answer();
+        for addr in addrs {
+            msg.push((
+                &name,
+                Class::IN,
+                Ttl::from_secs(3600),
+                A::from_str(addr).unwrap(),
+            ))
+            .unwrap();
+        }
+        msg.into_message()
+    }
+
+    let a = mk_msg(1, &["192.0.2.1", "192.0.2.2"], Rcode::NOERROR);
+    let b = mk_msg(2, &["192.0.2.2", "192.0.2.1"], Rcode::NOERROR);
+    assert!(messages_equivalent(&a, &b));
+    assert!(messages_equivalent(&b, &a));
+
+    // Differences in the content or the rcode are detected.
+    let c = mk_msg(1, &["192.0.2.1", "192.0.2.3"], Rcode::NOERROR);
+    assert!(!messages_equivalent(&a, &c));
+    let d = mk_msg(1, &["192.0.2.1"], Rcode::NOERROR);
+    assert!(!messages_equivalent(&a, &d));
+    let e = mk_msg(1, &["192.0.2.1", "192.0.2.1"], Rcode::NOERROR);
+    assert!(!messages_equivalent(&a, &e));
+    let f = mk_msg(1, &["192.0.2.1", "192.0.2.2"], Rcode::SERVFAIL);
+    assert!(!messages_equivalent(&a, &f));
+}
@@ -3,15 +3,17 @@ use core::future::{ready, Ready};
 
 use core::marker::PhantomData;
 use std::string::{String, ToString};
+use std::vec::Vec;
 
 use futures_util::stream::Once;
 use octseq::{Octets, OctetsBuilder};
 use tracing::warn;
 
-use crate::base::iana::OptRcode;
+use crate::base::iana::{ExtendedErrorCode, OptRcode};
 use crate::base::message_builder::{
     AdditionalBuilder, OptBuilder, PushError,
 };
+use crate::base::opt::ExtendedError;
 use crate::base::wire::Composer;
 use crate::base::Message;
 use crate::base::{MessageBuilder, ParsedName, Rtype, StreamTarget};
@@ -193,6 +195,47 @@ pub fn mk_error_response<RequestOctets, Target>(
     msg: &Message<RequestOctets>,
     rcode: OptRcode,
 ) -> AdditionalBuilder<StreamTarget<Target>>
+where
+    RequestOctets: Octets,
+    Target: Composer + Default,
+{
+    mk_error_response_inner(msg, rcode, None)
+}
+
+//------------ mk_error_response_with_ede ------------------------------------
+
+/// Creates an error response carrying an extended DNS error.
+///
+/// This is like [`mk_error_response`] but additionally adds an [RFC 8914]
+/// extended DNS error option with the given code and text to the response
+/// to explain to the client why its request failed.
+///
+/// [RFC 8914]: https://datatracker.ietf.org/doc/html/rfc8914
+pub fn mk_error_response_with_ede<RequestOctets, Target>(
+    msg: &Message<RequestOctets>,
+    rcode: OptRcode,
+    code: ExtendedErrorCode,
+    text: &str,
+) -> AdditionalBuilder<StreamTarget<Target>>
+where
+    RequestOctets: Octets,
+    Target: Composer + Default,
+{
+    let ede = match ExtendedError::<Vec<u8>>::new_with_str(code, text) {
+        Ok(ede) => Some(ede),
+        Err(err) => {
+            warn!("Failed to create extended error '{code}': {err}");
+            None
+        }
+    };
+    mk_error_response_inner(msg, rcode, ede.as_ref())
+}
+
+fn mk_error_response_inner<RequestOctets, Target>(
+    msg: &Message<RequestOctets>,
+    rcode: OptRcode,
+    ede: Option<&ExtendedError<Vec<u8>>>,
+) -> AdditionalBuilder<StreamTarget<Target>>
 where
     RequestOctets: Octets,
     Target: Composer + Default,
@@ -205,6 +248,9 @@ where
     // setting the rcode in the main message header.
     if let Err(err) = add_edns_options(&mut additional, |opt| {
         opt.set_rcode(rcode);
+        if let Some(ede) = ede {
+            opt.push(ede)?;
+        }
         Ok(())
     }) {
         warn!("Failed to set (extended) error '{rcode}' in response: {err}");
@@ -157,7 +157,7 @@ impl<Octs> Cdnskey<Octs> {
             u16::scan(scanner)?,
             u8::scan(scanner)?,
             SecurityAlgorithm::scan(scanner)?,
-            scanner.convert_entry(base64::SymbolConverter::new())?,
+            base64::scan(scanner)?,
         )
         .map_err(|err| S::Error::custom(err.as_str()))
     }
@@ -12,24 +12,24 @@ use crate::base::rdata::{
 };
 use crate::base::scan::{Scan, Scanner, ScannerError};
 use crate::base::serial::Serial;
-use crate::base::wire::{Compose, Composer, FormError, Parse, ParseError};
+use crate::base::wire::{Compose, Composer, Parse, ParseError};
 use crate::base::zonefile_fmt::{self, Formatter, ZonefileFmt};
 use crate::base::Ttl;
 use crate::utils::{base16, base64};
 use core::cmp::Ordering;
 use core::convert::TryInto;
+use core::time::Duration;
 use core::{cmp, fmt, hash, str};
-use octseq::builder::{
-    EmptyBuilder, FreezeBuilder, FromBuilder, OctetsBuilder, Truncate,
-};
+#[cfg(feature = "serde")]
+use octseq::builder::{EmptyBuilder, FromBuilder, OctetsBuilder, Truncate};
 use octseq::octets::{Octets, OctetsFrom, OctetsInto};
 use octseq::parse::Parser;
-#[cfg(feature = "serde")]
-use octseq::serde::{DeserializeOctets, SerializeOctets};
-#[cfg(feature = "std")]
-use std::vec::Vec;
 use time::{Date, Month, PrimitiveDateTime, Time};
 
+pub use super::rtype_bitmap::{
+    RtypeBitmap, RtypeBitmapBuilder, RtypeBitmapError, RtypeBitmapIter,
+};
+
 //------------ Dnskey --------------------------------------------------------
 
 #[derive(Clone)]
@@ -162,6 +162,15 @@ impl<Octs> Dnskey<Octs> {
         self.flags() & 0b0000_0000_0000_0001 != 0
     }
 
+    /// Returns whether the Secure Entry Point (SEP) flag is set.
+    ///
+    /// This is a shorthand for [`is_secure_entry_point()`].
+    ///
+    /// [`is_secure_entry_point()`]: Self::is_secure_entry_point
+    pub fn is_sep(&self) -> bool {
+        self.is_secure_entry_point()
+    }
+
     /// Returns whether the Zone Key flag is set.
     ///
     /// If the flag is not set, the key MUST NOT be used to verify RRSIGs that
@@ -172,6 +181,22 @@ impl<Octs> Dnskey<Octs> {
         self.flags() & 0b0000_0001_0000_0000 != 0
     }
 
+    /// Returns the role this key likely has in its zone.
+    ///
+    /// The role is derived from the Zone Key and SEP flags. As the SEP flag
+    /// is only a hint, this is merely a guess that tooling can use to, for
+    /// instance, try key signing keys first. It must not be used to decide
+    /// whether a key can validate a signature.
+    pub fn likely_role(&self) -> DnskeyRole {
+        if !self.is_zone_key() {
+            DnskeyRole::Other
+        } else if self.is_secure_entry_point() {
+            DnskeyRole::Ksk
+        } else {
+            DnskeyRole::Zsk
+        }
+    }
+
     /// Returns the key tag for this DNSKEY data.
     pub fn key_tag(&self) -> u16
     where
@@ -264,7 +289,7 @@ impl<Octs> Dnskey<Octs> {
             u16::scan(scanner)?,
             u8::scan(scanner)?,
             SecurityAlgorithm::scan(scanner)?,
-            scanner.convert_entry(base64::SymbolConverter::new())?,
+            base64::scan(scanner)?,
         )
         .map_err(|err| S::Error::custom(err.as_str()))
     }
@@ -462,6 +487,23 @@ impl<Octs: AsRef<[u8]>> ZonefileFmt for Dnskey<Octs> {
     }
 }
 
+//------------ DnskeyRole ----------------------------------------------------
+
+/// The likely role of a DNSKEY in its zone.
+///
+/// See [`Dnskey::likely_role()`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DnskeyRole {
+    /// A key signing key, a zone key with the SEP flag set.
+    Ksk,
+
+    /// A zone signing key, a zone key without the SEP flag.
+    Zsk,
+
+    /// A key that is not a zone key and thus not used for DNSSEC.
+    Other,
+}
+
 //------------ ProtoRrsig ----------------------------------------------------
 
 /// The RRSIG RDATA to be included when creating the signature.
@@ -501,6 +543,38 @@ impl<Name> ProtoRrsig<Name> {
         }
     }
 
+    pub fn type_covered(&self) -> Rtype {
+        self.type_covered
+    }
+
+    pub fn algorithm(&self) -> SecurityAlgorithm {
+        self.algorithm
+    }
+
+    pub fn labels(&self) -> u8 {
+        self.labels
+    }
+
+    pub fn original_ttl(&self) -> Ttl {
+        self.original_ttl
+    }
+
+    pub fn expiration(&self) -> Timestamp {
+        self.expiration
+    }
+
+    pub fn inception(&self) -> Timestamp {
+        self.inception
+    }
+
+    pub fn key_tag(&self) -> u16 {
+        self.key_tag
+    }
+
+    pub fn signer_name(&self) -> &Name {
+        &self.signer_name
+    }
+
     pub fn into_rrsig<Octs: AsRef<[u8]>>(
         self,
         signature: Octs,
@@ -704,6 +778,51 @@ impl Timestamp {
     pub fn into_int(self) -> u32 {
         self.0.into_int()
     }
+
+    /// Returns the timestamp `duration` after this one.
+    ///
+    /// Following serial number arithmetic, the result wraps around at
+    /// `2^32`. Only durations of up to `2^31 - 1` seconds can be added, so
+    /// that the result still compares as later than `self`. For larger
+    /// durations, `None` is returned. Sub-second parts of `duration` are
+    /// ignored.
+    #[must_use]
+    pub fn checked_add(self, duration: Duration) -> Option<Self> {
+        let secs = Self::serial_secs(duration)?;
+        Some(Self(self.0.add(secs)))
+    }
+
+    /// Returns the timestamp `duration` before this one.
+    ///
+    /// This is the counterpart of [`checked_add`][Self::checked_add] and
+    /// is subject to the same limits.
+    #[must_use]
+    pub fn checked_sub(self, duration: Duration) -> Option<Self> {
+        let secs = Self::serial_secs(duration)?;
+        Some(Self(Serial(self.0.into_int().wrapping_sub(secs))))
+    }
+
+    /// Returns an inception and expiration time starting now.
+    ///
+    /// The returned inception time is the current time and the expiration
+    /// time lies `validity` after it. This is convenient for determining
+    /// the validity period of newly created [RRSIG] records. Returns `None`
+    /// if `validity` exceeds `2^31 - 1` seconds.
+    ///
+    /// [RRSIG]: Rrsig
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn window_from_now(validity: Duration) -> Option<(Self, Self)> {
+        let inception = Self::now();
+        Some((inception, inception.checked_add(validity)?))
+    }
+
+    /// Returns the number of seconds in `duration` if it can be added.
+    fn serial_secs(duration: Duration) -> Option<u32> {
+        u32::try_from(duration.as_secs())
+            .ok()
+            .filter(|secs| *secs <= 0x7FFF_FFFF)
+    }
 }
 
 /// # Parsing and Composing
@@ -1044,7 +1163,7 @@ impl<Octs, Name> Rrsig<Octs, Name> {
             Timestamp::scan(scanner)?,
             u16::scan(scanner)?,
             scanner.scan_name()?,
-            scanner.convert_entry(base64::SymbolConverter::new())?,
+            base64::scan(scanner)?,
         )
         .map_err(|err| S::Error::custom(err.as_str()))
     }
@@ -2062,668 +2181,6 @@ impl<Octs: AsRef<[u8]>> ZonefileFmt for Ds<Octs> {
     }
 }
 
-//------------ RtypeBitmap ---------------------------------------------------
-
-#[derive(Clone)]
-pub struct RtypeBitmap<Octs>(Octs);
-
-impl<Octs> RtypeBitmap<Octs> {
-    pub fn from_octets(octets: Octs) -> Result<Self, RtypeBitmapError>
-    where
-        Octs: AsRef<[u8]>,
-    {
-        {
-            let mut data = octets.as_ref();
-            while !data.is_empty() {
-                // At least bitmap number and length must be present.
-                if data.len() < 2 {
-                    return Err(RtypeBitmapErrorEnum::ShortInput.into());
-                }
-
-                let len = (data[1] as usize) + 2;
-                // https://tools.ietf.org/html/rfc4034#section-4.1.2:
-                //  Blocks with no types present MUST NOT be included.
-                if len == 2 {
-                    return Err(RtypeBitmapErrorEnum::BadRtypeBitmap.into());
-                }
-                if len > 34 {
-                    return Err(RtypeBitmapErrorEnum::BadRtypeBitmap.into());
-                }
-                if data.len() < len {
-                    return Err(RtypeBitmapErrorEnum::ShortInput.into());
-                }
-                data = &data[len..];
-            }
-        }
-        Ok(RtypeBitmap(octets))
-    }
-
-    #[must_use]
-    pub fn builder() -> RtypeBitmapBuilder<Octs::Builder>
-    where
-        Octs: FromBuilder,
-        <Octs as FromBuilder>::Builder: EmptyBuilder,
-    {
-        RtypeBitmapBuilder::new()
-    }
-
-    pub fn scan<S: Scanner<Octets = Octs>>(
-        scanner: &mut S,
-    ) -> Result<Self, S::Error> {
-        let mut builder =
-            RtypeBitmapBuilder::with_builder(scanner.octets_builder()?);
-        while scanner.continues() {
-            builder
-                .add(Rtype::scan(scanner)?)
-                .map_err(|_| S::Error::short_buf())?;
-        }
-        Ok(builder.finalize())
-    }
-
-    pub fn as_octets(&self) -> &Octs {
-        &self.0
-    }
-
-    pub(super) fn convert_octets<Target: OctetsFrom<Octs>>(
-        self,
-    ) -> Result<RtypeBitmap<Target>, Target::Error> {
-        Ok(RtypeBitmap(self.0.try_octets_into()?))
-    }
-}
-
-impl<Octs: AsRef<[u8]>> RtypeBitmap<Octs> {
-    pub fn as_slice(&self) -> &[u8] {
-        self.0.as_ref()
-    }
-
-    pub fn iter(&self) -> RtypeBitmapIter<'_> {
-        RtypeBitmapIter::new(self.0.as_ref())
-    }
-
-    pub fn contains(&self, rtype: Rtype) -> bool {
-        let (block, octet, mask) = split_rtype(rtype);
-        let mut data = self.0.as_ref();
-        while !data.is_empty() {
-            let ((window_num, window), next_data) =
-                read_window(data).unwrap();
-            if window_num == block {
-                return !(window.len() <= octet || window[octet] & mask == 0);
-            }
-            data = next_data;
-        }
-        false
-    }
-
-    pub fn parse<'a, Src: Octets<Range<'a> = Octs> + ?Sized>(
-        parser: &mut Parser<'a, Src>,
-    ) -> Result<Self, ParseError> {
-        let len = parser.remaining();
-        RtypeBitmap::from_octets(parser.parse_octets(len)?)
-            .map_err(Into::into)
-    }
-
-    pub fn compose_len(&self) -> u16 {
-        u16::try_from(self.0.as_ref().len()).expect("long rtype bitmap")
-    }
-
-    pub fn compose<Target: OctetsBuilder + ?Sized>(
-        &self,
-        target: &mut Target,
-    ) -> Result<(), Target::AppendError> {
-        target.append_slice(self.0.as_ref())
-    }
-
-    #[must_use]
-    pub fn is_empty(&self) -> bool {
-        self.iter().next().is_none()
-    }
-}
-
-//--- AsRef
-
-impl<T, Octs: AsRef<T>> AsRef<T> for RtypeBitmap<Octs> {
-    fn as_ref(&self) -> &T {
-        self.0.as_ref()
-    }
-}
-
-//--- OctetsFrom
-
-impl<Octs, SrcOcts> OctetsFrom<RtypeBitmap<SrcOcts>> for RtypeBitmap<Octs>
-where
-    Octs: OctetsFrom<SrcOcts>,
-{
-    type Error = Octs::Error;
-
-    fn try_octets_from(
-        source: RtypeBitmap<SrcOcts>,
-    ) -> Result<Self, Self::Error> {
-        Octs::try_octets_from(source.0).map(RtypeBitmap)
-    }
-}
-
-//--- PartialEq and Eq
-
-impl<O, OO> PartialEq<RtypeBitmap<OO>> for RtypeBitmap<O>
-where
-    O: AsRef<[u8]>,
-    OO: AsRef<[u8]>,
-{
-    fn eq(&self, other: &RtypeBitmap<OO>) -> bool {
-        self.0.as_ref().eq(other.0.as_ref())
-    }
-}
-
-impl<O: AsRef<[u8]>> Eq for RtypeBitmap<O> {}
-
-//--- PartialOrd, CanonicalOrd, and Ord
-
-impl<O, OO> PartialOrd<RtypeBitmap<OO>> for RtypeBitmap<O>
-where
-    O: AsRef<[u8]>,
-    OO: AsRef<[u8]>,
-{
-    fn partial_cmp(&self, other: &RtypeBitmap<OO>) -> Option<Ordering> {
-        self.0.as_ref().partial_cmp(other.0.as_ref())
-    }
-}
-
-impl<O, OO> CanonicalOrd<RtypeBitmap<OO>> for RtypeBitmap<O>
-where
-    O: AsRef<[u8]>,
-    OO: AsRef<[u8]>,
-{
-    fn canonical_cmp(&self, other: &RtypeBitmap<OO>) -> Ordering {
-        self.0.as_ref().cmp(other.0.as_ref())
-    }
-}
-
-impl<O: AsRef<[u8]>> Ord for RtypeBitmap<O> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.0.as_ref().cmp(other.0.as_ref())
-    }
-}
-
-//--- Hash
-
-impl<O: AsRef<[u8]>> hash::Hash for RtypeBitmap<O> {
-    fn hash<H: hash::Hasher>(&self, state: &mut H) {
-        self.0.as_ref().hash(state)
-    }
-}
-
-//--- IntoIterator
-
-impl<'a, Octs: AsRef<[u8]>> IntoIterator for &'a RtypeBitmap<Octs> {
-    type Item = Rtype;
-    type IntoIter = RtypeBitmapIter<'a>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
-    }
-}
-
-//--- Display
-
-impl<Octs: AsRef<[u8]>> fmt::Display for RtypeBitmap<Octs> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut iter = self.iter();
-        if let Some(rtype) = iter.next() {
-            fmt::Display::fmt(&rtype, f)?;
-        }
-        for rtype in iter {
-            write!(f, " {}", rtype)?
-        }
-        Ok(())
-    }
-}
-
-//--- ZonefileFmt
-
-impl<Octs: AsRef<[u8]>> ZonefileFmt for RtypeBitmap<Octs> {
-    fn fmt(&self, p: &mut impl Formatter) -> zonefile_fmt::Result {
-        for rtype in self {
-            p.write_token(rtype)?;
-        }
-        Ok(())
-    }
-}
-
-//--- Debug
-
-impl<Octs: AsRef<[u8]>> fmt::Debug for RtypeBitmap<Octs> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("RtypeBitmap(")?;
-        fmt::Display::fmt(self, f)?;
-        f.write_str(")")
-    }
-}
-
-//--- Serialize and Deserialize
-
-#[cfg(feature = "serde")]
-impl<Octs> serde::Serialize for RtypeBitmap<Octs>
-where
-    Octs: AsRef<[u8]> + SerializeOctets,
-{
-    fn serialize<S: serde::Serializer>(
-        &self,
-        serializer: S,
-    ) -> Result<S::Ok, S::Error> {
-        if serializer.is_human_readable() {
-            struct Inner<'a>(&'a [u8]);
-
-            impl serde::Serialize for Inner<'_> {
-                fn serialize<S: serde::Serializer>(
-                    &self,
-                    serializer: S,
-                ) -> Result<S::Ok, S::Error> {
-                    use serde::ser::SerializeSeq;
-
-                    let mut serializer = serializer.serialize_seq(None)?;
-                    for item in RtypeBitmapIter::new(self.0) {
-                        serializer.serialize_element(&item)?;
-                    }
-                    serializer.end()
-                }
-            }
-
-            serializer.serialize_newtype_struct(
-                "RtypeBitmap",
-                &Inner(self.0.as_ref()),
-            )
-        } else {
-            serializer.serialize_newtype_struct(
-                "RtypeBitmap",
-                &self.0.as_serialized_octets(),
-            )
-        }
-    }
-}
-
-#[cfg(feature = "serde")]
-impl<'de, Octs> serde::Deserialize<'de> for RtypeBitmap<Octs>
-where
-    Octs: FromBuilder + DeserializeOctets<'de>,
-    <Octs as FromBuilder>::Builder:
-        EmptyBuilder + Truncate + AsRef<[u8]> + AsMut<[u8]>,
-{
-    fn deserialize<D: serde::Deserializer<'de>>(
-        deserializer: D,
-    ) -> Result<Self, D::Error> {
-        use core::marker::PhantomData;
-
-        struct InnerVisitor<'de, T: DeserializeOctets<'de>>(T::Visitor);
-
-        impl<'de, Octs> serde::de::Visitor<'de> for InnerVisitor<'de, Octs>
-        where
-            Octs: FromBuilder + DeserializeOctets<'de>,
-            <Octs as FromBuilder>::Builder: OctetsBuilder
-                + EmptyBuilder
-                + Truncate
-                + AsRef<[u8]>
-                + AsMut<[u8]>,
-        {
-            type Value = RtypeBitmap<Octs>;
-
-            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                f.write_str("a record type bitmap")
-            }
-
-            fn visit_seq<A: serde::de::SeqAccess<'de>>(
-                self,
-                mut seq: A,
-            ) -> Result<Self::Value, A::Error> {
-                use serde::de::Error;
-
-                let mut builder = RtypeBitmap::<Octs>::builder();
-                while let Some(element) = seq.next_element()? {
-                    builder.add(element).map_err(|_| {
-                        A::Error::custom(octseq::builder::ShortBuf)
-                    })?;
-                }
-
-                Ok(builder.finalize())
-            }
-
-            fn visit_borrowed_bytes<E: serde::de::Error>(
-                self,
-                value: &'de [u8],
-            ) -> Result<Self::Value, E> {
-                self.0.visit_borrowed_bytes(value).and_then(|octets| {
-                    RtypeBitmap::from_octets(octets).map_err(E::custom)
-                })
-            }
-
-            #[cfg(feature = "std")]
-            fn visit_byte_buf<E: serde::de::Error>(
-                self,
-                value: std::vec::Vec<u8>,
-            ) -> Result<Self::Value, E> {
-                self.0.visit_byte_buf(value).and_then(|octets| {
-                    RtypeBitmap::from_octets(octets).map_err(E::custom)
-                })
-            }
-        }
-
-        struct NewtypeVisitor<T>(PhantomData<T>);
-
-        impl<'de, Octs> serde::de::Visitor<'de> for NewtypeVisitor<Octs>
-        where
-            Octs: FromBuilder + DeserializeOctets<'de>,
-            <Octs as FromBuilder>::Builder: OctetsBuilder
-                + EmptyBuilder
-                + Truncate
-                + AsRef<[u8]>
-                + AsMut<[u8]>,
-        {
-            type Value = RtypeBitmap<Octs>;
-
-            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                f.write_str("a record type bitmap")
-            }
-
-            fn visit_newtype_struct<D: serde::Deserializer<'de>>(
-                self,
-                deserializer: D,
-            ) -> Result<Self::Value, D::Error> {
-                if deserializer.is_human_readable() {
-                    deserializer
-                        .deserialize_seq(InnerVisitor(Octs::visitor()))
-                } else {
-                    Octs::deserialize_with_visitor(
-                        deserializer,
-                        InnerVisitor(Octs::visitor()),
-                    )
-                }
-            }
-        }
-
-        deserializer.deserialize_newtype_struct(
-            "RtypeBitmap",
-            NewtypeVisitor(PhantomData),
-        )
-    }
-}
-
-//------------ RtypeBitmapBuilder --------------------------------------------
-
-/// A builder for a record type bitmap.
-//
-//  Here is how this is going to work: We keep one long Builder into which
-//  we place all added types. The buffer contains a sequence of blocks
-//  encoded similarly to the final format but with all 32 octets of the
-//  bitmap present. Blocks are in order and are only added when needed (which
-//  means we may have to insert a block in the middle). When finalizing, we
-//  compress the block buffer by dropping the unncessary octets of each
-//  block.
-#[derive(Clone, Debug)]
-pub struct RtypeBitmapBuilder<Builder> {
-    buf: Builder,
-}
-
-impl<Builder: OctetsBuilder> RtypeBitmapBuilder<Builder> {
-    #[must_use]
-    pub fn new() -> Self
-    where
-        Builder: EmptyBuilder,
-    {
-        RtypeBitmapBuilder {
-            // Start out with the capacity for one block.
-            buf: Builder::with_capacity(34),
-        }
-    }
-
-    pub fn with_builder(builder: Builder) -> Self {
-        RtypeBitmapBuilder { buf: builder }
-    }
-}
-
-#[cfg(feature = "std")]
-impl RtypeBitmapBuilder<Vec<u8>> {
-    #[must_use]
-    pub fn new_vec() -> Self {
-        Self::new()
-    }
-}
-
-impl<Builder> RtypeBitmapBuilder<Builder>
-where
-    Builder: OctetsBuilder + AsRef<[u8]> + AsMut<[u8]>,
-{
-    pub fn add(&mut self, rtype: Rtype) -> Result<(), Builder::AppendError> {
-        let (block, octet, bit) = split_rtype(rtype);
-        let block = self.get_block(block)?;
-        if (block[1] as usize) < (octet + 1) {
-            block[1] = (octet + 1) as u8
-        }
-        block[octet + 2] |= bit;
-        Ok(())
-    }
-
-    fn get_block(
-        &mut self,
-        block: u8,
-    ) -> Result<&mut [u8], Builder::AppendError> {
-        let mut pos = 0;
-        while pos < self.buf.as_ref().len() {
-            match self.buf.as_ref()[pos].cmp(&block) {
-                Ordering::Equal => {
-                    return Ok(&mut self.buf.as_mut()[pos..pos + 34])
-                }
-                Ordering::Greater => {
-                    // We need the length from before we add the new block
-                    let len = self.buf.as_ref().len();
-
-                    // Allocate space for the new block
-                    self.buf.append_slice(&[0; 34])?;
-
-                    // Move everything after this block back by 34 bytes
-                    let buf = self.buf.as_mut();
-                    buf.copy_within(pos..len, pos + 34);
-                    buf[pos..pos + 34].fill(0);
-                    buf[pos] = block;
-
-                    return Ok(&mut buf[pos..pos + 34]);
-                }
-                Ordering::Less => pos += 34,
-            }
-        }
-
-        self.buf.append_slice(&[0; 34])?;
-        self.buf.as_mut()[pos] = block;
-        Ok(&mut self.buf.as_mut()[pos..pos + 34])
-    }
-
-    pub fn finalize(mut self) -> RtypeBitmap<Builder::Octets>
-    where
-        Builder: FreezeBuilder + Truncate,
-    {
-        let mut dst_pos = 0;
-        let buf_len = self.buf.as_ref().len();
-        for src_pos in (0..buf_len).step_by(34) {
-            let chunk_len = (self.buf.as_ref()[src_pos + 1] as usize) + 2;
-            let buf = self.buf.as_mut();
-            buf.copy_within(src_pos..src_pos + chunk_len, dst_pos);
-            dst_pos += chunk_len;
-        }
-        self.buf.truncate(dst_pos);
-        RtypeBitmap(self.buf.freeze())
-    }
-}
-
-//--- Default
-
-impl<Builder> Default for RtypeBitmapBuilder<Builder>
-where
-    Builder: OctetsBuilder + EmptyBuilder,
-{
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-//------------ RtypeBitmapIter -----------------------------------------------
-
-pub struct RtypeBitmapIter<'a> {
-    /// The data to iterate over.
-    ///
-    /// This starts with the octets of the current block without the block
-    /// number and length.
-    data: &'a [u8],
-
-    /// The base value of the current block, i.e., its upper 8 bits.
-    block: u16,
-
-    /// The length of the current block’s data.
-    len: usize,
-
-    /// Index of the current octet in the current block.
-    octet: usize,
-
-    /// Index of the next set bit in the current octet in the current block.
-    bit: u16,
-}
-
-impl<'a> RtypeBitmapIter<'a> {
-    fn new(data: &'a [u8]) -> Self {
-        if data.is_empty() {
-            RtypeBitmapIter {
-                data,
-                block: 0,
-                len: 0,
-                octet: 0,
-                bit: 0,
-            }
-        } else {
-            let mut res = RtypeBitmapIter {
-                data: &data[2..],
-                block: u16::from(data[0]) << 8,
-                len: usize::from(data[1]),
-                octet: 0,
-                bit: 0,
-            };
-            if res.data[0] & 0x80 == 0 {
-                res.advance()
-            }
-            res
-        }
-    }
-
-    fn advance(&mut self) {
-        loop {
-            self.bit += 1;
-            if self.bit == 8 {
-                self.bit = 0;
-                self.octet += 1;
-                if self.octet == self.len {
-                    self.data = &self.data[self.len..];
-                    if self.data.is_empty() {
-                        return;
-                    }
-                    self.block = u16::from(self.data[0]) << 8;
-                    self.len = usize::from(self.data[1]);
-                    self.data = &self.data[2..];
-                    self.octet = 0;
-                }
-            }
-            if self.data[self.octet] & (0x80 >> self.bit) != 0 {
-                return;
-            }
-        }
-    }
-}
-
-impl Iterator for RtypeBitmapIter<'_> {
-    type Item = Rtype;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.data.is_empty() {
-            return None;
-        }
-        let res = Rtype::from_int(
-            self.block | ((self.octet as u16) << 3) | self.bit,
-        );
-        self.advance();
-        Some(res)
-    }
-}
-
-//============ Error Types ===================================================
-
-//------------ RtypeBitmapError ----------------------------------------------
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub struct RtypeBitmapError(RtypeBitmapErrorEnum);
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum RtypeBitmapErrorEnum {
-    ShortInput,
-    BadRtypeBitmap,
-}
-
-//--- From
-
-impl From<RtypeBitmapError> for ParseError {
-    fn from(err: RtypeBitmapError) -> ParseError {
-        match err.0 {
-            RtypeBitmapErrorEnum::ShortInput => ParseError::ShortInput,
-            RtypeBitmapErrorEnum::BadRtypeBitmap => {
-                FormError::new("invalid NSEC bitmap").into()
-            }
-        }
-    }
-}
-
-impl From<RtypeBitmapErrorEnum> for RtypeBitmapError {
-    fn from(err: RtypeBitmapErrorEnum) -> Self {
-        Self(err)
-    }
-}
-
-//--- Display and Error
-
-impl fmt::Display for RtypeBitmapError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.0 {
-            RtypeBitmapErrorEnum::ShortInput => ParseError::ShortInput.fmt(f),
-            RtypeBitmapErrorEnum::BadRtypeBitmap => {
-                f.write_str("invalid record type bitmap")
-            }
-        }
-    }
-}
-
-#[cfg(feature = "std")]
-impl std::error::Error for RtypeBitmapError {}
-
-//============ Friendly Helper Functions =====================================
-
-/// Splits an Rtype value into window number, octet number, and octet mask.
-fn split_rtype(rtype: Rtype) -> (u8, usize, u8) {
-    let rtype = rtype.to_int();
-    (
-        (rtype >> 8) as u8,
-        ((rtype & 0xFF) >> 3) as usize,
-        0b1000_0000 >> (rtype & 0x07),
-    )
-}
-
-/// Splits the next bitmap window from the bitmap and returns None when there's no next window.
-#[allow(clippy::type_complexity)]
-fn read_window(data: &[u8]) -> Option<((u8, &[u8]), &[u8])> {
-    data.split_first().and_then(|(n, data)| {
-        data.split_first().and_then(|(l, data)| {
-            if data.len() >= usize::from(*l) {
-                let (window, data) = data.split_at(usize::from(*l));
-                Some(((*n, window), data))
-            } else {
-                None
-            }
-        })
-    })
-}
-
 //============ Errors ========================================================
 
 #[derive(Clone, Copy, Debug)]
@@ -2841,80 +2298,37 @@ mod test {
         test_scan(&["10", "5", "2", "6b6579"], Ds::scan, &rdata);
     }
 
-    //--- RtypeBitmape
-
-    #[test]
-    fn rtype_split() {
-        assert_eq!(split_rtype(Rtype::A), (0, 0, 0b01000000));
-        assert_eq!(split_rtype(Rtype::NS), (0, 0, 0b00100000));
-        assert_eq!(split_rtype(Rtype::CAA), (1, 0, 0b01000000));
-    }
-
-    #[test]
-    fn rtype_bitmap_read_window() {
-        let mut builder = RtypeBitmapBuilder::new_vec();
-        builder.add(Rtype::A).unwrap();
-        builder.add(Rtype::CAA).unwrap();
-        let bitmap = builder.finalize();
-
-        let ((n, window), data) = read_window(bitmap.as_slice()).unwrap();
-        assert_eq!((n, window), (0u8, b"\x40".as_ref()));
-        let ((n, window), data) = read_window(data).unwrap();
-        assert_eq!((n, window), (1u8, b"\x40".as_ref()));
-        assert!(data.is_empty());
-        assert!(read_window(data).is_none());
-    }
+    //--- Timestamp
 
     #[test]
-    fn rtype_bitmap_builder() {
-        let mut builder = RtypeBitmapBuilder::new_vec();
-        builder.add(Rtype::from_int(1234)).unwrap(); // 0x04D2
-        builder.add(Rtype::A).unwrap(); // 0x0001
-        builder.add(Rtype::MX).unwrap(); // 0x000F
-        builder.add(Rtype::RRSIG).unwrap(); // 0x002E
-        builder.add(Rtype::NSEC).unwrap(); // 0x002F
-        let bitmap = builder.finalize();
+    fn timestamp_validity_window() {
+        const FOURTEEN_DAYS: Duration = Duration::from_secs(14 * 24 * 3600);
+
+        let inception = Timestamp::from(1_700_000_000);
+        let expiration = inception.checked_add(FOURTEEN_DAYS).unwrap();
+        assert_eq!(expiration.into_int(), 1_701_209_600);
+        assert!(expiration > inception);
+        assert_eq!(expiration.checked_sub(FOURTEEN_DAYS), Some(inception));
+
+        // Windows crossing the 2^32 boundary wrap around but still compare
+        // correctly.
+        let inception = Timestamp::from(u32::MAX - 100);
+        let expiration = inception.checked_add(FOURTEEN_DAYS).unwrap();
+        assert_eq!(expiration.into_int(), 1_209_499);
+        assert!(expiration > inception);
+        assert_eq!(expiration.checked_sub(FOURTEEN_DAYS), Some(inception));
+
+        // Durations beyond 2^31 - 1 seconds cannot be represented.
+        assert!(inception
+            .checked_add(Duration::from_secs(0x8000_0000))
+            .is_none());
+
+        let (inception, expiration) =
+            Timestamp::window_from_now(FOURTEEN_DAYS).unwrap();
         assert_eq!(
-            bitmap.as_slice(),
-            &b"\x00\x06\x40\x01\x00\x00\x00\x03\
-                     \x04\x1b\x00\x00\x00\x00\x00\x00\
-                     \x00\x00\x00\x00\x00\x00\x00\x00\
-                     \x00\x00\x00\x00\x00\x00\x00\x00\
-                     \x00\x00\x00\x00\x20"[..]
+            expiration.into_int().wrapping_sub(inception.into_int()),
+            14 * 24 * 3600
         );
-
-        assert!(bitmap.contains(Rtype::A));
-        assert!(bitmap.contains(Rtype::MX));
-        assert!(bitmap.contains(Rtype::RRSIG));
-        assert!(bitmap.contains(Rtype::NSEC));
-        assert!(bitmap.contains(Rtype::from(1234)));
-        assert!(!bitmap.contains(Rtype::from(1235)));
-        assert!(!bitmap.contains(Rtype::NS));
-    }
-
-    #[test]
-    fn rtype_bitmap_iter() {
-        use std::vec::Vec;
-
-        let mut builder = RtypeBitmapBuilder::new_vec();
-        let types = vec![
-            Rtype::NS,
-            Rtype::SOA,
-            Rtype::MX,
-            Rtype::TXT,
-            Rtype::RRSIG,
-            Rtype::DNSKEY,
-            Rtype::NSEC3PARAM,
-            Rtype::SPF,
-            Rtype::CAA,
-        ];
-        for t in types.iter() {
-            builder.add(*t).unwrap();
-        }
-
-        let bitmap = builder.finalize();
-        let bitmap_types: Vec<_> = bitmap.iter().collect();
-        assert_eq!(types, bitmap_types);
     }
 
     #[test]
@@ -2991,4 +2405,54 @@ mod test {
         assert!(dnskey.is_secure_entry_point());
         assert!(!dnskey.is_revoked());
     }
+
+    #[test]
+    fn dnskey_likely_role() {
+        // The root zone keys from test-data/validator.
+        let zsk = Dnskey::new(
+            256,
+            3,
+            SecurityAlgorithm::RSASHA256,
+            base64::decode::<Vec<u8>>(
+                "AwEAAfC/6HLClwss6h7rPfoG2cliv4/SPJRd2HPEglRsvKZRbPP2RLfi\
+                obeAkczcdqaD5q8loEt14lcTgDqwzOISZ3YvSVkM4JRMFwKzcjukKo5C\
+                sDVbMmhTD0C0yxWICRQ1M+Y5/XkZAT7mt4cb3fWcN9xgyq1wEXQX+zdL\
+                QHrNEVQSiL5SoA5cOtCSoQ45n8bKDXdw/0jjP9Rw1FVKsdzLVkQSrVMm\
+                8k30WUkHm/SK/n/954KENkdQOA6Li2vO9nicQdegyAkDeNJCdPN/p3jE\
+                hCTQLyO4AlAmyaPcDHeeo7OXr/VsYu4NTDde9hBuS0zx/rewD+BvSnmn\
+                NHNmH2FjUE8=",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let ksk = Dnskey::new(
+            257,
+            3,
+            SecurityAlgorithm::RSASHA256,
+            base64::decode::<Vec<u8>>(
+                "AwEAAaz/tAm8yTn4Mfeh5eyI96WSVexTBAvkMgJzkKTOiW1vkIbzxeF3\
+                +/4RgWOq7HrxRixHlFlExOLAJr5emLvN7SWXgnLh4+B5xQlNVz8Og8kv\
+                ArMtNROxVQuCaSnIDdD5LKyWbRd2n9WGe2R8PzgCmr3EgVLrjyBxWezF\
+                0jLHwVN8efS3rCj/EWgvIWgb9tarpVUDK/b58Da+sqqls3eNbuv7pr+e\
+                oZG+SrDK6nWeL3c6H5Apxz7LjVc1uTIdsIXxuOLYA4/ilBmSVIzuDWfd\
+                RUfhHdY6+cn8HFRm+2hM8AnXGXws9555KrUB5qihylGa8subX2Nn6UwN\
+                R1AkUTV74bU=",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert!(ksk.is_sep());
+        assert_eq!(ksk.key_tag(), 20326);
+        assert_eq!(ksk.likely_role(), DnskeyRole::Ksk);
+
+        assert!(!zsk.is_sep());
+        assert!(zsk.is_zone_key());
+        assert_eq!(zsk.likely_role(), DnskeyRole::Zsk);
+
+        let other =
+            Dnskey::new(0, 3, SecurityAlgorithm::RSASHA256, zsk.public_key())
+                .unwrap();
+        assert_eq!(other.likely_role(), DnskeyRole::Other);
+    }
 }
@@ -51,7 +51,10 @@ pub mod dname;
 pub mod dnssec;
 pub mod naptr;
 pub mod nsec3;
+#[cfg(feature = "std")]
+pub mod registry;
 pub mod rfc1035;
+pub mod rtype_bitmap;
 pub mod srv;
 pub mod svcb;
 pub mod tsig;
@@ -4,7 +4,7 @@
 //!
 //! [RFC 5155]: https://tools.ietf.org/html/rfc5155
 
-use super::dnssec::RtypeBitmap;
+use super::rtype_bitmap::RtypeBitmap;
 use crate::base::cmp::CanonicalOrd;
 use crate::base::iana::{Nsec3HashAlgorithm, Rtype};
 use crate::base::rdata::{ComposeRecordData, ParseRecordData, RecordData};
@@ -1672,7 +1672,7 @@ impl std::error::Error for OwnerHashError {}
 #[cfg(test)]
 #[cfg(all(feature = "std", feature = "bytes"))]
 mod test {
-    use super::super::dnssec::RtypeBitmapBuilder;
+    use super::super::rtype_bitmap::RtypeBitmapBuilder;
     use super::*;
     use crate::base::rdata::test::{
         test_compose_parse, test_rdlen, test_scan,
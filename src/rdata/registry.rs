@@ -0,0 +1,561 @@
+//! A registry for record data of additional record types.
+//!
+//! The record data types of this crate only cover the record types it knows
+//! about. Record data of all other types ends up as [`UnknownRecordData`].
+//! This module allows applications to plug in their own implementation for
+//! such a record type at runtime.
+//!
+//! A parser for a record type is added to a [`Registry`] via
+//! [`Registry::register`]. It receives the raw record data and returns a
+//! value of a type implementing [`CustomRecordData`]. When parsing record
+//! data via [`Registry::parse_rdata`] or [`Registry::parse_record`], the
+//! registry is consulted first and only if no parser was registered for a
+//! record type, the data is parsed as [`AllRecordData`] – and thus,
+//! eventually, as [`UnknownRecordData`].
+//!
+//! # Example
+//!
+//! ```
+//! use std::any::Any;
+//! use std::fmt;
+//! use std::sync::Arc;
+//! use std::vec::Vec;
+//! use domain::base::iana::Rtype;
+//! use domain::base::wire::ParseError;
+//! use domain::rdata::registry::{CustomRecordData, Registry};
+//!
+//! #[derive(Debug)]
+//! struct Wallet(Vec<u8>);
+//!
+//! impl fmt::Display for Wallet {
+//!     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+//!         write!(f, "{}", String::from_utf8_lossy(&self.0))
+//!     }
+//! }
+//!
+//! impl CustomRecordData for Wallet {
+//!     fn rtype(&self) -> Rtype {
+//!         Rtype::from_int(262)
+//!     }
+//!
+//!     fn compose_rdata(&self, target: &mut Vec<u8>) {
+//!         target.extend_from_slice(&self.0)
+//!     }
+//!
+//!     fn as_any(&self) -> &dyn Any {
+//!         self
+//!     }
+//! }
+//!
+//! fn parse_wallet(
+//!     data: &[u8],
+//! ) -> Result<Arc<dyn CustomRecordData>, ParseError> {
+//!     Ok(Arc::new(Wallet(data.into())))
+//! }
+//!
+//! let mut registry = Registry::new();
+//! registry.register(Rtype::from_int(262), parse_wallet);
+//! assert!(registry.is_registered(Rtype::from_int(262)));
+//! ```
+//!
+//! [`UnknownRecordData`]: crate::base::rdata::UnknownRecordData
+
+use super::AllRecordData;
+use crate::base::iana::Rtype;
+use crate::base::name::{ParsedName, ToName};
+use crate::base::rdata::{
+    ComposeRecordData, LongRecordData, ParseAnyRecordData, RecordData,
+    UnknownRecordData,
+};
+use crate::base::record::{ParsedRecord, Record};
+use crate::base::wire::{Composer, ParseError};
+use core::any::Any;
+use core::fmt;
+use octseq::octets::Octets;
+use octseq::parse::Parser;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::vec::Vec;
+
+//------------ ParseFn -------------------------------------------------------
+
+/// A function parsing the record data of a registered record type.
+///
+/// The function receives the complete record data of a record and needs to
+/// consume all of it.
+pub type ParseFn = fn(&[u8]) -> Result<Arc<dyn CustomRecordData>, ParseError>;
+
+//------------ Registry ------------------------------------------------------
+
+/// A set of parsers for additional record types.
+///
+/// Parsers can also be registered for record types known to this crate.
+/// In this case, the registered parser is used instead of the built-in
+/// record data type.
+#[derive(Clone, Default)]
+pub struct Registry {
+    /// The parsers by record type.
+    parsers: BTreeMap<Rtype, ParseFn>,
+}
+
+impl Registry {
+    /// Creates a new, empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a parser for the record data of the given record type.
+    ///
+    /// If a parser was registered for the record type before, it is
+    /// replaced and returned.
+    pub fn register(
+        &mut self,
+        rtype: Rtype,
+        parse: ParseFn,
+    ) -> Option<ParseFn> {
+        self.parsers.insert(rtype, parse)
+    }
+
+    /// Removes the parser for the given record type.
+    ///
+    /// Returns the parser if one was registered.
+    pub fn unregister(&mut self, rtype: Rtype) -> Option<ParseFn> {
+        self.parsers.remove(&rtype)
+    }
+
+    /// Returns whether a parser is registered for the given record type.
+    pub fn is_registered(&self, rtype: Rtype) -> bool {
+        self.parsers.contains_key(&rtype)
+    }
+
+    /// Parses record data of the given record type.
+    ///
+    /// The parser must be limited to the record data. If a parser is
+    /// registered for the record type, it is used and the data it produces
+    /// must be of record type `rtype`. Otherwise, the data is parsed as
+    /// [`AllRecordData`].
+    #[allow(clippy::type_complexity)]
+    pub fn parse_rdata<'a, Octs: Octets + ?Sized>(
+        &self,
+        rtype: Rtype,
+        parser: &mut Parser<'a, Octs>,
+    ) -> Result<
+        RegistryRecordData<Octs::Range<'a>, ParsedName<Octs::Range<'a>>>,
+        ParseError,
+    > {
+        match self.parsers.get(&rtype) {
+            Some(parse) => {
+                let data = parser.parse_octets(parser.remaining())?;
+                Self::parse_registered(rtype, *parse, data.as_ref())
+            }
+            None => AllRecordData::parse_any_rdata(rtype, parser)
+                .map(RegistryRecordData::Other),
+        }
+    }
+
+    /// Trades a parsed record for a record with registry record data.
+    ///
+    /// The record data is parsed as described for
+    /// [`parse_rdata`][Self::parse_rdata].
+    #[allow(clippy::type_complexity)]
+    pub fn parse_record<'a, Octs: Octets + ?Sized>(
+        &self,
+        record: ParsedRecord<'a, Octs>,
+    ) -> Result<
+        Record<
+            ParsedName<Octs::Range<'a>>,
+            RegistryRecordData<Octs::Range<'a>, ParsedName<Octs::Range<'a>>>,
+        >,
+        ParseError,
+    > {
+        let rtype = record.rtype();
+        let class = record.class();
+        let ttl = record.ttl();
+        let (owner, data) = match self.parsers.get(&rtype) {
+            Some(parse) => {
+                // Unknown record data always parses successfully.
+                let (owner, data) = record
+                    .into_record::<UnknownRecordData<_>>()?
+                    .ok_or(ParseError::form_error("invalid record data"))?
+                    .into_owner_and_data();
+                let data = Self::parse_registered(
+                    rtype,
+                    *parse,
+                    data.data().as_ref(),
+                )?;
+                (owner, data)
+            }
+            None => {
+                let (owner, data) = record
+                    .into_any_record::<AllRecordData<_, _>>()?
+                    .into_owner_and_data();
+                (owner, RegistryRecordData::Other(data))
+            }
+        };
+        Ok(Record::new(owner, class, ttl, data))
+    }
+
+    /// Parses record data with a registered parser.
+    fn parse_registered<Octs, Name>(
+        rtype: Rtype,
+        parse: ParseFn,
+        data: &[u8],
+    ) -> Result<RegistryRecordData<Octs, Name>, ParseError> {
+        let data = parse(data)?;
+        if data.rtype() != rtype {
+            return Err(ParseError::form_error(
+                "registered parser returned wrong record type",
+            ));
+        }
+        RegisteredRecordData::new(data)
+            .map(RegistryRecordData::Registered)
+            .map_err(|err| ParseError::form_error(err.as_str()))
+    }
+}
+
+//--- Debug
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.parsers.keys()).finish()
+    }
+}
+
+//------------ CustomRecordData ----------------------------------------------
+
+/// Record data produced by a parser from a registry.
+///
+/// Because the registry is not aware of the type of a record’s data, the
+/// data is kept as a trait object. Use
+/// [`RegistryRecordData::downcast_ref`] to get back to the concrete type.
+///
+/// Record data of record types not defined in RFC 1035 must neither be
+/// compressed nor be changed for the canonical form, see [RFC 3597]. The
+/// wire format produced by [`compose_rdata`][Self::compose_rdata] is
+/// therefore used for all purposes.
+///
+/// [RFC 3597]: https://datatracker.ietf.org/doc/html/rfc3597
+pub trait CustomRecordData:
+    fmt::Debug + fmt::Display + Send + Sync + Any
+{
+    /// Returns the record type of the data.
+    fn rtype(&self) -> Rtype;
+
+    /// Appends the wire format of the record data to `target`.
+    fn compose_rdata(&self, target: &mut Vec<u8>);
+
+    /// Returns an [`Any`] interface to the record data.
+    ///
+    /// This is used by [`RegistryRecordData::downcast_ref`] to get back to
+    /// the concrete type. Implementations simply return `self`.
+    fn as_any(&self) -> &dyn Any;
+}
+
+//------------ RegisteredRecordData ------------------------------------------
+
+/// Record data of a registered record type together with its wire format.
+///
+/// The wire format is produced once when the value is created, which also
+/// ensures that it isn’t too long for record data.
+#[derive(Clone)]
+pub struct RegisteredRecordData {
+    /// The record data.
+    data: Arc<dyn CustomRecordData>,
+
+    /// The wire format of the record data.
+    rdata: Vec<u8>,
+}
+
+impl RegisteredRecordData {
+    /// Creates a new value from record data.
+    ///
+    /// Returns an error if the wire format of the data is too long.
+    pub fn new(
+        data: Arc<dyn CustomRecordData>,
+    ) -> Result<Self, LongRecordData> {
+        let mut rdata = Vec::new();
+        data.compose_rdata(&mut rdata);
+        LongRecordData::check_len(rdata.len())?;
+        Ok(RegisteredRecordData { data, rdata })
+    }
+
+    /// Returns a reference to the record data.
+    pub fn data(&self) -> &dyn CustomRecordData {
+        self.data.as_ref()
+    }
+
+    /// Returns the wire format of the record data.
+    pub fn rdata(&self) -> &[u8] {
+        &self.rdata
+    }
+}
+
+//--- Debug
+
+impl fmt::Debug for RegisteredRecordData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.data.fmt(f)
+    }
+}
+
+//------------ RegistryRecordData --------------------------------------------
+
+/// Record data for all record types including those from a registry.
+///
+/// Values of this type are produced by [`Registry::parse_rdata`] and
+/// [`Registry::parse_record`]. Record data of registered record types is
+/// kept in the `Registered` variant, all other data as [`AllRecordData`].
+#[derive(Clone)]
+pub enum RegistryRecordData<Octs, Name> {
+    /// Record data produced by a registered parser.
+    Registered(RegisteredRecordData),
+
+    /// Record data of all other record types.
+    Other(AllRecordData<Octs, Name>),
+}
+
+impl<Octs, Name> RegistryRecordData<Octs, Name> {
+    /// Returns the registered record data if this is what we have.
+    pub fn registered(&self) -> Option<&dyn CustomRecordData> {
+        match *self {
+            RegistryRecordData::Registered(ref data) => Some(data.data()),
+            RegistryRecordData::Other(_) => None,
+        }
+    }
+
+    /// Returns the registered record data as a concrete type.
+    ///
+    /// Returns `None` if the data is not registered record data or it is
+    /// not of type `T`.
+    pub fn downcast_ref<T: CustomRecordData>(&self) -> Option<&T> {
+        self.registered()?.as_any().downcast_ref::<T>()
+    }
+
+    /// Returns the built-in record data if this is what we have.
+    pub fn other(&self) -> Option<&AllRecordData<Octs, Name>> {
+        match *self {
+            RegistryRecordData::Registered(_) => None,
+            RegistryRecordData::Other(ref data) => Some(data),
+        }
+    }
+}
+
+//--- From
+
+impl<Octs, Name> From<AllRecordData<Octs, Name>>
+    for RegistryRecordData<Octs, Name>
+{
+    fn from(data: AllRecordData<Octs, Name>) -> Self {
+        RegistryRecordData::Other(data)
+    }
+}
+
+impl<Octs, Name> From<RegisteredRecordData>
+    for RegistryRecordData<Octs, Name>
+{
+    fn from(data: RegisteredRecordData) -> Self {
+        RegistryRecordData::Registered(data)
+    }
+}
+
+//--- RecordData
+
+impl<Octs, Name> RecordData for RegistryRecordData<Octs, Name>
+where
+    AllRecordData<Octs, Name>: RecordData,
+{
+    fn rtype(&self) -> Rtype {
+        match *self {
+            RegistryRecordData::Registered(ref data) => data.data().rtype(),
+            RegistryRecordData::Other(ref data) => data.rtype(),
+        }
+    }
+}
+
+//--- ComposeRecordData
+
+impl<Octs, Name> ComposeRecordData for RegistryRecordData<Octs, Name>
+where
+    Octs: AsRef<[u8]>,
+    Name: ToName,
+{
+    fn rdlen(&self, compress: bool) -> Option<u16> {
+        match *self {
+            RegistryRecordData::Registered(ref data) => {
+                // The length was checked when the value was created.
+                u16::try_from(data.rdata().len()).ok()
+            }
+            RegistryRecordData::Other(ref data) => data.rdlen(compress),
+        }
+    }
+
+    fn compose_rdata<Target: Composer + ?Sized>(
+        &self,
+        target: &mut Target,
+    ) -> Result<(), Target::AppendError> {
+        match *self {
+            RegistryRecordData::Registered(ref data) => {
+                target.append_slice(data.rdata())
+            }
+            RegistryRecordData::Other(ref data) => data.compose_rdata(target),
+        }
+    }
+
+    fn compose_canonical_rdata<Target: Composer + ?Sized>(
+        &self,
+        target: &mut Target,
+    ) -> Result<(), Target::AppendError> {
+        match *self {
+            RegistryRecordData::Registered(ref data) => {
+                target.append_slice(data.rdata())
+            }
+            RegistryRecordData::Other(ref data) => {
+                data.compose_canonical_rdata(target)
+            }
+        }
+    }
+}
+
+//--- Display and Debug
+
+impl<Octs, Name> fmt::Display for RegistryRecordData<Octs, Name>
+where
+    Octs: Octets,
+    Name: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RegistryRecordData::Registered(ref data) => {
+                fmt::Display::fmt(data.data(), f)
+            }
+            RegistryRecordData::Other(ref data) => data.fmt(f),
+        }
+    }
+}
+
+impl<Octs, Name> fmt::Debug for RegistryRecordData<Octs, Name>
+where
+    Octs: Octets,
+    Name: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RegistryRecordData::Registered(ref data) => {
+                f.debug_tuple("Registered").field(data).finish()
+            }
+            RegistryRecordData::Other(ref data) => {
+                f.debug_tuple("Other").field(data).finish()
+            }
+        }
+    }
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::iana::Class;
+    use crate::base::{Message, MessageBuilder, Name, Ttl};
+    use core::str::FromStr;
+    use std::string::ToString;
+
+    #[derive(Debug)]
+    struct Example(Vec<u8>);
+
+    impl fmt::Display for Example {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "example {}", self.0.len())
+        }
+    }
+
+    impl CustomRecordData for Example {
+        fn rtype(&self) -> Rtype {
+            Rtype::from_int(65280)
+        }
+
+        fn compose_rdata(&self, target: &mut Vec<u8>) {
+            target.extend_from_slice(&self.0)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    fn parse_example(
+        data: &[u8],
+    ) -> Result<Arc<dyn CustomRecordData>, ParseError> {
+        Ok(Arc::new(Example(data.into())))
+    }
+
+    fn mk_message(rtype: Rtype) -> Message<Vec<u8>> {
+        let mut msg = MessageBuilder::new_vec().answer();
+        msg.push(Record::new(
+            Name::<Vec<u8>>::from_str("example.com.").unwrap(),
+            Class::IN,
+            Ttl::HOUR,
+            UnknownRecordData::from_octets(rtype, b"abc".as_ref()).unwrap(),
+        ))
+        .unwrap();
+        Message::from_octets(msg.finish()).unwrap()
+    }
+
+    #[test]
+    fn registered_type_is_parsed() {
+        let rtype = Rtype::from_int(65280);
+        let msg = mk_message(rtype);
+        let mut registry = Registry::new();
+
+        // Without a registered parser, we get unknown data.
+        let record = registry
+            .parse_record(msg.answer().unwrap().next().unwrap().unwrap())
+            .unwrap();
+        assert!(matches!(
+            record.data(),
+            RegistryRecordData::Other(AllRecordData::Unknown(_))
+        ));
+
+        // With one, our parser is used.
+        assert!(registry.register(rtype, parse_example).is_none());
+        assert!(registry.is_registered(rtype));
+        let record = registry
+            .parse_record(msg.answer().unwrap().next().unwrap().unwrap())
+            .unwrap();
+        assert_eq!(record.owner().to_string(), "example.com");
+        assert_eq!(record.ttl(), Ttl::HOUR);
+        assert_eq!(record.data().rtype(), rtype);
+        assert_eq!(
+            record.data().downcast_ref::<Example>().unwrap().0,
+            b"abc"
+        );
+        assert_eq!(record.data().to_string(), "example 3");
+
+        // Composing produces the original data.
+        let mut target = Vec::new();
+        record.data().compose_rdata(&mut target).unwrap();
+        assert_eq!(target, b"abc");
+        assert_eq!(record.data().rdlen(false), Some(3));
+
+        assert!(registry.unregister(rtype).is_some());
+        assert!(!registry.is_registered(rtype));
+    }
+
+    #[test]
+    fn wrong_rtype_is_rejected() {
+        // The parser produces data for a different record type.
+        let rtype = Rtype::from_int(65281);
+        let msg = mk_message(rtype);
+        let mut registry = Registry::new();
+        registry.register(rtype, parse_example);
+        assert!(registry
+            .parse_record(msg.answer().unwrap().next().unwrap().unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn long_rdata_is_rejected() {
+        let data = Arc::new(Example(vec![0; usize::from(u16::MAX) + 1]));
+        assert!(RegisteredRecordData::new(data).is_err());
+    }
+}
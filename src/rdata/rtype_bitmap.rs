@@ -0,0 +1,853 @@
+//! Record type bitmaps.
+//!
+//! Several record types, such as NSEC and NSEC3 from [RFC 4034] and
+//! [RFC 5155] or CSYNC from [RFC 7477], carry the set of record types present
+//! at a name in the form of a bitmap split into window blocks. This module
+//! provides the [`RtypeBitmap`] type shared by all of them, together with
+//! [`RtypeBitmapBuilder`] for creating new bitmaps.
+//!
+//! [RFC 4034]: https://tools.ietf.org/html/rfc4034#section-4.1.2
+//! [RFC 5155]: https://tools.ietf.org/html/rfc5155#section-3.2.1
+//! [RFC 7477]: https://tools.ietf.org/html/rfc7477#section-2.1.1
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::scan::{Scanner, ScannerError};
+use crate::base::wire::{FormError, ParseError};
+use crate::base::zonefile_fmt::{self, Formatter, ZonefileFmt};
+use core::cmp::Ordering;
+use core::{fmt, hash};
+use octseq::builder::{
+    EmptyBuilder, FreezeBuilder, FromBuilder, OctetsBuilder, Truncate,
+};
+use octseq::octets::{Octets, OctetsFrom, OctetsInto};
+use octseq::parse::Parser;
+#[cfg(feature = "serde")]
+use octseq::serde::{DeserializeOctets, SerializeOctets};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+//------------ RtypeBitmap ---------------------------------------------------
+
+/// A record type bitmap.
+///
+/// The bitmap is stored in its wire format: a sequence of window blocks,
+/// each consisting of the window number, the length of the bitmap octets
+/// for the window, and the bitmap octets themselves. Windows appear in
+/// increasing order and windows without any types present are omitted.
+///
+/// Use [`RtypeBitmap::builder`] to create a new bitmap from a set of record
+/// types and [`RtypeBitmap::contains`] or [`RtypeBitmap::iter`] to read
+/// one.
+#[derive(Clone)]
+pub struct RtypeBitmap<Octs>(Octs);
+
+impl<Octs> RtypeBitmap<Octs> {
+    /// Creates a bitmap from octets containing its wire format.
+    ///
+    /// Returns an error if the octets are not a correctly encoded sequence
+    /// of window blocks.
+    pub fn from_octets(octets: Octs) -> Result<Self, RtypeBitmapError>
+    where
+        Octs: AsRef<[u8]>,
+    {
+        {
+            let mut data = octets.as_ref();
+            while !data.is_empty() {
+                // At least bitmap number and length must be present.
+                if data.len() < 2 {
+                    return Err(RtypeBitmapErrorEnum::ShortInput.into());
+                }
+
+                let len = (data[1] as usize) + 2;
+                // https://tools.ietf.org/html/rfc4034#section-4.1.2:
+                //  Blocks with no types present MUST NOT be included.
+                if len == 2 {
+                    return Err(RtypeBitmapErrorEnum::BadRtypeBitmap.into());
+                }
+                if len > 34 {
+                    return Err(RtypeBitmapErrorEnum::BadRtypeBitmap.into());
+                }
+                if data.len() < len {
+                    return Err(RtypeBitmapErrorEnum::ShortInput.into());
+                }
+                data = &data[len..];
+            }
+        }
+        Ok(RtypeBitmap(octets))
+    }
+
+    /// Returns a builder for a new bitmap.
+    #[must_use]
+    pub fn builder() -> RtypeBitmapBuilder<Octs::Builder>
+    where
+        Octs: FromBuilder,
+        <Octs as FromBuilder>::Builder: EmptyBuilder,
+    {
+        RtypeBitmapBuilder::new()
+    }
+
+    pub fn scan<S: Scanner<Octets = Octs>>(
+        scanner: &mut S,
+    ) -> Result<Self, S::Error> {
+        let mut builder =
+            RtypeBitmapBuilder::with_builder(scanner.octets_builder()?);
+        while scanner.continues() {
+            builder
+                .add(Rtype::scan(scanner)?)
+                .map_err(|_| S::Error::short_buf())?;
+        }
+        Ok(builder.finalize())
+    }
+
+    pub fn as_octets(&self) -> &Octs {
+        &self.0
+    }
+
+    pub(super) fn convert_octets<Target: OctetsFrom<Octs>>(
+        self,
+    ) -> Result<RtypeBitmap<Target>, Target::Error> {
+        Ok(RtypeBitmap(self.0.try_octets_into()?))
+    }
+}
+
+impl<Octs: AsRef<[u8]>> RtypeBitmap<Octs> {
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+
+    /// Returns an iterator over the record types in the bitmap.
+    ///
+    /// The types are returned in increasing order.
+    pub fn iter(&self) -> RtypeBitmapIter<'_> {
+        RtypeBitmapIter::new(self.0.as_ref())
+    }
+
+    /// Returns whether the given record type is present in the bitmap.
+    pub fn contains(&self, rtype: Rtype) -> bool {
+        let (block, octet, mask) = split_rtype(rtype);
+        let mut data = self.0.as_ref();
+        while !data.is_empty() {
+            let ((window_num, window), next_data) =
+                read_window(data).unwrap();
+            if window_num == block {
+                return !(window.len() <= octet || window[octet] & mask == 0);
+            }
+            data = next_data;
+        }
+        false
+    }
+
+    pub fn parse<'a, Src: Octets<Range<'a> = Octs> + ?Sized>(
+        parser: &mut Parser<'a, Src>,
+    ) -> Result<Self, ParseError> {
+        let len = parser.remaining();
+        RtypeBitmap::from_octets(parser.parse_octets(len)?)
+            .map_err(Into::into)
+    }
+
+    pub fn compose_len(&self) -> u16 {
+        u16::try_from(self.0.as_ref().len()).expect("long rtype bitmap")
+    }
+
+    pub fn compose<Target: OctetsBuilder + ?Sized>(
+        &self,
+        target: &mut Target,
+    ) -> Result<(), Target::AppendError> {
+        target.append_slice(self.0.as_ref())
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+//--- AsRef
+
+impl<T, Octs: AsRef<T>> AsRef<T> for RtypeBitmap<Octs> {
+    fn as_ref(&self) -> &T {
+        self.0.as_ref()
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octs, SrcOcts> OctetsFrom<RtypeBitmap<SrcOcts>> for RtypeBitmap<Octs>
+where
+    Octs: OctetsFrom<SrcOcts>,
+{
+    type Error = Octs::Error;
+
+    fn try_octets_from(
+        source: RtypeBitmap<SrcOcts>,
+    ) -> Result<Self, Self::Error> {
+        Octs::try_octets_from(source.0).map(RtypeBitmap)
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<O, OO> PartialEq<RtypeBitmap<OO>> for RtypeBitmap<O>
+where
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+{
+    fn eq(&self, other: &RtypeBitmap<OO>) -> bool {
+        self.0.as_ref().eq(other.0.as_ref())
+    }
+}
+
+impl<O: AsRef<[u8]>> Eq for RtypeBitmap<O> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<O, OO> PartialOrd<RtypeBitmap<OO>> for RtypeBitmap<O>
+where
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &RtypeBitmap<OO>) -> Option<Ordering> {
+        self.0.as_ref().partial_cmp(other.0.as_ref())
+    }
+}
+
+impl<O, OO> CanonicalOrd<RtypeBitmap<OO>> for RtypeBitmap<O>
+where
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &RtypeBitmap<OO>) -> Ordering {
+        self.0.as_ref().cmp(other.0.as_ref())
+    }
+}
+
+impl<O: AsRef<[u8]>> Ord for RtypeBitmap<O> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.as_ref().cmp(other.0.as_ref())
+    }
+}
+
+//--- Hash
+
+impl<O: AsRef<[u8]>> hash::Hash for RtypeBitmap<O> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.0.as_ref().hash(state)
+    }
+}
+
+//--- IntoIterator
+
+impl<'a, Octs: AsRef<[u8]>> IntoIterator for &'a RtypeBitmap<Octs> {
+    type Item = Rtype;
+    type IntoIter = RtypeBitmapIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+//--- Display
+
+impl<Octs: AsRef<[u8]>> fmt::Display for RtypeBitmap<Octs> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut iter = self.iter();
+        if let Some(rtype) = iter.next() {
+            fmt::Display::fmt(&rtype, f)?;
+        }
+        for rtype in iter {
+            write!(f, " {}", rtype)?
+        }
+        Ok(())
+    }
+}
+
+//--- ZonefileFmt
+
+impl<Octs: AsRef<[u8]>> ZonefileFmt for RtypeBitmap<Octs> {
+    fn fmt(&self, p: &mut impl Formatter) -> zonefile_fmt::Result {
+        for rtype in self {
+            p.write_token(rtype)?;
+        }
+        Ok(())
+    }
+}
+
+//--- Debug
+
+impl<Octs: AsRef<[u8]>> fmt::Debug for RtypeBitmap<Octs> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RtypeBitmap(")?;
+        fmt::Display::fmt(self, f)?;
+        f.write_str(")")
+    }
+}
+
+//--- Serialize and Deserialize
+
+#[cfg(feature = "serde")]
+impl<Octs> serde::Serialize for RtypeBitmap<Octs>
+where
+    Octs: AsRef<[u8]> + SerializeOctets,
+{
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            struct Inner<'a>(&'a [u8]);
+
+            impl serde::Serialize for Inner<'_> {
+                fn serialize<S: serde::Serializer>(
+                    &self,
+                    serializer: S,
+                ) -> Result<S::Ok, S::Error> {
+                    use serde::ser::SerializeSeq;
+
+                    let mut serializer = serializer.serialize_seq(None)?;
+                    for item in RtypeBitmapIter::new(self.0) {
+                        serializer.serialize_element(&item)?;
+                    }
+                    serializer.end()
+                }
+            }
+
+            serializer.serialize_newtype_struct(
+                "RtypeBitmap",
+                &Inner(self.0.as_ref()),
+            )
+        } else {
+            serializer.serialize_newtype_struct(
+                "RtypeBitmap",
+                &self.0.as_serialized_octets(),
+            )
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, Octs> serde::Deserialize<'de> for RtypeBitmap<Octs>
+where
+    Octs: FromBuilder + DeserializeOctets<'de>,
+    <Octs as FromBuilder>::Builder:
+        EmptyBuilder + Truncate + AsRef<[u8]> + AsMut<[u8]>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        use core::marker::PhantomData;
+
+        struct InnerVisitor<'de, T: DeserializeOctets<'de>>(T::Visitor);
+
+        impl<'de, Octs> serde::de::Visitor<'de> for InnerVisitor<'de, Octs>
+        where
+            Octs: FromBuilder + DeserializeOctets<'de>,
+            <Octs as FromBuilder>::Builder: OctetsBuilder
+                + EmptyBuilder
+                + Truncate
+                + AsRef<[u8]>
+                + AsMut<[u8]>,
+        {
+            type Value = RtypeBitmap<Octs>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a record type bitmap")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<Self::Value, A::Error> {
+                use serde::de::Error;
+
+                let mut builder = RtypeBitmap::<Octs>::builder();
+                while let Some(element) = seq.next_element()? {
+                    builder.add(element).map_err(|_| {
+                        A::Error::custom(octseq::builder::ShortBuf)
+                    })?;
+                }
+
+                Ok(builder.finalize())
+            }
+
+            fn visit_borrowed_bytes<E: serde::de::Error>(
+                self,
+                value: &'de [u8],
+            ) -> Result<Self::Value, E> {
+                self.0.visit_borrowed_bytes(value).and_then(|octets| {
+                    RtypeBitmap::from_octets(octets).map_err(E::custom)
+                })
+            }
+
+            #[cfg(feature = "std")]
+            fn visit_byte_buf<E: serde::de::Error>(
+                self,
+                value: std::vec::Vec<u8>,
+            ) -> Result<Self::Value, E> {
+                self.0.visit_byte_buf(value).and_then(|octets| {
+                    RtypeBitmap::from_octets(octets).map_err(E::custom)
+                })
+            }
+        }
+
+        struct NewtypeVisitor<T>(PhantomData<T>);
+
+        impl<'de, Octs> serde::de::Visitor<'de> for NewtypeVisitor<Octs>
+        where
+            Octs: FromBuilder + DeserializeOctets<'de>,
+            <Octs as FromBuilder>::Builder: OctetsBuilder
+                + EmptyBuilder
+                + Truncate
+                + AsRef<[u8]>
+                + AsMut<[u8]>,
+        {
+            type Value = RtypeBitmap<Octs>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a record type bitmap")
+            }
+
+            fn visit_newtype_struct<D: serde::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Self::Value, D::Error> {
+                if deserializer.is_human_readable() {
+                    deserializer
+                        .deserialize_seq(InnerVisitor(Octs::visitor()))
+                } else {
+                    Octs::deserialize_with_visitor(
+                        deserializer,
+                        InnerVisitor(Octs::visitor()),
+                    )
+                }
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(
+            "RtypeBitmap",
+            NewtypeVisitor(PhantomData),
+        )
+    }
+}
+
+//------------ RtypeBitmapBuilder --------------------------------------------
+
+/// A builder for a record type bitmap.
+//
+//  Here is how this is going to work: We keep one long Builder into which
+//  we place all added types. The buffer contains a sequence of blocks
+//  encoded similarly to the final format but with all 32 octets of the
+//  bitmap present. Blocks are in order and are only added when needed (which
+//  means we may have to insert a block in the middle). When finalizing, we
+//  compress the block buffer by dropping the unncessary octets of each
+//  block.
+#[derive(Clone, Debug)]
+pub struct RtypeBitmapBuilder<Builder> {
+    buf: Builder,
+}
+
+impl<Builder: OctetsBuilder> RtypeBitmapBuilder<Builder> {
+    #[must_use]
+    pub fn new() -> Self
+    where
+        Builder: EmptyBuilder,
+    {
+        RtypeBitmapBuilder {
+            // Start out with the capacity for one block.
+            buf: Builder::with_capacity(34),
+        }
+    }
+
+    pub fn with_builder(builder: Builder) -> Self {
+        RtypeBitmapBuilder { buf: builder }
+    }
+}
+
+#[cfg(feature = "std")]
+impl RtypeBitmapBuilder<Vec<u8>> {
+    #[must_use]
+    pub fn new_vec() -> Self {
+        Self::new()
+    }
+}
+
+impl<Builder> RtypeBitmapBuilder<Builder>
+where
+    Builder: OctetsBuilder + AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Adds a record type to the bitmap.
+    ///
+    /// Adding a type that is already present has no effect.
+    pub fn add(&mut self, rtype: Rtype) -> Result<(), Builder::AppendError> {
+        let (block, octet, bit) = split_rtype(rtype);
+        let block = self.get_block(block)?;
+        if (block[1] as usize) < (octet + 1) {
+            block[1] = (octet + 1) as u8
+        }
+        block[octet + 2] |= bit;
+        Ok(())
+    }
+
+    fn get_block(
+        &mut self,
+        block: u8,
+    ) -> Result<&mut [u8], Builder::AppendError> {
+        let mut pos = 0;
+        while pos < self.buf.as_ref().len() {
+            match self.buf.as_ref()[pos].cmp(&block) {
+                Ordering::Equal => {
+                    return Ok(&mut self.buf.as_mut()[pos..pos + 34])
+                }
+                Ordering::Greater => {
+                    // We need the length from before we add the new block
+                    let len = self.buf.as_ref().len();
+
+                    // Allocate space for the new block
+                    self.buf.append_slice(&[0; 34])?;
+
+                    // Move everything after this block back by 34 bytes
+                    let buf = self.buf.as_mut();
+                    buf.copy_within(pos..len, pos + 34);
+                    buf[pos..pos + 34].fill(0);
+                    buf[pos] = block;
+
+                    return Ok(&mut buf[pos..pos + 34]);
+                }
+                Ordering::Less => pos += 34,
+            }
+        }
+
+        self.buf.append_slice(&[0; 34])?;
+        self.buf.as_mut()[pos] = block;
+        Ok(&mut self.buf.as_mut()[pos..pos + 34])
+    }
+
+    /// Converts the builder into the final bitmap in wire format.
+    pub fn finalize(mut self) -> RtypeBitmap<Builder::Octets>
+    where
+        Builder: FreezeBuilder + Truncate,
+    {
+        let mut dst_pos = 0;
+        let buf_len = self.buf.as_ref().len();
+        for src_pos in (0..buf_len).step_by(34) {
+            let chunk_len = (self.buf.as_ref()[src_pos + 1] as usize) + 2;
+            let buf = self.buf.as_mut();
+            buf.copy_within(src_pos..src_pos + chunk_len, dst_pos);
+            dst_pos += chunk_len;
+        }
+        self.buf.truncate(dst_pos);
+        RtypeBitmap(self.buf.freeze())
+    }
+}
+
+//--- Default
+
+impl<Builder> Default for RtypeBitmapBuilder<Builder>
+where
+    Builder: OctetsBuilder + EmptyBuilder,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//------------ RtypeBitmapIter -----------------------------------------------
+
+/// An iterator over the record types in a [`RtypeBitmap`].
+pub struct RtypeBitmapIter<'a> {
+    /// The data to iterate over.
+    ///
+    /// This starts with the octets of the current block without the block
+    /// number and length.
+    data: &'a [u8],
+
+    /// The base value of the current block, i.e., its upper 8 bits.
+    block: u16,
+
+    /// The length of the current block’s data.
+    len: usize,
+
+    /// Index of the current octet in the current block.
+    octet: usize,
+
+    /// Index of the next set bit in the current octet in the current block.
+    bit: u16,
+}
+
+impl<'a> RtypeBitmapIter<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        if data.is_empty() {
+            RtypeBitmapIter {
+                data,
+                block: 0,
+                len: 0,
+                octet: 0,
+                bit: 0,
+            }
+        } else {
+            let mut res = RtypeBitmapIter {
+                data: &data[2..],
+                block: u16::from(data[0]) << 8,
+                len: usize::from(data[1]),
+                octet: 0,
+                bit: 0,
+            };
+            if res.data[0] & 0x80 == 0 {
+                res.advance()
+            }
+            res
+        }
+    }
+
+    fn advance(&mut self) {
+        loop {
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.octet += 1;
+                if self.octet == self.len {
+                    self.data = &self.data[self.len..];
+                    if self.data.is_empty() {
+                        return;
+                    }
+                    self.block = u16::from(self.data[0]) << 8;
+                    self.len = usize::from(self.data[1]);
+                    self.data = &self.data[2..];
+                    self.octet = 0;
+                }
+            }
+            if self.data[self.octet] & (0x80 >> self.bit) != 0 {
+                return;
+            }
+        }
+    }
+}
+
+impl Iterator for RtypeBitmapIter<'_> {
+    type Item = Rtype;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let res = Rtype::from_int(
+            self.block | ((self.octet as u16) << 3) | self.bit,
+        );
+        self.advance();
+        Some(res)
+    }
+}
+
+//============ Error Types ===================================================
+
+//------------ RtypeBitmapError ----------------------------------------------
+
+/// The octets are not a valid record type bitmap.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RtypeBitmapError(RtypeBitmapErrorEnum);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum RtypeBitmapErrorEnum {
+    ShortInput,
+    BadRtypeBitmap,
+}
+
+//--- From
+
+impl From<RtypeBitmapError> for ParseError {
+    fn from(err: RtypeBitmapError) -> ParseError {
+        match err.0 {
+            RtypeBitmapErrorEnum::ShortInput => ParseError::ShortInput,
+            RtypeBitmapErrorEnum::BadRtypeBitmap => {
+                FormError::new("invalid NSEC bitmap").into()
+            }
+        }
+    }
+}
+
+impl From<RtypeBitmapErrorEnum> for RtypeBitmapError {
+    fn from(err: RtypeBitmapErrorEnum) -> Self {
+        Self(err)
+    }
+}
+
+//--- Display and Error
+
+impl fmt::Display for RtypeBitmapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            RtypeBitmapErrorEnum::ShortInput => ParseError::ShortInput.fmt(f),
+            RtypeBitmapErrorEnum::BadRtypeBitmap => {
+                f.write_str("invalid record type bitmap")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RtypeBitmapError {}
+
+//============ Friendly Helper Functions =====================================
+
+/// Splits an Rtype value into window number, octet number, and octet mask.
+fn split_rtype(rtype: Rtype) -> (u8, usize, u8) {
+    let rtype = rtype.to_int();
+    (
+        (rtype >> 8) as u8,
+        ((rtype & 0xFF) >> 3) as usize,
+        0b1000_0000 >> (rtype & 0x07),
+    )
+}
+
+/// Splits the next bitmap window from the bitmap and returns None when there's no next window.
+#[allow(clippy::type_complexity)]
+fn read_window(data: &[u8]) -> Option<((u8, &[u8]), &[u8])> {
+    data.split_first().and_then(|(n, data)| {
+        data.split_first().and_then(|(l, data)| {
+            if data.len() >= usize::from(*l) {
+                let (window, data) = data.split_at(usize::from(*l));
+                Some(((*n, window), data))
+            } else {
+                None
+            }
+        })
+    })
+}
+
+//============ Test ==========================================================
+
+#[cfg(test)]
+#[cfg(all(feature = "std", feature = "bytes"))]
+mod test {
+    use super::*;
+    #[test]
+    fn rtype_split() {
+        assert_eq!(split_rtype(Rtype::A), (0, 0, 0b01000000));
+        assert_eq!(split_rtype(Rtype::NS), (0, 0, 0b00100000));
+        assert_eq!(split_rtype(Rtype::CAA), (1, 0, 0b01000000));
+    }
+
+    #[test]
+    fn rtype_bitmap_read_window() {
+        let mut builder = RtypeBitmapBuilder::new_vec();
+        builder.add(Rtype::A).unwrap();
+        builder.add(Rtype::CAA).unwrap();
+        let bitmap = builder.finalize();
+
+        let ((n, window), data) = read_window(bitmap.as_slice()).unwrap();
+        assert_eq!((n, window), (0u8, b"\x40".as_ref()));
+        let ((n, window), data) = read_window(data).unwrap();
+        assert_eq!((n, window), (1u8, b"\x40".as_ref()));
+        assert!(data.is_empty());
+        assert!(read_window(data).is_none());
+    }
+
+    #[test]
+    fn rtype_bitmap_builder() {
+        let mut builder = RtypeBitmapBuilder::new_vec();
+        builder.add(Rtype::from_int(1234)).unwrap(); // 0x04D2
+        builder.add(Rtype::A).unwrap(); // 0x0001
+        builder.add(Rtype::MX).unwrap(); // 0x000F
+        builder.add(Rtype::RRSIG).unwrap(); // 0x002E
+        builder.add(Rtype::NSEC).unwrap(); // 0x002F
+        let bitmap = builder.finalize();
+        assert_eq!(
+            bitmap.as_slice(),
+            &b"\x00\x06\x40\x01\x00\x00\x00\x03\
+                     \x04\x1b\x00\x00\x00\x00\x00\x00\
+                     \x00\x00\x00\x00\x00\x00\x00\x00\
+                     \x00\x00\x00\x00\x00\x00\x00\x00\
+                     \x00\x00\x00\x00\x20"[..]
+        );
+
+        assert!(bitmap.contains(Rtype::A));
+        assert!(bitmap.contains(Rtype::MX));
+        assert!(bitmap.contains(Rtype::RRSIG));
+        assert!(bitmap.contains(Rtype::NSEC));
+        assert!(bitmap.contains(Rtype::from(1234)));
+        assert!(!bitmap.contains(Rtype::from(1235)));
+        assert!(!bitmap.contains(Rtype::NS));
+    }
+
+    #[test]
+    fn rtype_bitmap_iter() {
+        use std::vec::Vec;
+
+        let mut builder = RtypeBitmapBuilder::new_vec();
+        let types = vec![
+            Rtype::NS,
+            Rtype::SOA,
+            Rtype::MX,
+            Rtype::TXT,
+            Rtype::RRSIG,
+            Rtype::DNSKEY,
+            Rtype::NSEC3PARAM,
+            Rtype::SPF,
+            Rtype::CAA,
+        ];
+        for t in types.iter() {
+            builder.add(*t).unwrap();
+        }
+
+        let bitmap = builder.finalize();
+        let bitmap_types: Vec<_> = bitmap.iter().collect();
+        assert_eq!(types, bitmap_types);
+    }
+
+    #[test]
+    fn rtype_bitmap_multiple_windows() {
+        // Types spread across windows 0, 1, 0x80 and 0xFF, added out of
+        // order.
+        let types = [
+            Rtype::from_int(0xFF00),
+            Rtype::CAA,
+            Rtype::A,
+            Rtype::from_int(0x8001),
+            Rtype::NSEC,
+            Rtype::from_int(0x01FF),
+        ];
+
+        let mut builder = RtypeBitmapBuilder::new_vec();
+        for rtype in types {
+            builder.add(rtype).unwrap();
+        }
+        // Adding a type twice must not change the result.
+        builder.add(Rtype::A).unwrap();
+        let bitmap = builder.finalize();
+
+        // Each window only contains the octets up to its last set bit.
+        assert_eq!(
+            bitmap.as_slice(),
+            &b"\x00\x06\x40\x00\x00\x00\x00\x01\
+               \x01\x20\x40\x00\x00\x00\x00\x00\x00\x00\
+               \x00\x00\x00\x00\x00\x00\x00\x00\
+               \x00\x00\x00\x00\x00\x00\x00\x00\
+               \x00\x00\x00\x00\x00\x00\x00\x01\
+               \x80\x01\x40\
+               \xFF\x01\x80"[..]
+        );
+
+        // The wire format can be read back.
+        let bitmap = RtypeBitmap::from_octets(bitmap.as_slice()).unwrap();
+        for rtype in types {
+            assert!(bitmap.contains(rtype));
+        }
+        assert!(!bitmap.contains(Rtype::NS));
+        assert!(!bitmap.contains(Rtype::from_int(0x8000)));
+        assert!(!bitmap.contains(Rtype::from_int(0xFEFF)));
+
+        let mut expected = Vec::from(types);
+        expected.sort();
+        assert_eq!(bitmap.iter().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn rtype_bitmap_from_bad_octets() {
+        // Empty window.
+        assert!(RtypeBitmap::from_octets(b"\x00\x00".as_ref()).is_err());
+        // Window too long.
+        assert!(RtypeBitmap::from_octets([0u8, 33].as_ref()).is_err());
+        // Short window.
+        assert!(RtypeBitmap::from_octets(b"\x00\x02\x40".as_ref()).is_err());
+    }
+}
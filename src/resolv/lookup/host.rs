@@ -132,8 +132,24 @@ where
     }
 
     /// Returns an iterator over the IP addresses returned by the lookup.
+    ///
+    /// The iterator returns the IPv6 addresses first, followed by the IPv4
+    /// addresses.
     pub fn iter(&self) -> FoundHostsIter<'_> {
+        self.iter_with_preference(AddrPreference::default())
+    }
+
+    /// Returns an iterator over the IP addresses in the preferred order.
+    ///
+    /// The order in which the addresses of each family are returned is
+    /// determined by `preference`.
+    pub fn iter_with_preference(
+        &self,
+        preference: AddrPreference,
+    ) -> FoundHostsIter<'_> {
         FoundHostsIter {
+            preference,
+            v6_next: preference != AddrPreference::Ipv4First,
             aaaa_name: self
                 .aaaa
                 .as_ref()
@@ -167,28 +183,64 @@ where
     /// `port`. The returned iterator implements `ToSocketAddrs` and thus
     /// can be used where `std::net` wants addresses right away.
     pub fn port_iter(&self, port: u16) -> FoundHostsSocketIter<'_> {
+        self.port_iter_with_preference(port, AddrPreference::default())
+    }
+
+    /// Returns an iterator over socket addresses in the preferred order.
+    ///
+    /// This is like [`port_iter`][Self::port_iter] but orders the
+    /// addresses according to `preference`.
+    pub fn port_iter_with_preference(
+        &self,
+        port: u16,
+        preference: AddrPreference,
+    ) -> FoundHostsSocketIter<'_> {
         FoundHostsSocketIter {
-            iter: self.iter(),
+            iter: self.iter_with_preference(preference),
             port,
         }
     }
 }
 
+//------------ AddrPreference ------------------------------------------------
+
+/// The order in which the addresses of a host are returned.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AddrPreference {
+    /// All IPv6 addresses are returned before the IPv4 addresses.
+    ///
+    /// This is the default.
+    #[default]
+    Ipv6First,
+
+    /// All IPv4 addresses are returned before the IPv6 addresses.
+    Ipv4First,
+
+    /// IPv6 and IPv4 addresses are returned alternately, starting with IPv6.
+    ///
+    /// This spreads connection attempts across both address families as
+    /// recommended by [RFC 8305].
+    ///
+    /// [RFC 8305]: https://tools.ietf.org/html/rfc8305
+    Interleaved,
+}
+
 //------------ FoundHostsIter ------------------------------------------------
 
 /// An iterator over the IP addresses returned by a host lookup.
 #[derive(Clone)]
 pub struct FoundHostsIter<'a> {
+    preference: AddrPreference,
+    v6_next: bool,
     aaaa_name: Option<ParsedName<&'a [u8]>>,
     a_name: Option<ParsedName<&'a [u8]>>,
     aaaa: Option<RecordIter<'a, [u8], Aaaa>>,
     a: Option<RecordIter<'a, [u8], A>>,
 }
 
-impl Iterator for FoundHostsIter<'_> {
-    type Item = IpAddr;
-
-    fn next(&mut self) -> Option<IpAddr> {
+impl FoundHostsIter<'_> {
+    /// Returns the next IPv6 address.
+    fn next_v6(&mut self) -> Option<IpAddr> {
         while let Some(res) = self.aaaa.as_mut().and_then(Iterator::next) {
             if let Ok(record) = res {
                 if Some(record.owner()) == self.aaaa_name.as_ref() {
@@ -196,6 +248,11 @@ impl Iterator for FoundHostsIter<'_> {
                 }
             }
         }
+        None
+    }
+
+    /// Returns the next IPv4 address.
+    fn next_v4(&mut self) -> Option<IpAddr> {
         while let Some(res) = self.a.as_mut().and_then(Iterator::next) {
             if let Ok(record) = res {
                 if Some(record.owner()) == self.a_name.as_ref() {
@@ -207,6 +264,22 @@ impl Iterator for FoundHostsIter<'_> {
     }
 }
 
+impl Iterator for FoundHostsIter<'_> {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<IpAddr> {
+        let v6_next = self.v6_next;
+        if self.preference == AddrPreference::Interleaved {
+            self.v6_next = !v6_next;
+        }
+        if v6_next {
+            self.next_v6().or_else(|| self.next_v4())
+        } else {
+            self.next_v4().or_else(|| self.next_v6())
+        }
+    }
+}
+
 //------------ FoundHostsSocketIter ------------------------------------------
 
 /// An iterator over socket addresses derived from a host lookup.
@@ -233,3 +306,104 @@ impl ToSocketAddrs for FoundHostsSocketIter<'_> {
         Ok(self.clone())
     }
 }
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{Message, MessageBuilder, Name, Question};
+    use core::future::{ready, Ready};
+    use core::str::FromStr;
+    use std::vec::Vec;
+
+    /// A resolver with two IPv6 and two IPv4 addresses for every name.
+    struct DualStack;
+
+    struct Answer(Message<Vec<u8>>);
+
+    impl AsRef<Message<Vec<u8>>> for Answer {
+        fn as_ref(&self) -> &Message<Vec<u8>> {
+            &self.0
+        }
+    }
+
+    impl Resolver for DualStack {
+        type Octets = Vec<u8>;
+        type Answer = Answer;
+        type Query = Ready<Result<Answer, io::Error>>;
+
+        fn query<N, Q>(&self, question: Q) -> Self::Query
+        where
+            N: ToName,
+            Q: Into<Question<N>>,
+        {
+            let question = question.into();
+            let mut msg = MessageBuilder::new_vec().question();
+            msg.push(&question).unwrap();
+            let mut msg = msg.answer();
+            let qname = question.qname();
+            if question.qtype() == Rtype::AAAA {
+                msg.push((
+                    qname,
+                    3600,
+                    Aaaa::from_str("2001:db8::1").unwrap(),
+                ))
+                .unwrap();
+                msg.push((
+                    qname,
+                    3600,
+                    Aaaa::from_str("2001:db8::2").unwrap(),
+                ))
+                .unwrap();
+            } else {
+                msg.push((qname, 3600, A::from_str("192.0.2.1").unwrap()))
+                    .unwrap();
+                msg.push((qname, 3600, A::from_str("192.0.2.2").unwrap()))
+                    .unwrap();
+            }
+            ready(Ok(Answer(msg.into_message())))
+        }
+    }
+
+    fn addrs(
+        hosts: &FoundHosts<DualStack>,
+        pref: AddrPreference,
+    ) -> Vec<IpAddr> {
+        hosts.iter_with_preference(pref).collect()
+    }
+
+    #[tokio::test]
+    async fn address_preference() {
+        let name = Name::<Vec<u8>>::from_str("host.example.").unwrap();
+        let hosts = lookup_host(&DualStack, &name).await.unwrap();
+
+        let v6_1: IpAddr = "2001:db8::1".parse().unwrap();
+        let v6_2: IpAddr = "2001:db8::2".parse().unwrap();
+        let v4_1: IpAddr = "192.0.2.1".parse().unwrap();
+        let v4_2: IpAddr = "192.0.2.2".parse().unwrap();
+
+        assert_eq!(
+            hosts.iter().collect::<Vec<_>>(),
+            [v6_1, v6_2, v4_1, v4_2]
+        );
+        assert_eq!(
+            addrs(&hosts, AddrPreference::Ipv6First),
+            [v6_1, v6_2, v4_1, v4_2]
+        );
+        assert_eq!(
+            addrs(&hosts, AddrPreference::Ipv4First),
+            [v4_1, v4_2, v6_1, v6_2]
+        );
+        assert_eq!(
+            addrs(&hosts, AddrPreference::Interleaved),
+            [v6_1, v4_1, v6_2, v4_2]
+        );
+        assert_eq!(
+            hosts
+                .port_iter_with_preference(53, AddrPreference::Ipv4First)
+                .next(),
+            Some(SocketAddr::new(v4_1, 53))
+        );
+    }
+}
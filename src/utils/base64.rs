@@ -12,7 +12,7 @@
 //!
 //! [RFC 4648]: https://tools.ietf.org/html/rfc4648
 
-use crate::base::scan::{ConvertSymbols, EntrySymbol, ScannerError};
+use crate::base::scan::{ConvertSymbols, EntrySymbol, Scanner, ScannerError};
 use core::fmt;
 use octseq::builder::{
     EmptyBuilder, FreezeBuilder, FromBuilder, OctetsBuilder, ShortBuf,
@@ -194,6 +194,16 @@ pub mod serde {
     }
 }
 
+/// Scans *base64* encoded data from the remainder of an entry.
+///
+/// This is the format used by record types such as DNSKEY or CERT for their
+/// final field. The data can be spread over any number of words which are
+/// concatenated before decoding. Padding at the end of the data is
+/// optional.
+pub fn scan<S: Scanner>(scanner: &mut S) -> Result<S::Octets, S::Error> {
+    scanner.convert_entry(SymbolConverter::new())
+}
+
 //------------ Decoder -------------------------------------------------------
 
 /// A base 64 decoder.
@@ -418,11 +428,23 @@ where
     }
 
     fn process_tail(&mut self) -> Result<Option<&[u8]>, Error> {
-        // next is either 0 or 0xF0 for a completed group.
-        if self.next & 0x0F != 0 {
-            Err(Error::custom("incomplete Base 64 data"))
-        } else {
-            Ok(None)
+        // next is either 0 or 0xF0 for a completed group. Since padding is
+        // optional, a final group of two or three characters without any
+        // padding is fine, too.
+        match self.next {
+            0 | EOF_MARKER => Ok(None),
+            2 => {
+                self.output[0] = (self.input[0] << 2) | (self.input[1] >> 4);
+                self.next = EOF_MARKER;
+                Ok(Some(&self.output[..1]))
+            }
+            3 if self.input[2] != PAD_MARKER => {
+                self.output[0] = (self.input[0] << 2) | (self.input[1] >> 4);
+                self.output[1] = (self.input[1] << 4) | (self.input[2] >> 2);
+                self.next = EOF_MARKER;
+                Ok(Some(&self.output[..2]))
+            }
+            _ => Err(Error::custom("incomplete Base 64 data")),
         }
     }
 }
@@ -585,6 +607,34 @@ mod test {
         }
     }
 
+    #[test]
+    fn scan_entry() {
+        use crate::base::scan::IterScanner;
+        use std::vec::Vec;
+
+        fn scan(
+            words: &[&str],
+        ) -> Result<Vec<u8>, crate::base::scan::StrError> {
+            super::scan(&mut IterScanner::<_, Vec<u8>>::new(words.iter()))
+        }
+
+        // Words are concatenated, even if split inside a group.
+        assert_eq!(scan(&["Zm9v", "YmFy"]).unwrap(), b"foobar");
+        assert_eq!(scan(&["Zm", "9vY", "mE="]).unwrap(), b"fooba");
+
+        // Padding is optional.
+        assert_eq!(scan(&["Zm9vYg=="]).unwrap(), b"foob");
+        assert_eq!(scan(&["Zm9vYg"]).unwrap(), b"foob");
+        assert_eq!(scan(&["Zm9v", "YmE"]).unwrap(), b"fooba");
+
+        // Invalid characters, incomplete data, and data after padding.
+        assert!(scan(&["Zm9v", "Ym$y"]).is_err());
+        assert!(scan(&["Zm9v-mFy"]).is_err());
+        assert!(scan(&["Zm9vY"]).is_err());
+        assert!(scan(&["Zm9vYm="]).is_err());
+        assert!(scan(&["Zg==", "Zm9v"]).is_err());
+    }
+
     #[test]
     fn display_bytes() {
         use super::*;
@@ -39,12 +39,18 @@ pub struct Answer {
     /// The content of the answer.
     content: AnswerContent,
 
+    /// The RRSIG records covering the content of the answer, if any.
+    signatures: Option<SharedRrset>,
+
     /// The optional additional section to be included in the answer.
     additional: Option<AnswerAdditional>,
 
     /// The optional authority section to be included in the answer.
     authority: Option<AnswerAuthority>,
 
+    /// The DNSSEC chain records requested via the CHAIN option.
+    chain: Vec<(StoredName, SharedRrset)>,
+
     /// Should the answer be flagged as authoritative?
     authoritative: bool,
 }
@@ -58,7 +64,9 @@ impl Answer {
         Answer {
             rcode,
             content: AnswerContent::NoData,
+            signatures: None,
             authority: Default::default(),
+            chain: Vec::new(),
             additional: Default::default(),
             authoritative: false,
         }
@@ -74,7 +82,9 @@ impl Answer {
         Answer {
             rcode,
             content: AnswerContent::NoData,
+            signatures: None,
             authority: Some(authority),
+            chain: Vec::new(),
             additional: Default::default(),
             authoritative: false,
         }
@@ -97,6 +107,14 @@ impl Answer {
         self.content = AnswerContent::Data(answer);
     }
 
+    /// Adds the RRSIG records covering the answer to the answer section.
+    ///
+    /// The records are placed after the content of the answer section and
+    /// use the QNAME as their owner.
+    pub fn add_signatures(&mut self, signatures: SharedRrset) {
+        self.signatures = Some(signatures);
+    }
+
     /// Sets the content of the additional section.
     pub fn set_additional(&mut self, additional: AnswerAdditional) {
         self.additional = Some(additional)
@@ -107,6 +125,19 @@ impl Answer {
         self.authority = Some(authority)
     }
 
+    /// Sets the DNSSEC chain records to include in the answer.
+    ///
+    /// This is used to answer queries with the CHAIN option defined in
+    /// [RFC 7901]. The records, as returned by [`ZoneTree::chain`], are
+    /// placed at the end of the authority section. Echoing the CHAIN
+    /// option in the OPT record of the response is left to the caller.
+    ///
+    /// [RFC 7901]: https://www.rfc-editor.org/rfc/rfc7901
+    /// [`ZoneTree::chain`]: crate::zonetree::ZoneTree::chain
+    pub fn set_chain(&mut self, chain: Vec<(StoredName, SharedRrset)>) {
+        self.chain = chain;
+    }
+
     /// Marks the response authoritative or not.
     ///
     /// Determines whether or not the response will have the AA (Authoritative
@@ -162,6 +193,14 @@ impl Answer {
             AnswerContent::NoData => {}
         }
 
+        if let Some(signatures) = self.signatures.as_ref() {
+            for item in signatures.data() {
+                builder
+                    .push((qname, qclass, signatures.ttl(), item))
+                    .unwrap();
+            }
+        }
+
         let mut builder = builder.authority();
         if let Some(authority) = self.authority.as_ref() {
             if let Some(soa) = authority.soa.as_ref() {
@@ -200,6 +239,14 @@ impl Answer {
             }
         }
 
+        for (owner, rrset) in &self.chain {
+            for item in rrset.data() {
+                builder
+                    .push((owner.clone(), qclass, rrset.ttl(), item))
+                    .unwrap()
+            }
+        }
+
         let mut builder = builder.additional();
 
         if let Some(additional) = self.additional.as_ref() {
@@ -227,10 +274,38 @@ impl Answer {
         &self.content
     }
 
+    /// Gets the RRSIG records covering the answer section content, if any.
+    pub fn signatures(&self) -> Option<&SharedRrset> {
+        self.signatures.as_ref()
+    }
+
     /// Gets the authority section content for this answer.
     pub fn authority(&self) -> Option<&AnswerAuthority> {
         self.authority.as_ref()
     }
+
+    /// Gets the DNSSEC chain records included in the answer.
+    pub fn chain(&self) -> &[(StoredName, SharedRrset)] {
+        &self.chain
+    }
+
+    /// Returns whether the answer is flagged as authoritative.
+    pub fn is_authoritative(&self) -> bool {
+        self.authoritative
+    }
+
+    /// Returns whether this answer is a referral to a child zone.
+    ///
+    /// A referral is a non-authoritative answer with an empty answer section
+    /// and the NS records of the delegation but no SOA record in the
+    /// authority section.
+    pub fn is_referral(&self) -> bool {
+        !self.authoritative
+            && matches!(self.content, AnswerContent::NoData)
+            && self.authority.as_ref().is_some_and(|authority| {
+                authority.soa.is_none() && authority.ns.is_some()
+            })
+    }
 }
 
 //------------ AnswerContent -------------------------------------------------
@@ -0,0 +1,368 @@
+//! Checking zones for common errors.
+//!
+//! The zone consistency checker walks a [`Zone`] and reports problems that
+//! are not necessarily rejected when the zone is built but that will cause
+//! resolution problems when the zone is served. See [`check_zone`] for the
+//! list of problems that are detected.
+use core::fmt;
+
+use std::boxed::Box;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use std::vec::Vec;
+
+use crate::base::iana::Rtype;
+use crate::rdata::ZoneRecordData;
+
+use super::types::StoredName;
+use super::Zone;
+
+//------------ Diagnostic ----------------------------------------------------
+
+/// A problem found in a zone by [`check_zone`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Diagnostic {
+    /// The apex of the zone has no SOA record.
+    MissingApexSoa,
+
+    /// The apex of the zone has no NS records.
+    MissingApexNs,
+
+    /// A CNAME record exists at an owner name together with other data.
+    ///
+    /// Only RRSIG and NSEC records are allowed next to a CNAME record, see
+    /// [RFC 2181, Section 10.1] and [RFC 4035, Section 2.5].
+    ///
+    /// [RFC 2181, Section 10.1]:
+    ///     https://datatracker.ietf.org/doc/html/rfc2181#section-10.1
+    /// [RFC 4035, Section 2.5]:
+    ///     https://datatracker.ietf.org/doc/html/rfc4035#section-2.5
+    CnameAndOtherData {
+        /// The owner name of the CNAME record.
+        owner: StoredName,
+
+        /// One of the other record types found at the owner name.
+        rtype: Rtype,
+    },
+
+    /// A name server at or below a zone cut has no glue address records.
+    MissingGlue {
+        /// The owner of the NS records referring to the name server.
+        owner: StoredName,
+
+        /// The name of the name server.
+        nameserver: StoredName,
+    },
+
+    /// A name server in the authoritative data of the zone has no address
+    /// records.
+    DanglingNs {
+        /// The owner of the NS records referring to the name server.
+        owner: StoredName,
+
+        /// The name of the name server.
+        nameserver: StoredName,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::MissingApexSoa => write!(f, "Missing SOA at apex"),
+            Diagnostic::MissingApexNs => write!(f, "Missing NS at apex"),
+            Diagnostic::CnameAndOtherData { owner, rtype } => {
+                write!(f, "CNAME and {rtype} at {owner}")
+            }
+            Diagnostic::MissingGlue { owner, nameserver } => {
+                write!(f, "Missing glue for {nameserver} (NS of {owner})")
+            }
+            Diagnostic::DanglingNs { owner, nameserver } => {
+                write!(
+                    f,
+                    "No address records for {nameserver} (NS of {owner})"
+                )
+            }
+        }
+    }
+}
+
+//------------ check_zone ----------------------------------------------------
+
+/// Checks a zone for common errors.
+///
+/// The following problems are reported:
+///
+/// - The apex lacks an SOA or NS RRset.
+/// - A CNAME record coexists with other data at the same owner name.
+/// - A name server of a delegation (or of the apex) is located at or below
+///   a zone cut in this zone but no glue address records are present.
+/// - A name server is located in the authoritative data of this zone but
+///   has no A or AAAA records.
+///
+/// Name servers outside of the zone are not checked. The result is empty if
+/// no problems were found.
+pub async fn check_zone(zone: &Zone) -> Vec<Diagnostic> {
+    let apex = zone.apex_name().clone();
+
+    // Collect the record types and NS targets of every owner name.
+    let content = Arc::new(Mutex::new(ZoneContent::default()));
+    let content_clone = content.clone();
+    zone.read()
+        .walk_async(Box::new(move |owner, rrset, _at_zone_cut| {
+            let mut content = content_clone.lock().unwrap();
+            if rrset.rtype() == Rtype::NS {
+                for data in rrset.data() {
+                    if let ZoneRecordData::Ns(ns) = data {
+                        content
+                            .nameservers
+                            .push((owner.clone(), ns.nsdname().clone()));
+                    }
+                }
+            }
+            content
+                .rtypes
+                .entry(owner)
+                .or_default()
+                .insert(rrset.rtype());
+        }))
+        .await;
+    let content = content.lock().unwrap();
+
+    let mut diagnostics = Vec::new();
+
+    // The apex must have SOA and NS RRsets.
+    let apex_rtypes = content.rtypes.get(&apex);
+    if !apex_rtypes.is_some_and(|rtypes| rtypes.contains(&Rtype::SOA)) {
+        diagnostics.push(Diagnostic::MissingApexSoa);
+    }
+    if !apex_rtypes.is_some_and(|rtypes| rtypes.contains(&Rtype::NS)) {
+        diagnostics.push(Diagnostic::MissingApexNs);
+    }
+
+    // CNAMEs can only be accompanied by DNSSEC records.
+    for (owner, rtypes) in content.rtypes.iter() {
+        if !rtypes.contains(&Rtype::CNAME) {
+            continue;
+        }
+        if let Some(rtype) = rtypes.iter().find(|rtype| {
+            !matches!(**rtype, Rtype::CNAME | Rtype::RRSIG | Rtype::NSEC)
+        }) {
+            diagnostics.push(Diagnostic::CnameAndOtherData {
+                owner: owner.clone(),
+                rtype: *rtype,
+            });
+        }
+    }
+
+    // Zone cuts are NS RRsets anywhere but at the apex.
+    let cuts: Vec<_> = content
+        .nameservers
+        .iter()
+        .map(|(owner, _)| owner)
+        .filter(|owner| **owner != apex)
+        .collect();
+
+    for (owner, nameserver) in content.nameservers.iter() {
+        if !nameserver.ends_with(&apex) {
+            // Out of zone, we cannot check these.
+            continue;
+        }
+        let has_address =
+            content.rtypes.get(nameserver).is_some_and(|rtypes| {
+                rtypes.contains(&Rtype::A) || rtypes.contains(&Rtype::AAAA)
+            });
+        if has_address {
+            continue;
+        }
+        let below_cut = cuts.iter().any(|cut| nameserver.ends_with(*cut));
+        let diagnostic = if below_cut {
+            Diagnostic::MissingGlue {
+                owner: owner.clone(),
+                nameserver: nameserver.clone(),
+            }
+        } else {
+            Diagnostic::DanglingNs {
+                owner: owner.clone(),
+                nameserver: nameserver.clone(),
+            }
+        };
+        if !diagnostics.contains(&diagnostic) {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    diagnostics
+}
+
+//------------ ZoneContent ---------------------------------------------------
+
+/// The parts of a zone needed for checking it.
+#[derive(Default)]
+struct ZoneContent {
+    /// The record types present at each owner name.
+    rtypes: BTreeMap<StoredName, BTreeSet<Rtype>>,
+
+    /// The owner and target of every NS record.
+    nameservers: Vec<(StoredName, StoredName)>,
+}
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use std::vec;
+
+    use bytes::Bytes;
+
+    use crate::base::iana::Class;
+    use crate::base::rdata::RecordData;
+    use crate::base::{Name, Record, Ttl};
+    use crate::rdata::{Cname, Ns, Soa, ZoneRecordData, A};
+    use crate::zonetree::test_util::n;
+    use crate::zonetree::{Rrset, SharedRr, SharedRrset, ZoneBuilder};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn healthy_zone_has_no_diagnostics() {
+        let mut builder = ZoneBuilder::new(n("example.com."), Class::IN);
+        insert_apex(&mut builder, "ns.example.com.");
+        insert(&mut builder, "ns.example.com.", a("192.0.2.1"));
+        insert_cut(
+            &mut builder,
+            "sub.example.com.",
+            "ns.sub.example.com.",
+            true,
+        );
+        insert_cut(
+            &mut builder,
+            "other.example.com.",
+            "ns.example.net.",
+            false,
+        );
+
+        assert_eq!(check_zone(&builder.build()).await, vec![]);
+    }
+
+    #[tokio::test]
+    async fn broken_zone() {
+        let mut builder = ZoneBuilder::new(n("example.com."), Class::IN);
+
+        // An apex with NS but without SOA. The name server has no address.
+        let mut ns = Rrset::new(Rtype::NS, Ttl::HOUR);
+        ns.push_data(ZoneRecordData::Ns(Ns::new(n("ns.example.com."))));
+        builder
+            .insert_rrset(&n("example.com."), ns.into_shared())
+            .unwrap();
+
+        // A CNAME with other data.
+        builder
+            .insert_cname(
+                &n("www.example.com."),
+                SharedRr::new(
+                    Ttl::HOUR,
+                    ZoneRecordData::Cname(Cname::new(n("example.com."))),
+                ),
+            )
+            .unwrap();
+        insert(&mut builder, "www.example.com.", a("192.0.2.2"));
+
+        // A delegation to an in-bailiwick name server without glue.
+        insert_cut(
+            &mut builder,
+            "sub.example.com.",
+            "ns.sub.example.com.",
+            false,
+        );
+
+        let diagnostics = check_zone(&builder.build()).await;
+        assert_eq!(diagnostics.len(), 4);
+        assert!(diagnostics.contains(&Diagnostic::MissingApexSoa));
+        assert!(!diagnostics.contains(&Diagnostic::MissingApexNs));
+        assert!(diagnostics.contains(&Diagnostic::CnameAndOtherData {
+            owner: n("www.example.com."),
+            rtype: Rtype::A,
+        }));
+        assert!(diagnostics.contains(&Diagnostic::MissingGlue {
+            owner: n("sub.example.com."),
+            nameserver: n("ns.sub.example.com."),
+        }));
+        assert!(diagnostics.contains(&Diagnostic::DanglingNs {
+            owner: n("example.com."),
+            nameserver: n("ns.example.com."),
+        }));
+    }
+
+    #[tokio::test]
+    async fn empty_zone() {
+        let builder = ZoneBuilder::new(n("example.com."), Class::IN);
+        assert_eq!(
+            check_zone(&builder.build()).await,
+            vec![Diagnostic::MissingApexSoa, Diagnostic::MissingApexNs]
+        );
+    }
+
+    //--- Helpers
+
+    fn a(addr: &str) -> ZoneRecordData<Bytes, Name<Bytes>> {
+        ZoneRecordData::A(A::from_str(addr).unwrap())
+    }
+
+    fn insert(
+        builder: &mut ZoneBuilder,
+        owner: &str,
+        data: ZoneRecordData<Bytes, Name<Bytes>>,
+    ) {
+        let mut rrset = Rrset::new(data.rtype(), Ttl::HOUR);
+        rrset.push_data(data);
+        builder
+            .insert_rrset(&n(owner), rrset.into_shared())
+            .unwrap();
+    }
+
+    fn insert_apex(builder: &mut ZoneBuilder, nameserver: &str) {
+        insert(
+            builder,
+            "example.com.",
+            ZoneRecordData::Soa(Soa::new(
+                n(nameserver),
+                n("hostmaster.example.com."),
+                1.into(),
+                Ttl::HOUR,
+                Ttl::HOUR,
+                Ttl::HOUR,
+                Ttl::HOUR,
+            )),
+        );
+        insert(
+            builder,
+            "example.com.",
+            ZoneRecordData::Ns(Ns::new(n(nameserver))),
+        );
+    }
+
+    fn insert_cut(
+        builder: &mut ZoneBuilder,
+        owner: &str,
+        nameserver: &str,
+        with_glue: bool,
+    ) {
+        let mut ns = Rrset::new(Rtype::NS, Ttl::HOUR);
+        ns.push_data(ZoneRecordData::Ns(Ns::new(n(nameserver))));
+        let glue = if with_glue {
+            vec![Record::new(
+                n(nameserver),
+                Class::IN,
+                Ttl::HOUR,
+                a("192.0.2.53"),
+            )]
+        } else {
+            vec![]
+        };
+        builder
+            .insert_zone_cut(&n(owner), SharedRrset::new(ns), None, glue)
+            .unwrap();
+    }
+}
@@ -71,6 +71,33 @@ impl ZoneBuilder {
         }
     }
 
+    /// Sets whether referrals include glue records.
+    ///
+    /// When a query hits a zone cut, the response refers the client to the
+    /// name servers of the child zone. If enabled, which is the default,
+    /// the additional section of such a referral contains the glue address
+    /// records of those name servers that are within this zone (i.e. that
+    /// are in-bailiwick). Glue for any other name servers is never
+    /// included.
+    pub fn set_referral_glue(&mut self, enabled: bool) {
+        self.apex.set_referral_glue(enabled);
+    }
+
+    /// Sets whether the SOA in negative responses has a limited TTL.
+    ///
+    /// NXDOMAIN and NODATA responses include the SOA record of the zone in
+    /// the authority section. Its TTL determines how long resolvers cache
+    /// the negative response. If enabled, which is the default, the TTL is
+    /// the minimum of the TTL of the SOA record and its MINIMUM field as
+    /// required by [RFC 2308, Section 3]. Otherwise the TTL of the SOA
+    /// record is used as is.
+    ///
+    /// [RFC 2308, Section 3]:
+    ///     https://datatracker.ietf.org/doc/html/rfc2308#section-3
+    pub fn set_soa_minimum_ttl(&mut self, enabled: bool) {
+        self.apex.set_soa_minimum_ttl(enabled);
+    }
+
     /// Builds an in-memory [`Zone`] from this builder.
     ///
     /// Calling this function consumes the [`ZoneBuilder`]. The returned
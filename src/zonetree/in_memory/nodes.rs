@@ -7,20 +7,26 @@ use std::collections::{hash_map, HashMap};
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::vec::Vec;
 
+use futures_util::stream::poll_fn;
 use parking_lot::{
     RwLock, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard,
 };
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio::sync::Mutex;
 
 use crate::base::iana::{Class, Rtype};
 use crate::base::name::{Label, OwnedLabel, ToName};
+use crate::base::Serial;
+use crate::rdata::ZoneRecordData;
 use crate::zonetree::error::{CnameError, OutOfZone, ZoneCutError};
-use crate::zonetree::types::{StoredName, ZoneCut};
+use crate::zonetree::types::{InMemoryZoneDiff, StoredName, ZoneCut};
 use crate::zonetree::util::rel_name_rev_iter;
 use crate::zonetree::walk::WalkState;
 use crate::zonetree::{
-    ReadableZone, SharedRr, SharedRrset, WritableZone, ZoneStore,
+    ReadableZone, SharedRr, SharedRrset, WritableZone, ZoneDiffStream,
+    ZoneStore,
 };
 
 use super::read::ReadZone;
@@ -37,6 +43,17 @@ pub struct ZoneApex {
     children: NodeChildren,
     update_lock: Arc<Mutex<()>>,
     versions: Arc<RwLock<ZoneVersions>>,
+
+    /// Whether to include glue records in referrals.
+    referral_glue: bool,
+
+    /// Whether to limit the TTL of the SOA in negative responses to the
+    /// SOA MINIMUM field.
+    soa_minimum_ttl: bool,
+
+    /// The senders of the streams returned by [`ZoneStore::subscribe_diffs`].
+    diff_observers:
+        parking_lot::Mutex<Vec<UnboundedSender<InMemoryZoneDiff>>>,
 }
 
 impl ZoneApex {
@@ -49,6 +66,9 @@ impl ZoneApex {
             children: Default::default(),
             update_lock: Default::default(),
             versions: Default::default(),
+            referral_glue: true,
+            soa_minimum_ttl: true,
+            diff_observers: Default::default(),
         }
     }
 
@@ -67,6 +87,9 @@ impl ZoneApex {
             children,
             update_lock: Default::default(),
             versions: Arc::new(RwLock::new(versions)),
+            referral_glue: true,
+            soa_minimum_ttl: true,
+            diff_observers: Default::default(),
         }
     }
 
@@ -111,6 +134,42 @@ impl ZoneApex {
     pub fn name(&self) -> &StoredName {
         &self.apex_name
     }
+
+    /// Returns whether glue records are included in referrals.
+    pub fn referral_glue(&self) -> bool {
+        self.referral_glue
+    }
+
+    /// Sets whether glue records are included in referrals.
+    pub fn set_referral_glue(&mut self, enabled: bool) {
+        self.referral_glue = enabled;
+    }
+
+    /// Returns whether the SOA TTL in negative responses is limited to the
+    /// SOA MINIMUM field.
+    pub fn soa_minimum_ttl(&self) -> bool {
+        self.soa_minimum_ttl
+    }
+
+    /// Sets whether the SOA TTL in negative responses is limited to the SOA
+    /// MINIMUM field.
+    pub fn set_soa_minimum_ttl(&mut self, enabled: bool) {
+        self.soa_minimum_ttl = enabled;
+    }
+
+    /// Returns whether anyone is subscribed to the diffs of this zone.
+    pub fn has_diff_observers(&self) -> bool {
+        let mut observers = self.diff_observers.lock();
+        observers.retain(|tx| !tx.is_closed());
+        !observers.is_empty()
+    }
+
+    /// Sends a committed diff to everyone subscribed to this zone.
+    pub fn notify_diff_observers(&self, diff: &InMemoryZoneDiff) {
+        self.diff_observers
+            .lock()
+            .retain(|tx| tx.send(diff.clone()).is_ok());
+    }
 }
 
 //--- impl ZoneStore
@@ -151,6 +210,20 @@ impl ZoneStore for ZoneApex {
     fn as_any(&self) -> &dyn Any {
         self as &dyn Any
     }
+
+    fn subscribe_diffs(&self) -> Option<ZoneDiffStream> {
+        let (tx, mut rx) = unbounded_channel();
+        self.diff_observers.lock().push(tx);
+        Some(Box::pin(poll_fn(move |cx| rx.poll_recv(cx))))
+    }
+
+    fn current_serial(&self) -> Option<Serial> {
+        let version = self.versions().read().current().0;
+        match self.get_soa(version)?.data() {
+            ZoneRecordData::Soa(soa) => Some(soa.serial()),
+            _ => None,
+        }
+    }
 }
 
 //--- impl From<&'a ZoneApex>
@@ -385,6 +458,11 @@ impl NodeChildren {
         op(lock.get(label).unwrap(), true)
     }
 
+    /// Returns whether the closure returns true for any of the children.
+    pub fn any(&self, op: impl FnMut(&Arc<ZoneNode>) -> bool) -> bool {
+        self.children.read().values().any(op)
+    }
+
     fn rollback(&self, version: Version) {
         self.children
             .read()
@@ -2,12 +2,14 @@
 use core::iter;
 
 use std::sync::Arc;
+use std::vec::Vec;
 
 use bytes::Bytes;
 
 use crate::base::iana::{Rcode, Rtype};
 use crate::base::name::Label;
 use crate::base::Name;
+use crate::rdata::ZoneRecordData;
 use crate::zonetree::answer::{Answer, AnswerAdditional, AnswerAuthority};
 use crate::zonetree::error::OutOfZone;
 use crate::zonetree::types::ZoneCut;
@@ -99,15 +101,7 @@ impl ReadZone {
                     // There is nothing more in this zone, only a cut here.
                     // Respond with NODATA and an authority section referring the
                     // client to the nameserver that should know more.
-                    NodeAnswer::authority(
-                        AnswerAuthority::new(
-                            cut.name.clone(),
-                            None,
-                            Some(cut.ns.clone()),
-                            cut.ds.as_ref().cloned(),
-                        ),
-                        AnswerAdditional::new(cut.glue.clone()),
-                    )
+                    self.referral(cut)
                 }
             }
             Some(Special::NxDomain) => {
@@ -155,6 +149,12 @@ impl ReadZone {
     ) -> NodeAnswer {
         node.with_special(self.version, |special| match special {
             Some(Special::Cut(cut)) => self.query_at_cut(cut, qtype),
+            // The RRSIG and NSEC records of a CNAME are kept alongside it.
+            Some(Special::Cname(_))
+                if matches!(qtype, Rtype::RRSIG | Rtype::NSEC) =>
+            {
+                self.query_rrsets(node.rrsets(), qtype, walk)
+            }
             Some(Special::Cname(cname)) => NodeAnswer::cname(cname.clone()),
             Some(Special::NxDomain) => NodeAnswer::nx_domain(),
             None => self.query_rrsets(node.rrsets(), qtype, walk),
@@ -228,16 +228,100 @@ impl ReadZone {
                     NodeAnswer::no_data()
                 }
             }
-            _ => NodeAnswer::authority(
-                AnswerAuthority::new(
-                    cut.name.clone(),
-                    None,
-                    Some(cut.ns.clone()),
-                    cut.ds.as_ref().cloned(),
-                ),
-                AnswerAdditional::new(cut.glue.clone()),
+            _ => self.referral(cut),
+        }
+    }
+
+    fn referral(&self, cut: &ZoneCut) -> NodeAnswer {
+        // Only name servers within this zone get glue. Addresses of any
+        // other name servers are not ours to hand out.
+        let glue = if self.apex.referral_glue() {
+            cut.glue
+                .iter()
+                .filter(|rec| {
+                    rec.owner().ends_with(self.apex.name())
+                        && cut.ns.data().iter().any(|ns| match ns {
+                            ZoneRecordData::Ns(ns) => {
+                                ns.nsdname() == rec.owner()
+                            }
+                            _ => false,
+                        })
+                })
+                .cloned()
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        NodeAnswer::authority(
+            AnswerAuthority::new(
+                cut.name.clone(),
+                None,
+                Some(cut.ns.clone()),
+                cut.ds.as_ref().cloned(),
             ),
+            AnswerAdditional::new(glue),
+        )
+    }
+
+    fn rtypes_in_children<'l>(
+        &self,
+        children: &NodeChildren,
+        label: &Label,
+        mut qname: impl Iterator<Item = &'l Label> + Clone,
+    ) -> Option<Vec<Rtype>> {
+        children.with(label, |node| {
+            let node = node?;
+            match qname.next() {
+                Some(label) => {
+                    self.rtypes_in_children(node.children(), label, qname)
+                }
+                None => self.rtypes_at_node(node),
+            }
+        })
+    }
+
+    fn rtypes_at_node(&self, node: &ZoneNode) -> Option<Vec<Rtype>> {
+        let mut rtypes = self.rrset_types(node.rrsets());
+        node.with_special(self.version, |special| match special {
+            Some(Special::Cut(cut)) => {
+                rtypes.push(Rtype::NS);
+                if cut.ds.is_some() {
+                    rtypes.push(Rtype::DS);
+                }
+            }
+            Some(Special::Cname(_)) => rtypes.push(Rtype::CNAME),
+            Some(Special::NxDomain) | None => {}
+        });
+
+        // A node without any RRsets may still be an empty non-terminal.
+        if rtypes.is_empty()
+            && !node
+                .children()
+                .any(|child| self.has_data_at_or_below(child))
+        {
+            return None;
         }
+        Some(rtypes)
+    }
+
+    fn has_data_at_or_below(&self, node: &ZoneNode) -> bool {
+        !node.rrsets().is_empty(self.version)
+            || node.with_special(self.version, |special| {
+                matches!(special, Some(Special::Cut(_) | Special::Cname(_)))
+            })
+            || node
+                .children()
+                .any(|child| self.has_data_at_or_below(child))
+    }
+
+    fn rrset_types(&self, rrsets: &NodeRrsets) -> Vec<Rtype> {
+        let guard = rrsets.iter();
+        guard
+            .iter()
+            .filter(|(_rtype, rrset)| rrset.get(self.version).is_some())
+            .map(|(rtype, _rrset)| *rtype)
+            .collect()
     }
 
     fn query_children<'l>(
@@ -322,6 +406,24 @@ impl ReadableZone for ReadZone {
         self.query_rrsets(self.apex.rrsets(), Rtype::ANY, walk.clone());
         self.query_below_apex(Label::root(), iter::empty(), Rtype::ANY, walk);
     }
+
+    fn rtypes_at(
+        &self,
+        qname: Name<Bytes>,
+    ) -> Result<Option<Vec<Rtype>>, OutOfZone> {
+        let mut qname = self.apex.prepare_name(&qname)?;
+
+        let rtypes = if let Some(label) = qname.next() {
+            self.rtypes_in_children(self.apex.children(), label, qname)
+        } else {
+            Some(self.rrset_types(self.apex.rrsets()))
+        };
+
+        Ok(rtypes.map(|mut rtypes| {
+            rtypes.sort();
+            rtypes
+        }))
+    }
 }
 
 //------------ NodeAnswer ----------------------------------------------------
@@ -397,7 +499,19 @@ impl NodeAnswer {
 
     fn into_answer(mut self, zone: &ReadZone) -> Answer {
         if self.add_soa {
-            if let Some(soa) = zone.apex.get_soa(zone.version) {
+            if let Some(mut soa) = zone.apex.get_soa(zone.version) {
+                if zone.apex.soa_minimum_ttl() {
+                    // RFC 2308, section 3: the TTL of the SOA in a negative
+                    // response is the minimum of its TTL and MINIMUM field.
+                    if let ZoneRecordData::Soa(data) = soa.data() {
+                        if data.minimum() < soa.ttl() {
+                            soa = SharedRr::new(
+                                data.minimum(),
+                                soa.data().clone(),
+                            );
+                        }
+                    }
+                }
                 self.answer.set_authority(AnswerAuthority::new(
                     zone.apex.name().clone(),
                     Some(soa),
@@ -415,13 +529,16 @@ impl NodeAnswer {
 mod tests {
     use super::*;
     use crate::base::iana::Class;
+    use crate::base::message::RecordSection;
     use crate::base::name::OwnedLabel;
-    use crate::base::Ttl;
-    use crate::rdata::{ZoneRecordData, A};
-    use crate::zonetree::StoredName;
+    use crate::base::{Message, MessageBuilder, Record, ToName, Ttl};
+    use crate::rdata::{Ns, ZoneRecordData, A};
+    use crate::zonetree::test_util::{load_zone, load_zone_builder, n};
+    use crate::zonetree::{StoredName, Zone, ZoneBuilder};
     use core::str::FromStr;
     use core::sync::atomic::{AtomicU8, Ordering};
     use std::boxed::Box;
+    use std::vec;
 
     #[test]
     fn should_walk_below_ents() {
@@ -487,4 +604,259 @@ mod tests {
         // I.e. a.b.c. isn't missed because it is below the ENT b.c.
         assert_eq!(count.load(Ordering::SeqCst), 2);
     }
+
+    #[test]
+    fn rtypes_at() {
+        let zone_file = b"\
+example.com. 3600 IN SOA ns.example.com. hostmaster.example.com. 1 3600 300 604800 300
+example.com. 3600 IN NS ns.example.com.
+ns.example.com. 3600 IN A 192.0.2.1
+www.example.com. 3600 IN A 192.0.2.2
+www.example.com. 3600 IN MX 10 mail.example.com.
+www.example.com. 3600 IN RRSIG A 8 3 3600 20250101000000 20240101000000 12345 example.com. AAAA
+a.b.example.com. 3600 IN A 192.0.2.3
+";
+        let zone = load_zone(zone_file);
+        let read = zone.read();
+
+        // A name with A, MX and RRSIG records.
+        assert_eq!(
+            read.rtypes_at(n("www.example.com.")).unwrap(),
+            Some(vec![Rtype::A, Rtype::MX, Rtype::RRSIG])
+        );
+
+        // The apex.
+        assert_eq!(
+            read.rtypes_at(n("example.com.")).unwrap(),
+            Some(vec![Rtype::NS, Rtype::SOA])
+        );
+
+        // An empty non-terminal exists but has no record types.
+        assert_eq!(
+            read.rtypes_at(n("b.example.com.")).unwrap(),
+            Some(vec![])
+        );
+
+        // A non-existent name.
+        assert_eq!(read.rtypes_at(n("nx.example.com.")).unwrap(), None);
+        assert_eq!(read.rtypes_at(n("x.www.example.com.")).unwrap(), None);
+
+        // A name outside the zone.
+        assert!(read.rtypes_at(n("example.org.")).is_err());
+    }
+
+    #[test]
+    fn negative_soa_ttl_is_floored() {
+        // NODATA and NXDOMAIN.
+        assert_eq!(
+            negative_soa_ttl(true, "www.example.com."),
+            Ttl::from_secs(300)
+        );
+        assert_eq!(
+            negative_soa_ttl(true, "nx.example.com."),
+            Ttl::from_secs(300)
+        );
+        assert_eq!(negative_soa_ttl(false, "www.example.com."), Ttl::HOUR);
+    }
+
+    /// Queries a zone with an SOA TTL of one hour and an SOA MINIMUM of
+    /// five minutes for the AAAA records of `qname` and returns the TTL of
+    /// the SOA in the authority section of the response.
+    fn negative_soa_ttl(soa_minimum_ttl: bool, qname: &str) -> Ttl {
+        let zone_file = b"\
+example.com. 3600 IN SOA ns.example.com. hostmaster.example.com. 1 3600 300 604800 300
+example.com. 3600 IN NS ns.example.com.
+ns.example.com. 3600 IN A 192.0.2.1
+www.example.com. 3600 IN A 192.0.2.2
+";
+        let mut builder = load_zone_builder(zone_file);
+        builder.set_soa_minimum_ttl(soa_minimum_ttl);
+        let zone = builder.build();
+
+        let qname = n(qname);
+        let answer = zone.read().query(qname.clone(), Rtype::AAAA).unwrap();
+
+        let mut query = MessageBuilder::new_vec().question();
+        query.push((&qname, Rtype::AAAA)).unwrap();
+        let query = query.into_message();
+        let response = answer
+            .to_message(&query, MessageBuilder::new_vec())
+            .into_message();
+        assert_eq!(response.header_counts().ancount(), 0);
+        let mut authority = response.authority().unwrap();
+        let soa = authority.next().unwrap().unwrap();
+        assert_eq!(soa.rtype(), Rtype::SOA);
+        soa.ttl()
+    }
+
+    #[test]
+    fn query_dnssec_ok_includes_signatures() {
+        let zone = signed_zone();
+        let read = zone.read();
+
+        // A positive answer.
+        let answer = read
+            .query_dnssec(n("www.example.com."), Rtype::A, true)
+            .unwrap();
+        let response = to_response(&answer, "www.example.com.", Rtype::A);
+        assert_eq!(
+            section_rtypes(response.answer().unwrap()),
+            vec![Rtype::A, Rtype::RRSIG]
+        );
+
+        // A CNAME answer.
+        let answer = read
+            .query_dnssec(n("ftp.example.com."), Rtype::A, true)
+            .unwrap();
+        let response = to_response(&answer, "ftp.example.com.", Rtype::A);
+        assert_eq!(
+            section_rtypes(response.answer().unwrap()),
+            vec![Rtype::CNAME, Rtype::RRSIG]
+        );
+
+        // Negative answers are not signed.
+        let answer = read
+            .query_dnssec(n("www.example.com."), Rtype::AAAA, true)
+            .unwrap();
+        let response = to_response(&answer, "www.example.com.", Rtype::AAAA);
+        assert_eq!(response.header_counts().ancount(), 0);
+        assert_eq!(
+            section_rtypes(response.authority().unwrap()),
+            vec![Rtype::SOA]
+        );
+    }
+
+    #[test]
+    fn query_dnssec_not_ok_excludes_signatures() {
+        let zone = signed_zone();
+        let read = zone.read();
+
+        let answer = read
+            .query_dnssec(n("www.example.com."), Rtype::A, false)
+            .unwrap();
+        assert!(answer.signatures().is_none());
+        let response = to_response(&answer, "www.example.com.", Rtype::A);
+        assert_eq!(
+            section_rtypes(response.answer().unwrap()),
+            vec![Rtype::A]
+        );
+
+        let answer = read
+            .query_dnssec(n("www.example.com."), Rtype::AAAA, false)
+            .unwrap();
+        let response = to_response(&answer, "www.example.com.", Rtype::AAAA);
+        assert_eq!(
+            section_rtypes(response.authority().unwrap()),
+            vec![Rtype::SOA]
+        );
+    }
+
+    /// Returns a zone with (bogus) signatures for all RRsets.
+    fn signed_zone() -> Zone {
+        let zone_file = b"\
+example.com. 3600 IN SOA ns.example.com. hostmaster.example.com. 1 3600 300 604800 300
+example.com. 3600 IN RRSIG SOA 13 2 3600 20250101000000 20240101000000 12345 example.com. AAAA
+example.com. 3600 IN NS ns.example.com.
+example.com. 3600 IN RRSIG NS 13 2 3600 20250101000000 20240101000000 12345 example.com. AAAA
+ftp.example.com. 3600 IN CNAME www.example.com.
+ftp.example.com. 3600 IN RRSIG CNAME 13 3 3600 20250101000000 20240101000000 12345 example.com. AAAA
+ns.example.com. 3600 IN A 192.0.2.1
+www.example.com. 3600 IN A 192.0.2.2
+www.example.com. 3600 IN RRSIG A 13 3 3600 20250101000000 20240101000000 12345 example.com. AAAA
+www.example.com. 300 IN NSEC example.com. A RRSIG NSEC
+www.example.com. 3600 IN RRSIG NSEC 13 3 300 20250101000000 20240101000000 12345 example.com. AAAA
+";
+        load_zone(zone_file)
+    }
+
+    fn to_response(
+        answer: &Answer,
+        qname: &str,
+        qtype: Rtype,
+    ) -> Message<Vec<u8>> {
+        let mut query = MessageBuilder::new_vec().question();
+        query.push((&n(qname), qtype)).unwrap();
+        let query = query.into_message();
+        answer
+            .to_message(&query, MessageBuilder::new_vec())
+            .into_message()
+    }
+
+    fn section_rtypes(section: RecordSection<'_, Vec<u8>>) -> Vec<Rtype> {
+        section.map(|rr| rr.unwrap().rtype()).collect()
+    }
+
+    #[test]
+    fn referral_includes_in_bailiwick_glue_only() {
+        let (ns, additional) = referral(true);
+        assert_eq!(ns, vec![n("ns.sub.example.com."), n("ns.example.net.")]);
+        assert_eq!(additional, vec![n("ns.sub.example.com.")]);
+    }
+
+    #[test]
+    fn referral_without_glue() {
+        let (ns, additional) = referral(false);
+        assert_eq!(ns, vec![n("ns.sub.example.com."), n("ns.example.net.")]);
+        assert!(additional.is_empty());
+    }
+
+    /// Queries a zone with a delegation and returns the NS targets from the
+    /// authority section and the owners of the records in the additional
+    /// section of the referral.
+    fn referral(glue: bool) -> (Vec<StoredName>, Vec<StoredName>) {
+        let mut builder = ZoneBuilder::new(n("example.com."), Class::IN);
+        builder.set_referral_glue(glue);
+
+        let mut ns = Rrset::new(Rtype::NS, Ttl::HOUR);
+        ns.push_data(ZoneRecordData::Ns(Ns::new(n("ns.sub.example.com."))));
+        ns.push_data(ZoneRecordData::Ns(Ns::new(n("ns.example.net."))));
+        let glue = vec![
+            Record::new(
+                n("ns.sub.example.com."),
+                Class::IN,
+                Ttl::HOUR,
+                ZoneRecordData::A(A::from_str("192.0.2.1").unwrap()),
+            ),
+            Record::new(
+                n("ns.example.net."),
+                Class::IN,
+                Ttl::HOUR,
+                ZoneRecordData::A(A::from_str("192.0.2.2").unwrap()),
+            ),
+        ];
+        builder
+            .insert_zone_cut(
+                &n("sub.example.com."),
+                SharedRrset::new(ns),
+                None,
+                glue,
+            )
+            .unwrap();
+        let zone = builder.build();
+
+        let qname = n("www.sub.example.com.");
+        let answer = zone.read().query(qname.clone(), Rtype::A).unwrap();
+
+        let mut query = MessageBuilder::new_vec().question();
+        query.push((&qname, Rtype::A)).unwrap();
+        let query = query.into_message();
+        let response = answer
+            .to_message(&query, MessageBuilder::new_vec())
+            .into_message();
+
+        assert!(!response.header().aa());
+        assert_eq!(response.header_counts().ancount(), 0);
+        let ns = response
+            .authority()
+            .unwrap()
+            .limit_to::<Ns<_>>()
+            .map(|rr| rr.unwrap().data().nsdname().to_name())
+            .collect();
+        let additional = response
+            .additional()
+            .unwrap()
+            .map(|rr| rr.unwrap().owner().to_name())
+            .collect();
+        (ns, additional)
+    }
 }
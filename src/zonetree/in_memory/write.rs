@@ -81,6 +81,13 @@ pub struct WriteZone {
     /// diff collection.
     diff: Arc<Mutex<Option<Arc<Mutex<InMemoryZoneDiffBuilder>>>>>,
 
+    /// Whether the caller of [`open()`] asked for a diff.
+    ///
+    /// A diff is also collected if someone is subscribed to the diffs of the
+    /// zone, but then it is only handed to the subscribers and not returned
+    /// from [`commit()`].
+    diff_requested: Arc<AtomicBool>,
+
     /// The zone is dirty if changes have been made but not yet committed.
     ///
     /// This flag is set when a zone is opened for editing, and cleared when
@@ -102,6 +109,7 @@ impl WriteZone {
             new_version,
             published_versions,
             diff: Default::default(),
+            diff_requested: Default::default(),
             dirty: Default::default(),
         }
     }
@@ -227,6 +235,7 @@ impl Clone for WriteZone {
             new_version: self.new_version,
             published_versions: self.published_versions.clone(),
             diff: self.diff.clone(),
+            diff_requested: self.diff_requested.clone(),
             dirty: Default::default(),
         }
     }
@@ -256,6 +265,8 @@ impl WritableZone for WriteZone {
                 + Sync,
         >,
     > {
+        self.diff_requested.store(create_diff, Ordering::SeqCst);
+        let create_diff = create_diff || self.apex.has_diff_observers();
         let new_apex = WriteNode::new_apex(self.clone(), create_diff);
 
         if let Ok(write_node) = &new_apex {
@@ -337,6 +348,13 @@ impl WritableZone for WriteZone {
 
         self.publish_new_zone_version();
 
+        if let Some(diff) = &out_diff {
+            self.apex.notify_diff_observers(diff);
+        }
+        if !self.diff_requested.load(Ordering::SeqCst) {
+            out_diff = None;
+        }
+
         Box::pin(ready(Ok(out_diff)))
     }
 }
@@ -451,7 +469,10 @@ impl WriteNode {
             let current_rrset = if let Some(current_rrset) = rrsets
                 .get(new_rrset.rtype(), self.zone.last_published_version())
             {
-                let changed = new_rrset != current_rrset;
+                // A TTL change has to be recorded in the diff, too, so
+                // compare the TTLs as well as the records.
+                let changed = new_rrset.ttl() != current_rrset.ttl()
+                    || !new_rrset.canonical_eq(&current_rrset);
 
                 if changed && !current_rrset.is_empty() {
                     Some(current_rrset)
@@ -467,17 +488,22 @@ impl WriteNode {
                     trace!("Diff detected: update of existing RRSET - recording change of RRSET from {current_rrset:?} to {new_rrset:#?}");
 
                     // Check each resource record in the RRset being updated
-                    // to see if it is missing from the new RRSet.
+                    // to see if it is missing from the new RRSet. Only the
+                    // individual records go into the diff, not the whole
+                    // RRset, and removed records keep their old TTL. If the
+                    // TTL changed, all records are removed and added again.
+                    let current_rrset = current_rrset.as_ref().unwrap();
+                    let ttl_changed = current_rrset.ttl() != new_rrset.ttl();
                     let new_rrs = new_rrset.as_rrset().data();
-                    let mut removed_rrs =
-                        Rrset::new(new_rrset.rtype(), new_rrset.ttl());
+                    let mut removed_rrs = Rrset::new(
+                        current_rrset.rtype(),
+                        current_rrset.ttl(),
+                    );
                     for removed_rr in current_rrset
-                        .as_ref()
-                        .unwrap()
                         .as_rrset()
                         .data()
                         .iter()
-                        .filter(|rr| !new_rrs.contains(rr))
+                        .filter(|rr| ttl_changed || !new_rrs.contains(rr))
                     {
                         removed_rrs.push_data(removed_rr.clone());
                     }
@@ -492,15 +518,14 @@ impl WriteNode {
 
                     // Check each resource record in the new RRset to see if
                     // it is missing from the RRset being updated.
-                    let old_rrs =
-                        current_rrset.as_ref().unwrap().as_rrset().data();
+                    let old_rrs = current_rrset.as_rrset().data();
                     let mut added_rrs =
                         Rrset::new(new_rrset.rtype(), new_rrset.ttl());
                     for added_rr in new_rrset
                         .as_rrset()
                         .data()
                         .iter()
-                        .filter(|rr| !old_rrs.contains(rr))
+                        .filter(|rr| ttl_changed || !old_rrs.contains(rr))
                     {
                         added_rrs.push_data(added_rr.clone());
                     }
@@ -102,6 +102,7 @@
 //! [`ZoneUpdater`]: update::ZoneUpdater
 
 mod answer;
+pub mod check;
 pub mod error;
 mod in_memory;
 pub mod parsed;
@@ -112,11 +113,14 @@ pub mod update;
 mod walk;
 mod zone;
 
+#[cfg(test)]
+pub(crate) mod test_util;
+
 pub use self::answer::{Answer, AnswerAuthority, AnswerContent};
 pub use self::in_memory::ZoneBuilder;
 pub use self::traits::{
     ReadableZone, WritableZone, WritableZoneNode, ZoneDiff, ZoneDiffItem,
-    ZoneStore,
+    ZoneDiffStream, ZoneStore,
 };
 pub use self::tree::{ZoneSetIter, ZoneTree};
 pub use self::types::{
@@ -143,10 +143,11 @@ impl Zonefile {
                     }
                 }
                 Rtype::CNAME => {
-                    if let Some(normal_records) =
-                        self.normal.get(record.owner())
+                    if let Some(rtype) = self
+                        .normal
+                        .get(record.owner())
+                        .and_then(|normal| normal.first_cname_incompatible())
                     {
-                        let rtype = normal_records.sample_rtype().unwrap();
                         Err(RecordError::IllegalCname(record, rtype))
                     } else if let Some(zone_cut) =
                         self.zone_cuts.get(record.owner())
@@ -173,7 +174,9 @@ impl Zonefile {
                     if let Some(zone_cut) = incompatible_zone_cut {
                         let rtype = zone_cut.sample_rtype().unwrap();
                         Err(RecordError::IllegalRecord(record, rtype))
-                    } else if self.cnames.contains(record.owner()) {
+                    } else if self.cnames.contains(record.owner())
+                        && !is_cname_compatible(record.rtype())
+                    {
                         Err(RecordError::IllegalRecord(record, Rtype::CNAME))
                     } else {
                         self.normal
@@ -447,13 +450,26 @@ impl Normal {
         self.records.into_iter()
     }
 
-    fn sample_rtype(&self) -> Option<Rtype> {
-        self.records.iter().next().map(|(&rtype, _)| rtype)
-    }
-
     fn first_non_glue(&self) -> Option<(&Rtype, &Rrset)> {
         self.records.iter().find(|(rtype, _)| !rtype.is_glue())
     }
+
+    fn first_cname_incompatible(&self) -> Option<Rtype> {
+        self.records
+            .keys()
+            .find(|&&rtype| !is_cname_compatible(rtype))
+            .copied()
+    }
+}
+
+/// Returns whether records of the given type may share an owner with a CNAME.
+///
+/// Only RRSIG and NSEC records may, see [RFC 4035 section 2.5].
+///
+/// [RFC 4035 section 2.5]:
+///     https://www.rfc-editor.org/rfc/rfc4035#section-2.5
+fn is_cname_compatible(rtype: Rtype) -> bool {
+    matches!(rtype, Rtype::RRSIG | Rtype::NSEC)
 }
 
 //------------ ZoneCut -------------------------------------------------------
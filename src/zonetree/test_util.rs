@@ -0,0 +1,31 @@
+//! Helpers shared by the zone tree tests.
+
+use core::str::FromStr;
+
+use bytes::Bytes;
+
+use crate::base::Name;
+use crate::zonefile::inplace::Zonefile;
+
+use super::{Zone, ZoneBuilder};
+
+/// Parses an absolute domain name.
+pub(crate) fn n(name: &str) -> Name<Bytes> {
+    Name::from_str(name).unwrap()
+}
+
+/// Loads a zone from the content of a zone file.
+pub(crate) fn load_zone(bytes: &[u8]) -> Zone {
+    let reader = Zonefile::load(&mut std::io::BufReader::new(bytes)).unwrap();
+    Zone::try_from(reader).unwrap()
+}
+
+/// Loads the content of a zone file into a zone builder.
+///
+/// Use this instead of [`load_zone`] if the zone needs to be configured
+/// before it is built.
+pub(crate) fn load_zone_builder(bytes: &[u8]) -> ZoneBuilder {
+    let reader = Zonefile::load(&mut std::io::BufReader::new(bytes)).unwrap();
+    let zonefile = super::parsed::Zonefile::try_from(reader).unwrap();
+    ZoneBuilder::try_from(zonefile).unwrap()
+}
@@ -16,7 +16,8 @@ use std::boxed::Box;
 use std::fmt::Debug;
 use std::future::Future;
 use std::io;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::vec::Vec;
 
 use bytes::Bytes;
 use futures_util::Stream;
@@ -24,11 +25,12 @@ use futures_util::Stream;
 use crate::base::iana::Class;
 use crate::base::name::Label;
 use crate::base::{Name, Rtype, Serial, ToName};
+use crate::rdata::ZoneRecordData;
 
-use super::answer::Answer;
+use super::answer::{Answer, AnswerContent};
 use super::error::OutOfZone;
 use super::types::{InMemoryZoneDiff, ZoneCut};
-use super::{SharedRr, SharedRrset, StoredName, WalkOp};
+use super::{Rrset, SharedRr, SharedRrset, StoredName, WalkOp};
 
 //------------ ZoneStore -----------------------------------------------------
 
@@ -46,6 +48,9 @@ pub trait ZoneStore: Debug + Sync + Send + Any {
     fn apex_name(&self) -> &StoredName;
 
     /// Get a read interface to this store.
+    ///
+    /// The returned interface must provide a consistent snapshot of the
+    /// zone as it was when this method was called, see [`ReadableZone`].
     fn read(self: Arc<Self>) -> Box<dyn ReadableZone>;
 
     /// Get a write interface to this store.
@@ -66,8 +71,41 @@ pub trait ZoneStore: Debug + Sync + Send + Any {
     /// [`ZoneStore`] implementation. See [`Zone`] for how this can used to
     /// layer functionality on top of a zone.
     fn as_any(&self) -> &dyn Any;
+
+    /// Subscribes to the changes made to the zone.
+    ///
+    /// The returned stream yields a diff for every change set committed to
+    /// the zone after subscribing, in the order they were committed. Only
+    /// commits that change the SOA serial of the zone produce a diff.
+    ///
+    /// Returns `None` if the store does not support observing changes, which
+    /// is the default.
+    fn subscribe_diffs(&self) -> Option<ZoneDiffStream> {
+        None
+    }
+
+    /// Returns the SOA serial of the current version of the zone.
+    ///
+    /// This is meant for frequent checks of the serial, such as when
+    /// deciding whether to send NOTIFY messages or to refresh a secondary
+    /// zone, and should be considerably cheaper than obtaining a
+    /// [`ReadableZone`] and querying it for the SOA record.
+    ///
+    /// Returns `None` if the zone has no SOA record or if the store does
+    /// not support this, which is the default.
+    fn current_serial(&self) -> Option<Serial> {
+        None
+    }
 }
 
+//------------ ZoneDiffStream ------------------------------------------------
+
+/// A stream of diffs committed to a zone.
+///
+/// See [`ZoneStore::subscribe_diffs()`].
+pub type ZoneDiffStream =
+    Pin<Box<dyn Stream<Item = InMemoryZoneDiff> + Send + 'static>>;
+
 //------------ ReadableZone --------------------------------------------------
 
 /// A read interface to a [`Zone`].
@@ -75,6 +113,12 @@ pub trait ZoneStore: Debug + Sync + Send + Any {
 /// A [`ReadableZone`] mplementation provides (a)synchronous read access to
 /// the [`ZoneStore`] backing storage for a [`Zone`].
 ///
+/// A read interface provides a consistent snapshot of a single version of
+/// the zone: changes committed to the zone after the interface was obtained
+/// are not visible through it. This allows walking the entire zone, e.g.,
+/// to serve an AXFR, while the zone is being updated concurrently. The data
+/// of the snapshot is kept alive for as long as the interface exists.
+///
 /// [`Zone`]: super::Zone
 pub trait ReadableZone: Send + Sync {
     /// Returns true if ths `_async` variants of the functions offered by this
@@ -102,12 +146,84 @@ pub trait ReadableZone: Send + Sync {
         _qtype: Rtype,
     ) -> Result<Answer, OutOfZone>;
 
+    /// Lookup an [`Answer`] with or without DNSSEC records.
+    ///
+    /// Behaves like [`query`][ReadableZone::query] if `dnssec_ok` is false.
+    /// Otherwise, the RRSIG records covering the answer RRset or CNAME
+    /// record are added to the answer section.
+    ///
+    /// This allows returning signatures only to clients that set the DO
+    /// bit, see [RFC 3225]. Only positive answers are signed. Negative
+    /// answers and referrals are answered as by
+    /// [`query`][ReadableZone::query] as proving the non-existence of
+    /// names or types requires NSEC or NSEC3 records that this method
+    /// does not look up. Queries for RRSIG records are likewise answered
+    /// unchanged.
+    ///
+    /// [RFC 3225]: https://www.rfc-editor.org/rfc/rfc3225
+    fn query_dnssec(
+        &self,
+        qname: Name<Bytes>,
+        qtype: Rtype,
+        dnssec_ok: bool,
+    ) -> Result<Answer, OutOfZone> {
+        let mut answer = self.query(qname.clone(), qtype)?;
+        if !dnssec_ok || qtype == Rtype::RRSIG || answer.is_referral() {
+            return Ok(answer);
+        }
+
+        let covered = match answer.content() {
+            AnswerContent::Data(rrset) => rrset.rtype(),
+            AnswerContent::Cname(_) => Rtype::CNAME,
+            AnswerContent::NoData => return Ok(answer),
+        };
+        if let Some(sigs) = signatures(self, qname, covered)? {
+            answer.add_signatures(sigs);
+        }
+        Ok(answer)
+    }
+
     /// Iterate over the entire contents of the zone.
     ///
     /// This function visits every node in the tree, synchronously, invoking
     /// the given callback function at every leaf node found.
     fn walk(&self, _op: WalkOp);
 
+    /// Returns the record types present at the given owner name.
+    ///
+    /// Returns `None` if `qname` does not exist in the zone. An empty
+    /// non-terminal exists but has no RRsets and thus results in an empty
+    /// list. The returned record types are sorted in ascending order which
+    /// makes them suitable for constructing NSEC or NSEC3 type bitmaps.
+    ///
+    /// The default implementation walks the entire zone. Implementations
+    /// are encouraged to provide a more efficient variant. Note that the
+    /// default implementation has no way of knowing the apex of the zone
+    /// and thus treats out of zone names as non-existent.
+    fn rtypes_at(
+        &self,
+        qname: Name<Bytes>,
+    ) -> Result<Option<Vec<Rtype>>, OutOfZone> {
+        let found = Arc::new(Mutex::new((false, Vec::new())));
+        let found_clone = found.clone();
+        self.walk(Box::new(move |owner, rrset, _at_zone_cut| {
+            if owner.ends_with(&qname) {
+                let mut found = found_clone.lock().unwrap();
+                found.0 = true;
+                if owner == qname {
+                    found.1.push(rrset.rtype());
+                }
+            }
+        }));
+        let (exists, mut rtypes) = found.lock().unwrap().clone();
+        if !exists {
+            return Ok(None);
+        }
+        rtypes.sort();
+        rtypes.dedup();
+        Ok(Some(rtypes))
+    }
+
     //--- Async variants
 
     /// Asynchronous variant of [`query`][ReadableZone::query].
@@ -120,6 +236,17 @@ pub trait ReadableZone: Send + Sync {
         Box::pin(ready(self.query(qname, qtype)))
     }
 
+    /// Asynchronous variant of [`query_dnssec`][ReadableZone::query_dnssec].
+    fn query_dnssec_async(
+        &self,
+        qname: Name<Bytes>,
+        qtype: Rtype,
+        dnssec_ok: bool,
+    ) -> Pin<Box<dyn Future<Output = Result<Answer, OutOfZone>> + Send + Sync>>
+    {
+        Box::pin(ready(self.query_dnssec(qname, qtype, dnssec_ok)))
+    }
+
     /// Asynchronous variant of [`walk`][ReadableZone::walk].
     fn walk_async(
         &self,
@@ -128,6 +255,45 @@ pub trait ReadableZone: Send + Sync {
         self.walk(op);
         Box::pin(ready(()))
     }
+
+    /// Asynchronous variant of [`rtypes_at`][ReadableZone::rtypes_at].
+    #[allow(clippy::type_complexity)]
+    fn rtypes_at_async(
+        &self,
+        qname: Name<Bytes>,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<Vec<Rtype>>, OutOfZone>>
+                + Send
+                + Sync,
+        >,
+    > {
+        Box::pin(ready(self.rtypes_at(qname)))
+    }
+}
+
+/// Returns the RRSIG records at `owner` that cover `covered`.
+fn signatures<Z: ReadableZone + ?Sized>(
+    zone: &Z,
+    owner: Name<Bytes>,
+    covered: Rtype,
+) -> Result<Option<SharedRrset>, OutOfZone> {
+    let answer = zone.query(owner, Rtype::RRSIG)?;
+    let AnswerContent::Data(rrsigs) = answer.content() else {
+        return Ok(None);
+    };
+    let mut sigs = Rrset::new(Rtype::RRSIG, rrsigs.ttl());
+    for data in rrsigs.data() {
+        if let ZoneRecordData::Rrsig(rrsig) = data {
+            if rrsig.type_covered() == covered {
+                sigs.push_data(data.clone());
+            }
+        }
+    }
+    if sigs.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(sigs.into_shared()))
 }
 
 //------------ WritableZone --------------------------------------------------
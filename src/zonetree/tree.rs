@@ -4,10 +4,12 @@ use std::collections::hash_map;
 use std::collections::HashMap;
 use std::vec::Vec;
 
-use crate::base::iana::Class;
+use crate::base::iana::{Class, Rtype};
 use crate::base::name::{Label, OwnedLabel, ToLabelIter, ToName};
 
+use super::answer::AnswerContent;
 use super::error::ZoneTreeModificationError;
+use super::types::{SharedRrset, StoredName};
 use super::zone::Zone;
 
 //------------ ZoneTree ------------------------------------------------------
@@ -61,6 +63,52 @@ impl ZoneTree {
         self.roots.get(class)?.find_zone(qname.iter_labels().rev())
     }
 
+    /// Returns the DNSSEC chain from `start` down to the zone of `qname`.
+    ///
+    /// This collects the records requested by the CHAIN option defined in
+    /// [RFC 7901]: for every zone in the tree with an apex below `start`
+    /// and at or above `qname`, the DS RRset from the parent zone and the
+    /// DNSKEY RRset of the zone, each followed by the RRSIG records
+    /// covering it if the zone has them. The records are ordered from
+    /// `start` downwards and are meant to be added to a response via
+    /// [`Answer::set_chain`].
+    ///
+    /// The result is empty if `start` is not a suffix of `qname`.
+    ///
+    /// [RFC 7901]: https://www.rfc-editor.org/rfc/rfc7901
+    /// [`Answer::set_chain`]: super::Answer::set_chain
+    pub fn chain(
+        &self,
+        qname: &StoredName,
+        class: Class,
+        start: &impl ToName,
+    ) -> Vec<(StoredName, SharedRrset)> {
+        let mut chain = Vec::new();
+        if !qname.ends_with(start) {
+            return chain;
+        }
+
+        let mut names: Vec<_> = qname
+            .iter_suffixes()
+            .take_while(|name| !name.name_eq(start))
+            .collect();
+        names.reverse();
+
+        for name in names {
+            let Some(zone) = self.get_zone(&name, class) else {
+                continue;
+            };
+            if let Some(parent) = name
+                .parent()
+                .and_then(|parent| self.find_zone(&parent, class))
+            {
+                push_chain_rrset(&mut chain, parent, name.clone(), Rtype::DS);
+            }
+            push_chain_rrset(&mut chain, zone, name, Rtype::DNSKEY);
+        }
+        chain
+    }
+
     /// Returns an iterator over all of the [`Zone`]s in the tree.
     pub fn iter_zones(&self) -> ZoneSetIter<'_> {
         ZoneSetIter::new(self)
@@ -80,6 +128,25 @@ impl ZoneTree {
     }
 }
 
+/// Adds an RRset and its signatures from `zone` to a chain.
+fn push_chain_rrset(
+    chain: &mut Vec<(StoredName, SharedRrset)>,
+    zone: &Zone,
+    name: StoredName,
+    rtype: Rtype,
+) {
+    let Ok(answer) = zone.read().query_dnssec(name.clone(), rtype, true)
+    else {
+        return;
+    };
+    if let AnswerContent::Data(rrset) = answer.content() {
+        chain.push((name.clone(), rrset.clone()));
+        if let Some(sigs) = answer.signatures() {
+            chain.push((name, sigs.clone()));
+        }
+    }
+}
+
 //------------ Roots ---------------------------------------------------------
 
 #[derive(Clone, Default, Debug)]
@@ -171,12 +238,21 @@ impl ZoneSetNode {
     ) -> Result<(), ZoneTreeModificationError> {
         match apex_name.next() {
             Some(label) => {
-                if self.children.remove(label).is_none() {
+                let Some(child) = self.children.get_mut(label) else {
                     return Err(ZoneTreeModificationError::ZoneDoesNotExist);
+                };
+                child.remove_zone(apex_name)?;
+
+                // Only drop the node if it no longer leads to any zone, as
+                // zones below the removed one must remain reachable.
+                if child.zone.is_none() && child.children.is_empty() {
+                    let _ = self.children.remove(label);
                 }
             }
             None => {
-                self.zone = None;
+                if self.zone.take().is_none() {
+                    return Err(ZoneTreeModificationError::ZoneDoesNotExist);
+                }
             }
         }
         Ok(())
@@ -258,3 +334,212 @@ impl<'a> Iterator for NodesIter<'a> {
         Some(node)
     }
 }
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::base::iana::Rcode;
+    use crate::base::{MessageBuilder, Name, Rtype, ToName};
+    use crate::rdata::Ns;
+    use crate::zonetree::test_util::{load_zone, n};
+    use crate::zonetree::AnswerContent;
+
+    use super::*;
+
+    const PARENT: &[u8] = b"\
+example.com. 3600 IN SOA ns.example.com. hostmaster.example.com. 1 3600 300 604800 300
+example.com. 3600 IN NS ns.example.com.
+ns.example.com. 3600 IN A 192.0.2.1
+sub.example.com. 3600 IN NS ns.sub.example.com.
+ns.sub.example.com. 3600 IN A 192.0.2.2
+other.example.com. 3600 IN NS ns.other.example.com.
+ns.other.example.com. 3600 IN A 192.0.2.3
+";
+
+    const CHILD: &[u8] = b"\
+sub.example.com. 3600 IN SOA ns.sub.example.com. hostmaster.example.com. 1 3600 300 604800 300
+sub.example.com. 3600 IN NS ns.sub.example.com.
+ns.sub.example.com. 3600 IN A 192.0.2.2
+www.sub.example.com. 3600 IN A 192.0.2.10
+";
+
+    const SIGNED_PARENT: &[u8] = b"\
+example.com. 3600 IN SOA ns.example.com. hostmaster.example.com. 1 3600 300 604800 300
+example.com. 3600 IN NS ns.example.com.
+example.com. 3600 IN DNSKEY 257 3 13 AAAA
+example.com. 3600 IN RRSIG DNSKEY 13 2 3600 20250101000000 20240101000000 12345 example.com. AAAA
+ns.example.com. 3600 IN A 192.0.2.1
+sub.example.com. 3600 IN NS ns.sub.example.com.
+sub.example.com. 3600 IN DS 23456 13 2 0123456789ABCDEF
+ns.sub.example.com. 3600 IN A 192.0.2.2
+";
+
+    const SIGNED_CHILD: &[u8] = b"\
+sub.example.com. 3600 IN SOA ns.sub.example.com. hostmaster.example.com. 1 3600 300 604800 300
+sub.example.com. 3600 IN NS ns.sub.example.com.
+sub.example.com. 3600 IN DNSKEY 257 3 13 AAAA
+sub.example.com. 3600 IN RRSIG DNSKEY 13 3 3600 20250101000000 20240101000000 23456 sub.example.com. AAAA
+ns.sub.example.com. 3600 IN A 192.0.2.2
+www.sub.example.com. 3600 IN A 192.0.2.10
+";
+
+    #[test]
+    fn most_specific_zone_answers() {
+        let mut tree = ZoneTree::new();
+        tree.insert_zone(load_zone(PARENT)).unwrap();
+        tree.insert_zone(load_zone(CHILD)).unwrap();
+
+        // The child zone is served, so it answers instead of the delegation
+        // in the parent zone.
+        let qname = n("www.sub.example.com.");
+        let zone = tree.find_zone(&qname, Class::IN).unwrap();
+        assert_eq!(zone.apex_name(), &n("sub.example.com."));
+        let answer = zone.read().query(qname, Rtype::A).unwrap();
+        assert_eq!(answer.rcode(), Rcode::NOERROR);
+        assert!(matches!(answer.content(), AnswerContent::Data(_)));
+
+        // The other child zone is not served, so the parent zone refers.
+        let qname = n("www.other.example.com.");
+        let zone = tree.find_zone(&qname, Class::IN).unwrap();
+        assert_eq!(zone.apex_name(), &n("example.com."));
+        assert_referral(zone, qname, "other.example.com.");
+    }
+
+    #[test]
+    fn parent_refers_after_child_removal() {
+        let mut tree = ZoneTree::new();
+        tree.insert_zone(load_zone(PARENT)).unwrap();
+        tree.insert_zone(load_zone(CHILD)).unwrap();
+
+        tree.remove_zone(&n("sub.example.com."), Class::IN).unwrap();
+        assert!(tree.get_zone(&n("sub.example.com."), Class::IN).is_none());
+        assert!(tree.get_zone(&n("example.com."), Class::IN).is_some());
+
+        let qname = n("www.sub.example.com.");
+        let zone = tree.find_zone(&qname, Class::IN).unwrap();
+        assert_eq!(zone.apex_name(), &n("example.com."));
+        assert_referral(zone, qname, "sub.example.com.");
+
+        // Removing the parent zone leaves nothing to answer from.
+        tree.remove_zone(&n("example.com."), Class::IN).unwrap();
+        assert!(tree.find_zone(&n("example.com."), Class::IN).is_none());
+        assert!(tree.remove_zone(&n("example.com."), Class::IN).is_err());
+    }
+
+    #[test]
+    fn removing_parent_keeps_child() {
+        let mut tree = ZoneTree::new();
+        tree.insert_zone(load_zone(PARENT)).unwrap();
+        tree.insert_zone(load_zone(CHILD)).unwrap();
+
+        tree.remove_zone(&n("example.com."), Class::IN).unwrap();
+
+        let zone = tree
+            .find_zone(&n("www.sub.example.com."), Class::IN)
+            .unwrap();
+        assert_eq!(zone.apex_name(), &n("sub.example.com."));
+        assert!(tree
+            .find_zone(&n("www.other.example.com."), Class::IN)
+            .is_none());
+    }
+
+    #[test]
+    fn chain_from_closest_trust_point() {
+        let mut tree = ZoneTree::new();
+        tree.insert_zone(load_zone(SIGNED_PARENT)).unwrap();
+        tree.insert_zone(load_zone(SIGNED_CHILD)).unwrap();
+
+        // A query with a CHAIN option starting at the parent zone.
+        let qname = n("www.sub.example.com.");
+        let mut query = MessageBuilder::new_vec().question();
+        query.push((&qname, Rtype::A)).unwrap();
+        let mut query = query.additional();
+        query.opt(|opt| opt.chain(n("example.com."))).unwrap();
+        let query = query.into_message();
+
+        let start = query.opt().unwrap().opt().chain().unwrap();
+        assert_eq!(start.start(), &n("example.com."));
+
+        let chain = tree.chain(&qname, Class::IN, start.start());
+        assert_eq!(
+            chain_rtypes(&chain),
+            vec![
+                (n("sub.example.com."), Rtype::DS),
+                (n("sub.example.com."), Rtype::DNSKEY),
+                (n("sub.example.com."), Rtype::RRSIG),
+            ]
+        );
+
+        let zone = tree.find_zone(&qname, Class::IN).unwrap();
+        let mut answer = zone.read().query(qname, Rtype::A).unwrap();
+        answer.set_chain(chain);
+        let response = answer
+            .to_message(&query, MessageBuilder::new_vec())
+            .into_message();
+        let authority: Vec<_> = response
+            .authority()
+            .unwrap()
+            .map(|rr| rr.unwrap().rtype())
+            .collect();
+        assert_eq!(authority, vec![Rtype::DS, Rtype::DNSKEY, Rtype::RRSIG]);
+
+        // Starting further up includes the parent's keys but not its DS,
+        // which is not in the tree.
+        let chain =
+            tree.chain(&n("www.sub.example.com."), Class::IN, &n("com."));
+        assert_eq!(
+            chain_rtypes(&chain),
+            vec![
+                (n("example.com."), Rtype::DNSKEY),
+                (n("example.com."), Rtype::RRSIG),
+                (n("sub.example.com."), Rtype::DS),
+                (n("sub.example.com."), Rtype::DNSKEY),
+                (n("sub.example.com."), Rtype::RRSIG),
+            ]
+        );
+
+        // A start that isn't a suffix of the query name.
+        assert!(tree
+            .chain(&n("www.sub.example.com."), Class::IN, &n("example.net."))
+            .is_empty());
+    }
+
+    //--- Helpers
+
+    fn chain_rtypes(
+        chain: &[(StoredName, SharedRrset)],
+    ) -> Vec<(StoredName, Rtype)> {
+        chain
+            .iter()
+            .map(|(owner, rrset)| (owner.clone(), rrset.rtype()))
+            .collect()
+    }
+
+    /// Asserts that a query for `qname` in `zone` results in a referral to
+    /// `cut`.
+    fn assert_referral(zone: &Zone, qname: Name<Bytes>, cut: &str) {
+        let answer = zone.read().query(qname.clone(), Rtype::A).unwrap();
+
+        let mut query = MessageBuilder::new_vec().question();
+        query.push((&qname, Rtype::A)).unwrap();
+        let query = query.into_message();
+        let response = answer
+            .to_message(&query, MessageBuilder::new_vec())
+            .into_message();
+
+        assert_eq!(response.header().rcode(), Rcode::NOERROR);
+        assert!(!response.header().aa());
+        assert_eq!(response.header_counts().ancount(), 0);
+        let owners: Vec<_> = response
+            .authority()
+            .unwrap()
+            .limit_to::<Ns<_>>()
+            .map(|rr| rr.unwrap().owner().to_name::<Bytes>())
+            .collect();
+        assert!(!owners.is_empty());
+        assert!(owners.iter().all(|owner| *owner == n(cut)));
+    }
+}
@@ -1,5 +1,6 @@
 //! Zone tree related types.
 
+use core::cmp::Ordering;
 use core::future::{ready, Future};
 use core::pin::Pin;
 use core::task::{Context, Poll};
@@ -16,6 +17,7 @@ use serde::{Deserialize, Serialize};
 use tracing::trace;
 
 use super::traits::{ZoneDiff, ZoneDiffItem};
+use crate::base::cmp::CanonicalOrd;
 use crate::base::name::Name;
 use crate::base::rdata::RecordData;
 use crate::base::record::Record;
@@ -168,6 +170,36 @@ impl Rrset {
         self.push_data(record.into_data());
     }
 
+    /// Returns whether two RRsets contain the same records.
+    ///
+    /// The record data is compared in canonical form as defined by
+    /// [RFC 4034 section 6.2], i.e., domain names embedded in the data are
+    /// compared case-insensitively. The order of the records and the TTL
+    /// of the RRsets are ignored, as is the owner name and class which an
+    /// [`Rrset`] doesn't carry. Callers should only compare RRsets of the
+    /// same owner and class.
+    ///
+    /// [RFC 4034 section 6.2]:
+    ///     https://datatracker.ietf.org/doc/html/rfc4034#section-6.2
+    pub fn canonical_eq(&self, other: &Rrset) -> bool {
+        if self.rtype != other.rtype {
+            return false;
+        }
+        fn canonical(rrset: &Rrset) -> Vec<&StoredRecordData> {
+            let mut data: Vec<_> = rrset.data.iter().collect();
+            data.sort_by(|a, b| a.canonical_cmp(*b));
+            data.dedup_by(|a, b| a.canonical_cmp(*b).is_eq());
+            data
+        }
+        let left = canonical(self);
+        let right = canonical(other);
+        left.len() == right.len()
+            && left
+                .iter()
+                .zip(right.iter())
+                .all(|(a, b)| a.canonical_cmp(*b).is_eq())
+    }
+
     /// Converts this [`Rrset`] to an [`SharedRrset`].
     pub fn into_shared(self) -> SharedRrset {
         SharedRrset::new(self)
@@ -393,6 +425,58 @@ impl InMemoryZoneDiff {
             removed: removed.into(),
         })
     }
+
+    /// Selects the chain of diffs leading from serial `start` to `end`.
+    ///
+    /// Starting with the diff whose start serial is `start`, the diffs are
+    /// followed from one to the next by matching the end serial of a diff
+    /// with the start serial of the next until a diff ending at `end` is
+    /// found. The order of `diffs` doesn't matter.
+    ///
+    /// Serials are compared using the serial number arithmetic of [RFC
+    /// 1982], so ranges that wrap around at 2^32 are handled. A diff is
+    /// only selected if its end serial is greater than its start serial
+    /// and doesn't go past `end`.
+    ///
+    /// Returns an empty vec if `start` and `end` are equal. Returns `None`
+    /// if `start` isn't less than `end` or if the diffs don't form a chain
+    /// between the two.
+    ///
+    /// [RFC 1982]: https://www.rfc-editor.org/rfc/rfc1982
+    pub fn diffs_for_range(
+        diffs: &[InMemoryZoneDiff],
+        start: Serial,
+        end: Serial,
+    ) -> Option<Vec<InMemoryZoneDiff>> {
+        if start == end {
+            return Some(Vec::new());
+        }
+        if start.partial_cmp(&end) != Some(Ordering::Less) {
+            return None;
+        }
+
+        let mut chain = Vec::new();
+        let mut serial = start;
+        while serial != end {
+            // Every diff can be used at most once, which also stops us from
+            // going round in circles.
+            if chain.len() == diffs.len() {
+                return None;
+            }
+            let next = diffs.iter().find(|diff| {
+                diff.start_serial == serial
+                    && diff.end_serial.partial_cmp(&serial)
+                        == Some(Ordering::Greater)
+                    && matches!(
+                        diff.end_serial.partial_cmp(&end),
+                        Some(Ordering::Less | Ordering::Equal)
+                    )
+            })?;
+            chain.push(next.clone());
+            serial = next.end_serial;
+        }
+        Some(chain)
+    }
 }
 
 //--- impl ZoneDiff
@@ -686,3 +770,154 @@ impl<R> std::fmt::Display for ZoneUpdate<R> {
         }
     }
 }
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use crate::rdata::{Ns, Soa, A};
+
+    use super::*;
+
+    fn mk_diff(start: u32, end: u32) -> InMemoryZoneDiff {
+        let apex = StoredName::from_str("example.com.").unwrap();
+        let soa_rrset = |serial| {
+            let mut rrset = Rrset::new(Rtype::SOA, Ttl::from_secs(3600));
+            rrset.push_data(ZoneRecordData::Soa(Soa::new(
+                StoredName::from_str("ns.example.com.").unwrap(),
+                StoredName::from_str("hostmaster.example.com.").unwrap(),
+                Serial(serial),
+                Ttl::from_secs(3600),
+                Ttl::from_secs(300),
+                Ttl::from_secs(604800),
+                Ttl::from_secs(300),
+            )));
+            rrset.into_shared()
+        };
+        let mut builder = InMemoryZoneDiffBuilder::new();
+        builder.remove(apex.clone(), Rtype::SOA, soa_rrset(start));
+        builder.add(apex, Rtype::SOA, soa_rrset(end));
+        builder.build().unwrap()
+    }
+
+    fn serials(diffs: &[InMemoryZoneDiff]) -> Vec<(u32, u32)> {
+        diffs
+            .iter()
+            .map(|diff| {
+                (diff.start_serial.into_int(), diff.end_serial.into_int())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diffs_for_range_across_wrap() {
+        let diffs = [
+            mk_diff(0xFFFF_FFFE, 0xFFFF_FFFF),
+            mk_diff(0xFFFF_FFFF, 0),
+            mk_diff(0, 1),
+            mk_diff(1, 2),
+        ];
+
+        // The start is numerically greater than the end.
+        let chain = InMemoryZoneDiff::diffs_for_range(
+            &diffs,
+            Serial(0xFFFF_FFFF),
+            Serial(1),
+        )
+        .unwrap();
+        assert_eq!(serials(&chain), [(0xFFFF_FFFF, 0), (0, 1)]);
+
+        let chain = InMemoryZoneDiff::diffs_for_range(
+            &diffs,
+            Serial(0xFFFF_FFFE),
+            Serial(2),
+        )
+        .unwrap();
+        assert_eq!(
+            serials(&chain),
+            [(0xFFFF_FFFE, 0xFFFF_FFFF), (0xFFFF_FFFF, 0), (0, 1), (1, 2)]
+        );
+
+        // Order of the diffs doesn't matter.
+        let mut reversed = diffs.clone();
+        reversed.reverse();
+        let chain = InMemoryZoneDiff::diffs_for_range(
+            &reversed,
+            Serial(0xFFFF_FFFF),
+            Serial(1),
+        )
+        .unwrap();
+        assert_eq!(serials(&chain), [(0xFFFF_FFFF, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn diffs_for_range_unavailable() {
+        let diffs = [mk_diff(0xFFFF_FFFF, 0), mk_diff(0, 1)];
+
+        assert!(InMemoryZoneDiff::diffs_for_range(
+            &diffs,
+            Serial(1),
+            Serial(1)
+        )
+        .unwrap()
+        .is_empty());
+
+        // Going backwards isn't possible.
+        assert!(InMemoryZoneDiff::diffs_for_range(
+            &diffs,
+            Serial(1),
+            Serial(0xFFFF_FFFF)
+        )
+        .is_none());
+
+        // There is no diff starting at the requested serial.
+        assert!(InMemoryZoneDiff::diffs_for_range(
+            &diffs,
+            Serial(0xFFFF_FFFE),
+            Serial(1)
+        )
+        .is_none());
+
+        // The chain doesn't reach the requested end.
+        assert!(InMemoryZoneDiff::diffs_for_range(
+            &diffs,
+            Serial(0xFFFF_FFFF),
+            Serial(2)
+        )
+        .is_none());
+    }
+
+    fn ns_rrset(ttl: u32, names: &[&str]) -> Rrset {
+        let mut rrset = Rrset::new(Rtype::NS, Ttl::from_secs(ttl));
+        for name in names {
+            rrset.push_data(ZoneRecordData::Ns(Ns::new(
+                Name::from_str(name).unwrap(),
+            )));
+        }
+        rrset
+    }
+
+    #[test]
+    fn canonical_eq_ignores_order_and_ttl() {
+        let left = ns_rrset(3600, &["ns1.example.com.", "ns2.example.com."]);
+        let right = ns_rrset(300, &["NS2.example.com.", "ns1.example.com."]);
+        assert!(left.canonical_eq(&right));
+        assert!(right.canonical_eq(&left));
+    }
+
+    #[test]
+    fn canonical_eq_detects_changed_data() {
+        let left = ns_rrset(3600, &["ns1.example.com.", "ns2.example.com."]);
+        let right = ns_rrset(3600, &["ns1.example.com.", "ns3.example.com."]);
+        assert!(!left.canonical_eq(&right));
+
+        let fewer = ns_rrset(3600, &["ns1.example.com."]);
+        assert!(!left.canonical_eq(&fewer));
+
+        let mut a = Rrset::new(Rtype::A, Ttl::from_secs(3600));
+        a.push_data(ZoneRecordData::A(A::from_octets(192, 0, 2, 1)));
+        assert!(!left.canonical_eq(&a));
+    }
+}
@@ -8,6 +8,7 @@ use core::marker::PhantomData;
 use core::pin::Pin;
 
 use std::boxed::Box;
+use std::vec::Vec;
 
 use bytes::Bytes;
 use tracing::trace;
@@ -16,10 +17,11 @@ use crate::base::name::{FlattenInto, Label};
 use crate::base::scan::ScannerError;
 use crate::base::{Name, ParsedName, Record, Rtype, ToName};
 use crate::rdata::ZoneRecordData;
+use crate::zonefile::inplace::{self, Entry};
 use crate::zonetree::{Rrset, SharedRrset};
 
 use super::error::OutOfZone;
-use super::types::ZoneUpdate;
+use super::types::{StoredName, StoredRecord, ZoneUpdate};
 use super::util::rel_name_rev_iter;
 use super::{InMemoryZoneDiff, WritableZone, WritableZoneNode, Zone};
 
@@ -431,12 +433,17 @@ where
         let tree_node = tree_node.as_ref().unwrap_or(self.write.root());
 
         // Prepare an RRset that contains all of the records of the existing
-        // RRset in the tree except the one to delete.
-        let mut rrset = Rrset::new(rec.rtype(), rec.ttl());
+        // RRset in the tree except the one to delete. The TTL of the record
+        // to delete doesn't change the TTL of the remaining records.
         let rtype = rec.rtype();
         let data = rec.data();
+        let existing_rrset = tree_node.get_rrset(rtype).await?;
+        let ttl = existing_rrset
+            .as_ref()
+            .map_or(rec.ttl(), |rrset| rrset.ttl());
+        let mut rrset = Rrset::new(rtype, ttl);
 
-        if let Some(existing_rrset) = tree_node.get_rrset(rtype).await? {
+        if let Some(existing_rrset) = existing_rrset {
             for existing_data in existing_rrset.data() {
                 if existing_data != data {
                     rrset.push_data(existing_data.clone());
@@ -492,6 +499,74 @@ where
     }
 }
 
+//------------ reload_zone() --------------------------------------------------
+
+/// Replaces the content of a [`Zone`] with the content of a zone file.
+///
+/// The zone file is read and parsed on a blocking thread of the Tokio
+/// runtime so that other tasks, such as those serving queries from the zone,
+/// are not held up. Only once the whole zone file has been parsed
+/// successfully is the content of the zone replaced.
+///
+/// The new content is written to a new version of the zone that becomes
+/// visible to readers in one go when it is committed. Until then, readers
+/// keep seeing the complete old version of the zone. If reloading fails, the
+/// zone is left unchanged.
+///
+/// Relative names in the zone file are relative to the apex of the zone.
+/// The zone file must contain an SOA record at the apex. Returns the diff
+/// between the old and the new version of the zone, if the zone produces
+/// one.
+pub async fn reload_zone<R>(
+    zone: &Zone,
+    mut source: R,
+) -> Result<Option<InMemoryZoneDiff>, Error>
+where
+    R: std::io::Read + Send + 'static,
+{
+    let apex_name = zone.apex_name().clone();
+    let class = zone.class();
+    let origin = apex_name.clone();
+    let mut records =
+        tokio::task::spawn_blocking(move || -> Result<_, Error> {
+            let mut zonefile = inplace::Zonefile::load(&mut source)?;
+            zonefile.set_origin(origin);
+            zonefile.set_default_class(class);
+            let mut records = Vec::new();
+            for entry in zonefile {
+                match entry.map_err(Error::Zonefile)? {
+                    Entry::Record(record) => {
+                        let record: StoredRecord = record.flatten_into();
+                        records.push(record);
+                    }
+                    Entry::Include { .. } => {
+                        return Err(Error::IoError(std::io::Error::new(
+                            std::io::ErrorKind::Unsupported,
+                            "$INCLUDE is not supported",
+                        )));
+                    }
+                }
+            }
+            Ok(records)
+        })
+        .await
+        .map_err(|err| Error::IoError(std::io::Error::other(err)))??;
+
+    let Some(soa_idx) = records.iter().position(|record| {
+        record.rtype() == Rtype::SOA && *record.owner() == apex_name
+    }) else {
+        return Err(Error::MissingSoa);
+    };
+    let soa = records.swap_remove(soa_idx);
+
+    let mut updater = ZoneUpdater::<StoredName>::new(zone.clone()).await?;
+    updater.apply(ZoneUpdate::DeleteAllRecords).await?;
+    for record in records {
+        updater.apply(ZoneUpdate::AddRecord(record)).await?;
+    }
+    updater.apply(ZoneUpdate::Finished(soa)).await
+}
+
 //------------ ZoneUpdaterState -----------------------------------------------
 
 /// The current state of a [`ZoneUpdater`].
@@ -626,12 +701,13 @@ impl ReopenableZoneWriter {
 #[cfg(test)]
 mod tests {
     use core::str::FromStr;
-    use core::sync::atomic::{AtomicUsize, Ordering};
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
     use std::sync::Arc;
     use std::vec::Vec;
 
     use bytes::BytesMut;
+    use futures_util::StreamExt;
     use octseq::Octets;
 
     use crate::base::iana::{Class, Rcode};
@@ -644,7 +720,7 @@ mod tests {
     use crate::logging::init_logging;
     use crate::net::xfr::protocol::XfrResponseInterpreter;
     use crate::rdata::{Ns, Soa, A};
-    use crate::zonetree::ZoneBuilder;
+    use crate::zonetree::{AnswerContent, ZoneBuilder};
 
     use super::*;
 
@@ -796,6 +872,282 @@ mod tests {
         assert_eq!(diff.end_serial, Serial(20240923));
     }
 
+    #[tokio::test]
+    async fn diff_observers_receive_committed_diffs() {
+        init_logging();
+
+        let zone = mk_empty_zone("example.com");
+        let mut diffs = zone.subscribe_diffs().unwrap();
+        let www = "www.example.com";
+
+        // Populate the zone. There is no prior SOA serial so no diff.
+        let mut updater = ZoneUpdater::new(zone.clone()).await.unwrap();
+        let soa_rec = mk_soa_rec("example.com", Serial(1));
+        updater
+            .apply(ZoneUpdate::AddRecord(soa_rec.clone()))
+            .await
+            .unwrap();
+        updater.apply(ZoneUpdate::Finished(soa_rec)).await.unwrap();
+
+        // Apply two updates, each adding an address record.
+        for (serial, last_octet) in [(2, 1), (3, 2)] {
+            let mut updater = ZoneUpdater::new(zone.clone()).await.unwrap();
+            let soa_rec = mk_soa_rec("example.com", Serial(serial));
+            let a_rec = mk_a_rec(www, last_octet, 0);
+            updater
+                .apply(ZoneUpdate::AddRecord(soa_rec.clone()))
+                .await
+                .unwrap();
+            updater.apply(ZoneUpdate::AddRecord(a_rec)).await.unwrap();
+            updater.apply(ZoneUpdate::Finished(soa_rec)).await.unwrap();
+        }
+
+        let www = Name::<Bytes>::from_str(www).unwrap();
+        for (start, end) in [(1, 2), (2, 3)] {
+            let diff = diffs.next().await.unwrap();
+            assert_eq!(diff.start_serial, Serial(start));
+            assert_eq!(diff.end_serial, Serial(end));
+            assert!(diff.added.contains_key(&(www.clone(), Rtype::A)));
+        }
+    }
+
+    #[tokio::test]
+    async fn diff_contains_only_changed_records() {
+        init_logging();
+
+        let zone = mk_empty_zone("example.com");
+        let www = "www.example.com";
+
+        // Populate the zone with a multi-record A RRset.
+        let mut updater = ZoneUpdater::new(zone.clone()).await.unwrap();
+        let soa_rec = mk_soa_rec("example.com", Serial(1));
+        updater
+            .apply(ZoneUpdate::AddRecord(soa_rec.clone()))
+            .await
+            .unwrap();
+        for last_octet in 1..=3 {
+            updater
+                .apply(ZoneUpdate::AddRecord(mk_a_rec(www, last_octet, 3600)))
+                .await
+                .unwrap();
+        }
+        updater.apply(ZoneUpdate::Finished(soa_rec)).await.unwrap();
+
+        // Replace a single record of the RRset. The TTL of the record to
+        // delete differs from the TTL of the RRset in the zone.
+        let mut updater = ZoneUpdater::new(zone.clone()).await.unwrap();
+        let soa_rec = mk_soa_rec("example.com", Serial(2));
+        updater
+            .apply(ZoneUpdate::AddRecord(soa_rec.clone()))
+            .await
+            .unwrap();
+        updater
+            .apply(ZoneUpdate::DeleteRecord(mk_a_rec(www, 2, 0)))
+            .await
+            .unwrap();
+        updater
+            .apply(ZoneUpdate::AddRecord(mk_a_rec(www, 4, 3600)))
+            .await
+            .unwrap();
+        let diff = updater
+            .apply(ZoneUpdate::Finished(soa_rec))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(diff.start_serial, Serial(1));
+        assert_eq!(diff.end_serial, Serial(2));
+
+        // The removed record has the TTL it had in the zone, not the one of
+        // the delete request.
+        let key = (Name::<Bytes>::from_str(www).unwrap(), Rtype::A);
+        let removed = diff.removed.get(&key).unwrap();
+        assert_eq!(removed.ttl(), Ttl::from_secs(3600));
+        assert_eq!(removed.data(), [mk_a_rec(www, 2, 3600).into_data()]);
+        let added = diff.added.get(&key).unwrap();
+        assert_eq!(added.ttl(), Ttl::from_secs(3600));
+        assert_eq!(added.data(), [mk_a_rec(www, 4, 3600).into_data()]);
+
+        // Nothing but the SOA and the A record changed.
+        assert_eq!(diff.removed.len(), 2);
+        assert_eq!(diff.added.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn diff_contains_ttl_change() {
+        init_logging();
+
+        let zone = mk_empty_zone("example.com");
+        let www = "www.example.com";
+
+        let mut updater = ZoneUpdater::new(zone.clone()).await.unwrap();
+        let soa_rec = mk_soa_rec("example.com", Serial(1));
+        updater
+            .apply(ZoneUpdate::AddRecord(soa_rec.clone()))
+            .await
+            .unwrap();
+        for last_octet in 1..=2 {
+            updater
+                .apply(ZoneUpdate::AddRecord(mk_a_rec(www, last_octet, 3600)))
+                .await
+                .unwrap();
+        }
+        updater.apply(ZoneUpdate::Finished(soa_rec)).await.unwrap();
+
+        // Re-add a record with a different TTL which changes the TTL of the
+        // whole RRset but none of its records.
+        let mut updater = ZoneUpdater::new(zone.clone()).await.unwrap();
+        let soa_rec = mk_soa_rec("example.com", Serial(2));
+        updater
+            .apply(ZoneUpdate::AddRecord(soa_rec.clone()))
+            .await
+            .unwrap();
+        updater
+            .apply(ZoneUpdate::DeleteRecord(mk_a_rec(www, 1, 3600)))
+            .await
+            .unwrap();
+        updater
+            .apply(ZoneUpdate::AddRecord(mk_a_rec(www, 1, 7200)))
+            .await
+            .unwrap();
+        let diff = updater
+            .apply(ZoneUpdate::Finished(soa_rec))
+            .await
+            .unwrap()
+            .unwrap();
+
+        // All records are removed with the old TTL and added with the new.
+        let key = (Name::<Bytes>::from_str(www).unwrap(), Rtype::A);
+        let removed = diff.removed.get(&key).unwrap();
+        assert_eq!(removed.ttl(), Ttl::from_secs(3600));
+        let mut data = removed.data().to_vec();
+        data.sort();
+        assert_eq!(
+            data,
+            [
+                mk_a_rec(www, 1, 3600).into_data(),
+                mk_a_rec(www, 2, 3600).into_data()
+            ]
+        );
+        let added = diff.added.get(&key).unwrap();
+        assert_eq!(added.ttl(), Ttl::from_secs(7200));
+        let mut data = added.data().to_vec();
+        data.sort();
+        assert_eq!(
+            data,
+            [
+                mk_a_rec(www, 1, 7200).into_data(),
+                mk_a_rec(www, 2, 7200).into_data()
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn reload_zone_is_atomic() {
+        init_logging();
+
+        const NUM_RECORDS: u8 = 200;
+
+        fn mk_zonefile(
+            serial: u32,
+            prefix: &str,
+        ) -> std::io::Cursor<Vec<u8>> {
+            let mut zonefile = format!(
+                "@ 3600 IN SOA ns hostmaster {serial} 3600 300 604800 300\n\
+                 @ 3600 IN NS ns\n\
+                 ns 3600 IN A 192.0.2.1\n"
+            );
+            for i in 0..NUM_RECORDS {
+                zonefile.push_str(&format!(
+                    "{prefix}{i} 3600 IN A 192.0.2.{i}\n"
+                ));
+            }
+            std::io::Cursor::new(zonefile.into_bytes())
+        }
+
+        let zone = mk_empty_zone("example.com");
+
+        // The initial load has no prior SOA serial so no diff.
+        let diff = reload_zone(&zone, mk_zonefile(1, "old")).await.unwrap();
+        assert!(diff.is_none());
+
+        // Read the zone while it is reloaded. Every read must see either
+        // all of the old or all of the new zone.
+        let done = Arc::new(AtomicBool::new(false));
+        let reader = tokio::spawn({
+            let zone = zone.clone();
+            let done = done.clone();
+            async move {
+                let apex = Name::from_str("example.com").unwrap();
+                let mut reads = 0;
+                let mut saw_new = false;
+                while !done.load(Ordering::SeqCst) || !saw_new {
+                    let read = zone.read();
+                    let answer =
+                        read.query(apex.clone(), Rtype::SOA).unwrap();
+                    let Some((_, ZoneRecordData::Soa(soa))) =
+                        answer.content().first()
+                    else {
+                        panic!("missing SOA");
+                    };
+                    let (present, absent) = match soa.serial() {
+                        Serial(1) => ("old", "new"),
+                        Serial(2) => ("new", "old"),
+                        serial => panic!("unexpected serial {serial}"),
+                    };
+                    saw_new = soa.serial() == Serial(2);
+                    for i in 0..NUM_RECORDS {
+                        for (prefix, exists) in
+                            [(present, true), (absent, false)]
+                        {
+                            let qname = Name::from_str(&format!(
+                                "{prefix}{i}.example.com"
+                            ))
+                            .unwrap();
+                            let answer = read.query(qname, Rtype::A).unwrap();
+                            assert_eq!(
+                                matches!(
+                                    answer.content(),
+                                    AnswerContent::Data(_)
+                                ),
+                                exists,
+                                "{prefix}{i} at serial {}",
+                                soa.serial()
+                            );
+                        }
+                    }
+                    reads += 1;
+                    tokio::task::yield_now().await;
+                }
+                reads
+            }
+        });
+
+        let diff = reload_zone(&zone, mk_zonefile(2, "new"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(diff.start_serial, Serial(1));
+        assert_eq!(diff.end_serial, Serial(2));
+        done.store(true, Ordering::SeqCst);
+
+        let reads = reader.await.unwrap();
+        assert!(reads > 0);
+
+        // A broken zone file leaves the zone untouched.
+        let res = reload_zone(
+            &zone,
+            std::io::Cursor::new(b"@ 3600 IN A 192.0.2.1\n".to_vec()),
+        )
+        .await;
+        assert!(matches!(res, Err(Error::MissingSoa)));
+        let answer = zone
+            .read()
+            .query(Name::from_str("new0.example.com").unwrap(), Rtype::A)
+            .unwrap();
+        assert!(matches!(answer.content(), AnswerContent::Data(_)));
+    }
+
     #[tokio::test]
     async fn axfr_response_generates_expected_events() {
         init_logging();
@@ -1271,6 +1623,33 @@ mod tests {
         Soa::new(mname, rname, serial, ttl, ttl, ttl, ttl)
     }
 
+    fn mk_soa_rec(
+        apex_name: &str,
+        serial: Serial,
+    ) -> Record<ParsedName<Bytes>, ZoneRecordData<Bytes, ParsedName<Bytes>>>
+    {
+        Record::new(
+            ParsedName::from(Name::from_str(apex_name).unwrap()),
+            Class::IN,
+            Ttl::from_secs(0),
+            ZoneRecordData::Soa(mk_soa(serial)),
+        )
+    }
+
+    fn mk_a_rec(
+        owner: &str,
+        last_octet: u8,
+        ttl: u32,
+    ) -> Record<ParsedName<Bytes>, ZoneRecordData<Bytes, ParsedName<Bytes>>>
+    {
+        Record::new(
+            ParsedName::from(Name::from_str(owner).unwrap()),
+            Class::IN,
+            Ttl::from_secs(ttl),
+            ZoneRecordData::A(A::new(Ipv4Addr::new(192, 0, 2, last_octet))),
+        )
+    }
+
     fn mk_request(qname: &str, qtype: Rtype) -> QuestionBuilder<BytesMut> {
         let req = MessageBuilder::new_bytes();
         let mut req = req.question();
@@ -1317,6 +1696,12 @@ pub enum Error {
 
     /// The updater has finished and cannot be used anymore.
     Finished,
+
+    /// The zone file could not be parsed.
+    Zonefile(inplace::Error),
+
+    /// The zone file lacks an SOA record at the apex.
+    MissingSoa,
 }
 
 //--- Display
@@ -1329,6 +1714,8 @@ impl std::fmt::Display for Error {
             Error::IoError(err) => write!(f, "I/O error: {err}"),
 
             Error::Finished => f.write_str("Finished"),
+            Error::Zonefile(err) => write!(f, "zone file error: {err}"),
+            Error::MissingSoa => f.write_str("MissingSoa"),
         }
     }
 }
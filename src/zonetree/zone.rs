@@ -4,14 +4,16 @@ use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
-use crate::base::iana::Class;
+use crate::base::iana::{Class, Rtype};
+use crate::base::{Serial, ToName};
 use crate::zonefile::inplace;
 
-use super::error::{RecordError, ZoneErrors};
+use super::answer::Answer;
+use super::error::{OutOfZone, RecordError, ZoneErrors};
 use super::in_memory::ZoneBuilder;
 use super::traits::WritableZone;
 use super::types::StoredName;
-use super::{parsed, ReadableZone, ZoneStore};
+use super::{parsed, ReadableZone, ZoneDiffStream, ZoneStore};
 
 /// A single DNS zone.
 ///
@@ -72,7 +74,20 @@ impl Zone {
         self.store.apex_name()
     }
 
+    /// Returns whether `name` is within this zone.
+    ///
+    /// This is the case if `name` is the apex of the zone or a name below
+    /// it. Names for which this returns `false` are exactly those for which
+    /// querying the zone fails with [`OutOfZone`], so this can be used to
+    /// check names before querying without creating an error.
+    pub fn contains(&self, name: &impl ToName) -> bool {
+        name.ends_with(self.apex_name())
+    }
+
     /// Gets a read interface to this zone.
+    ///
+    /// The interface sees the zone as it is now. Changes committed later
+    /// are not visible through it.
     pub fn read(&self) -> Box<dyn ReadableZone> {
         self.store.clone().read()
     }
@@ -84,6 +99,40 @@ impl Zone {
     {
         self.store.clone().write()
     }
+
+    /// Subscribes to the changes made to this zone.
+    ///
+    /// See [`ZoneStore::subscribe_diffs()`].
+    pub fn subscribe_diffs(&self) -> Option<ZoneDiffStream> {
+        self.store.subscribe_diffs()
+    }
+
+    /// Returns the SOA serial of the current version of this zone.
+    ///
+    /// This doesn't require a read interface to the zone, see
+    /// [`ZoneStore::current_serial()`].
+    pub fn current_serial(&self) -> Option<Serial> {
+        self.store.current_serial()
+    }
+
+    /// Builds the referral for a query that falls below a delegation.
+    ///
+    /// If `qname` is at or below a zone cut in this zone and the query is
+    /// not one the parent side of the cut answers itself (i.e., a DS query
+    /// for the name of the cut), returns the referral: a non-authoritative
+    /// answer with the NS records (and DS records, if any) of the delegation
+    /// in the authority section and the glue in the additional section.
+    ///
+    /// Returns `Ok(None)` if the query is answered authoritatively by this
+    /// zone and an error if `qname` is not within this zone.
+    pub fn referral(
+        &self,
+        qname: StoredName,
+        qtype: Rtype,
+    ) -> Result<Option<Answer>, OutOfZone> {
+        let answer = self.read().query(qname, qtype)?;
+        Ok(answer.is_referral().then_some(answer))
+    }
 }
 
 impl AsRef<dyn ZoneStore> for Zone {
@@ -128,3 +177,131 @@ impl TryFrom<parsed::Zonefile> for Zone {
         )?))
     }
 }
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use bytes::Bytes;
+
+    use crate::base::{MessageBuilder, Name};
+    use crate::rdata::Ns;
+    use crate::zonetree::test_util::{load_zone, n};
+    use crate::zonetree::AnswerContent;
+
+    use super::*;
+
+    const ZONE: &[u8] = b"\
+example.com. 3600 IN SOA ns.example.com. hostmaster.example.com. 1 3600 300 604800 300
+example.com. 3600 IN NS ns.example.com.
+ns.example.com. 3600 IN A 192.0.2.1
+sub.example.com. 3600 IN NS ns.sub.example.com.
+sub.example.com. 3600 IN DS 60485 5 1 2BB183AF5F22588179A53B0A98631FAD1A292118
+ns.sub.example.com. 3600 IN A 192.0.2.2
+";
+
+    #[test]
+    fn referral_below_delegation() {
+        let zone = load_zone(ZONE);
+        let qname = n("www.sub.example.com.");
+        let answer = zone.referral(qname.clone(), Rtype::A).unwrap().unwrap();
+        assert!(!answer.is_authoritative());
+
+        let mut query = MessageBuilder::new_vec().question();
+        query.push((&qname, Rtype::A)).unwrap();
+        let query = query.into_message();
+        let response = answer
+            .to_message(&query, MessageBuilder::new_vec())
+            .into_message();
+        assert!(!response.header().aa());
+        assert_eq!(response.header_counts().ancount(), 0);
+
+        let authority = response.authority().unwrap();
+        let ns: Vec<_> = authority
+            .limit_to::<Ns<_>>()
+            .map(|rr| rr.unwrap())
+            .collect();
+        assert_eq!(ns.len(), 1);
+        assert_eq!(ns[0].owner().to_name::<Bytes>(), n("sub.example.com."));
+        assert_eq!(
+            ns[0].data().nsdname().to_name::<Bytes>(),
+            n("ns.sub.example.com.")
+        );
+        assert_eq!(response.header_counts().nscount(), 2);
+
+        let additional: Vec<Name<Bytes>> = response
+            .additional()
+            .unwrap()
+            .map(|rr| rr.unwrap().owner().to_name())
+            .collect();
+        assert_eq!(additional, [n("ns.sub.example.com.")]);
+
+        // The name of the cut itself is referred, too.
+        assert!(zone
+            .referral(n("sub.example.com."), Rtype::NS)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn no_referral_at_or_above_delegation() {
+        let zone = load_zone(ZONE);
+
+        // The parent answers DS queries for the cut itself.
+        assert!(zone
+            .referral(n("sub.example.com."), Rtype::DS)
+            .unwrap()
+            .is_none());
+        let answer =
+            zone.read().query(n("sub.example.com."), Rtype::DS).unwrap();
+        assert!(answer.is_authoritative());
+        assert!(matches!(answer.content(), AnswerContent::Data(_)));
+
+        for (qname, qtype) in [
+            ("example.com.", Rtype::NS),
+            ("ns.example.com.", Rtype::A),
+            ("nonexistent.example.com.", Rtype::A),
+        ] {
+            assert!(zone.referral(n(qname), qtype).unwrap().is_none());
+            let answer = zone.read().query(n(qname), qtype).unwrap();
+            assert!(answer.is_authoritative());
+        }
+
+        assert!(zone.referral(n("example.net."), Rtype::A).is_err());
+    }
+
+    #[test]
+    fn contains_matches_query() {
+        let zone = load_zone(ZONE);
+
+        for qname in [
+            "example.com.",
+            "EXAMPLE.com.",
+            "ns.example.com.",
+            "www.sub.example.com.",
+            "nonexistent.example.com.",
+        ] {
+            assert!(zone.contains(&n(qname)));
+            assert!(zone.read().query(n(qname), Rtype::A).is_ok());
+        }
+
+        for qname in [".", "com.", "example.net.", "example.com.org."] {
+            assert!(!zone.contains(&n(qname)));
+            assert!(zone.read().query(n(qname), Rtype::A).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn current_serial() {
+        let zone = load_zone(ZONE);
+        assert_eq!(zone.current_serial(), Some(Serial(1)));
+
+        // Committing a change bumps the serial.
+        let mut write = zone.write().await;
+        write.open(false).await.unwrap();
+        write.commit(true).await.unwrap();
+        assert_eq!(zone.current_serial(), Some(Serial(2)));
+    }
+}
@@ -152,3 +152,77 @@ fn tcp() {
         do_client_simple(&stelline, &step_value, tcp).await;
     });
 }
+
+// TCP Fast Open is only supported on Linux.
+#[cfg(target_os = "linux")]
+mod tcp_fast_open {
+    use super::*;
+    use domain::base::{MessageBuilder, Name, Rtype};
+    use domain::net::client::protocol::TcpConnect;
+    use domain::net::client::request::{RequestMessage, SendRequest};
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio_tfo::TfoListener;
+
+    #[tokio::test]
+    async fn query_succeeds() {
+        // The server accepts TCP Fast Open.
+        let listener = TfoListener::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (sock, _) = listener.accept().await.unwrap();
+            echo_query(sock).await;
+        });
+        query_fast_open(addr).await;
+    }
+
+    #[tokio::test]
+    async fn fallback_without_server_support() {
+        // A regular listener that does not support TCP Fast Open.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (sock, _) = listener.accept().await.unwrap();
+            echo_query(sock).await;
+        });
+        query_fast_open(addr).await;
+    }
+
+    /// Sends a query with TCP Fast Open enabled and checks the reply.
+    async fn query_fast_open(addr: SocketAddr) {
+        let mut connect = TcpConnect::new(addr);
+        connect.set_fast_open(true);
+        let (conn, transport) = multi_stream::Connection::new(connect);
+        tokio::spawn(async move {
+            transport.run().await;
+        });
+
+        let mut msg = MessageBuilder::new_vec();
+        msg.header_mut().set_rd(true);
+        let mut msg = msg.question();
+        msg.push((Name::vec_from_str("example.com").unwrap(), Rtype::A))
+            .unwrap();
+        let req = RequestMessage::new(msg).unwrap();
+        let reply = conn.send_request(req).get_response().await.unwrap();
+        assert!(reply.header().qr());
+        assert_eq!(
+            reply.sole_question().unwrap().qname().to_string(),
+            "example.com"
+        );
+    }
+
+    /// Answers one query by sending it back with the QR bit set.
+    async fn echo_query(mut sock: impl AsyncRead + AsyncWrite + Unpin) {
+        let len = sock.read_u16().await.unwrap();
+        let mut buf = vec![0; len.into()];
+        sock.read_exact(&mut buf).await.unwrap();
+        buf[2] |= 0x80;
+        sock.write_u16(len).await.unwrap();
+        sock.write_all(&buf).await.unwrap();
+
+        // Keep the connection open until the client is done.
+        let _ = sock.read_u8().await;
+    }
+}